@@ -0,0 +1,70 @@
+use proc_macro::TokenStream;
+use prompt_def::compose::load_file;
+use prompt_def::validate::{has_errors, Severity};
+use quote::quote;
+use std::path::{Path, PathBuf};
+use syn::{parse_macro_input, LitStr};
+
+fn expand(path: &Path) -> Result<proc_macro2::TokenStream, String> {
+    let prompt = load_file(path).map_err(|e| e.to_string())?;
+    let issues = prompt.validate();
+    if has_errors(&issues) {
+        let errors: Vec<String> = issues
+            .iter()
+            .filter(|i| i.severity == Severity::Error)
+            .map(|i| i.to_string())
+            .collect();
+        return Err(format!("{}: {}", path.display(), errors.join("; ")));
+    }
+    let json = serde_json::to_string(&prompt).map_err(|e| e.to_string())?;
+    let tracked = path.display().to_string();
+    Ok(quote! {
+        {
+            const _: &str = include_str!(#tracked);
+            static PROMPT: ::std::sync::LazyLock<::prompt_def::prompt::Prompt> =
+                ::std::sync::LazyLock::new(|| {
+                    ::prompt_def::prompt::Prompt::from_str(#json, ::prompt_def::format::Format::Json)
+                        .expect("prompt was validated at compile time")
+                });
+            &*PROMPT
+        }
+    })
+}
+
+// Paths are relative to the invoking crate's CARGO_MANIFEST_DIR. Expands to a
+// `&'static Prompt` that is parsed from the embedded, already-validated JSON.
+#[proc_macro]
+pub fn include_prompt(input: TokenStream) -> TokenStream {
+    let literal = parse_macro_input!(input as LitStr);
+    let root = std::env::var("CARGO_MANIFEST_DIR").unwrap_or_default();
+    let path = PathBuf::from(root).join(literal.value());
+    match expand(&path) {
+        Ok(tokens) => tokens.into(),
+        Err(message) => syn::Error::new(literal.span(), message)
+            .to_compile_error()
+            .into(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs;
+
+    #[test]
+    fn test_expand_rejects_invalid_prompts() {
+        let dir = std::env::temp_dir().join(format!("prompt_def_macros_{}", std::process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        let broken = dir.join("broken.yaml");
+        fs::write(
+            &broken,
+            "type: completion\nvendor: openai\nmodel: gpt-4o\nprompt: Hi {{name\n",
+        )
+        .unwrap();
+        let missing = dir.join("missing.yaml");
+
+        assert!(expand(&broken).unwrap_err().contains("invalid-template"));
+        assert!(expand(&missing).is_err());
+        fs::remove_dir_all(&dir).unwrap();
+    }
+}
@@ -0,0 +1,12 @@
+use prompt_def::prompt::Prompt;
+use prompt_def_macros::include_prompt;
+
+#[test]
+fn test_include_prompt() {
+    let prompt: &'static Prompt = include_prompt!("tests/fixtures/greet.yaml");
+    assert_eq!(prompt.meta().unwrap().name.as_deref(), Some("greet"));
+    assert_eq!(
+        prompt.required_variables().unwrap(),
+        vec!["who".to_string()]
+    );
+}
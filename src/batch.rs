@@ -0,0 +1,195 @@
+use crate::exec::{BoxFuture, ExecError, ExecutionResult, PromptExecutor};
+use crate::prompt::Prompt;
+use std::collections::HashMap;
+use std::future::poll_fn;
+use std::task::Poll;
+
+#[derive(Debug, Clone, Copy)]
+pub struct BatchJob<'a> {
+    pub prompt: &'a Prompt,
+    pub vars: &'a HashMap<String, String>,
+}
+
+impl<'a> BatchJob<'a> {
+    pub fn new(prompt: &'a Prompt, vars: &'a HashMap<String, String>) -> BatchJob<'a> {
+        BatchJob { prompt, vars }
+    }
+
+    pub fn for_vars(prompt: &'a Prompt, rows: &'a [HashMap<String, String>]) -> Vec<BatchJob<'a>> {
+        rows.iter()
+            .map(|vars| BatchJob::new(prompt, vars))
+            .collect()
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Progress {
+    pub index: usize,
+    pub completed: usize,
+    pub failed: usize,
+    pub total: usize,
+}
+
+impl Progress {
+    pub fn remaining(&self) -> usize {
+        self.total - self.completed
+    }
+}
+
+pub type ProgressCallback<'a> = &'a mut (dyn FnMut(&Progress) + Send);
+
+pub type BatchResults = Vec<Result<ExecutionResult, ExecError>>;
+
+pub trait BatchExecutor: PromptExecutor {
+    fn execute_batch<'a>(
+        &'a self,
+        jobs: Vec<BatchJob<'a>>,
+        max_concurrency: usize,
+    ) -> BoxFuture<'a, BatchResults> {
+        Box::pin(async move {
+            let mut ignore = |_: &Progress| {};
+            self.execute_batch_with(jobs, max_concurrency, &mut ignore)
+                .await
+        })
+    }
+
+    fn execute_batch_with<'a>(
+        &'a self,
+        jobs: Vec<BatchJob<'a>>,
+        max_concurrency: usize,
+        on_progress: ProgressCallback<'a>,
+    ) -> BoxFuture<'a, BatchResults> {
+        Box::pin(async move {
+            let total = jobs.len();
+            let mut pending = jobs.into_iter().enumerate();
+            let mut active: Vec<(usize, BoxFuture<'a, Result<ExecutionResult, ExecError>>)> =
+                Vec::new();
+            let mut results: Vec<Option<Result<ExecutionResult, ExecError>>> =
+                (0..total).map(|_| None).collect();
+            let mut progress = Progress {
+                index: 0,
+                completed: 0,
+                failed: 0,
+                total,
+            };
+            poll_fn(|cx| loop {
+                while active.len() < max_concurrency.max(1) {
+                    let Some((index, job)) = pending.next() else {
+                        break;
+                    };
+                    active.push((index, self.execute(job.prompt, job.vars)));
+                }
+                if active.is_empty() {
+                    return Poll::Ready(());
+                }
+                let before = active.len();
+                let mut i = 0;
+                while i < active.len() {
+                    let Poll::Ready(result) = active[i].1.as_mut().poll(cx) else {
+                        i += 1;
+                        continue;
+                    };
+                    let (index, _) = active.swap_remove(i);
+                    progress.index = index;
+                    progress.completed += 1;
+                    progress.failed += usize::from(result.is_err());
+                    on_progress(&progress);
+                    results[index] = Some(result);
+                }
+                if active.len() == before {
+                    return Poll::Pending;
+                }
+            })
+            .await;
+            results.into_iter().flatten().collect()
+        })
+    }
+}
+
+impl<E: PromptExecutor + ?Sized> BatchExecutor for E {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::exec::block_on;
+    use crate::prompt::deserialize_prompt;
+    use std::future::Future;
+    use std::pin::Pin;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use std::task::Context;
+
+    struct YieldOnce(bool);
+
+    impl Future for YieldOnce {
+        type Output = ();
+
+        fn poll(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<()> {
+            if self.0 {
+                return Poll::Ready(());
+            }
+            self.0 = true;
+            cx.waker().wake_by_ref();
+            Poll::Pending
+        }
+    }
+
+    #[derive(Default)]
+    struct Echo {
+        active: AtomicUsize,
+        peak: AtomicUsize,
+    }
+
+    impl PromptExecutor for Echo {
+        fn execute<'a>(
+            &'a self,
+            _: &'a Prompt,
+            vars: &'a HashMap<String, String>,
+        ) -> BoxFuture<'a, Result<ExecutionResult, ExecError>> {
+            Box::pin(async move {
+                let now = self.active.fetch_add(1, Ordering::SeqCst) + 1;
+                self.peak.fetch_max(now, Ordering::SeqCst);
+                YieldOnce(false).await;
+                self.active.fetch_sub(1, Ordering::SeqCst);
+                match vars.get("row").map(|r| r.as_str()) {
+                    Some("3") => Err(ExecError::InvalidResponse("bad row".to_string())),
+                    row => Ok(ExecutionResult {
+                        text: row.unwrap_or_default().to_string(),
+                        raw: serde_json::Value::Null,
+                    }),
+                }
+            })
+        }
+    }
+
+    #[test]
+    fn test_bounded_batch() {
+        let prompt =
+            deserialize_prompt("type: completion\nvendor: openai\nmodel: gpt\nprompt: '{{row}}'\n");
+        let rows: Vec<HashMap<String, String>> = (0..10)
+            .map(|i| HashMap::from([("row".to_string(), i.to_string())]))
+            .collect();
+        let executor = Echo::default();
+        let mut seen = Vec::new();
+        let mut on_progress = |progress: &Progress| seen.push(*progress);
+
+        let results = block_on(executor.execute_batch_with(
+            BatchJob::for_vars(&prompt, &rows),
+            3,
+            &mut on_progress,
+        ));
+        assert_eq!(results.len(), 10);
+        assert_eq!(results[0].as_ref().unwrap().text, "0");
+        assert!(results[3].is_err());
+        assert_eq!(results[9].as_ref().unwrap().text, "9");
+        assert_eq!(executor.peak.load(Ordering::SeqCst), 3);
+
+        assert_eq!(seen.len(), 10);
+        let last = seen[9];
+        assert_eq!((last.completed, last.failed, last.remaining()), (10, 1, 0));
+
+        let serial = Echo::default();
+        let results = block_on(serial.execute_batch(BatchJob::for_vars(&prompt, &rows), 0));
+        assert_eq!(results.iter().filter(|r| r.is_ok()).count(), 9);
+        assert_eq!(serial.peak.load(Ordering::SeqCst), 1);
+    }
+}
@@ -0,0 +1,495 @@
+use prompt_def::compose::load_file;
+use prompt_def::prompt::{Parameter, Prompt};
+use prompt_def::registry::PromptRegistry;
+use prompt_def::template::MissingVariable;
+use prompt_def::tokens::HeuristicTokenizer;
+use prompt_def::validate::{has_errors, ValidationIssue};
+use std::collections::HashMap;
+use std::path::Path;
+use std::process::ExitCode;
+
+const USAGE: &str = "usage:
+  prompt validate <dir|file> [--strict]
+  prompt show <file>
+  prompt render <file> [--var key=value]... [--missing error|empty|keep]
+  prompt run <file> [--var key=value]... [--env name] [--stream]
+  prompt schema";
+
+#[derive(Debug, Default, PartialEq)]
+struct Args {
+    command: String,
+    target: String,
+    vars: HashMap<String, String>,
+    missing: MissingVariable,
+    env: Option<String>,
+    stream: bool,
+    strict: bool,
+}
+
+fn parse_args(args: impl IntoIterator<Item = String>) -> Result<Args, String> {
+    let mut parsed = Args::default();
+    let mut positional = Vec::new();
+    let mut args = args.into_iter();
+    while let Some(arg) = args.next() {
+        let mut value = |flag: &str| args.next().ok_or(format!("{} needs a value", flag));
+        match arg.as_str() {
+            "--var" => {
+                let pair = value("--var")?;
+                let (key, val) = pair
+                    .split_once('=')
+                    .ok_or(format!("expected key=value, got '{}'", pair))?;
+                parsed.vars.insert(key.to_string(), val.to_string());
+            }
+            "--missing" => {
+                parsed.missing = match value("--missing")?.as_str() {
+                    "error" => MissingVariable::Error,
+                    "empty" => MissingVariable::Empty,
+                    "keep" => MissingVariable::Keep,
+                    other => return Err(format!("unknown --missing mode '{}'", other)),
+                }
+            }
+            "--env" => parsed.env = Some(value("--env")?),
+            "--stream" => parsed.stream = true,
+            "--strict" => parsed.strict = true,
+            flag if flag.starts_with("--") => return Err(format!("unknown flag '{}'", flag)),
+            _ => positional.push(arg),
+        }
+    }
+    match positional.as_slice() {
+        [command] if command == "schema" => {
+            parsed.command = command.clone();
+            Ok(parsed)
+        }
+        [command, target] => {
+            parsed.command = command.clone();
+            parsed.target = target.clone();
+            Ok(parsed)
+        }
+        _ => Err("expected a command and a path".to_string()),
+    }
+}
+
+fn print_issues(name: &str, issues: &[ValidationIssue]) {
+    for issue in issues {
+        println!("{}: {}", name, issue);
+    }
+}
+
+fn passes(issues: &[ValidationIssue], strict: bool) -> bool {
+    let unknown = strict && issues.iter().any(|i| i.code == "unknown-field");
+    !has_errors(issues) && !unknown
+}
+
+fn validate(target: &str, strict: bool) -> Result<bool, String> {
+    let path = Path::new(target);
+    if path.is_file() {
+        let prompt = load_file(path).map_err(|e| e.to_string())?;
+        let issues = prompt.validate();
+        print_issues(target, &issues);
+        return Ok(passes(&issues, strict));
+    }
+    let registry = PromptRegistry::load_lazy(path).map_err(|e| e.to_string())?;
+    let mut ok = true;
+    for name in registry.list() {
+        match registry.get(name) {
+            Ok(prompt) => {
+                let issues = prompt.validate();
+                print_issues(name, &issues);
+                ok &= passes(&issues, strict);
+            }
+            Err(error) => {
+                println!("{}: error {}", name, error);
+                ok = false;
+            }
+        }
+    }
+    println!("checked {} prompts", registry.len());
+    Ok(ok)
+}
+
+fn show_parameters(parameters: &Option<Vec<Parameter>>) {
+    for parameter in parameters.iter().flatten() {
+        let value = serde_json::to_string(&parameter.value).unwrap_or_default();
+        println!("  {} = {}", parameter.name, value);
+    }
+}
+
+fn show(prompt: &Prompt) -> Result<(), String> {
+    let tokenizer = HeuristicTokenizer::default();
+    let (kind, vendor, model, parameters, tokens) = match prompt {
+        Prompt::Completion(c) => (
+            "completion",
+            &c.vendor,
+            &c.model,
+            &c.parameters,
+            c.estimate_tokens(&tokenizer),
+        ),
+        Prompt::Chat(c) => (
+            "chat",
+            &c.vendor,
+            &c.model,
+            &c.parameters,
+            c.estimate_tokens(&tokenizer),
+        ),
+        Prompt::Embedding(e) => (
+            "embedding",
+            &e.vendor,
+            &e.model,
+            &None,
+            e.estimate_tokens(&tokenizer),
+        ),
+        Prompt::Custom(k) => return Err(format!("prompt type '{}' is not supported", k.kind())),
+        Prompt::Unknown => return Err("unknown prompt type".to_string()),
+    };
+    if let Some(meta) = prompt.meta() {
+        let fields = [
+            ("name", &meta.name),
+            ("description", &meta.description),
+            ("version", &meta.version),
+            ("author", &meta.author),
+            ("created", &meta.created),
+            ("updated", &meta.updated),
+        ];
+        for (label, value) in fields {
+            if let Some(value) = value {
+                println!("{}: {}", label, value);
+            }
+        }
+        if let Some(tags) = &meta.tags {
+            println!("tags: {}", tags.join(", "));
+        }
+    }
+    println!("type: {}\nvendor: {}\nmodel: {}", kind, vendor, model);
+    if parameters.is_some() {
+        println!("parameters:");
+        show_parameters(parameters);
+    }
+    let variables = prompt.required_variables().map_err(|e| e.to_string())?;
+    let declared = prompt.meta().and_then(|m| m.variables.as_ref());
+    match declared.filter(|d| !d.is_empty()) {
+        Some(declared) => {
+            println!("variables:");
+            for (name, spec) in declared {
+                println!("  {}: {}", name, spec);
+            }
+            for name in variables.iter().filter(|n| !declared.contains_key(*n)) {
+                println!("  {}: undeclared", name);
+            }
+        }
+        None if !variables.is_empty() => println!("variables: {}", variables.join(", ")),
+        None => {}
+    }
+    println!("estimated tokens: {}", tokens);
+    Ok(())
+}
+
+fn render(prompt: &Prompt, args: &Args) -> Result<String, String> {
+    match prompt {
+        Prompt::Completion(c) => c.render_with(&args.vars, args.missing),
+        Prompt::Chat(c) => c.render_with(&args.vars, args.missing).map(|chat| {
+            chat.to_transcript()
+                .messages
+                .iter()
+                .map(|m| format!("[{}]\n{}\n", m.role, m.content))
+                .collect::<Vec<_>>()
+                .join("\n")
+        }),
+        Prompt::Embedding(e) => e
+            .render_with(&args.vars, args.missing)
+            .map(|embedding| embedding.input.join("\n")),
+        Prompt::Custom(k) => return Err(format!("prompt type '{}' is not supported", k.kind())),
+        Prompt::Unknown => return Err("unknown prompt type".to_string()),
+    }
+    .map_err(|e| e.to_string())
+}
+
+#[cfg(feature = "exec")]
+mod run {
+    use super::Args;
+    use prompt_def::exec::{
+        block_on, AnthropicExecutor, AzureOpenAiExecutor, BoxFuture, ExecError, HttpClient,
+        HttpRequest, HttpResponse, OpenAiExecutor, VendorExecutor, VertexExecutor,
+    };
+    use prompt_def::prompt::Prompt;
+    use prompt_def::request::Vendor;
+    use prompt_def::retry::RetryExecutor;
+    use std::env;
+    use std::io::{BufRead, BufReader, Read, Write};
+    use std::process::{Command, Stdio};
+    use std::sync::Arc;
+    use std::time::Duration;
+
+    const CURL_TIMEOUT_EXIT: i32 = 28;
+
+    // Shells out to curl so the CLI needs no TLS stack of its own. Headers and
+    // the body go through a config file on stdin so API keys stay off argv.
+    struct CurlClient {
+        timeout: Option<Duration>,
+    }
+
+    fn quote(text: &str) -> String {
+        let mut quoted = String::from("\"");
+        for c in text.chars() {
+            match c {
+                '\\' => quoted.push_str("\\\\"),
+                '"' => quoted.push_str("\\\""),
+                '\n' => quoted.push_str("\\n"),
+                '\r' => quoted.push_str("\\r"),
+                '\t' => quoted.push_str("\\t"),
+                c => quoted.push(c),
+            }
+        }
+        quoted.push('"');
+        quoted
+    }
+
+    pub(super) fn curl_config(request: &HttpRequest) -> String {
+        let mut config = format!("url = {}\nrequest = POST\n", quote(&request.url));
+        let headers = std::iter::once(("content-type", "application/json")).chain(
+            request
+                .headers
+                .iter()
+                .map(|(n, v)| (n.as_str(), v.as_str())),
+        );
+        for (name, value) in headers {
+            config.push_str(&format!(
+                "header = {}\n",
+                quote(&format!("{}: {}", name, value))
+            ));
+        }
+        config.push_str(&format!(
+            "data-raw = {}\n",
+            quote(&request.body.to_string())
+        ));
+        config
+    }
+
+    impl CurlClient {
+        fn spawn(&self, request: &HttpRequest) -> Result<std::process::Child, ExecError> {
+            let mut command = Command::new("curl");
+            command.args(["-sS", "-N", "-K", "-", "-w", "\n%{http_code}"]);
+            if let Some(timeout) = self.timeout {
+                let seconds = format!("{:.3}", timeout.as_secs_f64());
+                command.args(["--max-time", &seconds, "--connect-timeout", &seconds]);
+            }
+            let mut child = command
+                .stdin(Stdio::piped())
+                .stdout(Stdio::piped())
+                .spawn()
+                .map_err(|e| ExecError::Http(format!("could not run curl: {}", e)))?;
+            let mut stdin = child.stdin.take().expect("piped stdin");
+            stdin
+                .write_all(curl_config(request).as_bytes())
+                .map_err(|e| ExecError::Http(e.to_string()))?;
+            Ok(child)
+        }
+
+        fn finish(
+            &self,
+            mut child: std::process::Child,
+            body: String,
+            status: &str,
+        ) -> Result<HttpResponse, ExecError> {
+            let exit = child.wait().map_err(|e| ExecError::Http(e.to_string()))?;
+            if let (Some(CURL_TIMEOUT_EXIT), Some(timeout)) = (exit.code(), self.timeout) {
+                return Err(ExecError::Timeout(timeout));
+            }
+            let status = status
+                .trim()
+                .parse()
+                .ok()
+                .filter(|status| *status != 0)
+                .ok_or_else(|| ExecError::Http(format!("curl failed: {}", body)))?;
+            Ok(HttpResponse { status, body })
+        }
+    }
+
+    impl HttpClient for CurlClient {
+        fn post(&self, request: HttpRequest) -> BoxFuture<'_, Result<HttpResponse, ExecError>> {
+            Box::pin(async move {
+                let mut child = self.spawn(&request)?;
+                let mut output = String::new();
+                child
+                    .stdout
+                    .take()
+                    .expect("piped stdout")
+                    .read_to_string(&mut output)
+                    .map_err(|e| ExecError::Http(e.to_string()))?;
+                let (body, status) = output.rsplit_once('\n').unwrap_or(("", &output));
+                let (body, status) = (body.to_string(), status.to_string());
+                self.finish(child, body, &status)
+            })
+        }
+
+        fn post_stream<'a>(
+            &'a self,
+            request: HttpRequest,
+            on_chunk: &'a mut (dyn FnMut(&str) + Send),
+        ) -> BoxFuture<'a, Result<HttpResponse, ExecError>> {
+            Box::pin(async move {
+                let mut child = self.spawn(&request)?;
+                let stdout = BufReader::new(child.stdout.take().expect("piped stdout"));
+                let mut body = String::new();
+                let mut pending: Option<String> = None;
+                for line in stdout.lines() {
+                    let line = line.map_err(|e| ExecError::Http(e.to_string()))?;
+                    if let Some(previous) = pending.replace(line) {
+                        on_chunk(&format!("{}\n", previous));
+                        body.push_str(&previous);
+                        body.push('\n');
+                    }
+                }
+                self.finish(child, body, &pending.unwrap_or_default())
+            })
+        }
+    }
+
+    fn executor(timeout: Option<Duration>) -> RetryExecutor<VendorExecutor> {
+        let client: Arc<dyn HttpClient> = Arc::new(CurlClient { timeout });
+        let mut executor = VendorExecutor::new();
+        let key = env::var("OPENAI_API_KEY").unwrap_or_default();
+        let mut openai = OpenAiExecutor::new(client.clone(), key);
+        if let Ok(base_url) = env::var("OPENAI_BASE_URL") {
+            openai = openai.base_url(base_url);
+        }
+        executor = executor.with(Vendor::OpenAi, openai);
+        if let (Ok(key), Ok(endpoint)) = (
+            env::var("AZURE_OPENAI_API_KEY"),
+            env::var("AZURE_OPENAI_ENDPOINT"),
+        ) {
+            let mut azure = AzureOpenAiExecutor::new(client.clone(), key, endpoint);
+            if let Ok(api_version) = env::var("AZURE_OPENAI_API_VERSION") {
+                azure = azure.api_version(api_version);
+            }
+            executor = executor.with_vendor("azure", azure);
+        }
+        if let Ok(key) = env::var("ANTHROPIC_API_KEY") {
+            executor = executor.with(
+                Vendor::Anthropic,
+                AnthropicExecutor::new(client.clone(), key),
+            );
+        }
+        if let (Ok(token), Ok(project)) =
+            (env::var("GOOGLE_ACCESS_TOKEN"), env::var("GOOGLE_PROJECT"))
+        {
+            let location = env::var("GOOGLE_LOCATION").unwrap_or("us-central1".to_string());
+            executor = executor.with(
+                Vendor::Google,
+                VertexExecutor::new(client, token, project, location),
+            );
+        }
+        RetryExecutor::new(executor)
+    }
+
+    pub fn run(prompt: &Prompt, args: &Args) -> Result<(), String> {
+        let prompt = prompt.resolve_parameters(args.env.as_deref(), &[]);
+        let executor = executor(prompt.policy().and_then(|p| p.timeout()));
+        let result = if args.stream || prompt.is_streaming() {
+            let mut on_token = |token: &str| {
+                print!("{}", token);
+                let _ = std::io::stdout().flush();
+            };
+            let result = block_on(prompt.execute_streaming(&executor, &args.vars, &mut on_token));
+            println!();
+            result.map(|_| ())
+        } else {
+            block_on(prompt.execute(&executor, &args.vars)).map(|r| println!("{}", r.text))
+        };
+        result.map_err(|e| e.to_string())
+    }
+}
+
+fn execute(args: &Args) -> Result<bool, String> {
+    if args.command == "schema" {
+        let schema = serde_json::to_string_pretty(&Prompt::json_schema());
+        println!("{}", schema.map_err(|e| e.to_string())?);
+        return Ok(true);
+    }
+    if args.command == "validate" {
+        return validate(&args.target, args.strict);
+    }
+    let prompt = load_file(&args.target).map_err(|e| e.to_string())?;
+    match args.command.as_str() {
+        "show" => show(&prompt)?,
+        "render" => println!("{}", render(&prompt, args)?),
+        #[cfg(feature = "exec")]
+        "run" => run::run(&prompt, args)?,
+        #[cfg(not(feature = "exec"))]
+        "run" => return Err("built without the exec feature".to_string()),
+        other => return Err(format!("unknown command '{}'\n{}", other, USAGE)),
+    }
+    Ok(true)
+}
+
+fn main() -> ExitCode {
+    let args = match parse_args(std::env::args().skip(1)) {
+        Ok(args) => args,
+        Err(error) => {
+            eprintln!("{}\n{}", error, USAGE);
+            return ExitCode::from(2);
+        }
+    };
+    match execute(&args) {
+        Ok(true) => ExitCode::SUCCESS,
+        Ok(false) => ExitCode::FAILURE,
+        Err(error) => {
+            eprintln!("error: {}", error);
+            ExitCode::FAILURE
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn args(line: &str) -> Result<Args, String> {
+        parse_args(line.split_whitespace().map(|a| a.to_string()))
+    }
+
+    #[test]
+    fn test_parse_args() {
+        let parsed = args("render greet.yaml --var name=Ada --var lang=en --missing keep").unwrap();
+        assert_eq!(parsed.command, "render");
+        assert_eq!(parsed.target, "greet.yaml");
+        assert_eq!(parsed.vars["name"], "Ada");
+        assert_eq!(parsed.missing, MissingVariable::Keep);
+        assert!(args("run a.yaml --stream --env prod").unwrap().stream);
+        assert!(args("validate prompts --strict").unwrap().strict);
+
+        assert_eq!(args("schema").unwrap().command, "schema");
+
+        assert!(args("render").is_err());
+        assert!(args("render a.yaml --var oops").is_err());
+        assert!(args("render a.yaml --bogus").is_err());
+    }
+
+    #[test]
+    fn test_render_chat() {
+        let prompt = prompt_def::prompt::deserialize_prompt(
+            "type: chat\nvendor: openai\nmodel: gpt-4o\ncontext: Be {{tone}}\nmessages:\n  - input: hi\n",
+        );
+        let parsed = args("render x.yaml --var tone=brief").unwrap();
+        assert_eq!(
+            render(&prompt, &parsed).unwrap(),
+            "[system]\nBe brief\n\n[user]\nhi\n"
+        );
+    }
+
+    #[cfg(feature = "exec")]
+    #[test]
+    fn test_curl_config() {
+        let request = prompt_def::exec::HttpRequest {
+            url: "https://api.example.com/v1/chat".to_string(),
+            headers: vec![("authorization".to_string(), "Bearer sk-test".to_string())],
+            body: serde_json::json!({ "prompt": "say \"hi\"\n" }),
+        };
+        assert_eq!(
+            run::curl_config(&request),
+            "url = \"https://api.example.com/v1/chat\"\nrequest = POST\n\
+             header = \"content-type: application/json\"\n\
+             header = \"authorization: Bearer sk-test\"\n\
+             data-raw = \"{\\\"prompt\\\":\\\"say \\\\\\\"hi\\\\\\\"\\\\n\\\"}\"\n"
+        );
+    }
+}
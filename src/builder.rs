@@ -0,0 +1,384 @@
+use crate::prompt::{
+    Chat, ChatExample, Completion, CompletionExampleColumn, ExampleFormat, Message, Parameter,
+    PromptMeta,
+};
+use crate::sampling::Sampling;
+use crate::tools::Tool;
+use serde_yaml::Value;
+use std::fmt;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum BuildError {
+    MissingField(&'static str),
+    DuplicateParameter(String),
+}
+
+impl fmt::Display for BuildError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            BuildError::MissingField(field) => write!(f, "missing required field '{}'", field),
+            BuildError::DuplicateParameter(name) => write!(f, "duplicate parameter '{}'", name),
+        }
+    }
+}
+
+impl std::error::Error for BuildError {}
+
+fn required(value: Option<String>, field: &'static str) -> Result<String, BuildError> {
+    value
+        .filter(|v| !v.trim().is_empty())
+        .ok_or(BuildError::MissingField(field))
+}
+
+fn check_parameters(parameters: &[Parameter]) -> Result<(), BuildError> {
+    for (i, parameter) in parameters.iter().enumerate() {
+        if parameters[..i].iter().any(|p| p.name == parameter.name) {
+            return Err(BuildError::DuplicateParameter(parameter.name.clone()));
+        }
+    }
+    Ok(())
+}
+
+fn non_empty<T>(items: Vec<T>) -> Option<Vec<T>> {
+    if items.is_empty() {
+        None
+    } else {
+        Some(items)
+    }
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct CompletionBuilder {
+    meta: PromptMeta,
+    vendor: Option<String>,
+    model: Option<String>,
+    prompt: Option<String>,
+    system: Option<String>,
+    suffix: Option<String>,
+    parameters: Vec<Parameter>,
+    sampling: Sampling,
+    columns: Vec<CompletionExampleColumn>,
+    example_format: Option<ExampleFormat>,
+}
+
+impl CompletionBuilder {
+    pub fn new() -> CompletionBuilder {
+        CompletionBuilder::default()
+    }
+
+    pub fn meta(mut self, meta: PromptMeta) -> Self {
+        self.meta = meta;
+        self
+    }
+
+    pub fn vendor(mut self, vendor: impl Into<String>) -> Self {
+        self.vendor = Some(vendor.into());
+        self
+    }
+
+    pub fn model(mut self, model: impl Into<String>) -> Self {
+        self.model = Some(model.into());
+        self
+    }
+
+    pub fn prompt(mut self, prompt: impl Into<String>) -> Self {
+        self.prompt = Some(prompt.into());
+        self
+    }
+
+    pub fn system(mut self, system: impl Into<String>) -> Self {
+        self.system = Some(system.into());
+        self
+    }
+
+    pub fn suffix(mut self, suffix: impl Into<String>) -> Self {
+        self.suffix = Some(suffix.into());
+        self
+    }
+
+    pub fn example_format(mut self, format: ExampleFormat) -> Self {
+        self.example_format = Some(format);
+        self
+    }
+
+    pub fn parameter(mut self, name: impl Into<String>, value: impl Into<Value>) -> Self {
+        self.parameters.push(Parameter {
+            name: name.into(),
+            value: value.into(),
+        });
+        self
+    }
+
+    pub fn sampling(mut self, sampling: Sampling) -> Self {
+        self.sampling = sampling;
+        self
+    }
+
+    fn column_mut(&mut self, name: &str) -> &mut CompletionExampleColumn {
+        let index = match self.columns.iter().position(|c| c.name == name) {
+            Some(index) => index,
+            None => {
+                self.columns.push(CompletionExampleColumn {
+                    name: name.to_string(),
+                    values: Vec::new(),
+                    test: None,
+                });
+                self.columns.len() - 1
+            }
+        };
+        &mut self.columns[index]
+    }
+
+    pub fn column(mut self, name: &str, values: &[&str]) -> Self {
+        let column = self.column_mut(name);
+        column.values.extend(values.iter().map(|v| v.to_string()));
+        self
+    }
+
+    pub fn example(mut self, row: &[(&str, &str)]) -> Self {
+        let count = self
+            .columns
+            .iter()
+            .map(|c| c.values.len())
+            .max()
+            .unwrap_or(0);
+        for (name, _) in row {
+            self.column_mut(name);
+        }
+        for column in &mut self.columns {
+            let value = row
+                .iter()
+                .find(|(name, _)| *name == column.name)
+                .map(|(_, value)| value.to_string())
+                .unwrap_or_default();
+            column.values.resize(count, String::new());
+            column.values.push(value);
+        }
+        self
+    }
+
+    pub fn test(mut self, name: &str, value: impl Into<String>) -> Self {
+        self.column_mut(name).test = Some(value.into());
+        self
+    }
+
+    pub fn build(self) -> Result<Completion, BuildError> {
+        check_parameters(&self.parameters)?;
+        Ok(Completion {
+            meta: self.meta,
+            prompt_type: "completion".to_string(),
+            vendor: required(self.vendor, "vendor")?,
+            model: required(self.model, "model")?,
+            prompt: required(self.prompt, "prompt")?,
+            system: self.system,
+            suffix: self.suffix,
+            parameters: non_empty(self.parameters),
+            sampling: self.sampling,
+            parameters_by_env: None,
+            examples: non_empty(self.columns),
+            example_format: self.example_format,
+            output: None,
+            policy: None,
+            post_process: None,
+        })
+    }
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct ChatBuilder {
+    meta: PromptMeta,
+    vendor: Option<String>,
+    model: Option<String>,
+    context: Option<String>,
+    parameters: Vec<Parameter>,
+    sampling: Sampling,
+    examples: Vec<ChatExample>,
+    messages: Vec<Message>,
+    tools: Vec<Tool>,
+}
+
+impl ChatBuilder {
+    pub fn new() -> ChatBuilder {
+        ChatBuilder::default()
+    }
+
+    pub fn meta(mut self, meta: PromptMeta) -> Self {
+        self.meta = meta;
+        self
+    }
+
+    pub fn vendor(mut self, vendor: impl Into<String>) -> Self {
+        self.vendor = Some(vendor.into());
+        self
+    }
+
+    pub fn model(mut self, model: impl Into<String>) -> Self {
+        self.model = Some(model.into());
+        self
+    }
+
+    pub fn context(mut self, context: impl Into<String>) -> Self {
+        self.context = Some(context.into());
+        self
+    }
+
+    pub fn parameter(mut self, name: impl Into<String>, value: impl Into<Value>) -> Self {
+        self.parameters.push(Parameter {
+            name: name.into(),
+            value: value.into(),
+        });
+        self
+    }
+
+    pub fn sampling(mut self, sampling: Sampling) -> Self {
+        self.sampling = sampling;
+        self
+    }
+
+    pub fn example(mut self, input: impl Into<String>, output: impl Into<String>) -> Self {
+        self.examples.push(ChatExample {
+            input: input.into(),
+            output: Some(output.into()),
+        });
+        self
+    }
+
+    pub fn message(mut self, input: impl Into<String>) -> Self {
+        self.messages.push(Message {
+            input: input.into(),
+            content: None,
+            tool_calls: None,
+            tool_results: None,
+            output: None,
+        });
+        self
+    }
+
+    pub fn exchange(mut self, input: impl Into<String>, output: impl Into<String>) -> Self {
+        self.messages.push(Message {
+            input: input.into(),
+            content: None,
+            tool_calls: None,
+            tool_results: None,
+            output: Some(output.into()),
+        });
+        self
+    }
+
+    pub fn tool(mut self, tool: Tool) -> Self {
+        self.tools.push(tool);
+        self
+    }
+
+    pub fn build(self) -> Result<Chat, BuildError> {
+        check_parameters(&self.parameters)?;
+        Ok(Chat {
+            meta: self.meta,
+            prompt_type: "chat".to_string(),
+            vendor: required(self.vendor, "vendor")?,
+            model: required(self.model, "model")?,
+            parameters: non_empty(self.parameters),
+            sampling: self.sampling,
+            parameters_by_env: None,
+            examples: non_empty(self.examples),
+            context: self.context,
+            messages: non_empty(self.messages),
+            tools: non_empty(self.tools),
+            tool_choice: None,
+            output: None,
+            policy: None,
+            post_process: None,
+        })
+    }
+}
+
+impl Completion {
+    pub fn builder() -> CompletionBuilder {
+        CompletionBuilder::new()
+    }
+}
+
+impl Chat {
+    pub fn builder() -> ChatBuilder {
+        ChatBuilder::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::prompt::{deserialize_prompt, Prompt};
+
+    #[test]
+    fn test_completion_builder_matches_yaml() {
+        let built = Completion::builder()
+            .vendor("google")
+            .model("text-bison")
+            .prompt("Write a hello world in java")
+            .parameter("maxOutputTokens", 256)
+            .parameter("temperature", 0.4)
+            .example(&[("input", "a"), ("output", "x")])
+            .example(&[("input", "b"), ("output", "y")])
+            .test("input", "c")
+            .build()
+            .unwrap();
+
+        let yaml = r#"
+            type: completion
+            vendor: google
+            model: text-bison
+            prompt: Write a hello world in java
+            parameters:
+                - name: maxOutputTokens
+                  value: 256
+                - name: temperature
+                  value: 0.4
+            examples:
+                - name: input
+                  values: [a, b]
+                  test: c
+                - name: output
+                  values: [x, y]
+        "#;
+        assert_eq!(Prompt::Completion(built), deserialize_prompt(yaml));
+    }
+
+    #[test]
+    fn test_chat_builder() {
+        let chat = Chat::builder()
+            .vendor("google")
+            .model("chat-bison")
+            .context("Be nice")
+            .parameter("temperature", 0.2)
+            .example("who are u?", "I'm google")
+            .exchange("hi", "hello")
+            .message("what's your name?")
+            .build()
+            .unwrap();
+
+        assert_eq!(chat.prompt_type, "chat");
+        assert_eq!(chat.context, Some("Be nice".to_string()));
+        assert_eq!(chat.find_parameter_as_f32("temperature"), Some(0.2));
+        assert_eq!(chat.examples.unwrap().len(), 1);
+        let messages = chat.messages.unwrap();
+        assert_eq!(messages.len(), 2);
+        assert_eq!(messages[1].output, None);
+    }
+
+    #[test]
+    fn test_build_validation() {
+        assert_eq!(
+            Completion::builder().vendor("google").prompt("hi").build(),
+            Err(BuildError::MissingField("model"))
+        );
+        assert_eq!(
+            Chat::builder()
+                .vendor("google")
+                .model("chat-bison")
+                .parameter("temperature", 0.1)
+                .parameter("temperature", 0.2)
+                .build(),
+            Err(BuildError::DuplicateParameter("temperature".to_string()))
+        );
+    }
+}
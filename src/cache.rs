@@ -0,0 +1,396 @@
+use crate::exec::{BoxFuture, ExecError, ExecutionResult, PromptExecutor};
+use crate::observe::{PromptInfo, PromptObserver};
+use crate::prompt::Prompt;
+use crate::sha256::hex_digest;
+use crate::template::RenderError;
+#[cfg(feature = "fs")]
+use serde_json::{json, Value};
+use std::collections::{BTreeMap, HashMap};
+use std::fmt;
+#[cfg(feature = "fs")]
+use std::fs;
+#[cfg(feature = "fs")]
+use std::path::Path;
+use std::path::PathBuf;
+use std::sync::{Arc, Mutex};
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum CacheError {
+    Io { path: PathBuf, message: String },
+}
+
+impl fmt::Display for CacheError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            CacheError::Io { path, message } => write!(f, "{}: {}", path.display(), message),
+        }
+    }
+}
+
+impl std::error::Error for CacheError {}
+
+pub trait ResponseCache: Send + Sync {
+    fn get(&self, key: &str) -> Option<ExecutionResult>;
+
+    fn put(&self, key: &str, result: &ExecutionResult) -> Result<(), CacheError>;
+}
+
+impl Prompt {
+    pub fn cache_key(&self, vars: &HashMap<String, String>) -> Result<String, RenderError> {
+        let rendered = self.rendered_fingerprint(vars)?;
+        Ok(hex_digest(
+            format!("{}:{}", self.fingerprint(), rendered).as_bytes(),
+        ))
+    }
+
+    pub fn is_deterministic(&self) -> bool {
+        match self {
+            Prompt::Completion(c) => c.find_parameter_as_f32("temperature") == Some(0.0),
+            Prompt::Chat(c) => c.find_parameter_as_f32("temperature") == Some(0.0),
+            Prompt::Embedding(_) => true,
+            Prompt::Custom(_) | Prompt::Unknown => false,
+        }
+    }
+
+    pub async fn execute_cached(
+        &self,
+        executor: &(impl PromptExecutor + ?Sized),
+        cache: &(impl ResponseCache + ?Sized),
+        vars: &HashMap<String, String>,
+        on_error: impl FnOnce(CacheError),
+    ) -> Result<ExecutionResult, ExecError> {
+        let key = self.cache_key(vars)?;
+        if let Some(result) = cache.get(&key) {
+            return Ok(result);
+        }
+        let result = executor.execute(self, vars).await?;
+        if let Err(error) = cache.put(&key, &result) {
+            on_error(error);
+        }
+        Ok(result)
+    }
+}
+
+#[derive(Debug)]
+struct Slot {
+    result: ExecutionResult,
+    used: u64,
+}
+
+#[derive(Debug, Default)]
+struct Entries {
+    slots: HashMap<String, Slot>,
+    order: BTreeMap<u64, String>,
+    clock: u64,
+}
+
+#[derive(Debug)]
+pub struct MemoryCache {
+    capacity: usize,
+    entries: Mutex<Entries>,
+}
+
+impl MemoryCache {
+    pub fn new(capacity: usize) -> MemoryCache {
+        MemoryCache {
+            capacity,
+            entries: Mutex::new(Entries::default()),
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.entries
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .slots
+            .len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+}
+
+impl ResponseCache for MemoryCache {
+    fn get(&self, key: &str) -> Option<ExecutionResult> {
+        let mut entries = self.entries.lock().unwrap_or_else(|e| e.into_inner());
+        let Entries {
+            slots,
+            order,
+            clock,
+        } = &mut *entries;
+        let slot = slots.get_mut(key)?;
+        order.remove(&slot.used);
+        *clock += 1;
+        slot.used = *clock;
+        order.insert(*clock, key.to_string());
+        Some(slot.result.clone())
+    }
+
+    fn put(&self, key: &str, result: &ExecutionResult) -> Result<(), CacheError> {
+        if self.capacity == 0 {
+            return Ok(());
+        }
+        let mut entries = self.entries.lock().unwrap_or_else(|e| e.into_inner());
+        let Entries {
+            slots,
+            order,
+            clock,
+        } = &mut *entries;
+        *clock += 1;
+        match slots.get(key) {
+            Some(slot) => {
+                order.remove(&slot.used);
+            }
+            None if slots.len() >= self.capacity => {
+                if let Some((_, oldest)) = order.pop_first() {
+                    slots.remove(&oldest);
+                }
+            }
+            None => {}
+        }
+        order.insert(*clock, key.to_string());
+        slots.insert(
+            key.to_string(),
+            Slot {
+                result: result.clone(),
+                used: *clock,
+            },
+        );
+        Ok(())
+    }
+}
+
+#[cfg(feature = "fs")]
+#[derive(Debug, Clone)]
+pub struct FileCache {
+    dir: PathBuf,
+}
+
+#[cfg(feature = "fs")]
+impl FileCache {
+    pub fn new(dir: impl AsRef<Path>) -> FileCache {
+        FileCache {
+            dir: dir.as_ref().to_path_buf(),
+        }
+    }
+
+    pub fn dir(&self) -> &Path {
+        &self.dir
+    }
+
+    fn path(&self, key: &str) -> PathBuf {
+        self.dir.join(format!("{}.json", key))
+    }
+}
+
+#[cfg(feature = "fs")]
+impl ResponseCache for FileCache {
+    fn get(&self, key: &str) -> Option<ExecutionResult> {
+        let entry: Value = serde_json::from_str(&fs::read_to_string(self.path(key)).ok()?).ok()?;
+        Some(ExecutionResult {
+            text: entry.get("text")?.as_str()?.to_string(),
+            raw: entry.get("raw")?.clone(),
+        })
+    }
+
+    fn put(&self, key: &str, result: &ExecutionResult) -> Result<(), CacheError> {
+        let path = self.path(key);
+        let io_error = |path: &Path, error: std::io::Error| CacheError::Io {
+            path: path.to_path_buf(),
+            message: error.to_string(),
+        };
+        fs::create_dir_all(&self.dir).map_err(|e| io_error(&self.dir, e))?;
+        let entry = json!({ "text": result.text, "raw": result.raw });
+        let partial = path.with_extension("json.tmp");
+        fs::write(&partial, entry.to_string()).map_err(|e| io_error(&partial, e))?;
+        fs::rename(&partial, &path).map_err(|e| io_error(&path, e))
+    }
+}
+
+pub struct CachedExecutor<E, C> {
+    inner: E,
+    cache: C,
+    deterministic_only: bool,
+    observers: Vec<Arc<dyn PromptObserver>>,
+}
+
+impl<E, C> CachedExecutor<E, C> {
+    pub fn new(inner: E, cache: C) -> CachedExecutor<E, C> {
+        CachedExecutor {
+            inner,
+            cache,
+            deterministic_only: true,
+            observers: Vec::new(),
+        }
+    }
+
+    pub fn observer(mut self, observer: impl PromptObserver + 'static) -> Self {
+        self.observers.push(Arc::new(observer));
+        self
+    }
+
+    pub fn deterministic_only(mut self, deterministic_only: bool) -> Self {
+        self.deterministic_only = deterministic_only;
+        self
+    }
+
+    pub fn cache(&self) -> &C {
+        &self.cache
+    }
+}
+
+impl<E: PromptExecutor, C: ResponseCache> PromptExecutor for CachedExecutor<E, C> {
+    fn execute<'a>(
+        &'a self,
+        prompt: &'a Prompt,
+        vars: &'a HashMap<String, String>,
+    ) -> BoxFuture<'a, Result<ExecutionResult, ExecError>> {
+        Box::pin(async move {
+            if self.deterministic_only && !prompt.is_deterministic() {
+                return self.inner.execute(prompt, vars).await;
+            }
+            prompt
+                .execute_cached(&self.inner, &self.cache, vars, |error| {
+                    let info = PromptInfo::new(prompt);
+                    for observer in &self.observers {
+                        observer.on_cache_error(&info, &error);
+                    }
+                })
+                .await
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::exec::tests::MockClient;
+    use crate::exec::{block_on, OpenAiExecutor};
+    use crate::prompt::deserialize_prompt;
+    #[cfg(feature = "fs")]
+    use crate::registry::tests::temp_dir;
+    use serde_json::json;
+
+    fn result(text: &str) -> ExecutionResult {
+        ExecutionResult {
+            text: text.to_string(),
+            raw: json!({ "text": text }),
+        }
+    }
+
+    #[test]
+    fn test_memory_and_file_caches() {
+        let memory = MemoryCache::new(2);
+        memory.put("a", &result("1")).unwrap();
+        memory.put("b", &result("2")).unwrap();
+        assert_eq!(memory.get("a"), Some(result("1")));
+        memory.put("c", &result("3")).unwrap();
+        assert_eq!(memory.get("b"), None);
+        assert_eq!(memory.get("a"), Some(result("1")));
+        assert_eq!(memory.len(), 2);
+
+        #[cfg(feature = "fs")]
+        {
+            let dir = temp_dir("file_cache");
+            let files = FileCache::new(dir.join("responses"));
+            assert_eq!(files.get("k"), None);
+            files.put("k", &result("stored")).unwrap();
+            assert_eq!(FileCache::new(files.dir()).get("k"), Some(result("stored")));
+            fs::remove_dir_all(&dir).unwrap();
+        }
+    }
+
+    #[test]
+    fn test_cached_execution() {
+        let prompt = |temperature: f32| {
+            deserialize_prompt(&format!(
+                "type: completion\nvendor: openai\nmodel: gpt\nprompt: Hi {{{{name}}}}\nparameters:\n  - name: temperature\n    value: {}\n",
+                temperature
+            ))
+        };
+        let reply = json!({ "choices": [{ "text": "Hello" }] });
+        let client = MockClient::replying(&[
+            (200, reply.clone()),
+            (200, reply.clone()),
+            (200, reply.clone()),
+            (200, reply),
+        ]);
+        let executor = CachedExecutor::new(
+            OpenAiExecutor::new(client.clone(), "key"),
+            MemoryCache::new(8),
+        );
+        let ada = HashMap::from([("name".to_string(), "Ada".to_string())]);
+        let bob = HashMap::from([("name".to_string(), "Bob".to_string())]);
+
+        let zero = prompt(0.0);
+        assert!(zero.is_deterministic());
+        for _ in 0..3 {
+            assert_eq!(
+                block_on(zero.execute(&executor, &ada)).unwrap().text,
+                "Hello"
+            );
+        }
+        block_on(zero.execute(&executor, &bob)).unwrap();
+        assert_eq!(client.requests.lock().unwrap().len(), 2);
+
+        let warm = prompt(0.7);
+        assert_ne!(warm.cache_key(&ada), zero.cache_key(&ada));
+        block_on(warm.execute(&executor, &ada)).unwrap();
+        block_on(warm.execute(&executor, &ada)).unwrap();
+        assert_eq!(client.requests.lock().unwrap().len(), 4);
+        assert_eq!(executor.cache().len(), 2);
+    }
+
+    struct ReadOnly;
+
+    impl ResponseCache for ReadOnly {
+        fn get(&self, _key: &str) -> Option<ExecutionResult> {
+            None
+        }
+
+        fn put(&self, _key: &str, _result: &ExecutionResult) -> Result<(), CacheError> {
+            Err(CacheError::Io {
+                path: PathBuf::from("cache/k.json"),
+                message: "read-only file system".to_string(),
+            })
+        }
+    }
+
+    #[derive(Default)]
+    struct Errors(Mutex<Vec<String>>);
+
+    impl PromptObserver for Errors {
+        fn on_cache_error(&self, info: &PromptInfo, error: &CacheError) {
+            let message = format!("{}: {}", info.model.as_deref().unwrap_or(""), error);
+            self.0.lock().unwrap().push(message);
+        }
+    }
+
+    #[test]
+    fn test_cache_errors_reach_observers() {
+        let reply = json!({ "choices": [{ "text": "Hello" }] });
+        let client = MockClient::replying(&[(200, reply)]);
+        let errors = Arc::new(Errors::default());
+        let executor = CachedExecutor::new(OpenAiExecutor::new(client, "key"), ReadOnly)
+            .deterministic_only(false)
+            .observer(errors.clone());
+        let prompt =
+            deserialize_prompt("type: completion\nvendor: openai\nmodel: gpt\nprompt: Hi\n");
+        let result = block_on(prompt.execute(&executor, &HashMap::new())).unwrap();
+        assert_eq!(result.text, "Hello");
+        assert_eq!(
+            *errors.0.lock().unwrap(),
+            vec!["gpt: cache/k.json: read-only file system"]
+        );
+
+        let memory = MemoryCache::new(2);
+        memory.put("a", &result).unwrap();
+        memory.put("b", &result).unwrap();
+        memory.put("a", &result).unwrap();
+        memory.put("c", &result).unwrap();
+        assert!(memory.get("a").is_some());
+        assert_eq!(memory.get("b"), None);
+        assert_eq!(memory.len(), 2);
+    }
+}
@@ -0,0 +1,544 @@
+use crate::dataset::{DatasetError, DatasetSpec};
+use crate::format::{json_location, prompt_from_value, yaml_to_json_with, Format};
+use crate::locale::localize;
+use crate::markdown;
+use crate::prompt::{error_location, Location, Prompt, PromptError};
+use crate::registry::RegistryError;
+use crate::strict::{check_fields, located_fields, ParseOptions, UnknownField};
+use crate::toml;
+use serde_json::Value;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+const EXTENDS: &str = "extends";
+const INCLUDES: &str = "includes";
+const ABSTRACT: &str = "abstract";
+const EXAMPLES: &str = "examples";
+const SOURCE: &str = "source";
+
+pub fn load_file(path: impl AsRef<Path>) -> Result<Prompt, RegistryError> {
+    load_file_with(path, ParseOptions::default())
+}
+
+pub fn load_file_with(
+    path: impl AsRef<Path>,
+    options: ParseOptions,
+) -> Result<Prompt, RegistryError> {
+    let path = path.as_ref();
+    let source = read(path)?;
+    load_source(
+        path,
+        &source,
+        Format::from_path(path).unwrap_or(Format::Yaml),
+        options,
+    )
+}
+
+pub(crate) fn load_source(
+    path: &Path,
+    source: &str,
+    format: Format,
+    options: ParseOptions,
+) -> Result<Prompt, RegistryError> {
+    let parse_error = |error: PromptError| RegistryError::Parse {
+        path: path.to_path_buf(),
+        error,
+    };
+    if !has_references(source, format) {
+        return Prompt::parse_with(source, format, options).map_err(parse_error);
+    }
+    let mut resolver = Resolver::default();
+    let document = resolver.resolve(path, source, format)?;
+    let unknown = resolver.unknown_fields(&document);
+    prompt_from_value(document)
+        .and_then(|prompt| check_fields(prompt, unknown, options))
+        .map_err(parse_error)
+}
+
+pub(crate) fn load_localized(
+    path: &Path,
+    source: &str,
+    format: Format,
+    chain: &[String],
+) -> Result<Prompt, RegistryError> {
+    let mut resolver = Resolver::default();
+    let mut document = if has_references(source, format) {
+        resolver.resolve(path, source, format)?
+    } else {
+        parse(path, source, format)?
+    };
+    localize(&mut document, chain);
+    let unknown = if resolver.files.is_empty() {
+        located_fields(&document, source)
+    } else {
+        resolver.unknown_fields(&document)
+    };
+    prompt_from_value(document)
+        .and_then(|prompt| check_fields(prompt, unknown, ParseOptions::default()))
+        .map_err(|error| RegistryError::Parse {
+            path: path.to_path_buf(),
+            error,
+        })
+}
+
+pub(crate) fn has_references(source: &str, format: Format) -> bool {
+    if ![EXTENDS, INCLUDES, SOURCE, "!include"]
+        .iter()
+        .any(|word| source.contains(word))
+    {
+        return false;
+    }
+    match format {
+        Format::Yaml => serde_yaml::from_str::<serde_yaml::Value>(source).is_ok_and(|document| {
+            document.get(EXTENDS).is_some()
+                || document.get(INCLUDES).is_some()
+                || document
+                    .get(EXAMPLES)
+                    .is_some_and(|e| e.get(SOURCE).is_some())
+                || has_include(&document)
+        }),
+        Format::Json => {
+            serde_json::from_str::<Value>(source).is_ok_and(|document| references(&document))
+        }
+        Format::Toml => toml::parse(source).is_ok_and(|document| references(&document)),
+        Format::Markdown => markdown::split(source)
+            .is_ok_and(|(frontmatter, _)| has_references(frontmatter, Format::Yaml)),
+    }
+}
+
+fn references(document: &Value) -> bool {
+    document.get(EXTENDS).is_some()
+        || document.get(INCLUDES).is_some()
+        || document
+            .get(EXAMPLES)
+            .is_some_and(|e| e.get(SOURCE).is_some())
+}
+
+fn has_include(value: &serde_yaml::Value) -> bool {
+    match value {
+        serde_yaml::Value::Tagged(tagged) => tagged.tag == "include" || has_include(&tagged.value),
+        serde_yaml::Value::Sequence(items) => items.iter().any(has_include),
+        serde_yaml::Value::Mapping(mapping) => mapping.values().any(has_include),
+        _ => false,
+    }
+}
+
+fn read(path: &Path) -> Result<String, RegistryError> {
+    fs::read_to_string(path).map_err(|e| RegistryError::Io {
+        path: path.to_path_buf(),
+        message: e.to_string(),
+    })
+}
+
+fn invalid(path: &Path, message: impl Into<String>) -> RegistryError {
+    RegistryError::InvalidReference {
+        path: path.to_path_buf(),
+        message: message.into(),
+    }
+}
+
+fn dataset_error(path: &Path, error: DatasetError) -> RegistryError {
+    match error {
+        DatasetError::Io { path, message } => RegistryError::Io { path, message },
+        other => invalid(path, other.to_string()),
+    }
+}
+
+fn load_examples(path: &Path, dir: &Path, document: &mut Value) -> Result<(), RegistryError> {
+    let Some(object) = document.as_object_mut() else {
+        return Ok(());
+    };
+    let Some(spec) = object.get(EXAMPLES).filter(|e| e.get(SOURCE).is_some()) else {
+        return Ok(());
+    };
+    let spec: DatasetSpec = serde_json::from_value(spec.clone())
+        .map_err(|e| invalid(path, format!("invalid examples source: {}", e)))?;
+    let examples = match object.get("type").and_then(|t| t.as_str()) {
+        Some("chat") => spec
+            .load_chat_examples(dir)
+            .map(|examples| serde_json::to_value(examples).unwrap_or_default()),
+        _ => spec
+            .load_columns(dir)
+            .map(|columns| serde_json::to_value(columns).unwrap_or_default()),
+    }
+    .map_err(|e| dataset_error(path, e))?;
+    object.insert(EXAMPLES.to_string(), examples);
+    Ok(())
+}
+
+fn merge(base: Value, child: Value) -> Value {
+    match (base, child) {
+        (Value::Object(mut base), Value::Object(child)) => {
+            for (key, value) in child {
+                let merged = match (key.as_str(), base.remove(&key)) {
+                    ("parameters", Some(Value::Array(inherited))) => match value {
+                        Value::Array(overrides) => merge_parameters(inherited, overrides),
+                        other => other,
+                    },
+                    (_, Some(existing)) => merge(existing, value),
+                    (_, None) => value,
+                };
+                base.insert(key, merged);
+            }
+            Value::Object(base)
+        }
+        (_, child) => child,
+    }
+}
+
+fn merge_parameters(mut inherited: Vec<Value>, overrides: Vec<Value>) -> Value {
+    for parameter in overrides {
+        let name = parameter.get("name").cloned();
+        match inherited
+            .iter_mut()
+            .find(|p| p.get("name") == name.as_ref())
+        {
+            Some(existing) => *existing = parameter,
+            None => inherited.push(parameter),
+        }
+    }
+    Value::Array(inherited)
+}
+
+#[derive(Default)]
+struct Resolver {
+    stack: Vec<PathBuf>,
+    files: Vec<(PathBuf, String, Value)>,
+}
+
+impl Resolver {
+    fn unknown_fields(&self, merged: &Value) -> Vec<UnknownField> {
+        let mut unknown: Vec<UnknownField> = Vec::new();
+        for (i, (path, source, document)) in self.files.iter().enumerate() {
+            let mut document = document.clone();
+            if let (Some(object), Some(kind)) = (document.as_object_mut(), merged.get("type")) {
+                object.entry("type").or_insert_with(|| kind.clone());
+            }
+            for mut field in located_fields(&document, source) {
+                if unknown.iter().all(|f| f.path != field.path) {
+                    field.file = (i > 0).then(|| path.clone());
+                    unknown.push(field);
+                }
+            }
+        }
+        unknown
+    }
+
+    fn resolve(
+        &mut self,
+        path: &Path,
+        source: &str,
+        format: Format,
+    ) -> Result<Value, RegistryError> {
+        let key = fs::canonicalize(path).unwrap_or_else(|_| path.to_path_buf());
+        if let Some(start) = self.stack.iter().position(|p| *p == key) {
+            let mut cycle = self.stack[start..].to_vec();
+            cycle.push(key);
+            return Err(RegistryError::Cycle(cycle));
+        }
+        self.stack.push(key);
+        let result = self.resolve_document(path, source, format);
+        self.stack.pop();
+        result
+    }
+
+    fn resolve_document(
+        &mut self,
+        path: &Path,
+        source: &str,
+        format: Format,
+    ) -> Result<Value, RegistryError> {
+        let dir = path.parent().unwrap_or(Path::new(""));
+        let mut document = parse(path, source, format)?;
+        self.files
+            .push((path.to_path_buf(), source.to_string(), document.clone()));
+        let Some(object) = document.as_object_mut() else {
+            return Ok(document);
+        };
+        object.remove(ABSTRACT);
+
+        let includes = match object.remove(INCLUDES) {
+            None => Vec::new(),
+            Some(Value::Array(items)) => items
+                .iter()
+                .map(|item| match item.as_str() {
+                    Some(relative) => Ok(read(&dir.join(relative))?.trim_end().to_string()),
+                    None => Err(invalid(path, "includes must be a list of paths")),
+                })
+                .collect::<Result<Vec<_>, _>>()?,
+            Some(_) => return Err(invalid(path, "includes must be a list of paths")),
+        };
+        let mut document = match object.remove(EXTENDS) {
+            None => document,
+            Some(Value::String(relative)) => {
+                let base_path = dir.join(relative);
+                let base_source = read(&base_path)?;
+                let base_format = Format::from_path(&base_path).unwrap_or(Format::Yaml);
+                let mut base = self.resolve(&base_path, &base_source, base_format)?;
+                if let Some(base) = base.as_object_mut() {
+                    base.remove("name");
+                }
+                merge(base, document)
+            }
+            Some(_) => return Err(invalid(path, "extends must be a path")),
+        };
+        load_examples(path, dir, &mut document)?;
+
+        if let Some(object) = document.as_object_mut().filter(|_| !includes.is_empty()) {
+            let field = match object.get("type").and_then(|t| t.as_str()) {
+                Some("chat") => "context",
+                _ => "prompt",
+            };
+            let mut text = includes.join("\n\n");
+            if let Some(own) = object.get(field).and_then(|v| v.as_str()) {
+                text.push_str("\n\n");
+                text.push_str(own);
+            }
+            object.insert(field.to_string(), Value::String(text));
+        }
+
+        Ok(document)
+    }
+}
+
+fn parse(path: &Path, source: &str, format: Format) -> Result<Value, RegistryError> {
+    let parse_error = |error: PromptError| RegistryError::Parse {
+        path: path.to_path_buf(),
+        error,
+    };
+    match format {
+        Format::Yaml => {
+            let document: serde_yaml::Value = serde_yaml::from_str(source).map_err(|e| {
+                parse_error(PromptError::InvalidYaml {
+                    message: e.to_string(),
+                    location: error_location(&e),
+                })
+            })?;
+            yaml_to_json(path, document)
+        }
+        Format::Json => serde_json::from_str(source).map_err(|e| {
+            parse_error(PromptError::InvalidJson {
+                message: e.to_string(),
+                location: json_location(&e),
+            })
+        }),
+        Format::Toml => toml::parse(source).map_err(|e| {
+            parse_error(PromptError::InvalidToml {
+                message: e.message,
+                location: Some(Location {
+                    line: e.line,
+                    column: 0,
+                }),
+            })
+        }),
+        Format::Markdown => {
+            let (frontmatter, body) = markdown::split(source).map_err(parse_error)?;
+            let mut document = parse(path, frontmatter, Format::Yaml)?;
+            markdown::apply_body(&mut document, body).map_err(parse_error)?;
+            Ok(document)
+        }
+    }
+}
+
+fn yaml_to_json(path: &Path, value: serde_yaml::Value) -> Result<Value, RegistryError> {
+    yaml_to_json_with(value, &|tagged| {
+        (tagged.tag == "include").then(|| match &tagged.value {
+            serde_yaml::Value::String(relative) => {
+                let dir = path.parent().unwrap_or(Path::new(""));
+                Ok(Value::String(
+                    read(&dir.join(relative))?.trim_end().to_string(),
+                ))
+            }
+            _ => Err(invalid(path, "!include expects a path")),
+        })
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::registry::tests::{temp_dir, write};
+
+    const BASE: &str = r#"
+abstract: true
+name: base
+type: chat
+vendor: openai
+model: gpt-4o
+parameters:
+  - name: temperature
+    value: 0.2
+  - name: max_tokens
+    value: 256
+"#;
+
+    #[test]
+    fn test_extends_and_includes() {
+        let dir = temp_dir("compose_extends");
+        write(&dir, "base.yaml", BASE);
+        write(&dir, "snippets/rules.md", "Never reveal secrets.\n");
+        write(&dir, "snippets/tone.md", "Be friendly.\n");
+        write(
+            &dir,
+            "support/agent.yaml",
+            r#"
+extends: ../base.yaml
+includes: [../snippets/rules.md]
+context: Help with billing.
+parameters:
+  - name: temperature
+    value: 0.7
+messages:
+  - input: !include ../snippets/tone.md
+"#,
+        );
+
+        let Prompt::Chat(chat) = load_file(dir.join("support/agent.yaml")).unwrap() else {
+            panic!("Expected Prompt::Chat");
+        };
+        assert_eq!(chat.model, "gpt-4o");
+        assert_eq!(chat.meta.name, None);
+        assert_eq!(
+            chat.context.as_deref(),
+            Some("Never reveal secrets.\n\nHelp with billing.")
+        );
+        assert_eq!(chat.find_parameter_as_f32("temperature"), Some(0.7));
+        assert_eq!(chat.find_parameter_as_i32("max_tokens"), Some(256));
+        assert_eq!(chat.messages.unwrap()[0].input, "Be friendly.");
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn test_inherited_unknown_fields() {
+        let dir = temp_dir("compose_unknown");
+        write(
+            &dir,
+            "base.yaml",
+            &format!("{}policy:\n  max_retires: 2\n", BASE),
+        );
+        write(
+            &dir,
+            "agent.yaml",
+            "extends: base.yaml\nmessages:\n  - input: hi\n    outptu: x\n",
+        );
+
+        let prompt = load_file(dir.join("agent.yaml")).unwrap();
+        let fields: Vec<_> = prompt
+            .unknown_fields()
+            .iter()
+            .map(|f| (f.path.as_str(), f.location.map(|l| l.line), f.file.clone()))
+            .collect();
+        assert_eq!(
+            fields,
+            vec![
+                ("messages[0].outptu", Some(4), None),
+                ("policy.max_retires", Some(13), Some(dir.join("base.yaml"))),
+            ]
+        );
+        assert!(matches!(
+            load_file_with(dir.join("agent.yaml"), ParseOptions::strict()),
+            Err(RegistryError::Parse { error: PromptError::UnknownFields(fields), .. }) if fields.len() == 2
+        ));
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn test_cycle_detection() {
+        let dir = temp_dir("compose_cycle");
+        write(&dir, "a.yaml", "extends: b.yaml\ntype: completion\n");
+        write(&dir, "b.yaml", "extends: a.yaml\nprompt: hi\n");
+
+        match load_file(dir.join("a.yaml")) {
+            Err(RegistryError::Cycle(paths)) => assert_eq!(paths.len(), 3),
+            other => panic!("Expected a cycle, got {:?}", other),
+        }
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn test_dataset_examples() {
+        let dir = temp_dir("compose_dataset");
+        write(&dir, "base.yaml", BASE);
+        write(
+            &dir,
+            "data/faq.jsonl",
+            "{\"question\": \"Refund?\", \"answer\": \"Within 30 days.\"}\n{\"question\": \"Hours?\", \"answer\": \"9 to 5.\"}\n",
+        );
+        write(
+            &dir,
+            "data/langs.csv",
+            "lang,greeting\nfr,bonjour\nde,hallo\nes,hola\n",
+        );
+        write(
+            &dir,
+            "support/faq.yaml",
+            "extends: ../base.yaml\nexamples:\n  source: ../data/faq.jsonl\n  input_field: question\n  output_field: answer\n  limit: 1\n",
+        );
+        write(
+            &dir,
+            "greet.yaml",
+            "type: completion\nvendor: google\nmodel: text-bison\nprompt: Greet in {{lang}}\nexamples: { source: data/langs.csv, fields: [lang, greeting] }\n",
+        );
+
+        let Prompt::Chat(chat) = load_file(dir.join("support/faq.yaml")).unwrap() else {
+            panic!("Expected Prompt::Chat");
+        };
+        let examples = chat.examples.unwrap();
+        assert_eq!(examples.len(), 1);
+        assert_eq!(examples[0].input, "Refund?");
+        assert_eq!(examples[0].output.as_deref(), Some("Within 30 days."));
+
+        let Prompt::Completion(completion) = load_file(dir.join("greet.yaml")).unwrap() else {
+            panic!("Expected Prompt::Completion");
+        };
+        assert_eq!(completion.example_count(), 3);
+        assert_eq!(completion.examples.unwrap()[1].values[2], "hola");
+
+        write(
+            &dir,
+            "broken.yaml",
+            "type: chat\nexamples: { source: data/missing.jsonl }\n",
+        );
+        assert!(matches!(
+            load_file(dir.join("broken.yaml")),
+            Err(RegistryError::Io { .. })
+        ));
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn test_reference_detection() {
+        let plain = "type: completion\nvendor: openai\nmodel: gpt-4o\nprompt: This extends the includes, cite the source.\n";
+        assert!(!has_references(plain, Format::Yaml));
+        assert!(!has_references(
+            r#"{"type": "completion", "prompt": "extends"}"#,
+            Format::Json
+        ));
+        assert!(has_references("extends: base.yaml\n", Format::Yaml));
+        assert!(has_references(
+            "examples: { source: data.csv }\n",
+            Format::Yaml
+        ));
+        assert!(has_references(
+            "messages:\n  - input: !include a.md\n",
+            Format::Yaml
+        ));
+        assert!(has_references(r#"{"includes": ["a.md"]}"#, Format::Json));
+
+        let dir = temp_dir("compose_detection");
+        write(&dir, "plain.yaml", plain);
+        assert!(load_file(dir.join("plain.yaml")).is_ok());
+        write(&dir, "base.yaml", "type: chat\nmodel: [gpt\n");
+        write(&dir, "child.yaml", "extends: base.yaml\ncontext: hi\n");
+        for file in ["base.yaml", "child.yaml"] {
+            match load_file(dir.join(file)) {
+                Err(RegistryError::Parse {
+                    error: PromptError::InvalidYaml { location, .. },
+                    ..
+                }) => assert!(location.is_some(), "{}", file),
+                other => panic!("Expected InvalidYaml, got {:?}", other),
+            }
+        }
+        fs::remove_dir_all(&dir).unwrap();
+    }
+}
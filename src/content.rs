@@ -0,0 +1,310 @@
+use crate::base64;
+use crate::prompt::Chat;
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+#[cfg(feature = "fs")]
+use std::fs;
+use std::io;
+#[cfg(feature = "fs")]
+use std::path::Path;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum MediaSource {
+    Url(String),
+    Path(String),
+    Base64 { media_type: String, data: String },
+}
+
+fn media_type_for(path: &str) -> &'static str {
+    let extension = path.rsplit('.').next().unwrap_or("").to_lowercase();
+    match extension.as_str() {
+        "png" => "image/png",
+        "jpg" | "jpeg" => "image/jpeg",
+        "gif" => "image/gif",
+        "webp" => "image/webp",
+        "pdf" => "application/pdf",
+        "txt" | "md" => "text/plain",
+        _ => "application/octet-stream",
+    }
+}
+
+impl MediaSource {
+    pub fn parse(reference: &str) -> MediaSource {
+        if let Some(rest) = reference.strip_prefix("data:") {
+            if let Some((media_type, data)) = rest.split_once(";base64,") {
+                return MediaSource::Base64 {
+                    media_type: media_type.to_string(),
+                    data: data.to_string(),
+                };
+            }
+        }
+        if reference.starts_with("http://") || reference.starts_with("https://") {
+            MediaSource::Url(reference.to_string())
+        } else {
+            MediaSource::Path(reference.to_string())
+        }
+    }
+
+    pub fn reference(&self) -> String {
+        match self {
+            MediaSource::Url(url) => url.clone(),
+            MediaSource::Path(path) => path.clone(),
+            MediaSource::Base64 { media_type, data } => {
+                format!("data:{};base64,{}", media_type, data)
+            }
+        }
+    }
+
+    #[cfg(feature = "fs")]
+    pub fn inline(&self, base_dir: &Path) -> io::Result<MediaSource> {
+        self.inline_with(&mut |path| fs::read(base_dir.join(path)))
+    }
+
+    pub fn inline_with(
+        &self,
+        load: &mut dyn FnMut(&str) -> io::Result<Vec<u8>>,
+    ) -> io::Result<MediaSource> {
+        match self {
+            MediaSource::Path(path) => Ok(MediaSource::Base64 {
+                media_type: media_type_for(path).to_string(),
+                data: base64::encode(&load(path)?),
+            }),
+            other => Ok(other.clone()),
+        }
+    }
+
+    fn to_anthropic(&self) -> Value {
+        match self {
+            MediaSource::Base64 { media_type, data } => {
+                json!({ "type": "base64", "media_type": media_type, "data": data })
+            }
+            other => json!({ "type": "url", "url": other.reference() }),
+        }
+    }
+
+    fn to_gemini(&self) -> Value {
+        match self {
+            MediaSource::Base64 { media_type, data } => {
+                json!({ "inlineData": { "mimeType": media_type, "data": data } })
+            }
+            MediaSource::Url(reference) | MediaSource::Path(reference) => json!({
+                "fileData": { "mimeType": media_type_for(reference), "fileUri": reference },
+            }),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(try_from = "RawPart", into = "RawPart")]
+pub enum ContentPart {
+    Text(String),
+    Image(MediaSource),
+    Document(MediaSource),
+}
+
+#[derive(Default, Serialize, Deserialize)]
+struct RawPart {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    text: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    image: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    document: Option<String>,
+}
+
+impl TryFrom<RawPart> for ContentPart {
+    type Error = String;
+
+    fn try_from(raw: RawPart) -> Result<ContentPart, String> {
+        match (raw.text, raw.image, raw.document) {
+            (Some(text), None, None) => Ok(ContentPart::Text(text)),
+            (None, Some(image), None) => Ok(ContentPart::Image(MediaSource::parse(&image))),
+            (None, None, Some(document)) => {
+                Ok(ContentPart::Document(MediaSource::parse(&document)))
+            }
+            _ => Err("content part needs exactly one of text, image or document".to_string()),
+        }
+    }
+}
+
+impl From<ContentPart> for RawPart {
+    fn from(part: ContentPart) -> RawPart {
+        match part {
+            ContentPart::Text(text) => RawPart {
+                text: Some(text),
+                ..RawPart::default()
+            },
+            ContentPart::Image(source) => RawPart {
+                image: Some(source.reference()),
+                ..RawPart::default()
+            },
+            ContentPart::Document(source) => RawPart {
+                document: Some(source.reference()),
+                ..RawPart::default()
+            },
+        }
+    }
+}
+
+impl ContentPart {
+    pub fn text(&self) -> Option<&str> {
+        match self {
+            ContentPart::Text(text) => Some(text),
+            _ => None,
+        }
+    }
+
+    pub(crate) fn to_openai(&self) -> Value {
+        match self {
+            ContentPart::Text(text) => json!({ "type": "text", "text": text }),
+            ContentPart::Image(source) => {
+                json!({ "type": "image_url", "image_url": { "url": source.reference() } })
+            }
+            ContentPart::Document(source @ MediaSource::Base64 { .. }) => {
+                json!({ "type": "file", "file": { "file_data": source.reference() } })
+            }
+            ContentPart::Document(source) => json!({ "type": "text", "text": source.reference() }),
+        }
+    }
+
+    pub(crate) fn to_anthropic(&self) -> Value {
+        match self {
+            ContentPart::Text(text) => json!({ "type": "text", "text": text }),
+            ContentPart::Image(source) => {
+                json!({ "type": "image", "source": source.to_anthropic() })
+            }
+            ContentPart::Document(source) => {
+                json!({ "type": "document", "source": source.to_anthropic() })
+            }
+        }
+    }
+
+    pub(crate) fn to_gemini(&self) -> Value {
+        match self {
+            ContentPart::Text(text) => json!({ "text": text }),
+            ContentPart::Image(source) | ContentPart::Document(source) => source.to_gemini(),
+        }
+    }
+}
+
+pub(crate) fn content_value(
+    text: &str,
+    parts: &[ContentPart],
+    map: fn(&ContentPart) -> Value,
+) -> Value {
+    if parts.is_empty() {
+        return json!(text);
+    }
+    let mut blocks = Vec::new();
+    if !text.is_empty() {
+        blocks.push(json!({ "type": "text", "text": text }));
+    }
+    blocks.extend(parts.iter().map(map));
+    Value::Array(blocks)
+}
+
+impl Chat {
+    #[cfg(feature = "fs")]
+    pub fn inline_media(&self, base_dir: impl AsRef<Path>) -> io::Result<Chat> {
+        let base_dir = base_dir.as_ref();
+        self.inline_media_with(|path| fs::read(base_dir.join(path)))
+    }
+
+    pub fn inline_media_with(
+        &self,
+        mut load: impl FnMut(&str) -> io::Result<Vec<u8>>,
+    ) -> io::Result<Chat> {
+        let mut chat = self.clone();
+        for part in chat
+            .messages
+            .iter_mut()
+            .flatten()
+            .flat_map(|m| m.content.iter_mut().flatten())
+        {
+            match part {
+                ContentPart::Image(source) | ContentPart::Document(source) => {
+                    *source = source.inline_with(&mut load)?;
+                }
+                ContentPart::Text(_) => {}
+            }
+        }
+        Ok(chat)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::prompt::{deserialize_prompt, Prompt};
+    #[cfg(feature = "fs")]
+    use crate::registry::tests::temp_dir;
+
+    const CHAT: &str = r#"
+        type: chat
+        vendor: anthropic
+        model: claude-3-5-sonnet
+        messages:
+            - content:
+                - text: describe this
+                - image: ./chart.png
+                - document: https://example.com/report.pdf
+    "#;
+
+    #[test]
+    fn test_multimodal_requests() {
+        let prompt = deserialize_prompt(CHAT);
+        let Prompt::Chat(chat) = &prompt else {
+            panic!("Expected Prompt::Chat");
+        };
+        assert_eq!(
+            chat.messages.as_ref().unwrap()[0].content.as_ref().unwrap()[1],
+            ContentPart::Image(MediaSource::Path("./chart.png".to_string()))
+        );
+        assert_eq!(deserialize_prompt(&prompt.to_yaml().unwrap()), prompt);
+
+        let inlined = chat
+            .inline_media_with(|path| match path {
+                "./chart.png" => Ok(b"png".to_vec()),
+                _ => Err(io::ErrorKind::NotFound.into()),
+            })
+            .unwrap();
+        #[cfg(feature = "fs")]
+        {
+            let dir = temp_dir("content_inline");
+            fs::write(dir.join("chart.png"), b"png").unwrap();
+            assert_eq!(chat.inline_media(&dir).unwrap(), inlined);
+            fs::remove_dir_all(&dir).unwrap();
+        }
+
+        let anthropic = inlined.to_anthropic_messages_request();
+        assert_eq!(
+            anthropic["messages"][0]["content"],
+            json!([
+                { "type": "text", "text": "describe this" },
+                {
+                    "type": "image",
+                    "source": { "type": "base64", "media_type": "image/png", "data": "cG5n" },
+                },
+                {
+                    "type": "document",
+                    "source": { "type": "url", "url": "https://example.com/report.pdf" },
+                },
+            ])
+        );
+        let openai = inlined.to_openai_chat_request();
+        assert_eq!(
+            openai["messages"][0]["content"][1],
+            json!({ "type": "image_url", "image_url": { "url": "data:image/png;base64,cG5n" } })
+        );
+        assert_eq!(
+            chat.to_vertex_request()["instances"][0]["messages"][0]["content"],
+            "describe this"
+        );
+    }
+
+    #[test]
+    fn test_invalid_part() {
+        let yaml = "type: chat\nvendor: openai\nmodel: gpt-4o\nmessages:\n  - content:\n      - { text: a, image: b.png }\n";
+        assert!(crate::prompt::try_deserialize_prompt(yaml).is_err());
+    }
+}
@@ -0,0 +1,251 @@
+use crate::prompt::{Chat, Message, Prompt};
+use crate::template::RenderError;
+use crate::tokens::Tokenizer;
+use std::collections::HashMap;
+
+pub trait TruncationPolicy: Send + Sync {
+    fn truncate(&self, chat: &mut Chat);
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct KeepAll;
+
+impl TruncationPolicy for KeepAll {
+    fn truncate(&self, _: &mut Chat) {}
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct SlidingWindow {
+    pub turns: usize,
+}
+
+impl TruncationPolicy for SlidingWindow {
+    fn truncate(&self, chat: &mut Chat) {
+        if let Some(messages) = &mut chat.messages {
+            let excess = messages.len().saturating_sub(self.turns);
+            messages.drain(..excess);
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct TokenBudget<T> {
+    pub max_tokens: usize,
+    pub tokenizer: T,
+}
+
+impl<T: Tokenizer + Send + Sync> TruncationPolicy for TokenBudget<T> {
+    fn truncate(&self, chat: &mut Chat) {
+        while chat.estimate_tokens(&self.tokenizer) > self.max_tokens {
+            match &mut chat.messages {
+                Some(messages) if messages.len() > 1 => {
+                    messages.remove(0);
+                }
+                _ => break,
+            }
+        }
+    }
+}
+
+// Replaces everything but the last `keep` turns with the hook's summary,
+// which is appended to the chat context.
+pub struct SummarizeOldest<F> {
+    pub keep: usize,
+    pub summarize: F,
+}
+
+impl<F> TruncationPolicy for SummarizeOldest<F>
+where
+    F: Fn(&[Message]) -> String + Send + Sync,
+{
+    fn truncate(&self, chat: &mut Chat) {
+        let Some(messages) = &mut chat.messages else {
+            return;
+        };
+        let excess = messages.len().saturating_sub(self.keep);
+        if excess == 0 {
+            return;
+        }
+        let summary = (self.summarize)(&messages[..excess]);
+        messages.drain(..excess);
+        chat.context = Some(match chat.context.take() {
+            Some(context) => format!("{}\n\n{}", context, summary),
+            None => summary,
+        });
+    }
+}
+
+pub struct Conversation {
+    template: Chat,
+    history: Vec<Message>,
+    policy: Box<dyn TruncationPolicy>,
+}
+
+impl Conversation {
+    pub fn new(chat: Chat) -> Conversation {
+        let mut template = chat;
+        let history = template.messages.take().unwrap_or_default();
+        Conversation {
+            template,
+            history,
+            policy: Box::new(KeepAll),
+        }
+    }
+
+    pub fn with_policy(mut self, policy: impl TruncationPolicy + 'static) -> Self {
+        self.policy = Box::new(policy);
+        self
+    }
+
+    pub fn push_user(&mut self, input: impl Into<String>) {
+        self.history.push(Message {
+            input: input.into(),
+            ..Message::default()
+        });
+    }
+
+    pub fn push_assistant(&mut self, output: impl Into<String>) {
+        match self.history.last_mut() {
+            Some(last) if last.output.is_none() => last.output = Some(output.into()),
+            _ => self.history.push(Message {
+                output: Some(output.into()),
+                ..Message::default()
+            }),
+        }
+    }
+
+    pub fn history(&self) -> &[Message] {
+        &self.history
+    }
+
+    pub fn len(&self) -> usize {
+        self.history.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.history.is_empty()
+    }
+
+    pub fn clear(&mut self) {
+        self.history.clear();
+    }
+
+    pub fn to_chat(&self) -> Chat {
+        let mut chat = self.template.clone();
+        chat.messages = Some(self.history.clone());
+        self.policy.truncate(&mut chat);
+        chat
+    }
+
+    pub fn render(&self, vars: &HashMap<String, String>) -> Result<Chat, RenderError> {
+        self.to_chat().render(vars)
+    }
+
+    pub fn to_prompt(&self) -> Prompt {
+        Prompt::Chat(self.to_chat())
+    }
+}
+
+#[cfg(feature = "exec")]
+impl Conversation {
+    pub async fn send(
+        &mut self,
+        input: impl Into<String>,
+        executor: &(impl crate::exec::PromptExecutor + ?Sized),
+        vars: &HashMap<String, String>,
+    ) -> Result<String, crate::exec::ExecError> {
+        self.push_user(input);
+        let result = executor.execute(&self.to_prompt(), vars).await?;
+        self.push_assistant(result.text.clone());
+        Ok(result.text)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::prompt::deserialize_prompt;
+    use crate::tokens::WhitespaceTokenizer;
+
+    fn sample() -> Conversation {
+        let chat = match deserialize_prompt(
+            "type: chat\nvendor: openai\nmodel: gpt-4o\ncontext: Be brief\nmessages:\n  - input: hi\n    output: hello\n",
+        ) {
+            Prompt::Chat(chat) => chat,
+            other => panic!("Expected Prompt::Chat, got {:?}", other),
+        };
+        let mut conversation = Conversation::new(chat);
+        conversation.push_user("one two");
+        conversation.push_assistant("three four");
+        conversation.push_user("five six");
+        conversation
+    }
+
+    fn inputs(chat: &Chat) -> Vec<&str> {
+        chat.messages
+            .iter()
+            .flatten()
+            .map(|m| m.input.as_str())
+            .collect()
+    }
+
+    #[test]
+    fn test_history_and_policies() {
+        let conversation = sample();
+        assert_eq!(conversation.len(), 3);
+        assert_eq!(
+            conversation.history()[1].output.as_deref(),
+            Some("three four")
+        );
+        assert_eq!(
+            inputs(&conversation.to_chat()),
+            vec!["hi", "one two", "five six"]
+        );
+
+        let window = sample().with_policy(SlidingWindow { turns: 2 });
+        assert_eq!(inputs(&window.to_chat()), vec!["one two", "five six"]);
+        assert_eq!(window.len(), 3);
+
+        let budget = sample().with_policy(TokenBudget {
+            max_tokens: 12,
+            tokenizer: WhitespaceTokenizer,
+        });
+        assert_eq!(inputs(&budget.to_chat()), vec!["five six"]);
+
+        let summarized = sample().with_policy(SummarizeOldest {
+            keep: 1,
+            summarize: |old: &[Message]| format!("Earlier: {} turns", old.len()),
+        });
+        let chat = summarized.to_chat();
+        assert_eq!(inputs(&chat), vec!["five six"]);
+        assert_eq!(
+            chat.context.as_deref(),
+            Some("Be brief\n\nEarlier: 2 turns")
+        );
+    }
+
+    #[cfg(feature = "exec")]
+    #[test]
+    fn test_send() {
+        use crate::exec::tests::MockClient;
+        use crate::exec::{block_on, OpenAiExecutor};
+        use serde_json::json;
+
+        let client = MockClient::replying(&[(
+            200,
+            json!({ "choices": [{ "message": { "role": "assistant", "content": "seven" } }] }),
+        )]);
+        let executor = OpenAiExecutor::new(client.clone(), "key");
+        let mut conversation = sample().with_policy(SlidingWindow { turns: 2 });
+        conversation.push_assistant("ok");
+
+        let reply = block_on(conversation.send("eight", &executor, &HashMap::new())).unwrap();
+        assert_eq!(reply, "seven");
+        assert_eq!(
+            conversation.history().last().unwrap().output.as_deref(),
+            Some("seven")
+        );
+        let body = &client.requests.lock().unwrap()[0].body;
+        assert_eq!(body["messages"].as_array().unwrap().len(), 4);
+    }
+}
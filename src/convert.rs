@@ -0,0 +1,245 @@
+use crate::content::{content_value, ContentPart};
+use crate::prompt::Chat;
+use crate::request::Vendor;
+use crate::transcript::{ChatMessage, Role, Transcript};
+use serde_json::{json, Value};
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct VendorMessages {
+    pub system: Option<Value>,
+    pub messages: Vec<Value>,
+}
+
+fn system_text(transcript: &Transcript) -> Option<String> {
+    let parts: Vec<&str> = transcript
+        .messages
+        .iter()
+        .filter(|m| m.role == Role::System && !m.content.is_empty())
+        .map(|m| m.content.as_str())
+        .collect();
+    if parts.is_empty() {
+        None
+    } else {
+        Some(parts.join("\n\n"))
+    }
+}
+
+fn push_turn(turns: &mut Vec<(&'static str, Vec<Value>)>, role: &'static str, blocks: Vec<Value>) {
+    if blocks.is_empty() {
+        return;
+    }
+    match turns.last_mut() {
+        Some((last, existing)) if *last == role => existing.extend(blocks),
+        _ => turns.push((role, blocks)),
+    }
+}
+
+fn openai_message(m: &ChatMessage) -> Value {
+    let content = content_value(&m.content, &m.parts, ContentPart::to_openai);
+    let mut message = json!({ "role": m.role.as_str(), "content": content });
+    if !m.tool_calls.is_empty() {
+        let calls: Vec<Value> = m
+            .tool_calls
+            .iter()
+            .map(|call| {
+                json!({
+                    "id": call.id,
+                    "type": "function",
+                    "function": {
+                        "name": call.name,
+                        "arguments": call.arguments.to_string(),
+                    },
+                })
+            })
+            .collect();
+        message["tool_calls"] = Value::Array(calls);
+        if m.content.is_empty() {
+            message["content"] = Value::Null;
+        }
+    }
+    if let Some(id) = &m.tool_call_id {
+        message["tool_call_id"] = json!(id);
+    }
+    message
+}
+
+fn anthropic_blocks(m: &ChatMessage) -> Vec<Value> {
+    if let Some(id) = &m.tool_call_id {
+        return vec![json!({ "type": "tool_result", "tool_use_id": id, "content": m.content })];
+    }
+    let mut blocks = Vec::new();
+    if !m.content.is_empty() {
+        blocks.push(json!({ "type": "text", "text": m.content }));
+    }
+    blocks.extend(m.parts.iter().map(ContentPart::to_anthropic));
+    for call in &m.tool_calls {
+        blocks.push(json!({
+            "type": "tool_use",
+            "id": call.id,
+            "name": call.name,
+            "input": call.arguments,
+        }));
+    }
+    blocks
+}
+
+fn gemini_parts(m: &ChatMessage, transcript: &Transcript) -> Vec<Value> {
+    if let Some(id) = &m.tool_call_id {
+        let name = transcript
+            .messages
+            .iter()
+            .flat_map(|m| &m.tool_calls)
+            .find(|call| &call.id == id)
+            .map_or(id.as_str(), |call| call.name.as_str());
+        return vec![json!({
+            "functionResponse": { "name": name, "response": { "content": m.content } },
+        })];
+    }
+    let mut parts = Vec::new();
+    if !m.content.is_empty() {
+        parts.push(json!({ "text": m.content }));
+    }
+    parts.extend(m.parts.iter().map(ContentPart::to_gemini));
+    for call in &m.tool_calls {
+        parts.push(json!({ "functionCall": { "name": call.name, "args": call.arguments } }));
+    }
+    parts
+}
+
+impl Transcript {
+    pub fn to_openai_messages(&self) -> VendorMessages {
+        VendorMessages {
+            system: None,
+            messages: self.messages.iter().map(openai_message).collect(),
+        }
+    }
+
+    pub fn to_anthropic_messages(&self) -> VendorMessages {
+        let mut turns = Vec::new();
+        for m in self.without_system() {
+            let role = match m.role {
+                Role::Assistant => "assistant",
+                _ => "user",
+            };
+            push_turn(&mut turns, role, anthropic_blocks(m));
+        }
+        let messages = turns
+            .into_iter()
+            .map(|(role, blocks)| match blocks.as_slice() {
+                [block] if block["type"] == "text" => {
+                    json!({ "role": role, "content": block["text"] })
+                }
+                _ => json!({ "role": role, "content": blocks }),
+            })
+            .collect();
+        VendorMessages {
+            system: system_text(self).map(Value::String),
+            messages,
+        }
+    }
+
+    pub fn to_gemini_contents(&self) -> VendorMessages {
+        let mut turns = Vec::new();
+        for m in self.without_system() {
+            let role = match m.role {
+                Role::Assistant => "model",
+                _ => "user",
+            };
+            push_turn(&mut turns, role, gemini_parts(m, self));
+        }
+        VendorMessages {
+            system: system_text(self).map(|text| json!({ "parts": [{ "text": text }] })),
+            messages: turns
+                .into_iter()
+                .map(|(role, parts)| json!({ "role": role, "parts": parts }))
+                .collect(),
+        }
+    }
+}
+
+impl Chat {
+    pub fn to_vendor_messages(&self, vendor: Vendor) -> VendorMessages {
+        let transcript = self.to_transcript();
+        match vendor {
+            Vendor::OpenAi => transcript.to_openai_messages(),
+            Vendor::Anthropic => transcript.to_anthropic_messages(),
+            Vendor::Google => transcript.to_gemini_contents(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::prompt::{deserialize_prompt, Prompt};
+
+    fn chat() -> Chat {
+        match deserialize_prompt(include_str!("../tests/fixtures/convert/chat.yaml")) {
+            Prompt::Chat(chat) => chat,
+            other => panic!("Expected Prompt::Chat, got {:?}", other),
+        }
+    }
+
+    fn payload(source: &str) -> Value {
+        serde_json::from_str(source).unwrap()
+    }
+
+    #[test]
+    fn test_captured_payloads() {
+        let chat = chat();
+        assert_eq!(
+            chat.to_openai_chat_request(),
+            payload(include_str!("../tests/fixtures/convert/openai.json"))
+        );
+        assert_eq!(
+            chat.to_anthropic_messages_request(),
+            payload(include_str!("../tests/fixtures/convert/anthropic.json"))
+        );
+        assert_eq!(
+            chat.to_gemini_request(),
+            payload(include_str!("../tests/fixtures/convert/gemini.json"))
+        );
+    }
+
+    #[test]
+    fn test_role_alternation() {
+        let mut transcript = Transcript::new();
+        transcript.push(Role::System, "Be brief");
+        transcript.push(Role::System, "Answer in French");
+        transcript.push_user("hi");
+        transcript.push_user("are you there?");
+        transcript.push_assistant("");
+        transcript.push_assistant("oui");
+
+        let anthropic = transcript.to_anthropic_messages();
+        assert_eq!(
+            anthropic.system,
+            Some(json!("Be brief\n\nAnswer in French"))
+        );
+        assert_eq!(
+            anthropic.messages,
+            vec![
+                json!({ "role": "user", "content": [
+                    { "type": "text", "text": "hi" },
+                    { "type": "text", "text": "are you there?" },
+                ] }),
+                json!({ "role": "assistant", "content": "oui" }),
+            ]
+        );
+
+        let gemini = transcript.to_gemini_contents();
+        assert_eq!(
+            gemini.system,
+            Some(json!({ "parts": [{ "text": "Be brief\n\nAnswer in French" }] }))
+        );
+        assert_eq!(gemini.messages.len(), 2);
+        assert_eq!(
+            gemini.messages[1],
+            json!({ "role": "model", "parts": [{ "text": "oui" }] })
+        );
+
+        let openai = transcript.to_openai_messages();
+        assert_eq!(openai.system, None);
+        assert_eq!(openai.messages.len(), 6);
+    }
+}
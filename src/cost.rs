@@ -0,0 +1,306 @@
+#[cfg(feature = "exec")]
+use crate::exec::ExecutionResult;
+use crate::prompt::Prompt;
+use crate::request::Vendor;
+use crate::template::RenderError;
+use crate::tokens::Tokenizer;
+use std::collections::HashMap;
+use std::fmt;
+
+const BUILTIN_PRICES: &[(&str, &str, f64, f64)] = &[
+    ("openai", "gpt-4o", 2.50, 10.00),
+    ("openai", "gpt-4o-mini", 0.15, 0.60),
+    ("openai", "gpt-4.1", 2.00, 8.00),
+    ("openai", "gpt-4.1-mini", 0.40, 1.60),
+    ("openai", "gpt-4.1-nano", 0.10, 0.40),
+    ("openai", "gpt-3.5-turbo", 0.50, 1.50),
+    ("openai", "o3-mini", 1.10, 4.40),
+    ("openai", "text-embedding-3-small", 0.02, 0.0),
+    ("openai", "text-embedding-3-large", 0.13, 0.0),
+    ("anthropic", "claude-3-haiku", 0.25, 1.25),
+    ("anthropic", "claude-3-5-haiku", 0.80, 4.00),
+    ("anthropic", "claude-3-5-sonnet", 3.00, 15.00),
+    ("anthropic", "claude-3-7-sonnet", 3.00, 15.00),
+    ("anthropic", "claude-3-opus", 15.00, 75.00),
+    ("anthropic", "claude-sonnet-4", 3.00, 15.00),
+    ("anthropic", "claude-opus-4", 15.00, 75.00),
+    ("google", "gemini-1.5-flash", 0.075, 0.30),
+    ("google", "gemini-1.5-pro", 1.25, 5.00),
+    ("google", "gemini-2.0-flash", 0.10, 0.40),
+];
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct ModelPrice {
+    pub input_per_million: f64,
+    pub output_per_million: f64,
+}
+
+impl ModelPrice {
+    pub fn new(input_per_million: f64, output_per_million: f64) -> ModelPrice {
+        ModelPrice {
+            input_per_million,
+            output_per_million,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct PriceTable {
+    prices: Vec<(String, String, ModelPrice)>,
+}
+
+pub(crate) fn same_vendor(a: &str, b: &str) -> bool {
+    match (Vendor::from_name(a), Vendor::from_name(b)) {
+        (Some(a), Some(b)) => a == b,
+        _ => a.eq_ignore_ascii_case(b),
+    }
+}
+
+impl PriceTable {
+    pub fn new() -> PriceTable {
+        PriceTable::default()
+    }
+
+    pub fn builtin() -> PriceTable {
+        BUILTIN_PRICES.iter().fold(
+            PriceTable::new(),
+            |table, (vendor, model, input, output)| {
+                table.with_price(*vendor, *model, ModelPrice::new(*input, *output))
+            },
+        )
+    }
+
+    pub fn with_price(
+        mut self,
+        vendor: impl Into<String>,
+        model_prefix: impl Into<String>,
+        price: ModelPrice,
+    ) -> Self {
+        let (vendor, model_prefix) = (vendor.into(), model_prefix.into());
+        self.prices
+            .retain(|(v, m, _)| !(same_vendor(v, &vendor) && *m == model_prefix));
+        self.prices.push((vendor, model_prefix, price));
+        self
+    }
+
+    pub fn price(&self, vendor: &str, model: &str) -> Option<ModelPrice> {
+        self.prices
+            .iter()
+            .filter(|(v, prefix, _)| same_vendor(v, vendor) && model.starts_with(prefix.as_str()))
+            .max_by_key(|(_, prefix, _)| prefix.len())
+            .map(|(_, _, price)| *price)
+    }
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct Cost {
+    pub input_tokens: usize,
+    pub output_tokens: usize,
+    pub input_cost: f64,
+    pub output_cost: f64,
+}
+
+impl Cost {
+    pub fn new(price: ModelPrice, input_tokens: usize, output_tokens: usize) -> Cost {
+        Cost {
+            input_tokens,
+            output_tokens,
+            input_cost: input_tokens as f64 * price.input_per_million / 1_000_000.0,
+            output_cost: output_tokens as f64 * price.output_per_million / 1_000_000.0,
+        }
+    }
+
+    pub fn total(&self) -> f64 {
+        self.input_cost + self.output_cost
+    }
+}
+
+impl std::ops::Add for Cost {
+    type Output = Cost;
+
+    fn add(self, other: Cost) -> Cost {
+        Cost {
+            input_tokens: self.input_tokens + other.input_tokens,
+            output_tokens: self.output_tokens + other.output_tokens,
+            input_cost: self.input_cost + other.input_cost,
+            output_cost: self.output_cost + other.output_cost,
+        }
+    }
+}
+
+impl std::iter::Sum for Cost {
+    fn sum<I: Iterator<Item = Cost>>(iter: I) -> Cost {
+        iter.fold(Cost::default(), |a, b| a + b)
+    }
+}
+
+impl fmt::Display for Cost {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "${:.6} ({} input + {} output tokens)",
+            self.total(),
+            self.input_tokens,
+            self.output_tokens
+        )
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Usage {
+    pub input_tokens: usize,
+    pub output_tokens: usize,
+}
+
+#[cfg(feature = "exec")]
+const USAGE_POINTERS: &[(&str, &str)] = &[
+    ("/usage/prompt_tokens", "/usage/completion_tokens"),
+    ("/usage/input_tokens", "/usage/output_tokens"),
+    (
+        "/usageMetadata/promptTokenCount",
+        "/usageMetadata/candidatesTokenCount",
+    ),
+    (
+        "/metadata/tokenMetadata/inputTokenCount/totalTokens",
+        "/metadata/tokenMetadata/outputTokenCount/totalTokens",
+    ),
+];
+
+#[cfg(feature = "exec")]
+impl ExecutionResult {
+    pub fn usage(&self) -> Option<Usage> {
+        let count = |pointer: &str| self.raw.pointer(pointer)?.as_u64().map(|n| n as usize);
+        USAGE_POINTERS.iter().find_map(|(input, output)| {
+            Some(Usage {
+                input_tokens: count(input)?,
+                output_tokens: count(output)?,
+            })
+        })
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum CostError {
+    UnknownModel { vendor: String, model: String },
+    UnsupportedPrompt,
+    Render(RenderError),
+}
+
+impl fmt::Display for CostError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            CostError::UnknownModel { vendor, model } => {
+                write!(f, "no price known for {} model '{}'", vendor, model)
+            }
+            CostError::UnsupportedPrompt => write!(f, "prompt type has no vendor or model"),
+            CostError::Render(error) => write!(f, "{}", error),
+        }
+    }
+}
+
+impl std::error::Error for CostError {}
+
+impl From<RenderError> for CostError {
+    fn from(error: RenderError) -> Self {
+        CostError::Render(error)
+    }
+}
+
+impl Prompt {
+    fn vendor_model(&self) -> Result<(&str, &str), CostError> {
+        match self {
+            Prompt::Completion(c) => Ok((&c.vendor, &c.model)),
+            Prompt::Chat(c) => Ok((&c.vendor, &c.model)),
+            Prompt::Embedding(e) => Ok((&e.vendor, &e.model)),
+            Prompt::Custom(_) | Prompt::Unknown => Err(CostError::UnsupportedPrompt),
+        }
+    }
+
+    pub fn price(&self, prices: &PriceTable) -> Result<ModelPrice, CostError> {
+        let (vendor, model) = self.vendor_model()?;
+        prices
+            .price(vendor, model)
+            .ok_or_else(|| CostError::UnknownModel {
+                vendor: vendor.to_string(),
+                model: model.to_string(),
+            })
+    }
+
+    pub fn estimate_cost(
+        &self,
+        input_tokens: usize,
+        expected_output_tokens: usize,
+    ) -> Result<Cost, CostError> {
+        self.estimate_cost_with(&PriceTable::builtin(), input_tokens, expected_output_tokens)
+    }
+
+    pub fn estimate_cost_with(
+        &self,
+        prices: &PriceTable,
+        input_tokens: usize,
+        expected_output_tokens: usize,
+    ) -> Result<Cost, CostError> {
+        Ok(Cost::new(
+            self.price(prices)?,
+            input_tokens,
+            expected_output_tokens,
+        ))
+    }
+
+    pub fn estimate_request_cost(
+        &self,
+        prices: &PriceTable,
+        tokenizer: &impl Tokenizer,
+        vars: &HashMap<String, String>,
+        expected_output_tokens: usize,
+    ) -> Result<Cost, CostError> {
+        let input_tokens = match self {
+            Prompt::Completion(c) => tokenizer.count_tokens(&c.render(vars)?),
+            Prompt::Chat(c) => c.render(vars)?.estimate_tokens(tokenizer),
+            Prompt::Embedding(e) => e.render(vars)?.estimate_tokens(tokenizer),
+            Prompt::Custom(_) | Prompt::Unknown => return Err(CostError::UnsupportedPrompt),
+        };
+        self.estimate_cost_with(prices, input_tokens, expected_output_tokens)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::prompt::deserialize_prompt;
+    use crate::tokens::WhitespaceTokenizer;
+
+    #[test]
+    fn test_estimate_cost() {
+        let prompt = deserialize_prompt(
+            "type: completion\nvendor: openai\nmodel: gpt-4o-mini-2024-07-18\nprompt: Say {{word}} twice\n",
+        );
+        let cost = prompt.estimate_cost(1_000_000, 500_000).unwrap();
+        assert_eq!(cost.input_cost, 0.15);
+        assert_eq!(cost.output_cost, 0.30);
+        assert!((cost.total() - 0.45).abs() < 1e-9);
+
+        let prices =
+            PriceTable::builtin().with_price("openai", "gpt-4o-mini", ModelPrice::new(1.0, 2.0));
+        let vars = HashMap::from([("word".to_string(), "hello there".to_string())]);
+        let cost = prompt
+            .estimate_request_cost(&prices, &WhitespaceTokenizer, &vars, 10)
+            .unwrap();
+        assert_eq!((cost.input_tokens, cost.output_tokens), (4, 10));
+        assert_eq!(cost.to_string(), "$0.000024 (4 input + 10 output tokens)");
+        assert_eq!((cost + cost).input_tokens, 8);
+
+        let bison =
+            deserialize_prompt("type: completion\nvendor: vertex\nmodel: text-bison\nprompt: hi\n");
+        assert_eq!(
+            bison.estimate_cost(1, 1),
+            Err(CostError::UnknownModel {
+                vendor: "vertex".to_string(),
+                model: "text-bison".to_string(),
+            })
+        );
+        let prices =
+            PriceTable::new().with_price("google", "text-bison", ModelPrice::new(0.5, 0.5));
+        assert!(bison.estimate_cost_with(&prices, 2, 0).is_ok());
+    }
+}
@@ -0,0 +1,421 @@
+#[cfg(feature = "fs")]
+use crate::prompt::{ChatExample, CompletionExampleColumn};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::collections::BTreeMap;
+use std::fmt;
+#[cfg(feature = "fs")]
+use std::fs::File;
+#[cfg(feature = "fs")]
+use std::io::BufReader;
+use std::io::{self, BufRead};
+use std::path::{Path, PathBuf};
+
+pub type Record = BTreeMap<String, String>;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum DatasetError {
+    Io {
+        path: PathBuf,
+        message: String,
+    },
+    UnknownFormat(PathBuf),
+    Parse {
+        path: PathBuf,
+        line: usize,
+        message: String,
+    },
+    MissingField {
+        path: PathBuf,
+        line: usize,
+        field: String,
+    },
+}
+
+impl fmt::Display for DatasetError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            DatasetError::Io { path, message } => write!(f, "{}: {}", path.display(), message),
+            DatasetError::UnknownFormat(path) => {
+                write!(
+                    f,
+                    "{}: cannot tell dataset format, expected .csv or .jsonl",
+                    path.display()
+                )
+            }
+            DatasetError::Parse {
+                path,
+                line,
+                message,
+            } => write!(f, "{}:{}: {}", path.display(), line, message),
+            DatasetError::MissingField { path, line, field } => {
+                write!(f, "{}:{}: missing field '{}'", path.display(), line, field)
+            }
+        }
+    }
+}
+
+impl std::error::Error for DatasetError {}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum DatasetFormat {
+    Csv,
+    Jsonl,
+}
+
+impl DatasetFormat {
+    pub fn from_path(path: &Path) -> Option<DatasetFormat> {
+        match path.extension()?.to_str()?.to_lowercase().as_str() {
+            "csv" => Some(DatasetFormat::Csv),
+            "jsonl" | "ndjson" => Some(DatasetFormat::Jsonl),
+            _ => None,
+        }
+    }
+}
+
+fn split_csv(record: &str) -> Vec<String> {
+    let mut fields = Vec::new();
+    let mut field = String::new();
+    let mut quoted = false;
+    let mut chars = record.chars().peekable();
+    while let Some(c) = chars.next() {
+        match c {
+            '"' if quoted && chars.peek() == Some(&'"') => {
+                field.push('"');
+                chars.next();
+            }
+            '"' => quoted = !quoted,
+            ',' if !quoted => fields.push(std::mem::take(&mut field)),
+            c => field.push(c),
+        }
+    }
+    fields.push(field);
+    fields
+}
+
+fn value_text(value: Value) -> String {
+    match value {
+        Value::String(text) => text,
+        Value::Null => String::new(),
+        other => other.to_string(),
+    }
+}
+
+pub struct DatasetReader<R> {
+    lines: io::Lines<R>,
+    format: DatasetFormat,
+    path: PathBuf,
+    line: usize,
+    header: Option<Vec<String>>,
+}
+
+#[cfg(feature = "fs")]
+impl DatasetReader<BufReader<File>> {
+    pub fn open(path: impl AsRef<Path>) -> Result<Self, DatasetError> {
+        let path = path.as_ref();
+        let format = DatasetFormat::from_path(path)
+            .ok_or_else(|| DatasetError::UnknownFormat(path.to_path_buf()))?;
+        DatasetReader::open_as(path, format)
+    }
+
+    pub fn open_as(path: impl AsRef<Path>, format: DatasetFormat) -> Result<Self, DatasetError> {
+        let path = path.as_ref();
+        let file = File::open(path).map_err(|e| DatasetError::Io {
+            path: path.to_path_buf(),
+            message: e.to_string(),
+        })?;
+        Ok(DatasetReader::new(BufReader::new(file), format, path))
+    }
+}
+
+impl<R: BufRead> DatasetReader<R> {
+    pub fn new(reader: R, format: DatasetFormat, path: impl Into<PathBuf>) -> Self {
+        DatasetReader {
+            lines: reader.lines(),
+            format,
+            path: path.into(),
+            line: 0,
+            header: None,
+        }
+    }
+
+    pub fn line(&self) -> usize {
+        self.line
+    }
+
+    fn next_line(&mut self) -> Option<Result<String, DatasetError>> {
+        let line = self.lines.next()?;
+        self.line += 1;
+        Some(line.map_err(|e| DatasetError::Io {
+            path: self.path.clone(),
+            message: e.to_string(),
+        }))
+    }
+
+    fn parse_error(&self, message: impl Into<String>) -> DatasetError {
+        DatasetError::Parse {
+            path: self.path.clone(),
+            line: self.line,
+            message: message.into(),
+        }
+    }
+
+    fn next_csv_record(&mut self) -> Option<Result<Vec<String>, DatasetError>> {
+        let mut record = String::new();
+        loop {
+            let line = match self.next_line()? {
+                Ok(line) => line,
+                Err(error) => return Some(Err(error)),
+            };
+            if record.is_empty() && line.trim().is_empty() {
+                continue;
+            }
+            if !record.is_empty() {
+                record.push('\n');
+            }
+            record.push_str(line.trim_end_matches('\r'));
+            if record.matches('"').count().is_multiple_of(2) {
+                return Some(Ok(split_csv(&record)));
+            }
+        }
+    }
+
+    fn next_csv(&mut self) -> Option<Result<Record, DatasetError>> {
+        if self.header.is_none() {
+            match self.next_csv_record()? {
+                Ok(header) => self.header = Some(header),
+                Err(error) => return Some(Err(error)),
+            }
+        }
+        let fields = match self.next_csv_record()? {
+            Ok(fields) => fields,
+            Err(error) => return Some(Err(error)),
+        };
+        let header = self.header.as_deref().unwrap_or_default();
+        if fields.len() != header.len() {
+            return Some(Err(self.parse_error(format!(
+                "expected {} fields, found {}",
+                header.len(),
+                fields.len()
+            ))));
+        }
+        Some(Ok(header.iter().cloned().zip(fields).collect()))
+    }
+
+    fn next_jsonl(&mut self) -> Option<Result<Record, DatasetError>> {
+        loop {
+            let line = match self.next_line()? {
+                Ok(line) => line,
+                Err(error) => return Some(Err(error)),
+            };
+            if line.trim().is_empty() {
+                continue;
+            }
+            return Some(match serde_json::from_str::<Value>(&line) {
+                Ok(Value::Object(object)) => Ok(object
+                    .into_iter()
+                    .map(|(key, value)| (key, value_text(value)))
+                    .collect()),
+                Ok(_) => Err(self.parse_error("expected a JSON object")),
+                Err(error) => Err(self.parse_error(error.to_string())),
+            });
+        }
+    }
+}
+
+impl<R: BufRead> Iterator for DatasetReader<R> {
+    type Item = Result<Record, DatasetError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        match self.format {
+            DatasetFormat::Csv => self.next_csv(),
+            DatasetFormat::Jsonl => self.next_jsonl(),
+        }
+    }
+}
+
+fn default_input_field() -> String {
+    "input".to_string()
+}
+
+fn default_output_field() -> String {
+    "output".to_string()
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct DatasetSpec {
+    pub source: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub format: Option<DatasetFormat>,
+    #[serde(default = "default_input_field")]
+    pub input_field: String,
+    #[serde(default = "default_output_field")]
+    pub output_field: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub fields: Option<Vec<String>>,
+    #[serde(default)]
+    pub offset: usize,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub limit: Option<usize>,
+}
+
+impl DatasetSpec {
+    pub fn new(source: impl Into<String>) -> DatasetSpec {
+        DatasetSpec {
+            source: source.into(),
+            format: None,
+            input_field: default_input_field(),
+            output_field: default_output_field(),
+            fields: None,
+            offset: 0,
+            limit: None,
+        }
+    }
+}
+
+#[cfg(feature = "fs")]
+impl DatasetSpec {
+    pub fn path(&self, base_dir: &Path) -> PathBuf {
+        base_dir.join(&self.source)
+    }
+
+    pub fn records(&self, base_dir: &Path) -> Result<Vec<(usize, Record)>, DatasetError> {
+        let path = self.path(base_dir);
+        let mut reader = match self.format {
+            Some(format) => DatasetReader::open_as(&path, format)?,
+            None => DatasetReader::open(&path)?,
+        };
+        let mut records = Vec::new();
+        let mut skipped = 0;
+        while self.limit.is_none_or(|limit| records.len() < limit) {
+            let Some(record) = reader.next() else {
+                break;
+            };
+            let record = record?;
+            if skipped < self.offset {
+                skipped += 1;
+                continue;
+            }
+            records.push((reader.line(), record));
+        }
+        Ok(records)
+    }
+
+    fn field(
+        &self,
+        base_dir: &Path,
+        line: usize,
+        record: &mut Record,
+        field: &str,
+    ) -> Result<String, DatasetError> {
+        record
+            .remove(field)
+            .ok_or_else(|| DatasetError::MissingField {
+                path: self.path(base_dir),
+                line,
+                field: field.to_string(),
+            })
+    }
+
+    pub fn load_chat_examples(&self, base_dir: &Path) -> Result<Vec<ChatExample>, DatasetError> {
+        self.records(base_dir)?
+            .into_iter()
+            .map(|(line, mut record)| {
+                Ok(ChatExample {
+                    input: self.field(base_dir, line, &mut record, &self.input_field)?,
+                    output: Some(self.field(base_dir, line, &mut record, &self.output_field)?),
+                })
+            })
+            .collect()
+    }
+
+    pub fn load_columns(
+        &self,
+        base_dir: &Path,
+    ) -> Result<Vec<CompletionExampleColumn>, DatasetError> {
+        let names = self
+            .fields
+            .clone()
+            .unwrap_or_else(|| vec![self.input_field.clone(), self.output_field.clone()]);
+        let mut columns: Vec<CompletionExampleColumn> = names
+            .into_iter()
+            .map(|name| CompletionExampleColumn {
+                name,
+                values: Vec::new(),
+                test: None,
+            })
+            .collect();
+        for (line, mut record) in self.records(base_dir)? {
+            for column in &mut columns {
+                let value = self.field(base_dir, line, &mut record, &column.name)?;
+                column.values.push(value);
+            }
+        }
+        Ok(columns)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    #[cfg(feature = "fs")]
+    use crate::registry::tests::{temp_dir, write};
+    #[cfg(feature = "fs")]
+    use std::fs;
+
+    #[test]
+    fn test_csv_reader() {
+        let csv = "question,answer\r\n\"Say \"\"hi\"\"\",\"hi,\nthere\"\n\n2+2,4\n3+3\n";
+        let mut reader = DatasetReader::new(csv.as_bytes(), DatasetFormat::Csv, "data.csv");
+        let first = reader.next().unwrap().unwrap();
+        assert_eq!(first["question"], "Say \"hi\"");
+        assert_eq!(first["answer"], "hi,\nthere");
+        assert_eq!(reader.next().unwrap().unwrap()["answer"], "4");
+        assert!(matches!(
+            reader.next(),
+            Some(Err(DatasetError::Parse { line: 6, .. }))
+        ));
+        assert!(reader.next().is_none());
+    }
+
+    #[cfg(feature = "fs")]
+    #[test]
+    fn test_dataset_spec() {
+        let dir = temp_dir("dataset_spec");
+        let lines: Vec<String> = (0..1000)
+            .map(|i| format!(r#"{{"q": "q{}", "a": {}, "tag": null}}"#, i, i * 2))
+            .collect();
+        write(&dir, "data/qa.jsonl", &lines.join("\n"));
+
+        let spec = DatasetSpec {
+            input_field: "q".to_string(),
+            output_field: "a".to_string(),
+            offset: 2,
+            limit: Some(3),
+            ..DatasetSpec::new("data/qa.jsonl")
+        };
+        let examples = spec.load_chat_examples(&dir).unwrap();
+        assert_eq!(examples.len(), 3);
+        assert_eq!(examples[0].input, "q2");
+        assert_eq!(examples[2].output.as_deref(), Some("8"));
+
+        let columns = spec.load_columns(&dir).unwrap();
+        assert_eq!(columns[1].name, "a");
+        assert_eq!(columns[1].values, vec!["4", "6", "8"]);
+
+        let missing = DatasetSpec {
+            fields: Some(vec!["q".to_string(), "missing".to_string()]),
+            ..spec.clone()
+        };
+        assert!(matches!(
+            missing.load_columns(&dir),
+            Err(DatasetError::MissingField { line: 3, .. })
+        ));
+        assert!(matches!(
+            DatasetSpec::new("data/qa.txt").load_chat_examples(&dir),
+            Err(DatasetError::UnknownFormat(_))
+        ));
+        fs::remove_dir_all(&dir).unwrap();
+    }
+}
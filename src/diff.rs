@@ -0,0 +1,835 @@
+use crate::prompt::{
+    find_parameter, Chat, ChatExample, Completion, Embedding, Message, Parameter, Prompt,
+    PromptMeta,
+};
+use crate::sampling::Sampling;
+use serde::Serialize;
+use serde_yaml::Value;
+use std::fmt;
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[serde(tag = "change", rename_all = "snake_case")]
+pub enum Change {
+    TypeChanged {
+        from: String,
+        to: String,
+    },
+    VendorChanged {
+        from: String,
+        to: String,
+    },
+    ModelChanged {
+        from: String,
+        to: String,
+    },
+    PromptChanged {
+        from: String,
+        to: String,
+    },
+    ContextChanged {
+        from: Option<String>,
+        to: Option<String>,
+    },
+    SystemChanged {
+        from: Option<String>,
+        to: Option<String>,
+    },
+    SuffixChanged {
+        from: Option<String>,
+        to: Option<String>,
+    },
+    ParameterChanged {
+        name: String,
+        from: Option<Value>,
+        to: Option<Value>,
+    },
+    ExampleCellChanged {
+        column: String,
+        row: usize,
+        from: Option<String>,
+        to: Option<String>,
+    },
+    TestChanged {
+        column: String,
+        from: Option<String>,
+        to: Option<String>,
+    },
+    ExampleChanged {
+        index: usize,
+        from: Option<ChatExample>,
+        to: Option<ChatExample>,
+    },
+    MessageChanged {
+        index: usize,
+        from: Option<Message>,
+        to: Option<Message>,
+    },
+    InputChanged {
+        index: usize,
+        from: Option<String>,
+        to: Option<String>,
+    },
+    FieldChanged {
+        field: String,
+        from: Option<Value>,
+        to: Option<Value>,
+    },
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+#[serde(tag = "op", content = "line", rename_all = "lowercase")]
+pub enum LineChange {
+    Same(String),
+    Added(String),
+    Removed(String),
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct TextDiff {
+    pub field: String,
+    pub lines: Vec<LineChange>,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Serialize)]
+pub struct PromptDiff {
+    pub changes: Vec<Change>,
+    pub text: Vec<TextDiff>,
+}
+
+pub fn line_diff(from: &str, to: &str) -> Vec<LineChange> {
+    let a: Vec<&str> = from.lines().collect();
+    let b: Vec<&str> = to.lines().collect();
+    let mut lengths = vec![vec![0usize; b.len() + 1]; a.len() + 1];
+    for i in (0..a.len()).rev() {
+        for j in (0..b.len()).rev() {
+            lengths[i][j] = if a[i] == b[j] {
+                lengths[i + 1][j + 1] + 1
+            } else {
+                lengths[i + 1][j].max(lengths[i][j + 1])
+            };
+        }
+    }
+    let (mut i, mut j) = (0, 0);
+    let mut lines = Vec::new();
+    while i < a.len() || j < b.len() {
+        if i < a.len() && j < b.len() && a[i] == b[j] {
+            lines.push(LineChange::Same(a[i].to_string()));
+            i += 1;
+            j += 1;
+        } else if i < a.len() && (j == b.len() || lengths[i + 1][j] >= lengths[i][j + 1]) {
+            lines.push(LineChange::Removed(a[i].to_string()));
+            i += 1;
+        } else {
+            lines.push(LineChange::Added(b[j].to_string()));
+            j += 1;
+        }
+    }
+    lines
+}
+
+fn parameter_names(parameters: &Option<Vec<Parameter>>) -> Vec<String> {
+    parameters
+        .iter()
+        .flatten()
+        .map(|p| p.name.clone())
+        .collect()
+}
+
+fn column_names(completion: &Completion) -> Vec<String> {
+    completion
+        .examples
+        .iter()
+        .flatten()
+        .map(|c| c.name.clone())
+        .collect()
+}
+
+fn merge_names(mut names: Vec<String>, other: Vec<String>) -> Vec<String> {
+    for name in other {
+        if !names.contains(&name) {
+            names.push(name);
+        }
+    }
+    names
+}
+
+fn cell(completion: &Completion, column: &str, row: usize) -> Option<String> {
+    completion
+        .find_column(column)
+        .ok()
+        .and_then(|c| c.values.get(row).cloned())
+}
+
+fn test_value(completion: &Completion, column: &str) -> Option<String> {
+    completion
+        .find_column(column)
+        .ok()
+        .and_then(|c| c.test.clone())
+}
+
+fn yaml<T: Serialize>(value: &T) -> Option<Value> {
+    serde_yaml::to_value(value).ok().filter(|v| !v.is_null())
+}
+
+fn diff_field<T: Serialize + PartialEq>(diffs: &mut Vec<Change>, field: &str, from: &T, to: &T) {
+    if from != to {
+        diffs.push(Change::FieldChanged {
+            field: field.to_string(),
+            from: yaml(from),
+            to: yaml(to),
+        });
+    }
+}
+
+fn diff_meta(diffs: &mut Vec<Change>, kind: (&str, &str), from: &PromptMeta, to: &PromptMeta) {
+    diff_field(diffs, "type", &kind.0, &kind.1);
+    let fields = |meta: &PromptMeta| match yaml(meta) {
+        Some(Value::Mapping(fields)) => fields,
+        _ => Default::default(),
+    };
+    let (old, new) = (fields(from), fields(to));
+    for key in old
+        .keys()
+        .chain(new.keys().filter(|k| !old.contains_key(*k)))
+    {
+        let (a, b) = (old.get(key).cloned(), new.get(key).cloned());
+        if a != b {
+            diffs.push(Change::FieldChanged {
+                field: key.as_str().unwrap_or_default().to_string(),
+                from: a,
+                to: b,
+            });
+        }
+    }
+    let unknown = |meta: &PromptMeta| -> Vec<String> {
+        meta.unknown_fields.iter().map(|f| f.to_string()).collect()
+    };
+    diff_field(diffs, "unknown_fields", &unknown(from), &unknown(to));
+}
+
+fn diff_target(diffs: &mut Vec<Change>, from: (&str, &str), to: (&str, &str)) {
+    if from.0 != to.0 {
+        diffs.push(Change::VendorChanged {
+            from: from.0.to_string(),
+            to: to.0.to_string(),
+        });
+    }
+    if from.1 != to.1 {
+        diffs.push(Change::ModelChanged {
+            from: from.1.to_string(),
+            to: to.1.to_string(),
+        });
+    }
+}
+
+fn diff_parameters(
+    diffs: &mut Vec<Change>,
+    from: &Option<Vec<Parameter>>,
+    to: &Option<Vec<Parameter>>,
+) {
+    for name in merge_names(parameter_names(from), parameter_names(to)) {
+        let old = find_parameter(from, &name);
+        let new = find_parameter(to, &name);
+        if old != new {
+            diffs.push(Change::ParameterChanged {
+                name,
+                from: old,
+                to: new,
+            });
+        }
+    }
+}
+
+fn diff_sampling(diffs: &mut Vec<Change>, from: &Sampling, to: &Sampling) {
+    let (from, to) = (from.fields(), to.fields());
+    let value = |fields: &[(&str, serde_json::Value)], name: &str| {
+        let (_, value) = fields.iter().find(|(n, _)| *n == name)?;
+        serde_yaml::to_value(value).ok()
+    };
+    let mut names: Vec<&str> = Vec::new();
+    for (name, _) in from.iter().chain(&to) {
+        if !names.contains(name) {
+            names.push(name);
+        }
+    }
+    for name in names {
+        let (old, new) = (value(&from, name), value(&to, name));
+        if old != new {
+            diffs.push(Change::ParameterChanged {
+                name: name.to_string(),
+                from: old,
+                to: new,
+            });
+        }
+    }
+}
+
+fn diff_items<T: Clone + PartialEq>(
+    from: Option<&[T]>,
+    to: Option<&[T]>,
+    change: impl Fn(usize, Option<T>, Option<T>) -> Change,
+) -> Vec<Change> {
+    let (from, to) = (from.unwrap_or_default(), to.unwrap_or_default());
+    (0..from.len().max(to.len()))
+        .filter_map(|i| {
+            let (old, new) = (from.get(i), to.get(i));
+            (old != new).then(|| change(i, old.cloned(), new.cloned()))
+        })
+        .collect()
+}
+
+impl Completion {
+    pub fn diff(&self, other: &Completion) -> Vec<Change> {
+        let mut diffs = Vec::new();
+        diff_meta(
+            &mut diffs,
+            (&self.prompt_type, &other.prompt_type),
+            &self.meta,
+            &other.meta,
+        );
+        diff_target(
+            &mut diffs,
+            (&self.vendor, &self.model),
+            (&other.vendor, &other.model),
+        );
+        if self.prompt != other.prompt {
+            diffs.push(Change::PromptChanged {
+                from: self.prompt.clone(),
+                to: other.prompt.clone(),
+            });
+        }
+        if self.system != other.system {
+            diffs.push(Change::SystemChanged {
+                from: self.system.clone(),
+                to: other.system.clone(),
+            });
+        }
+        if self.suffix != other.suffix {
+            diffs.push(Change::SuffixChanged {
+                from: self.suffix.clone(),
+                to: other.suffix.clone(),
+            });
+        }
+        diff_parameters(&mut diffs, &self.parameters, &other.parameters);
+        diff_sampling(&mut diffs, &self.sampling, &other.sampling);
+
+        let rows = self.example_count().max(other.example_count());
+        for column in merge_names(column_names(self), column_names(other)) {
+            for row in 0..rows {
+                let from = cell(self, &column, row);
+                let to = cell(other, &column, row);
+                if from != to {
+                    diffs.push(Change::ExampleCellChanged {
+                        column: column.clone(),
+                        row,
+                        from,
+                        to,
+                    });
+                }
+            }
+            let from = test_value(self, &column);
+            let to = test_value(other, &column);
+            if from != to {
+                diffs.push(Change::TestChanged { column, from, to });
+            }
+        }
+        if diffs.is_empty() && column_names(self) != column_names(other) {
+            diff_field(
+                &mut diffs,
+                "examples",
+                &column_names(self),
+                &column_names(other),
+            );
+        }
+        diff_field(
+            &mut diffs,
+            "parameters_by_env",
+            &self.parameters_by_env,
+            &other.parameters_by_env,
+        );
+        diff_field(
+            &mut diffs,
+            "example_format",
+            &self.example_format,
+            &other.example_format,
+        );
+        diff_field(&mut diffs, "output", &self.output, &other.output);
+        diff_field(&mut diffs, "policy", &self.policy, &other.policy);
+        diff_field(
+            &mut diffs,
+            "post_process",
+            &self.post_process,
+            &other.post_process,
+        );
+        diffs
+    }
+}
+
+impl Chat {
+    pub fn diff(&self, other: &Chat) -> Vec<Change> {
+        let mut diffs = Vec::new();
+        diff_meta(
+            &mut diffs,
+            (&self.prompt_type, &other.prompt_type),
+            &self.meta,
+            &other.meta,
+        );
+        diff_target(
+            &mut diffs,
+            (&self.vendor, &self.model),
+            (&other.vendor, &other.model),
+        );
+        if self.context != other.context {
+            diffs.push(Change::ContextChanged {
+                from: self.context.clone(),
+                to: other.context.clone(),
+            });
+        }
+        diff_parameters(&mut diffs, &self.parameters, &other.parameters);
+        diff_sampling(&mut diffs, &self.sampling, &other.sampling);
+        diffs.extend(diff_items(
+            self.examples.as_deref(),
+            other.examples.as_deref(),
+            |index, from, to| Change::ExampleChanged { index, from, to },
+        ));
+        diffs.extend(diff_items(
+            self.messages.as_deref(),
+            other.messages.as_deref(),
+            |index, from, to| Change::MessageChanged { index, from, to },
+        ));
+        diff_field(
+            &mut diffs,
+            "parameters_by_env",
+            &self.parameters_by_env,
+            &other.parameters_by_env,
+        );
+        diff_field(&mut diffs, "tools", &self.tools, &other.tools);
+        diff_field(
+            &mut diffs,
+            "tool_choice",
+            &self.tool_choice,
+            &other.tool_choice,
+        );
+        diff_field(&mut diffs, "output", &self.output, &other.output);
+        diff_field(&mut diffs, "policy", &self.policy, &other.policy);
+        diff_field(
+            &mut diffs,
+            "post_process",
+            &self.post_process,
+            &other.post_process,
+        );
+        diffs
+    }
+}
+
+impl Embedding {
+    pub fn diff(&self, other: &Embedding) -> Vec<Change> {
+        let mut diffs = Vec::new();
+        diff_meta(
+            &mut diffs,
+            (&self.prompt_type, &other.prompt_type),
+            &self.meta,
+            &other.meta,
+        );
+        diff_target(
+            &mut diffs,
+            (&self.vendor, &self.model),
+            (&other.vendor, &other.model),
+        );
+        let settings = |embedding: &Embedding| {
+            [
+                ("dimensions", embedding.dimensions.map(Value::from)),
+                (
+                    "encoding_format",
+                    embedding
+                        .encoding_format
+                        .and_then(|f| serde_yaml::to_value(f).ok()),
+                ),
+            ]
+        };
+        for ((name, from), (_, to)) in settings(self).into_iter().zip(settings(other)) {
+            if from != to {
+                diffs.push(Change::ParameterChanged {
+                    name: name.to_string(),
+                    from,
+                    to,
+                });
+            }
+        }
+        diffs.extend(diff_items(
+            Some(&self.input),
+            Some(&other.input),
+            |index, from, to| Change::InputChanged { index, from, to },
+        ));
+        diffs
+    }
+}
+
+fn type_name(prompt: &Prompt) -> String {
+    match prompt {
+        Prompt::Completion(_) => "completion".to_string(),
+        Prompt::Chat(_) => "chat".to_string(),
+        Prompt::Embedding(_) => "embedding".to_string(),
+        Prompt::Custom(kind) => kind.kind().to_string(),
+        Prompt::Unknown => "unknown".to_string(),
+    }
+}
+
+fn text_diff(change: &Change) -> Option<TextDiff> {
+    let (field, from, to) = match change {
+        Change::PromptChanged { from, to } => ("prompt", from.as_str(), to.as_str()),
+        Change::ContextChanged { from, to } => (
+            "context",
+            from.as_deref().unwrap_or(""),
+            to.as_deref().unwrap_or(""),
+        ),
+        Change::SystemChanged { from, to } => (
+            "system",
+            from.as_deref().unwrap_or(""),
+            to.as_deref().unwrap_or(""),
+        ),
+        _ => return None,
+    };
+    Some(TextDiff {
+        field: field.to_string(),
+        lines: line_diff(from, to),
+    })
+}
+
+impl Prompt {
+    pub fn diff(&self, other: &Prompt) -> PromptDiff {
+        let changes = match (self, other) {
+            (Prompt::Completion(a), Prompt::Completion(b)) => a.diff(b),
+            (Prompt::Chat(a), Prompt::Chat(b)) => a.diff(b),
+            (Prompt::Embedding(a), Prompt::Embedding(b)) => a.diff(b),
+            _ if type_name(self) != type_name(other) => vec![Change::TypeChanged {
+                from: type_name(self),
+                to: type_name(other),
+            }],
+            _ => Vec::new(),
+        };
+        PromptDiff {
+            text: changes.iter().filter_map(text_diff).collect(),
+            changes,
+        }
+    }
+}
+
+impl PromptDiff {
+    pub fn is_empty(&self) -> bool {
+        self.changes.is_empty()
+    }
+
+    pub fn to_json(&self) -> serde_json::Value {
+        serde_json::to_value(self).unwrap_or_default()
+    }
+}
+
+fn describe(value: &Option<Value>) -> String {
+    match value {
+        Some(value) => serde_json::to_string(value).unwrap_or_default(),
+        None => "none".to_string(),
+    }
+}
+
+fn added_removed<T>(from: &Option<T>, to: &Option<T>) -> &'static str {
+    match (from, to) {
+        (None, _) => "added",
+        (_, None) => "removed",
+        _ => "changed",
+    }
+}
+
+impl fmt::Display for Change {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Change::TypeChanged { from, to } => write!(f, "type changed from {} to {}", from, to),
+            Change::VendorChanged { from, to } => {
+                write!(f, "vendor changed from {} to {}", from, to)
+            }
+            Change::ModelChanged { from, to } => write!(f, "model changed from {} to {}", from, to),
+            Change::PromptChanged { .. } => write!(f, "prompt text changed"),
+            Change::ContextChanged { from, to } => {
+                write!(f, "context {}", added_removed(from, to))
+            }
+            Change::SystemChanged { from, to } => {
+                write!(f, "system {}", added_removed(from, to))
+            }
+            Change::SuffixChanged { from, to } => {
+                write!(f, "suffix {}", added_removed(from, to))
+            }
+            Change::ParameterChanged { name, from, to } => write!(
+                f,
+                "parameter {} {} ({} -> {})",
+                name,
+                added_removed(from, to),
+                describe(from),
+                describe(to)
+            ),
+            Change::ExampleCellChanged {
+                column,
+                row,
+                from,
+                to,
+            } => write!(
+                f,
+                "example {} row {} {}",
+                column,
+                row,
+                added_removed(from, to)
+            ),
+            Change::TestChanged { column, from, to } => {
+                write!(f, "test {} {}", column, added_removed(from, to))
+            }
+            Change::ExampleChanged { index, from, to } => {
+                write!(f, "example {} {}", index, added_removed(from, to))
+            }
+            Change::MessageChanged { index, from, to } => {
+                write!(f, "message {} {}", index, added_removed(from, to))
+            }
+            Change::InputChanged { index, from, to } => {
+                write!(f, "input {} {}", index, added_removed(from, to))
+            }
+            Change::FieldChanged { field, from, to } => {
+                write!(f, "{} {}", field, added_removed(from, to))
+            }
+        }
+    }
+}
+
+impl fmt::Display for PromptDiff {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for change in &self.changes {
+            writeln!(f, "{}", change)?;
+        }
+        for text in &self.text {
+            writeln!(f, "--- {}", text.field)?;
+            for line in &text.lines {
+                match line {
+                    LineChange::Same(line) => writeln!(f, "  {}", line)?,
+                    LineChange::Added(line) => writeln!(f, "+ {}", line)?,
+                    LineChange::Removed(line) => writeln!(f, "- {}", line)?,
+                }
+            }
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::prompt::{deserialize_prompt, Prompt};
+
+    fn completion(yaml: &str) -> Completion {
+        match deserialize_prompt(yaml) {
+            Prompt::Completion(completion) => completion,
+            other => panic!("Expected Prompt::Completion, got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn test_diff_ignores_field_order() {
+        let a = completion(
+            r#"
+            type: completion
+            vendor: google
+            model: text-bison
+            prompt: Hello
+            parameters:
+                - name: temperature
+                  value: 0.4
+                - name: maxOutputTokens
+                  value: 256
+        "#,
+        );
+        let b = completion(
+            r#"
+            model: text-bison
+            type: completion
+            prompt: Hello
+            vendor: google
+            parameters:
+                - name: maxOutputTokens
+                  value: 256
+                - name: temperature
+                  value: 0.4
+        "#,
+        );
+        assert!(a.diff(&b).is_empty());
+    }
+
+    #[test]
+    fn test_diff_reports_changes() {
+        let a = completion(
+            r#"
+            type: completion
+            vendor: google
+            model: text-bison
+            prompt: Hello
+            parameters:
+                - name: temperature
+                  value: 0.4
+            examples:
+                - name: input
+                  values:
+                    - a
+                    - b
+        "#,
+        );
+        let b = completion(
+            r#"
+            type: completion
+            vendor: google
+            model: text-bison
+            prompt: Hello there
+            parameters:
+                - name: temperature
+                  value: 0.7
+                - name: topK
+                  value: 40
+            examples:
+                - name: input
+                  values:
+                    - a
+                    - c
+        "#,
+        );
+        assert_eq!(
+            a.diff(&b),
+            vec![
+                Change::PromptChanged {
+                    from: "Hello".to_string(),
+                    to: "Hello there".to_string(),
+                },
+                Change::ParameterChanged {
+                    name: "temperature".to_string(),
+                    from: Some(Value::from(0.4)),
+                    to: Some(Value::from(0.7)),
+                },
+                Change::ParameterChanged {
+                    name: "topK".to_string(),
+                    from: None,
+                    to: Some(Value::from(40)),
+                },
+                Change::ExampleCellChanged {
+                    column: "input".to_string(),
+                    row: 1,
+                    from: Some("b".to_string()),
+                    to: Some("c".to_string()),
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn test_diff_reports_test_values() {
+        let yaml = |test: &str| {
+            completion(&format!(
+                "type: completion\nvendor: google\nmodel: text-bison\nprompt: Hello\nexamples:\n  - name: input\n    values: [a]\n{}",
+                test
+            ))
+        };
+        let (a, b) = (yaml("    test: x\n"), yaml("    test: y\n"));
+        assert_ne!(a, b);
+        assert_eq!(
+            a.diff(&b),
+            vec![Change::TestChanged {
+                column: "input".to_string(),
+                from: Some("x".to_string()),
+                to: Some("y".to_string()),
+            }]
+        );
+        assert_eq!(yaml("").diff(&b)[0].to_string(), "test input added");
+    }
+
+    #[test]
+    fn test_diff_covers_every_compared_field() {
+        let completion = "type: completion\nvendor: openai\nmodel: gpt-4o\nprompt: hi\n";
+        let chat = "type: chat\nvendor: openai\nmodel: gpt-4o\nmessages: [{input: hi}]\n";
+        let variants = [
+            (completion, "description: greets\n"),
+            (completion, "output: {format: json}\n"),
+            (completion, "policy: {max_retries: 3}\n"),
+            (completion, "post_process: [trim]\n"),
+            (completion, "example_format: {separator: '---'}\n"),
+            (
+                completion,
+                "parameters_by_env: {test: [{name: topK, value: 1}]}\n",
+            ),
+            (chat, "tools: [{name: f, parameters: {type: object}}]\n"),
+            (chat, "tool_choice: required\n"),
+            (chat, "output: {format: json}\n"),
+            (chat, "policy: {max_retries: 3}\n"),
+        ];
+        for (base, field) in variants {
+            let (a, b) = (
+                deserialize_prompt(base),
+                deserialize_prompt(&format!("{}{}", base, field)),
+            );
+            assert_ne!(a, b, "{}", field);
+            assert!(!a.diff(&b).changes.is_empty(), "{}", field);
+        }
+    }
+
+    #[test]
+    fn test_prompt_diff() {
+        let a = deserialize_prompt(
+            r#"
+            type: chat
+            vendor: openai
+            model: gpt-4o
+            context: "You are terse.\nAnswer in English."
+            examples:
+                - input: hi
+                  output: hello
+            parameters:
+                - name: temperature
+                  value: 0.2
+        "#,
+        );
+        let b = deserialize_prompt(
+            r#"
+            type: chat
+            vendor: openai
+            model: gpt-4o-mini
+            context: "You are terse.\nAnswer in French."
+            examples:
+                - input: hi
+                  output: hello
+                - input: bye
+                  output: au revoir
+        "#,
+        );
+        let diff = a.diff(&b);
+        assert_eq!(
+            diff.text[0].lines,
+            vec![
+                LineChange::Same("You are terse.".to_string()),
+                LineChange::Removed("Answer in English.".to_string()),
+                LineChange::Added("Answer in French.".to_string()),
+            ]
+        );
+        assert_eq!(
+            diff.to_string(),
+            "model changed from gpt-4o to gpt-4o-mini\n\
+             context changed\n\
+             parameter temperature removed (0.2 -> none)\n\
+             example 1 added\n\
+             --- context\n  You are terse.\n- Answer in English.\n+ Answer in French.\n"
+        );
+        assert_eq!(diff.to_json()["changes"][3]["change"], "example_changed");
+        assert_eq!(diff.to_json()["text"][0]["lines"][1]["op"], "removed");
+        assert!(a.diff(&a).is_empty());
+
+        let completion =
+            deserialize_prompt("type: completion\nvendor: openai\nmodel: gpt-4o\nprompt: hi\n");
+        assert_eq!(
+            a.diff(&completion).changes,
+            vec![Change::TypeChanged {
+                from: "chat".to_string(),
+                to: "completion".to_string(),
+            }]
+        );
+    }
+}
@@ -0,0 +1,561 @@
+use crate::cost::{Cost, PriceTable};
+use crate::exec::{BoxFuture, ExecError, ExecutionResult, PromptExecutor};
+use crate::pattern::{Pattern, PatternError};
+use crate::prompt::{Chat, Completion, Message, Prompt, PromptError};
+use crate::tokens::{HeuristicTokenizer, Tokenizer};
+use std::collections::HashMap;
+use std::fmt;
+use std::sync::Arc;
+
+pub trait Matcher: Send + Sync {
+    fn matches<'a>(
+        &'a self,
+        expected: &'a str,
+        actual: &'a str,
+    ) -> BoxFuture<'a, Result<bool, ExecError>>;
+}
+
+fn ready<'a>(passed: bool) -> BoxFuture<'a, Result<bool, ExecError>> {
+    Box::pin(async move { Ok(passed) })
+}
+
+#[derive(Debug, Clone, Copy, Default)]
+pub struct Exact;
+
+impl Matcher for Exact {
+    fn matches<'a>(
+        &'a self,
+        expected: &'a str,
+        actual: &'a str,
+    ) -> BoxFuture<'a, Result<bool, ExecError>> {
+        ready(expected.trim() == actual.trim())
+    }
+}
+
+#[derive(Debug, Clone, Copy, Default)]
+pub struct Contains;
+
+impl Matcher for Contains {
+    fn matches<'a>(
+        &'a self,
+        expected: &'a str,
+        actual: &'a str,
+    ) -> BoxFuture<'a, Result<bool, ExecError>> {
+        ready(
+            actual
+                .to_lowercase()
+                .contains(&expected.trim().to_lowercase()),
+        )
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct Regex(Pattern);
+
+impl Regex {
+    pub fn new(pattern: &str) -> Result<Regex, PatternError> {
+        Pattern::new(pattern).map(Regex)
+    }
+}
+
+impl Matcher for Regex {
+    fn matches<'a>(
+        &'a self,
+        _: &'a str,
+        actual: &'a str,
+    ) -> BoxFuture<'a, Result<bool, ExecError>> {
+        ready(self.0.is_match(actual))
+    }
+}
+
+#[derive(Debug, Clone, Copy)]
+pub struct Numeric {
+    pub tolerance: f64,
+}
+
+fn first_number(text: &str) -> Option<f64> {
+    let start = text.find(|c: char| c.is_ascii_digit() || c == '-')?;
+    let number: String = text[start..]
+        .chars()
+        .enumerate()
+        .take_while(|(i, c)| c.is_ascii_digit() || *c == '.' || (*i == 0 && *c == '-'))
+        .map(|(_, c)| c)
+        .collect();
+    number.trim_end_matches('.').parse().ok()
+}
+
+impl Matcher for Numeric {
+    fn matches<'a>(
+        &'a self,
+        expected: &'a str,
+        actual: &'a str,
+    ) -> BoxFuture<'a, Result<bool, ExecError>> {
+        let passed = match (first_number(expected), first_number(actual)) {
+            (Some(expected), Some(actual)) => (expected - actual).abs() <= self.tolerance,
+            _ => false,
+        };
+        ready(passed)
+    }
+}
+
+// The grader prompt is rendered with `expected` and `actual` variables and
+// passes when its reply starts with "pass" or "yes".
+pub struct LlmGraded {
+    executor: Arc<dyn PromptExecutor>,
+    grader: Prompt,
+}
+
+impl LlmGraded {
+    pub fn new(executor: Arc<dyn PromptExecutor>, grader: Prompt) -> LlmGraded {
+        LlmGraded { executor, grader }
+    }
+}
+
+impl Matcher for LlmGraded {
+    fn matches<'a>(
+        &'a self,
+        expected: &'a str,
+        actual: &'a str,
+    ) -> BoxFuture<'a, Result<bool, ExecError>> {
+        Box::pin(async move {
+            let vars = HashMap::from([
+                ("expected".to_string(), expected.to_string()),
+                ("actual".to_string(), actual.to_string()),
+            ]);
+            let verdict = self.executor.execute(&self.grader, &vars).await?;
+            let verdict = verdict.text.trim().to_lowercase();
+            Ok(verdict.starts_with("pass") || verdict.starts_with("yes"))
+        })
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct EvalCase {
+    pub name: String,
+    pub prompt: Prompt,
+    pub expected: String,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct CaseResult {
+    pub name: String,
+    pub expected: String,
+    pub actual: Option<String>,
+    pub passed: bool,
+    pub error: Option<String>,
+    pub cost: Option<Cost>,
+}
+
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct EvalReport {
+    pub results: Vec<CaseResult>,
+}
+
+impl EvalReport {
+    pub fn total(&self) -> usize {
+        self.results.len()
+    }
+
+    pub fn passed(&self) -> usize {
+        self.results.iter().filter(|r| r.passed).count()
+    }
+
+    pub fn failed(&self) -> usize {
+        self.total() - self.passed()
+    }
+
+    pub fn pass_rate(&self) -> f64 {
+        if self.results.is_empty() {
+            return 0.0;
+        }
+        self.passed() as f64 / self.total() as f64
+    }
+
+    pub fn is_success(&self) -> bool {
+        self.failed() == 0
+    }
+
+    pub fn failures(&self) -> impl Iterator<Item = &CaseResult> {
+        self.results.iter().filter(|r| !r.passed)
+    }
+
+    pub fn total_cost(&self) -> Option<Cost> {
+        let costs: Vec<Cost> = self.results.iter().filter_map(|r| r.cost).collect();
+        if costs.is_empty() {
+            None
+        } else {
+            Some(costs.into_iter().sum())
+        }
+    }
+}
+
+impl fmt::Display for EvalReport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(f, "{}/{} passed", self.passed(), self.total())?;
+        for failure in self.failures() {
+            match (&failure.error, &failure.actual) {
+                (Some(error), _) => writeln!(f, "  {}: error: {}", failure.name, error)?,
+                (None, actual) => writeln!(
+                    f,
+                    "  {}: expected {:?}, got {:?}",
+                    failure.name,
+                    failure.expected,
+                    actual.as_deref().unwrap_or("")
+                )?,
+            }
+        }
+        if let Some(cost) = self.total_cost() {
+            writeln!(f, "cost: {}", cost)?;
+        }
+        Ok(())
+    }
+}
+
+impl Completion {
+    pub fn eval_cases(&self, output_column: &str) -> Result<Vec<EvalCase>, PromptError> {
+        let output = self.find_column(output_column)?;
+        let mut cases = Vec::new();
+        for row in 0..self.example_count() {
+            let mut case = self.clone();
+            for column in case.examples.iter_mut().flatten() {
+                let value = if row < column.values.len() {
+                    column.values.remove(row)
+                } else {
+                    String::new()
+                };
+                column.test = Some(if column.name == output_column {
+                    String::new()
+                } else {
+                    value
+                });
+            }
+            cases.push(EvalCase {
+                name: format!("examples[{}]", row),
+                prompt: Prompt::Completion(case),
+                expected: output.values.get(row).cloned().unwrap_or_default(),
+            });
+        }
+        if let Some(expected) = output.test.as_ref().filter(|t| !t.is_empty()) {
+            let mut case = self.clone();
+            for column in case.examples.iter_mut().flatten() {
+                if column.name == output_column {
+                    column.test = Some(String::new());
+                }
+            }
+            cases.push(EvalCase {
+                name: "test".to_string(),
+                prompt: Prompt::Completion(case),
+                expected: expected.clone(),
+            });
+        }
+        Ok(cases)
+    }
+}
+
+impl Chat {
+    pub fn eval_cases(&self) -> Vec<EvalCase> {
+        let examples = self.examples.as_deref().unwrap_or_default();
+        examples
+            .iter()
+            .enumerate()
+            .filter_map(|(i, example)| {
+                let expected = example.output.clone()?;
+                let mut case = self.clone();
+                let mut others = examples.to_vec();
+                others.remove(i);
+                case.examples = Some(others).filter(|o| !o.is_empty());
+                case.messages = Some(vec![Message {
+                    input: example.input.clone(),
+                    ..Message::default()
+                }]);
+                Some(EvalCase {
+                    name: format!("examples[{}]", i),
+                    prompt: Prompt::Chat(case),
+                    expected,
+                })
+            })
+            .collect()
+    }
+}
+
+pub struct Evaluator<'a> {
+    executor: &'a dyn PromptExecutor,
+    matcher: Box<dyn Matcher + 'a>,
+    output_column: String,
+    prices: PriceTable,
+}
+
+impl<'a> Evaluator<'a> {
+    pub fn new(executor: &'a dyn PromptExecutor) -> Evaluator<'a> {
+        Evaluator {
+            executor,
+            matcher: Box::new(Exact),
+            output_column: "output".to_string(),
+            prices: PriceTable::builtin(),
+        }
+    }
+
+    pub fn matcher(mut self, matcher: impl Matcher + 'a) -> Self {
+        self.matcher = Box::new(matcher);
+        self
+    }
+
+    pub fn output_column(mut self, column: impl Into<String>) -> Self {
+        self.output_column = column.into();
+        self
+    }
+
+    pub fn prices(mut self, prices: PriceTable) -> Self {
+        self.prices = prices;
+        self
+    }
+
+    pub fn cases(&self, prompt: &Prompt) -> Result<Vec<EvalCase>, PromptError> {
+        match prompt {
+            Prompt::Completion(completion) => completion.eval_cases(&self.output_column),
+            Prompt::Chat(chat) => Ok(chat.eval_cases()),
+            Prompt::Embedding(_) | Prompt::Custom(_) | Prompt::Unknown => Ok(Vec::new()),
+        }
+    }
+
+    pub async fn run(
+        &self,
+        prompt: &Prompt,
+        vars: &HashMap<String, String>,
+    ) -> Result<EvalReport, PromptError> {
+        let mut report = EvalReport::default();
+        for case in self.cases(prompt)? {
+            report.results.push(self.run_case(case, vars).await);
+        }
+        Ok(report)
+    }
+
+    fn case_cost(
+        &self,
+        prompt: &Prompt,
+        vars: &HashMap<String, String>,
+        output: &ExecutionResult,
+    ) -> Option<Cost> {
+        match output.usage() {
+            Some(usage) => prompt
+                .estimate_cost_with(&self.prices, usage.input_tokens, usage.output_tokens)
+                .ok(),
+            None => {
+                let tokenizer = HeuristicTokenizer::default();
+                let output_tokens = tokenizer.count_tokens(&output.text);
+                prompt
+                    .estimate_request_cost(&self.prices, &tokenizer, vars, output_tokens)
+                    .ok()
+            }
+        }
+    }
+
+    async fn run_case(&self, case: EvalCase, vars: &HashMap<String, String>) -> CaseResult {
+        let mut result = CaseResult {
+            name: case.name,
+            expected: case.expected,
+            actual: None,
+            passed: false,
+            error: None,
+            cost: None,
+        };
+        match self.executor.execute(&case.prompt, vars).await {
+            Ok(output) => {
+                result.cost = self.case_cost(&case.prompt, vars, &output);
+                match self.matcher.matches(&result.expected, &output.text).await {
+                    Ok(passed) => result.passed = passed,
+                    Err(error) => result.error = Some(error.to_string()),
+                }
+                result.actual = Some(output.text);
+            }
+            Err(error) => result.error = Some(error.to_string()),
+        }
+        result
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::cost::{ModelPrice, Usage};
+    use crate::exec::block_on;
+    use crate::prompt::deserialize_prompt;
+    use serde_json::Value;
+
+    struct Answers(HashMap<&'static str, &'static str>);
+
+    impl PromptExecutor for Answers {
+        fn execute<'a>(
+            &'a self,
+            prompt: &'a Prompt,
+            _: &'a HashMap<String, String>,
+        ) -> BoxFuture<'a, Result<ExecutionResult, ExecError>> {
+            let question = match prompt {
+                Prompt::Completion(c) => c.final_prompt(),
+                Prompt::Chat(c) => c.messages.as_ref().unwrap()[0].input.clone(),
+                Prompt::Embedding(_) | Prompt::Custom(_) | Prompt::Unknown => String::new(),
+            };
+            let answer = self
+                .0
+                .iter()
+                .find(|(q, _)| question.ends_with(&format!("{}\noutput: \n", q)) || question == **q)
+                .map(|(_, a)| a.to_string());
+            Box::pin(async move {
+                answer
+                    .map(|text| ExecutionResult {
+                        text,
+                        raw: Value::Null,
+                    })
+                    .ok_or_else(|| ExecError::InvalidResponse("no answer".to_string()))
+            })
+        }
+    }
+
+    #[test]
+    fn test_completion_eval() {
+        let yaml = r#"
+            type: completion
+            vendor: openai
+            model: gpt-4o-mini
+            prompt: Add the numbers
+            examples:
+                - name: input
+                  values: ["1 + 1", "2 + 2", "3 + 3"]
+                  test: "4 + 4"
+                - name: output
+                  values: ["2", "4", "6"]
+                  test: "8"
+        "#;
+        let executor = Answers(HashMap::from([
+            ("input: 1 + 1", "2"),
+            ("input: 2 + 2", "5"),
+            ("input: 3 + 3", "The answer is 6.0"),
+            ("input: 4 + 4", "8"),
+        ]));
+        let prompt = deserialize_prompt(yaml);
+
+        let exact = block_on(Evaluator::new(&executor).run(&prompt, &HashMap::new())).unwrap();
+        assert_eq!((exact.total(), exact.passed()), (4, 2));
+        assert_eq!(
+            exact
+                .failures()
+                .map(|f| f.name.as_str())
+                .collect::<Vec<_>>(),
+            vec!["examples[1]", "examples[2]"]
+        );
+
+        let numeric = Evaluator::new(&executor).matcher(Numeric { tolerance: 0.5 });
+        let report = block_on(numeric.run(&prompt, &HashMap::new())).unwrap();
+        assert_eq!(report.passed(), 3);
+        assert!(report.to_string().starts_with("3/4 passed\n"));
+
+        let missing = Evaluator::new(&executor).output_column("answer");
+        assert!(block_on(missing.run(&prompt, &HashMap::new())).is_err());
+    }
+
+    #[test]
+    fn test_eval_cost() {
+        let yaml = r#"
+            type: completion
+            vendor: openai
+            model: gpt-4o-mini
+            prompt: Add the numbers
+            examples:
+                - name: input
+                  values: ["1 + 1", "2 + 2"]
+                - name: output
+                  values: ["2", "4"]
+        "#;
+        let executor = Answers(HashMap::from([
+            ("input: 1 + 1", "2"),
+            ("input: 2 + 2", "4"),
+        ]));
+        let prompt = deserialize_prompt(yaml);
+        let prices = PriceTable::new().with_price(
+            "openai",
+            "gpt-4o-mini",
+            ModelPrice::new(1_000_000.0, 0.0),
+        );
+        let report = block_on(
+            Evaluator::new(&executor)
+                .prices(prices)
+                .run(&prompt, &HashMap::new()),
+        )
+        .unwrap();
+        let cost = report.total_cost().unwrap();
+        assert_eq!(cost.input_cost, cost.input_tokens as f64);
+        assert_eq!(
+            cost.input_tokens,
+            report
+                .results
+                .iter()
+                .map(|r| r.cost.unwrap().input_tokens)
+                .sum::<usize>()
+        );
+        assert!(report.to_string().contains("\ncost: $"));
+
+        let raw = serde_json::json!({ "usage": { "input_tokens": 12, "output_tokens": 3 } });
+        let result = ExecutionResult {
+            text: String::new(),
+            raw,
+        };
+        assert_eq!(
+            result.usage(),
+            Some(Usage {
+                input_tokens: 12,
+                output_tokens: 3
+            })
+        );
+    }
+
+    #[test]
+    fn test_chat_eval_with_grader() {
+        let yaml = r#"
+            type: chat
+            vendor: openai
+            model: gpt-4o-mini
+            examples:
+                - input: capital of france
+                  output: Paris
+                - input: capital of spain
+                  output: Madrid
+        "#;
+        let executor = Answers(HashMap::from([
+            ("capital of france", "It is Paris."),
+            ("capital of spain", "Barcelona"),
+        ]));
+        let prompt = deserialize_prompt(yaml);
+
+        let report = block_on(
+            Evaluator::new(&executor)
+                .matcher(Contains)
+                .run(&prompt, &HashMap::new()),
+        )
+        .unwrap();
+        assert_eq!(report.passed(), 1);
+
+        let grader = deserialize_prompt(
+            "type: chat\nvendor: openai\nmodel: gpt-4o\nmessages:\n  - input: grade\n",
+        );
+        let graded = LlmGraded::new(
+            Arc::new(Answers(HashMap::from([("grade", "PASS")]))),
+            grader,
+        );
+        let report = block_on(
+            Evaluator::new(&executor)
+                .matcher(graded)
+                .run(&prompt, &HashMap::new()),
+        )
+        .unwrap();
+        assert!(report.is_success());
+        assert_eq!(report.pass_rate(), 1.0);
+
+        let regex = Evaluator::new(&executor).matcher(Regex::new("^It is").unwrap());
+        assert_eq!(
+            block_on(regex.run(&prompt, &HashMap::new()))
+                .unwrap()
+                .passed(),
+            1
+        );
+    }
+}
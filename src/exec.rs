@@ -0,0 +1,979 @@
+use crate::base64;
+use crate::cache::CacheError;
+use crate::pipeline::PipelineError;
+use crate::prompt::{Chat, Completion, Embedding, Prompt};
+use crate::request::{is_azure, is_gemini, Endpoint, RequestError, Vendor};
+use crate::stream::StreamingExecutor;
+use crate::template::RenderError;
+use serde_json::Value;
+use std::collections::HashMap;
+use std::fmt;
+use std::future::Future;
+use std::pin::Pin;
+use std::sync::Arc;
+use std::task::{Context, Poll, Wake, Waker};
+use std::thread::{self, Thread};
+use std::time::Duration;
+
+pub type BoxFuture<'a, T> = Pin<Box<dyn Future<Output = T> + Send + 'a>>;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ExecError {
+    Render(RenderError),
+    Request(RequestError),
+    Http(String),
+    Status { status: u16, body: String },
+    InvalidResponse(String),
+    NotConfigured(String),
+    Timeout(Duration),
+    Pipeline(PipelineError),
+    NoResponse(String),
+    Fixture(CacheError),
+}
+
+impl fmt::Display for ExecError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ExecError::Render(error) => write!(f, "{}", error),
+            ExecError::Request(error) => write!(f, "{}", error),
+            ExecError::Http(message) => write!(f, "http error: {}", message),
+            ExecError::Status { status, body } => write!(f, "http status {}: {}", status, body),
+            ExecError::InvalidResponse(message) => write!(f, "invalid response: {}", message),
+            ExecError::NotConfigured(vendor) => {
+                write!(f, "no executor configured for vendor '{}'", vendor)
+            }
+            ExecError::Timeout(timeout) => {
+                write!(f, "request timed out after {}ms", timeout.as_millis())
+            }
+            ExecError::Pipeline(error) => write!(f, "{}", error),
+            ExecError::NoResponse(prompt) => write!(f, "no recorded response for {}", prompt),
+            ExecError::Fixture(error) => write!(f, "fixture error: {}", error),
+        }
+    }
+}
+
+impl std::error::Error for ExecError {}
+
+impl From<RenderError> for ExecError {
+    fn from(error: RenderError) -> Self {
+        ExecError::Render(error)
+    }
+}
+
+impl From<PipelineError> for ExecError {
+    fn from(error: PipelineError) -> Self {
+        ExecError::Pipeline(error)
+    }
+}
+
+impl From<RequestError> for ExecError {
+    fn from(error: RequestError) -> Self {
+        ExecError::Request(error)
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct HttpRequest {
+    pub url: String,
+    pub headers: Vec<(String, String)>,
+    pub body: Value,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct HttpResponse {
+    pub status: u16,
+    pub body: String,
+}
+
+pub trait HttpClient: Send + Sync {
+    fn post(&self, request: HttpRequest) -> BoxFuture<'_, Result<HttpResponse, ExecError>>;
+
+    fn post_stream<'a>(
+        &'a self,
+        request: HttpRequest,
+        on_chunk: &'a mut (dyn FnMut(&str) + Send),
+    ) -> BoxFuture<'a, Result<HttpResponse, ExecError>> {
+        Box::pin(async move {
+            let response = self.post(request).await?;
+            on_chunk(&response.body);
+            Ok(response)
+        })
+    }
+}
+
+impl<C: HttpClient + ?Sized> HttpClient for Arc<C> {
+    fn post(&self, request: HttpRequest) -> BoxFuture<'_, Result<HttpResponse, ExecError>> {
+        (**self).post(request)
+    }
+
+    fn post_stream<'a>(
+        &'a self,
+        request: HttpRequest,
+        on_chunk: &'a mut (dyn FnMut(&str) + Send),
+    ) -> BoxFuture<'a, Result<HttpResponse, ExecError>> {
+        (**self).post_stream(request, on_chunk)
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct ExecutionResult {
+    pub text: String,
+    pub raw: Value,
+}
+
+fn embedding_vector(value: &Value) -> Option<Vec<f32>> {
+    match value {
+        Value::Array(values) => values
+            .iter()
+            .map(|v| v.as_f64().map(|v| v as f32))
+            .collect(),
+        Value::String(encoded) => Some(
+            base64::decode(encoded)?
+                .chunks_exact(4)
+                .map(|b| f32::from_le_bytes([b[0], b[1], b[2], b[3]]))
+                .collect(),
+        ),
+        _ => None,
+    }
+}
+
+fn embeddings_in(raw: &Value) -> Option<Vec<Vec<f32>>> {
+    if let Some(data) = raw.get("data").and_then(|d| d.as_array()) {
+        return data
+            .iter()
+            .map(|item| embedding_vector(item.get("embedding")?))
+            .collect();
+    }
+    raw.get("predictions")?
+        .as_array()?
+        .iter()
+        .map(|p| embedding_vector(p.pointer("/embeddings/values")?))
+        .collect()
+}
+
+impl ExecutionResult {
+    pub fn embeddings(&self) -> Option<Vec<Vec<f32>>> {
+        embeddings_in(&self.raw)
+    }
+}
+
+pub trait PromptExecutor: Send + Sync {
+    fn execute<'a>(
+        &'a self,
+        prompt: &'a Prompt,
+        vars: &'a HashMap<String, String>,
+    ) -> BoxFuture<'a, Result<ExecutionResult, ExecError>>;
+}
+
+impl Prompt {
+    pub async fn execute(
+        &self,
+        executor: &(impl PromptExecutor + ?Sized),
+        vars: &HashMap<String, String>,
+    ) -> Result<ExecutionResult, ExecError> {
+        executor.execute(self, vars).await
+    }
+}
+
+pub(crate) fn check_status(response: HttpResponse) -> Result<HttpResponse, ExecError> {
+    if !(200..300).contains(&response.status) {
+        return Err(ExecError::Status {
+            status: response.status,
+            body: response.body,
+        });
+    }
+    Ok(response)
+}
+
+pub(crate) async fn send_json(
+    client: &dyn HttpClient,
+    request: HttpRequest,
+) -> Result<Value, ExecError> {
+    let response = check_status(client.post(request).await?)?;
+    serde_json::from_str(&response.body).map_err(|e| ExecError::InvalidResponse(e.to_string()))
+}
+
+pub(crate) fn text_at(raw: &Value, pointer: &str) -> Result<String, ExecError> {
+    raw.pointer(pointer)
+        .and_then(|v| match v {
+            Value::Array(parts) => Some(parts.iter().filter_map(|p| p["text"].as_str()).collect()),
+            v => v.as_str().map(|s| s.to_string()),
+        })
+        .ok_or_else(|| ExecError::InvalidResponse(format!("missing {}", pointer)))
+}
+
+fn result_text(raw: &Value, pointer: Option<&str>) -> Result<String, ExecError> {
+    match pointer {
+        Some(pointer) => text_at(raw, pointer),
+        None => embeddings_in(raw)
+            .map(|vectors| serde_json::to_string(&vectors).unwrap_or_default())
+            .ok_or_else(|| ExecError::InvalidResponse("missing embeddings".to_string())),
+    }
+}
+
+pub(crate) fn render_prompt(
+    prompt: &Prompt,
+    vars: &HashMap<String, String>,
+) -> Result<RenderedPrompt, ExecError> {
+    match prompt {
+        Prompt::Completion(completion) => {
+            let mut rendered = completion.clone();
+            rendered.prompt = completion.render(vars)?;
+            rendered.examples = None;
+            Ok(RenderedPrompt::Completion(rendered))
+        }
+        Prompt::Chat(chat) => Ok(RenderedPrompt::Chat(chat.render(vars)?)),
+        Prompt::Embedding(embedding) => Ok(RenderedPrompt::Embedding(embedding.render(vars)?)),
+        Prompt::Custom(_) | Prompt::Unknown => Err(RequestError::UnsupportedPrompt.into()),
+    }
+}
+
+fn with_stream_flag(mut body: Value, stream: bool) -> Value {
+    if let Some(object) = body.as_object_mut() {
+        if stream {
+            object.insert("stream".to_string(), Value::Bool(true));
+        } else {
+            object.remove("stream");
+        }
+    }
+    body
+}
+
+fn prompt_endpoint(prompt: &Prompt) -> Option<&Endpoint> {
+    prompt.meta().map(|meta| &meta.endpoint)
+}
+
+fn resolve_base_url<'a>(prompt: &'a Prompt, configured: &'a str) -> (&'a str, bool) {
+    let configured = configured.trim_end_matches('/');
+    match prompt_endpoint(prompt).and_then(|e| e.base_url.as_deref()) {
+        Some(url) if url.trim_end_matches('/') != configured => (url.trim_end_matches('/'), false),
+        _ => (configured, true),
+    }
+}
+
+fn versioned_url(base_url: &str, path: &str) -> String {
+    let base_url = base_url.strip_suffix("/v1").unwrap_or(base_url);
+    format!("{}/v1{}", base_url, path)
+}
+
+fn openai_body(
+    prompt: &Prompt,
+    vars: &HashMap<String, String>,
+    stream: bool,
+) -> Result<(&'static str, Value, Option<&'static str>), ExecError> {
+    let (path, body, pointer) = match render_prompt(prompt, vars)? {
+        RenderedPrompt::Completion(c) => (
+            "/completions",
+            c.to_openai_request(),
+            Some("/choices/0/text"),
+        ),
+        RenderedPrompt::Chat(c) => (
+            "/chat/completions",
+            c.to_openai_chat_request(),
+            Some("/choices/0/message/content"),
+        ),
+        RenderedPrompt::Embedding(e) => ("/embeddings", e.to_openai_embedding_request(), None),
+    };
+    let stream = stream && pointer.is_some();
+    Ok((path, with_stream_flag(body, stream), pointer))
+}
+
+pub(crate) enum RenderedPrompt {
+    Completion(Completion),
+    Chat(Chat),
+    Embedding(Embedding),
+}
+
+pub struct OpenAiExecutor {
+    pub(crate) client: Arc<dyn HttpClient>,
+    api_key: String,
+    base_url: String,
+}
+
+impl OpenAiExecutor {
+    pub fn new(client: Arc<dyn HttpClient>, api_key: impl Into<String>) -> OpenAiExecutor {
+        OpenAiExecutor {
+            client,
+            api_key: api_key.into(),
+            base_url: "https://api.openai.com".to_string(),
+        }
+    }
+
+    pub fn compatible(client: Arc<dyn HttpClient>, base_url: impl Into<String>) -> OpenAiExecutor {
+        OpenAiExecutor::new(client, "").base_url(base_url)
+    }
+
+    pub fn api_key(mut self, api_key: impl Into<String>) -> Self {
+        self.api_key = api_key.into();
+        self
+    }
+
+    pub fn base_url(mut self, base_url: impl Into<String>) -> Self {
+        self.base_url = base_url.into();
+        self
+    }
+
+    pub(crate) fn build_request(
+        &self,
+        prompt: &Prompt,
+        vars: &HashMap<String, String>,
+        stream: bool,
+    ) -> Result<(HttpRequest, Option<&'static str>), ExecError> {
+        let (path, body, pointer) = openai_body(prompt, vars, stream)?;
+        let (base_url, configured) = resolve_base_url(prompt, &self.base_url);
+        let mut headers = Vec::new();
+        if configured && !self.api_key.is_empty() {
+            headers.push((
+                "Authorization".to_string(),
+                format!("Bearer {}", self.api_key),
+            ));
+        }
+        let request = HttpRequest {
+            url: versioned_url(base_url, path),
+            headers,
+            body,
+        };
+        Ok((request, pointer))
+    }
+}
+
+impl PromptExecutor for OpenAiExecutor {
+    fn execute<'a>(
+        &'a self,
+        prompt: &'a Prompt,
+        vars: &'a HashMap<String, String>,
+    ) -> BoxFuture<'a, Result<ExecutionResult, ExecError>> {
+        Box::pin(async move {
+            let (request, pointer) = self.build_request(prompt, vars, false)?;
+            let raw = send_json(self.client.as_ref(), request).await?;
+            Ok(ExecutionResult {
+                text: result_text(&raw, pointer)?,
+                raw,
+            })
+        })
+    }
+}
+
+pub struct AnthropicExecutor {
+    pub(crate) client: Arc<dyn HttpClient>,
+    api_key: String,
+    base_url: String,
+}
+
+impl AnthropicExecutor {
+    pub fn new(client: Arc<dyn HttpClient>, api_key: impl Into<String>) -> AnthropicExecutor {
+        AnthropicExecutor {
+            client,
+            api_key: api_key.into(),
+            base_url: "https://api.anthropic.com".to_string(),
+        }
+    }
+
+    pub fn base_url(mut self, base_url: impl Into<String>) -> Self {
+        self.base_url = base_url.into();
+        self
+    }
+
+    pub(crate) fn build_request(
+        &self,
+        prompt: &Prompt,
+        vars: &HashMap<String, String>,
+        stream: bool,
+    ) -> Result<HttpRequest, ExecError> {
+        let body = match render_prompt(prompt, vars)? {
+            RenderedPrompt::Completion(c) => c.to_anthropic_messages_request(),
+            RenderedPrompt::Chat(c) => c.to_anthropic_messages_request(),
+            RenderedPrompt::Embedding(e) => {
+                return Err(RequestError::UnsupportedVendor(e.vendor).into())
+            }
+        };
+        let (base_url, configured) = resolve_base_url(prompt, &self.base_url);
+        let mut headers = vec![("anthropic-version".to_string(), "2023-06-01".to_string())];
+        if configured {
+            headers.insert(0, ("x-api-key".to_string(), self.api_key.clone()));
+        }
+        Ok(HttpRequest {
+            url: versioned_url(base_url, "/messages"),
+            headers,
+            body: with_stream_flag(body, stream),
+        })
+    }
+}
+
+impl PromptExecutor for AnthropicExecutor {
+    fn execute<'a>(
+        &'a self,
+        prompt: &'a Prompt,
+        vars: &'a HashMap<String, String>,
+    ) -> BoxFuture<'a, Result<ExecutionResult, ExecError>> {
+        Box::pin(async move {
+            let request = self.build_request(prompt, vars, false)?;
+            let raw = send_json(self.client.as_ref(), request).await?;
+            let text: String = raw
+                .get("content")
+                .and_then(|c| c.as_array())
+                .ok_or_else(|| ExecError::InvalidResponse("missing /content".to_string()))?
+                .iter()
+                .filter_map(|block| block.get("text").and_then(|t| t.as_str()))
+                .collect();
+            Ok(ExecutionResult { text, raw })
+        })
+    }
+}
+
+pub const DEFAULT_AZURE_API_VERSION: &str = "2024-06-01";
+
+pub struct AzureOpenAiExecutor {
+    pub(crate) client: Arc<dyn HttpClient>,
+    api_key: String,
+    endpoint: String,
+    api_version: String,
+    deployments: HashMap<String, String>,
+}
+
+impl AzureOpenAiExecutor {
+    pub fn new(
+        client: Arc<dyn HttpClient>,
+        api_key: impl Into<String>,
+        endpoint: impl Into<String>,
+    ) -> AzureOpenAiExecutor {
+        AzureOpenAiExecutor {
+            client,
+            api_key: api_key.into(),
+            endpoint: endpoint.into(),
+            api_version: DEFAULT_AZURE_API_VERSION.to_string(),
+            deployments: HashMap::new(),
+        }
+    }
+
+    pub fn api_version(mut self, api_version: impl Into<String>) -> Self {
+        self.api_version = api_version.into();
+        self
+    }
+
+    pub fn deployment(mut self, model: impl Into<String>, deployment: impl Into<String>) -> Self {
+        self.deployments.insert(model.into(), deployment.into());
+        self
+    }
+
+    pub(crate) fn build_request(
+        &self,
+        prompt: &Prompt,
+        vars: &HashMap<String, String>,
+        stream: bool,
+    ) -> Result<(HttpRequest, Option<&'static str>), ExecError> {
+        let (path, body, pointer) = openai_body(prompt, vars, stream)?;
+        let endpoint = prompt_endpoint(prompt);
+        let model = body.get("model").and_then(|m| m.as_str()).unwrap_or("");
+        let deployment = endpoint
+            .and_then(|e| e.deployment.as_deref())
+            .or_else(|| self.deployments.get(model).map(|d| d.as_str()))
+            .unwrap_or(model);
+        let api_version = endpoint
+            .and_then(|e| e.api_version.as_deref())
+            .unwrap_or(&self.api_version);
+        let (base_url, configured) = resolve_base_url(prompt, &self.endpoint);
+        let mut headers = Vec::new();
+        if configured {
+            headers.push(("api-key".to_string(), self.api_key.clone()));
+        }
+        let request = HttpRequest {
+            url: format!(
+                "{}/openai/deployments/{}{}?api-version={}",
+                base_url, deployment, path, api_version
+            ),
+            headers,
+            body,
+        };
+        Ok((request, pointer))
+    }
+}
+
+impl PromptExecutor for AzureOpenAiExecutor {
+    fn execute<'a>(
+        &'a self,
+        prompt: &'a Prompt,
+        vars: &'a HashMap<String, String>,
+    ) -> BoxFuture<'a, Result<ExecutionResult, ExecError>> {
+        Box::pin(async move {
+            let (request, pointer) = self.build_request(prompt, vars, false)?;
+            let raw = send_json(self.client.as_ref(), request).await?;
+            Ok(ExecutionResult {
+                text: result_text(&raw, pointer)?,
+                raw,
+            })
+        })
+    }
+}
+
+pub struct VertexExecutor {
+    client: Arc<dyn HttpClient>,
+    access_token: String,
+    project: String,
+    location: String,
+    base_url: Option<String>,
+}
+
+impl VertexExecutor {
+    pub fn new(
+        client: Arc<dyn HttpClient>,
+        access_token: impl Into<String>,
+        project: impl Into<String>,
+        location: impl Into<String>,
+    ) -> VertexExecutor {
+        VertexExecutor {
+            client,
+            access_token: access_token.into(),
+            project: project.into(),
+            location: location.into(),
+            base_url: None,
+        }
+    }
+
+    pub fn base_url(mut self, base_url: impl Into<String>) -> Self {
+        self.base_url = Some(base_url.into());
+        self
+    }
+
+    fn endpoint(&self, model: &str, method: &str) -> String {
+        let base = self
+            .base_url
+            .clone()
+            .unwrap_or_else(|| format!("https://{}-aiplatform.googleapis.com", self.location));
+        format!(
+            "{}/v1/projects/{}/locations/{}/publishers/google/models/{}:{}",
+            base, self.project, self.location, model, method
+        )
+    }
+}
+
+fn vertex_method(model: &str, predict: &'static str) -> (&'static str, Option<&'static str>) {
+    if is_gemini(model) {
+        ("generateContent", Some("/candidates/0/content/parts"))
+    } else {
+        ("predict", Some(predict))
+    }
+}
+
+impl PromptExecutor for VertexExecutor {
+    fn execute<'a>(
+        &'a self,
+        prompt: &'a Prompt,
+        vars: &'a HashMap<String, String>,
+    ) -> BoxFuture<'a, Result<ExecutionResult, ExecError>> {
+        Box::pin(async move {
+            let (model, body, (method, pointer)) = match render_prompt(prompt, vars)? {
+                RenderedPrompt::Completion(c) => (
+                    c.model.clone(),
+                    c.to_google_request(),
+                    vertex_method(&c.model, "/predictions/0/content"),
+                ),
+                RenderedPrompt::Chat(c) => (
+                    c.model.clone(),
+                    c.to_google_request(),
+                    vertex_method(&c.model, "/predictions/0/candidates/0/content"),
+                ),
+                RenderedPrompt::Embedding(e) => (
+                    e.model.clone(),
+                    e.to_vertex_embedding_request(),
+                    ("predict", None),
+                ),
+            };
+            let request = HttpRequest {
+                url: self.endpoint(&model, method),
+                headers: vec![(
+                    "Authorization".to_string(),
+                    format!("Bearer {}", self.access_token),
+                )],
+                body,
+            };
+            let raw = send_json(self.client.as_ref(), request).await?;
+            Ok(ExecutionResult {
+                text: result_text(&raw, pointer)?,
+                raw,
+            })
+        })
+    }
+}
+
+#[derive(Default)]
+pub struct VendorExecutor {
+    pub(crate) executors: HashMap<Vendor, Box<dyn StreamingExecutor>>,
+    pub(crate) named: HashMap<String, Box<dyn StreamingExecutor>>,
+}
+
+impl VendorExecutor {
+    pub fn new() -> VendorExecutor {
+        VendorExecutor::default()
+    }
+
+    pub fn with(mut self, vendor: Vendor, executor: impl StreamingExecutor + 'static) -> Self {
+        self.executors.insert(vendor, Box::new(executor));
+        self
+    }
+
+    pub fn with_vendor(
+        mut self,
+        name: impl Into<String>,
+        executor: impl StreamingExecutor + 'static,
+    ) -> Self {
+        self.named
+            .insert(name.into().to_lowercase(), Box::new(executor));
+        self
+    }
+}
+
+impl VendorExecutor {
+    pub(crate) fn executor_for(
+        &self,
+        prompt: &Prompt,
+    ) -> Result<&dyn StreamingExecutor, ExecError> {
+        let vendor_name = match prompt {
+            Prompt::Completion(c) => &c.vendor,
+            Prompt::Chat(c) => &c.vendor,
+            Prompt::Embedding(e) => &e.vendor,
+            Prompt::Custom(_) | Prompt::Unknown => {
+                return Err(RequestError::UnsupportedPrompt.into())
+            }
+        };
+        if let Some(executor) = self.named.get(&vendor_name.to_lowercase()) {
+            return Ok(executor.as_ref());
+        }
+        if is_azure(vendor_name) {
+            return Err(ExecError::NotConfigured(vendor_name.clone()));
+        }
+        let vendor = prompt_endpoint(prompt)
+            .and_then(|e| e.api_vendor(vendor_name))
+            .ok_or_else(|| RequestError::UnsupportedVendor(vendor_name.clone()))?;
+        self.executors
+            .get(&vendor)
+            .map(|e| e.as_ref())
+            .ok_or_else(|| ExecError::NotConfigured(vendor_name.clone()))
+    }
+}
+
+impl PromptExecutor for VendorExecutor {
+    fn execute<'a>(
+        &'a self,
+        prompt: &'a Prompt,
+        vars: &'a HashMap<String, String>,
+    ) -> BoxFuture<'a, Result<ExecutionResult, ExecError>> {
+        Box::pin(async move { self.executor_for(prompt)?.execute(prompt, vars).await })
+    }
+}
+
+struct ThreadWaker(Thread);
+
+impl Wake for ThreadWaker {
+    fn wake(self: Arc<Self>) {
+        self.0.unpark();
+    }
+}
+
+pub fn block_on<F: Future>(future: F) -> F::Output {
+    let mut future = std::pin::pin!(future);
+    let waker = Waker::from(Arc::new(ThreadWaker(thread::current())));
+    let mut context = Context::from_waker(&waker);
+    loop {
+        match future.as_mut().poll(&mut context) {
+            Poll::Ready(output) => return output,
+            Poll::Pending => thread::park(),
+        }
+    }
+}
+
+#[cfg(test)]
+pub(crate) mod tests {
+    use super::*;
+    use crate::prompt::deserialize_prompt;
+    use serde_json::json;
+    use std::sync::Mutex;
+
+    #[derive(Default)]
+    pub(crate) struct MockClient {
+        pub(crate) responses: Mutex<Vec<HttpResponse>>,
+        pub(crate) requests: Mutex<Vec<HttpRequest>>,
+    }
+
+    impl MockClient {
+        pub(crate) fn replying(bodies: &[(u16, Value)]) -> Arc<MockClient> {
+            let responses = bodies
+                .iter()
+                .rev()
+                .map(|(status, body)| HttpResponse {
+                    status: *status,
+                    body: body.to_string(),
+                })
+                .collect();
+            Arc::new(MockClient {
+                responses: Mutex::new(responses),
+                requests: Mutex::new(Vec::new()),
+            })
+        }
+    }
+
+    impl HttpClient for MockClient {
+        fn post(&self, request: HttpRequest) -> BoxFuture<'_, Result<HttpResponse, ExecError>> {
+            self.requests.lock().unwrap().push(request);
+            let response = self.responses.lock().unwrap().pop();
+            Box::pin(
+                async move { response.ok_or_else(|| ExecError::Http("no response".to_string())) },
+            )
+        }
+    }
+
+    fn vars(pairs: &[(&str, &str)]) -> HashMap<String, String> {
+        pairs
+            .iter()
+            .map(|(k, v)| (k.to_string(), v.to_string()))
+            .collect()
+    }
+
+    #[test]
+    fn test_openai_chat_execution() {
+        let client = MockClient::replying(&[(
+            200,
+            json!({ "choices": [{ "message": { "role": "assistant", "content": "Bonjour" } }] }),
+        )]);
+        let executor = OpenAiExecutor::new(client.clone(), "sk-test").base_url("http://localhost");
+        let prompt = deserialize_prompt(
+            r#"
+            type: chat
+            vendor: openai
+            model: gpt-4o
+            messages:
+                - input: Translate {{word}} to french
+        "#,
+        );
+
+        let result = block_on(prompt.execute(&executor, &vars(&[("word", "hello")]))).unwrap();
+        assert_eq!(result.text, "Bonjour");
+
+        let requests = client.requests.lock().unwrap();
+        assert_eq!(requests[0].url, "http://localhost/v1/chat/completions");
+        assert_eq!(
+            requests[0].body["messages"][0]["content"],
+            "Translate hello to french"
+        );
+        assert_eq!(
+            requests[0].headers[0],
+            ("Authorization".to_string(), "Bearer sk-test".to_string())
+        );
+    }
+
+    #[test]
+    fn test_vendor_dispatch_and_errors() {
+        let anthropic = MockClient::replying(&[
+            (
+                200,
+                json!({ "content": [{ "type": "text", "text": "Hi" }] }),
+            ),
+            (429, json!({ "error": "rate limited" })),
+        ]);
+        let vertex =
+            MockClient::replying(&[(200, json!({ "predictions": [{ "content": "Yo" }] }))]);
+        let executor = VendorExecutor::new()
+            .with(
+                Vendor::Anthropic,
+                AnthropicExecutor::new(anthropic.clone(), "key"),
+            )
+            .with(
+                Vendor::Google,
+                VertexExecutor::new(vertex.clone(), "token", "proj", "us-central1"),
+            );
+
+        let claude = deserialize_prompt(
+            "type: completion\nvendor: anthropic\nmodel: claude-3-haiku\nprompt: hi\n",
+        );
+        let bison =
+            deserialize_prompt("type: completion\nvendor: google\nmodel: text-bison\nprompt: hi\n");
+        let gpt = deserialize_prompt("type: completion\nvendor: openai\nmodel: gpt\nprompt: hi\n");
+        let none = HashMap::new();
+
+        assert_eq!(
+            block_on(claude.execute(&executor, &none)).unwrap().text,
+            "Hi"
+        );
+        assert!(matches!(
+            block_on(claude.execute(&executor, &none)),
+            Err(ExecError::Status { status: 429, .. })
+        ));
+        assert_eq!(
+            block_on(bison.execute(&executor, &none)).unwrap().text,
+            "Yo"
+        );
+        assert_eq!(
+            vertex.requests.lock().unwrap()[0].url,
+            "https://us-central1-aiplatform.googleapis.com/v1/projects/proj/locations/us-central1/publishers/google/models/text-bison:predict"
+        );
+        assert_eq!(
+            block_on(gpt.execute(&executor, &none)),
+            Err(ExecError::NotConfigured("openai".to_string()))
+        );
+    }
+
+    #[test]
+    fn test_custom_endpoints() {
+        let reply = json!({ "choices": [{ "message": { "content": "ok" } }] });
+        let azure = MockClient::replying(&[(200, reply.clone()), (200, reply.clone())]);
+        let openai = MockClient::replying(&[(200, reply.clone()), (200, reply.clone())]);
+        let vllm = MockClient::replying(&[(200, reply)]);
+        let executor = VendorExecutor::new()
+            .with(Vendor::OpenAi, OpenAiExecutor::new(openai.clone(), "sk"))
+            .with_vendor(
+                "azure",
+                AzureOpenAiExecutor::new(azure.clone(), "az", "https://res.openai.azure.com/")
+                    .deployment("gpt-4o", "gpt4o-prod"),
+            )
+            .with_vendor(
+                "vllm",
+                OpenAiExecutor::compatible(vllm.clone(), "http://gpu:8000"),
+            );
+        let chat = "type: chat\nmodel: gpt-4o\nmessages:\n  - input: hi\n";
+        let none = HashMap::new();
+
+        let prompt = deserialize_prompt(&format!("vendor: azure\n{}", chat));
+        assert_eq!(
+            block_on(prompt.execute(&executor, &none)).unwrap().text,
+            "ok"
+        );
+        let prompt = deserialize_prompt(&format!(
+            "vendor: azure\ndeployment: canary\napi_version: 2024-10-21\n{}",
+            chat
+        ));
+        block_on(prompt.execute(&executor, &none)).unwrap();
+        let requests = azure.requests.lock().unwrap();
+        assert_eq!(
+            requests[0].url,
+            "https://res.openai.azure.com/openai/deployments/gpt4o-prod/chat/completions?api-version=2024-06-01"
+        );
+        assert_eq!(
+            requests[0].headers,
+            vec![("api-key".to_string(), "az".to_string())]
+        );
+        assert_eq!(
+            requests[1].url,
+            "https://res.openai.azure.com/openai/deployments/canary/chat/completions?api-version=2024-10-21"
+        );
+
+        let prompt = deserialize_prompt(&format!("vendor: vllm\n{}", chat));
+        block_on(prompt.execute(&executor, &none)).unwrap();
+        let request = &vllm.requests.lock().unwrap()[0];
+        assert_eq!(request.url, "http://gpu:8000/v1/chat/completions");
+        assert!(request.headers.is_empty());
+
+        let prompt = deserialize_prompt(&format!(
+            "vendor: ollama\napi_style: openai\nbase_url: http://localhost:11434\n{}",
+            chat
+        ));
+        block_on(prompt.execute(&executor, &none)).unwrap();
+        let request = openai.requests.lock().unwrap()[0].clone();
+        assert_eq!(request.url, "http://localhost:11434/v1/chat/completions");
+        assert!(request.headers.is_empty());
+
+        let prompt = deserialize_prompt(&format!(
+            "vendor: ollama\napi_style: openai\nbase_url: http://localhost:11434/v1/\n{}",
+            chat
+        ));
+        block_on(prompt.execute(&executor, &none)).unwrap();
+        assert_eq!(
+            openai.requests.lock().unwrap()[1].url,
+            "http://localhost:11434/v1/chat/completions"
+        );
+
+        let prompt = deserialize_prompt(&format!("vendor: azure\n{}", chat));
+        assert_eq!(
+            block_on(prompt.execute(&VendorExecutor::new(), &none)),
+            Err(ExecError::NotConfigured("azure".to_string()))
+        );
+    }
+
+    #[test]
+    fn test_vertex_gemini_execution() {
+        let reply = json!({ "candidates": [{ "content": { "role": "model", "parts": [
+            { "text": "Bon" },
+            { "text": "jour" },
+        ] } }] });
+        let vertex = MockClient::replying(&[(200, reply.clone()), (200, reply)]);
+        let executor = VertexExecutor::new(vertex.clone(), "token", "proj", "us-central1");
+        let chat = deserialize_prompt(
+            "type: chat\nvendor: google\nmodel: gemini-2.0-flash\ncontext: Be brief\nmessages: [{input: hi}]\nparameters: [{name: temperature, value: 0.2}]\n",
+        );
+        let completion = deserialize_prompt(
+            "type: completion\nvendor: google\nmodel: gemini-1.5-pro\nprompt: hi\n",
+        );
+        let none = HashMap::new();
+
+        assert_eq!(
+            block_on(executor.execute(&chat, &none)).unwrap().text,
+            "Bonjour"
+        );
+        assert_eq!(
+            block_on(executor.execute(&completion, &none)).unwrap().text,
+            "Bonjour"
+        );
+        let requests = vertex.requests.lock().unwrap();
+        assert!(requests[0]
+            .url
+            .ends_with("/models/gemini-2.0-flash:generateContent"));
+        assert_eq!(
+            requests[0].body["contents"],
+            json!([{ "role": "user", "parts": [{ "text": "hi" }] }])
+        );
+        assert_eq!(requests[0].body["generationConfig"]["temperature"], 0.2);
+        assert!(requests[0].body.get("instances").is_none());
+        assert!(requests[1]
+            .url
+            .ends_with("/models/gemini-1.5-pro:generateContent"));
+        assert_eq!(requests[1].body["contents"][0]["parts"][0]["text"], "hi");
+    }
+
+    #[test]
+    fn test_embedding_execution() {
+        let openai = MockClient::replying(&[
+            (200, json!({ "data": [{ "embedding": "AACAPwAAAL8=" }] })),
+            (
+                200,
+                json!({ "data": [{ "embedding": [0.25, 0.5] }, { "embedding": [1.0, 0.0] }] }),
+            ),
+        ]);
+        let vertex = MockClient::replying(&[(
+            200,
+            json!({ "predictions": [{ "embeddings": { "values": [0.5, 0.75] } }] }),
+        )]);
+        let executor = VendorExecutor::new()
+            .with(
+                Vendor::OpenAi,
+                OpenAiExecutor::new(openai.clone(), "sk").base_url("http://localhost"),
+            )
+            .with(
+                Vendor::Google,
+                VertexExecutor::new(vertex.clone(), "token", "proj", "us-central1"),
+            );
+        let prompt = deserialize_prompt(
+            "type: embedding\nvendor: openai\nmodel: text-embedding-3-small\ninput: ['a {{x}}', b]\n",
+        );
+        let vars = vars(&[("x", "cat")]);
+
+        let result = block_on(prompt.execute(&executor, &vars)).unwrap();
+        assert_eq!(result.embeddings(), Some(vec![vec![1.0, -0.5]]));
+        let result = block_on(prompt.execute(&executor, &vars)).unwrap();
+        assert_eq!(result.text, "[[0.25,0.5],[1.0,0.0]]");
+        assert_eq!(
+            result.embeddings(),
+            Some(vec![vec![0.25, 0.5], vec![1.0, 0.0]])
+        );
+        let requests = openai.requests.lock().unwrap();
+        assert_eq!(requests[0].url, "http://localhost/v1/embeddings");
+        assert_eq!(requests[0].body["input"], json!(["a cat", "b"]));
+
+        let gecko = deserialize_prompt(
+            "type: embedding\nvendor: google\nmodel: text-embedding-004\ninput: hello\n",
+        );
+        let result = block_on(gecko.execute(&executor, &vars)).unwrap();
+        assert_eq!(result.embeddings(), Some(vec![vec![0.5, 0.75]]));
+        assert!(vertex.requests.lock().unwrap()[0]
+            .url
+            .ends_with("/models/text-embedding-004:predict"));
+    }
+}
@@ -0,0 +1,126 @@
+use crate::prompt::{NameStyle, Prompt};
+use crate::sha256::hex_digest;
+use crate::template::RenderError;
+use serde_json::Value;
+use std::collections::HashMap;
+
+const META_FIELDS: [&str; 7] = [
+    "name",
+    "description",
+    "version",
+    "tags",
+    "author",
+    "created",
+    "updated",
+];
+
+fn normalize_parameters(parameters: &mut Value) {
+    if let Some(list) = parameters.as_array_mut() {
+        for parameter in list.iter_mut() {
+            if let Some(name) = parameter.get("name").and_then(|n| n.as_str()) {
+                parameter["name"] = Value::String(NameStyle::SnakeCase.convert(name));
+            }
+        }
+        list.sort_by(|a, b| a["name"].as_str().cmp(&b["name"].as_str()));
+    }
+}
+
+fn normalized(prompt: &Prompt) -> Value {
+    let mut document = serde_json::to_value(prompt).unwrap_or(Value::Null);
+    if let Some(object) = document.as_object_mut() {
+        for field in META_FIELDS {
+            object.remove(field);
+        }
+        if let Some(parameters) = object.get_mut("parameters") {
+            normalize_parameters(parameters);
+        }
+        for section in object
+            .get_mut("parameters_by_env")
+            .and_then(|e| e.as_object_mut())
+            .into_iter()
+            .flat_map(|e| e.values_mut())
+        {
+            normalize_parameters(section);
+        }
+    }
+    document
+}
+
+impl Prompt {
+    pub fn fingerprint(&self) -> String {
+        hex_digest(normalized(self).to_string().as_bytes())
+    }
+
+    pub fn rendered_fingerprint(
+        &self,
+        vars: &HashMap<String, String>,
+    ) -> Result<String, RenderError> {
+        let rendered = match self {
+            Prompt::Completion(completion) => completion.render(vars)?,
+            Prompt::Chat(chat) => {
+                serde_json::to_string(&chat.render(vars)?.to_transcript()).unwrap_or_default()
+            }
+            Prompt::Embedding(embedding) => {
+                serde_json::to_string(&embedding.render(vars)?.input).unwrap_or_default()
+            }
+            Prompt::Custom(kind) => kind.to_value().to_string(),
+            Prompt::Unknown => String::new(),
+        };
+        Ok(hex_digest(rendered.as_bytes()))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::prompt::deserialize_prompt;
+
+    #[test]
+    fn test_fingerprint_is_normalized() {
+        let a = deserialize_prompt(
+            r#"
+            name: greet
+            version: 1
+            type: completion
+            vendor: google
+            model: text-bison
+            prompt: Hello {{name}}
+            parameters:
+                - name: maxOutputTokens
+                  value: 256
+                - name: temperature
+                  value: 0.4
+        "#,
+        );
+        let b = deserialize_prompt(
+            r#"
+            model: text-bison
+            type: completion
+            vendor: google
+            prompt: Hello {{name}}
+            version: 2
+            parameters:
+                - name: temperature
+                  value: 0.4
+                - name: max_output_tokens
+                  value: 256
+        "#,
+        );
+        assert_eq!(a.fingerprint(), b.fingerprint());
+        assert_eq!(a.fingerprint().len(), 64);
+
+        let c = a.with_overrides(&[("temperature", serde_yaml::Value::from(0.5))]);
+        assert_ne!(a.fingerprint(), c.fingerprint());
+
+        let vars = HashMap::from([("name".to_string(), "Ada".to_string())]);
+        assert_eq!(
+            a.rendered_fingerprint(&vars).unwrap(),
+            hex_digest(b"Hello Ada")
+        );
+        assert_eq!(
+            a.rendered_fingerprint(&vars).unwrap(),
+            c.rendered_fingerprint(&vars).unwrap()
+        );
+        assert!(a.rendered_fingerprint(&HashMap::new()).is_err());
+    }
+}
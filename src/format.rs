@@ -0,0 +1,325 @@
+use crate::kind::PromptParser;
+use crate::locale::{document_locales, localize, DEFAULT_LOCALE};
+use crate::markdown::markdown_document;
+use crate::prompt::{
+    try_deserialize_prompt, Chat, Completion, Embedding, Location, Prompt, PromptError,
+};
+use crate::toml;
+use serde_json::{Map, Value};
+use serde_yaml::value::TaggedValue;
+use std::convert::Infallible;
+use std::path::Path;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Format {
+    Yaml,
+    Json,
+    Toml,
+    Markdown,
+}
+
+impl Format {
+    pub fn from_extension(extension: &str) -> Option<Format> {
+        match extension.to_lowercase().as_str() {
+            "yaml" | "yml" => Some(Format::Yaml),
+            "json" => Some(Format::Json),
+            "toml" => Some(Format::Toml),
+            "md" => Some(Format::Markdown),
+            _ => None,
+        }
+    }
+
+    pub fn from_path(path: &Path) -> Option<Format> {
+        let format = path
+            .extension()
+            .and_then(|e| e.to_str())
+            .and_then(Format::from_extension)?;
+        let stem = path.file_stem().and_then(|s| s.to_str()).unwrap_or("");
+        if format == Format::Markdown && !stem.ends_with(".prompt") {
+            return None;
+        }
+        Some(format)
+    }
+}
+
+pub(crate) fn json_location(error: &serde_json::Error) -> Option<Location> {
+    if error.line() == 0 {
+        None
+    } else {
+        Some(Location {
+            line: error.line(),
+            column: error.column(),
+        })
+    }
+}
+
+fn schema_error(prompt_type: &str, error: serde_json::Error) -> PromptError {
+    PromptError::SchemaMismatch {
+        prompt_type: prompt_type.to_string(),
+        message: error.to_string(),
+        location: json_location(&error),
+    }
+}
+
+fn typed_prompt(
+    document: &Value,
+    completion: impl FnOnce() -> serde_json::Result<Completion>,
+    chat: impl FnOnce() -> serde_json::Result<Chat>,
+    embedding: impl FnOnce() -> serde_json::Result<Embedding>,
+) -> Result<Prompt, PromptError> {
+    let kind = document
+        .get("type")
+        .and_then(|t| t.as_str())
+        .ok_or(PromptError::MissingType)?;
+    match kind {
+        "completion" => completion()
+            .map(Prompt::Completion)
+            .map_err(|e| schema_error(kind, e)),
+        "chat" => chat().map(Prompt::Chat).map_err(|e| schema_error(kind, e)),
+        "embedding" => embedding()
+            .map(Prompt::Embedding)
+            .map_err(|e| schema_error(kind, e)),
+        other => PromptParser::parse(other, document),
+    }
+}
+
+pub fn try_deserialize_prompt_json(json: &str) -> Result<Prompt, PromptError> {
+    let document: Value = serde_json::from_str(json).map_err(|e| PromptError::InvalidJson {
+        message: e.to_string(),
+        location: json_location(&e),
+    })?;
+    if document_locales(&document).is_empty() {
+        typed_prompt(
+            &document,
+            || serde_json::from_str(json),
+            || serde_json::from_str(json),
+            || serde_json::from_str(json),
+        )
+    } else {
+        prompt_from_value(document)
+    }
+}
+
+pub fn try_deserialize_prompt_toml(source: &str) -> Result<Prompt, PromptError> {
+    let document = toml::parse(source).map_err(|e| PromptError::InvalidToml {
+        message: e.message.clone(),
+        location: Some(Location {
+            line: e.line,
+            column: 0,
+        }),
+    })?;
+    prompt_from_value(document)
+}
+
+pub(crate) fn prompt_from_value(mut document: Value) -> Result<Prompt, PromptError> {
+    localize(&mut document, &[DEFAULT_LOCALE.to_string()]);
+    typed_prompt(
+        &document,
+        || serde_json::from_value(document.clone()),
+        || serde_json::from_value(document.clone()),
+        || serde_json::from_value(document.clone()),
+    )
+}
+
+fn or_unknown(result: Result<Prompt, PromptError>) -> Prompt {
+    match result {
+        Ok(prompt) => prompt,
+        Err(PromptError::UnknownType(_)) => Prompt::Unknown,
+        Err(e) => panic!("{}", e),
+    }
+}
+
+pub fn deserialize_prompt_json(json: &str) -> Prompt {
+    or_unknown(try_deserialize_prompt_json(json))
+}
+
+pub fn deserialize_prompt_toml(source: &str) -> Prompt {
+    or_unknown(try_deserialize_prompt_toml(source))
+}
+
+pub(crate) fn yaml_to_json_with<E>(
+    value: serde_yaml::Value,
+    tagged: &impl Fn(&TaggedValue) -> Option<Result<Value, E>>,
+) -> Result<Value, E> {
+    Ok(match value {
+        serde_yaml::Value::Null => Value::Null,
+        serde_yaml::Value::Bool(b) => Value::Bool(b),
+        serde_yaml::Value::Number(n) => serde_json::to_value(n).unwrap_or(Value::Null),
+        serde_yaml::Value::String(s) => Value::String(s),
+        serde_yaml::Value::Sequence(items) => Value::Array(
+            items
+                .into_iter()
+                .map(|item| yaml_to_json_with(item, tagged))
+                .collect::<Result<_, _>>()?,
+        ),
+        serde_yaml::Value::Mapping(mapping) => {
+            let mut object = Map::new();
+            for (key, item) in mapping {
+                let key = match key {
+                    serde_yaml::Value::String(key) => key,
+                    other => serde_yaml::to_string(&other)
+                        .unwrap_or_default()
+                        .trim()
+                        .to_string(),
+                };
+                object.insert(key, yaml_to_json_with(item, tagged)?);
+            }
+            Value::Object(object)
+        }
+        serde_yaml::Value::Tagged(value) => match tagged(&value) {
+            Some(converted) => converted?,
+            None => yaml_to_json_with(value.value, tagged)?,
+        },
+    })
+}
+
+pub(crate) fn yaml_to_json(value: serde_yaml::Value) -> Value {
+    match yaml_to_json_with(value, &|_| None::<Result<Value, Infallible>>) {
+        Ok(value) => value,
+        Err(never) => match never {},
+    }
+}
+
+pub(crate) fn document(content: &str, format: Format) -> Option<Value> {
+    match format {
+        Format::Yaml => serde_yaml::from_str(content).ok().map(yaml_to_json),
+        Format::Json => serde_json::from_str(content).ok(),
+        Format::Toml => toml::parse(content).ok(),
+        Format::Markdown => markdown_document(content).ok(),
+    }
+}
+
+pub fn document_name(content: &str, format: Format) -> Option<String> {
+    document(content, format)?
+        .get("name")?
+        .as_str()
+        .map(|n| n.to_string())
+}
+
+impl Prompt {
+    pub fn from_str(content: &str, format: Format) -> Result<Prompt, PromptError> {
+        match format {
+            Format::Yaml => try_deserialize_prompt(content),
+            Format::Json => try_deserialize_prompt_json(content),
+            Format::Toml => try_deserialize_prompt_toml(content),
+            Format::Markdown => prompt_from_value(markdown_document(content)?),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::prompt::deserialize_prompt;
+
+    const YAML: &str = r#"
+type: completion
+vendor: google
+model: text-bison
+prompt: Write a hello world in java
+parameters:
+  - name: maxOutputTokens
+    value: 256
+  - name: temperature
+    value: 0.4
+examples:
+  - name: input
+    values: [a, b]
+    test: c
+  - name: output
+    values: [x, y]
+"#;
+
+    const JSON: &str = r#"{
+  "type": "completion",
+  "vendor": "google",
+  "model": "text-bison",
+  "prompt": "Write a hello world in java",
+  "parameters": [
+    { "name": "maxOutputTokens", "value": 256 },
+    { "name": "temperature", "value": 0.4 }
+  ],
+  "examples": [
+    { "name": "input", "values": ["a", "b"], "test": "c" },
+    { "name": "output", "values": ["x", "y"] }
+  ]
+}"#;
+
+    const TOML: &str = r#"
+type = "completion"
+vendor = "google"
+model = "text-bison"
+prompt = "Write a hello world in java"
+
+[[parameters]]
+name = "maxOutputTokens"
+value = 256
+
+[[parameters]]
+name = "temperature"
+value = 0.4
+
+[[examples]]
+name = "input"
+values = ["a", "b"]
+test = "c"
+
+[[examples]]
+name = "output"
+values = ["x", "y"]
+"#;
+
+    #[test]
+    fn test_format_parity() {
+        let yaml = deserialize_prompt(YAML);
+        assert_eq!(deserialize_prompt_json(JSON), yaml);
+        assert_eq!(deserialize_prompt_toml(TOML), yaml);
+        assert_eq!(Prompt::from_str(TOML, Format::Toml), Ok(yaml));
+    }
+
+    #[test]
+    fn test_chat_parity() {
+        let yaml = "type: chat\nvendor: google\nmodel: chat-bison\nmessages:\n  - input: hi\n";
+        let json = r#"{"type": "chat", "vendor": "google", "model": "chat-bison", "messages": [{"input": "hi"}]}"#;
+        let toml = "type = 'chat'\nvendor = 'google'\nmodel = 'chat-bison'\nmessages = [{ input = 'hi' }]\n";
+        assert_eq!(
+            Prompt::from_str(json, Format::Json),
+            Prompt::from_str(yaml, Format::Yaml)
+        );
+        assert_eq!(
+            Prompt::from_str(toml, Format::Toml),
+            Prompt::from_str(yaml, Format::Yaml)
+        );
+    }
+
+    #[test]
+    fn test_format_errors() {
+        assert!(matches!(
+            try_deserialize_prompt_json("{\"type\": "),
+            Err(PromptError::InvalidJson {
+                location: Some(_),
+                ..
+            })
+        ));
+        assert!(matches!(
+            try_deserialize_prompt_toml("type = \"completion\"\nvendor = \n"),
+            Err(PromptError::InvalidToml {
+                location: Some(Location { line: 2, .. }),
+                ..
+            })
+        ));
+        assert!(matches!(
+            try_deserialize_prompt_json(r#"{"type": "chat", "vendor": "google"}"#),
+            Err(PromptError::SchemaMismatch { .. })
+        ));
+        assert_eq!(
+            try_deserialize_prompt_toml("type = 'rerank'"),
+            Err(PromptError::UnknownType("rerank".to_string()))
+        );
+        assert_eq!(
+            Format::from_path(Path::new("a/b.prompt.toml")),
+            Some(Format::Toml)
+        );
+        assert_eq!(Format::from_path(Path::new("a/b.txt")), None);
+    }
+}
@@ -0,0 +1,830 @@
+use crate::builder::BuildError;
+use crate::prompt::{Chat, Completion, ExampleFormat, Parameter, Prompt};
+use crate::request::Vendor;
+use crate::variables::VariableSpec;
+use serde_json::{Map, Value};
+use std::fmt;
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum ImportError {
+    InvalidJson(String),
+    InvalidYaml(String),
+    Invalid { path: String, message: String },
+    Unsupported(String),
+    Build(BuildError),
+}
+
+impl fmt::Display for ImportError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ImportError::InvalidJson(message) => write!(f, "invalid JSON: {}", message),
+            ImportError::InvalidYaml(message) => write!(f, "invalid YAML: {}", message),
+            ImportError::Invalid { path, message } => write!(f, "{}: {}", path, message),
+            ImportError::Unsupported(feature) => write!(f, "unsupported {}", feature),
+            ImportError::Build(error) => write!(f, "{}", error),
+        }
+    }
+}
+
+impl std::error::Error for ImportError {}
+
+impl From<BuildError> for ImportError {
+    fn from(error: BuildError) -> Self {
+        ImportError::Build(error)
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ImportWarning {
+    pub path: String,
+    pub message: String,
+}
+
+impl fmt::Display for ImportWarning {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}: {}", self.path, self.message)
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Imported {
+    pub prompts: Vec<Prompt>,
+    pub warnings: Vec<ImportWarning>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ImportOptions {
+    pub vendor: String,
+    pub model: String,
+}
+
+impl ImportOptions {
+    pub fn new(vendor: impl Into<String>, model: impl Into<String>) -> ImportOptions {
+        ImportOptions {
+            vendor: vendor.into(),
+            model: model.into(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Role {
+    System,
+    User,
+    Assistant,
+}
+
+const CONNECTION: &[&str] = &[
+    "apiKey",
+    "apiKeyEnvar",
+    "apiHost",
+    "apiBaseUrl",
+    "organization",
+];
+
+struct Provider {
+    options: ImportOptions,
+    mode: Option<String>,
+    config: Map<String, Value>,
+}
+
+#[derive(Default)]
+struct Importer {
+    warnings: Vec<ImportWarning>,
+}
+
+fn invalid(path: &str, message: &str) -> ImportError {
+    ImportError::Invalid {
+        path: path.to_string(),
+        message: message.to_string(),
+    }
+}
+
+fn string<'a>(object: &'a Map<String, Value>, key: &str) -> Option<&'a str> {
+    object.get(key).and_then(|v| v.as_str())
+}
+
+fn as_list(value: Option<&Value>) -> Vec<&Value> {
+    match value {
+        Some(Value::Array(items)) => items.iter().collect(),
+        Some(Value::Null) | None => Vec::new(),
+        Some(other) => vec![other],
+    }
+}
+
+fn constructor(value: &Value) -> Option<(&str, &Map<String, Value>)> {
+    let object = value.as_object()?;
+    if let Some(id) = object.get("id").and_then(|id| id.as_array()) {
+        let kind = id.last()?.as_str()?;
+        return Some((kind, object.get("kwargs")?.as_object()?));
+    }
+    let kind = match string(object, "_type") {
+        Some("prompt") => "PromptTemplate",
+        Some("few_shot") => "FewShotPromptTemplate",
+        Some("chat") => "ChatPromptTemplate",
+        Some(other) => other,
+        None if object.contains_key("messages") => "ChatPromptTemplate",
+        None if object.contains_key("template") => "PromptTemplate",
+        None => return None,
+    };
+    Some((kind, object))
+}
+
+impl Importer {
+    fn warn(&mut self, path: &str, message: impl Into<String>) {
+        self.warnings.push(ImportWarning {
+            path: path.to_string(),
+            message: message.into(),
+        });
+    }
+
+    fn fstring(&mut self, text: &str, path: &str) -> String {
+        let mut out = String::new();
+        let mut chars = text.chars().peekable();
+        while let Some(c) = chars.next() {
+            match c {
+                '{' if chars.peek() == Some(&'{') => {
+                    chars.next();
+                    out.push('{');
+                }
+                '}' if chars.peek() == Some(&'}') => {
+                    chars.next();
+                    out.push('}');
+                }
+                '{' => {
+                    let mut field = String::new();
+                    let mut closed = false;
+                    for c in chars.by_ref() {
+                        if c == '}' {
+                            closed = true;
+                            break;
+                        }
+                        field.push(c);
+                    }
+                    if !closed {
+                        out.push('{');
+                        out.push_str(&field);
+                        continue;
+                    }
+                    let name = match field.find([':', '!']) {
+                        Some(index) => {
+                            self.warn(path, format!("format spec dropped from '{{{}}}'", field));
+                            &field[..index]
+                        }
+                        None => &field,
+                    };
+                    out.push_str(&format!("{{{{{}}}}}", name.trim()));
+                }
+                _ => out.push(c),
+            }
+        }
+        out
+    }
+
+    fn jinja(&mut self, text: &str, path: &str) -> String {
+        let mut out = String::new();
+        let mut rest = text;
+        while let Some(start) = rest.find("{{") {
+            out.push_str(&rest[..start]);
+            let Some(end) = rest[start..].find("}}").map(|e| start + e) else {
+                break;
+            };
+            let expression = rest[start + 2..end].trim();
+            let name = match expression.split_once('|') {
+                Some((name, _)) => {
+                    self.warn(
+                        path,
+                        format!("filter dropped from '{{{{ {} }}}}'", expression),
+                    );
+                    name.trim()
+                }
+                None => expression,
+            };
+            out.push_str(&format!("{{{{{}}}}}", name));
+            rest = &rest[end + 2..];
+        }
+        out.push_str(rest);
+        if out.contains("{%") || out.contains("{#") {
+            self.warn(path, "block tags and comments are kept as plain text");
+        }
+        out
+    }
+
+    fn template(
+        &mut self,
+        text: &str,
+        format: &str,
+        partials: Option<&Map<String, Value>>,
+        path: &str,
+    ) -> Result<String, ImportError> {
+        let mut text = match format {
+            "f-string" => self.fstring(text, path),
+            "jinja2" | "mustache" | "nunjucks" => self.jinja(text, path),
+            other => {
+                return Err(ImportError::Unsupported(format!(
+                    "template format '{}'",
+                    other
+                )))
+            }
+        };
+        for (name, value) in partials.into_iter().flatten() {
+            let value = match value {
+                Value::String(s) => s.clone(),
+                other => {
+                    self.warn(path, format!("partial variable '{}' is not a string", name));
+                    other.to_string()
+                }
+            };
+            text = text.replace(&format!("{{{{{}}}}}", name), &value);
+        }
+        Ok(text)
+    }
+
+    fn lc_template(
+        &mut self,
+        kwargs: &Map<String, Value>,
+        key: &str,
+        path: &str,
+    ) -> Result<String, ImportError> {
+        let text = string(kwargs, key).ok_or_else(|| invalid(path, &format!("missing {}", key)))?;
+        let format = string(kwargs, "template_format").unwrap_or("f-string");
+        let partials = kwargs.get("partial_variables").and_then(|p| p.as_object());
+        self.template(text, format, partials, path)
+    }
+
+    fn lc_prompt(
+        &mut self,
+        value: &Value,
+        options: &ImportOptions,
+        path: &str,
+    ) -> Result<Prompt, ImportError> {
+        let (kind, kwargs) =
+            constructor(value).ok_or_else(|| invalid(path, "not a LangChain prompt"))?;
+        match kind {
+            "PromptTemplate" => {
+                let prompt = self.lc_template(kwargs, "template", path)?;
+                Ok(Prompt::Completion(
+                    Completion::builder()
+                        .vendor(&options.vendor)
+                        .model(&options.model)
+                        .prompt(prompt)
+                        .build()?,
+                ))
+            }
+            "FewShotPromptTemplate" => self.lc_few_shot(kwargs, options, path),
+            "ChatPromptTemplate" => self.lc_chat(kwargs, options, path),
+            other => Err(ImportError::Unsupported(format!(
+                "LangChain prompt type '{}'",
+                other
+            ))),
+        }
+    }
+
+    fn lc_few_shot(
+        &mut self,
+        kwargs: &Map<String, Value>,
+        options: &ImportOptions,
+        path: &str,
+    ) -> Result<Prompt, ImportError> {
+        let mut template = |key: &str| match string(kwargs, key) {
+            Some(text) if !text.trim().is_empty() => self.lc_template(kwargs, key, path).map(Some),
+            _ => Ok(None),
+        };
+        let prefix = template("prefix")?;
+        let suffix = template("suffix")?;
+        let separator = string(kwargs, "example_separator").unwrap_or("\n\n");
+        let mut builder = Completion::builder()
+            .vendor(&options.vendor)
+            .model(&options.model)
+            .prompt(prefix.unwrap_or_default())
+            .example_format(ExampleFormat {
+                separator: Some(separator.to_string()),
+                ..ExampleFormat::default()
+            });
+        if let Some(suffix) = suffix {
+            builder = builder.suffix(suffix);
+        }
+        let example_prompt = kwargs.get("example_prompt").and_then(constructor);
+        let mut columns: Vec<String> = example_prompt
+            .and_then(|(_, kwargs)| kwargs.get("input_variables"))
+            .and_then(|v| v.as_array())
+            .map(|names| {
+                names
+                    .iter()
+                    .filter_map(|n| n.as_str().map(|n| n.to_string()))
+                    .collect()
+            })
+            .unwrap_or_default();
+        if example_prompt.is_some() {
+            self.warn(
+                &format!("{}.example_prompt", path),
+                "example layout replaced by name: value columns",
+            );
+        }
+        if kwargs.contains_key("example_selector") {
+            self.warn(
+                &format!("{}.example_selector", path),
+                "example selectors are not imported",
+            );
+        }
+        match kwargs.get("examples") {
+            Some(Value::Array(examples)) => {
+                for (i, example) in examples.iter().enumerate() {
+                    let Some(example) = example.as_object() else {
+                        self.warn(&format!("{}.examples[{}]", path, i), "not an object");
+                        continue;
+                    };
+                    if columns.is_empty() {
+                        columns = example.keys().cloned().collect();
+                    }
+                    let values: Vec<String> = columns
+                        .iter()
+                        .map(|c| match example.get(c) {
+                            Some(Value::String(s)) => s.clone(),
+                            Some(other) => other.to_string(),
+                            None => String::new(),
+                        })
+                        .collect();
+                    let row: Vec<(&str, &str)> = columns
+                        .iter()
+                        .zip(&values)
+                        .map(|(c, v)| (c.as_str(), v.as_str()))
+                        .collect();
+                    builder = builder.example(&row);
+                }
+            }
+            Some(Value::String(file)) => self.warn(
+                &format!("{}.examples", path),
+                format!("examples file '{}' is not imported", file),
+            ),
+            _ => {}
+        }
+        Ok(Prompt::Completion(builder.build()?))
+    }
+
+    fn lc_message(
+        &mut self,
+        value: &Value,
+        path: &str,
+    ) -> Result<Option<(Role, String)>, ImportError> {
+        if let Some([role, text]) = value.as_array().map(|v| v.as_slice()) {
+            let role = match role.as_str() {
+                Some("system") => Role::System,
+                Some("human" | "user") => Role::User,
+                Some("ai" | "assistant") => Role::Assistant,
+                _ => {
+                    self.warn(path, format!("unknown role {}", role));
+                    return Ok(None);
+                }
+            };
+            let text = text
+                .as_str()
+                .ok_or_else(|| invalid(path, "expected text"))?;
+            return Ok(Some((role, self.template(text, "f-string", None, path)?)));
+        }
+        let (kind, kwargs) =
+            constructor(value).ok_or_else(|| invalid(path, "not a LangChain message"))?;
+        let role = match kind {
+            "SystemMessagePromptTemplate" | "SystemMessage" => Role::System,
+            "HumanMessagePromptTemplate" | "HumanMessage" => Role::User,
+            "AIMessagePromptTemplate" | "AIMessage" => Role::Assistant,
+            "MessagesPlaceholder" => {
+                let name = string(kwargs, "variable_name").unwrap_or("messages");
+                self.warn(path, format!("messages placeholder '{}' dropped", name));
+                return Ok(None);
+            }
+            other => {
+                self.warn(path, format!("message type '{}' dropped", other));
+                return Ok(None);
+            }
+        };
+        let text = match kwargs.get("prompt") {
+            Some(prompt) => match constructor(prompt) {
+                Some(("PromptTemplate", prompt)) => self.lc_template(prompt, "template", path)?,
+                _ => {
+                    self.warn(path, "multi-part message templates are not imported");
+                    return Ok(None);
+                }
+            },
+            None => string(kwargs, "content")
+                .ok_or_else(|| invalid(path, "missing content"))?
+                .to_string(),
+        };
+        Ok(Some((role, text)))
+    }
+
+    fn lc_chat(
+        &mut self,
+        kwargs: &Map<String, Value>,
+        options: &ImportOptions,
+        path: &str,
+    ) -> Result<Prompt, ImportError> {
+        let mut turns = Vec::new();
+        for (i, message) in as_list(kwargs.get("messages")).into_iter().enumerate() {
+            let path = format!("{}.messages[{}]", path, i);
+            if let Some(turn) = self.lc_message(message, &path)? {
+                turns.push((turn, path));
+            }
+        }
+        self.chat(turns, options).map(Prompt::Chat)
+    }
+
+    fn chat(
+        &mut self,
+        turns: Vec<((Role, String), String)>,
+        options: &ImportOptions,
+    ) -> Result<Chat, ImportError> {
+        let mut system = Vec::new();
+        let mut messages: Vec<(String, Option<String>)> = Vec::new();
+        for ((role, text), path) in turns {
+            match role {
+                Role::System => {
+                    if !messages.is_empty() {
+                        self.warn(&path, "system message moved into the context");
+                    }
+                    system.push(text);
+                }
+                Role::User => messages.push((text, None)),
+                Role::Assistant => match messages.last_mut() {
+                    Some((_, output @ None)) => *output = Some(text),
+                    _ => self.warn(&path, "assistant message without a preceding user message"),
+                },
+            }
+        }
+        let mut builder = Chat::builder()
+            .vendor(&options.vendor)
+            .model(&options.model);
+        if !system.is_empty() {
+            builder = builder.context(system.join("\n\n"));
+        }
+        for (input, output) in messages {
+            builder = match output {
+                Some(output) => builder.exchange(input, output),
+                None => builder.message(input),
+            };
+        }
+        Ok(builder.build()?)
+    }
+
+    fn promptfoo_messages(
+        &mut self,
+        raw: &str,
+        path: &str,
+    ) -> Option<Vec<((Role, String), String)>> {
+        let messages: Vec<Value> = serde_json::from_str(raw.trim()).ok()?;
+        let mut turns = Vec::new();
+        for (i, message) in messages.iter().enumerate() {
+            let path = format!("{}[{}]", path, i);
+            let role = match message.get("role").and_then(|r| r.as_str()) {
+                Some("system") => Role::System,
+                Some("user") => Role::User,
+                Some("assistant") => Role::Assistant,
+                _ => {
+                    self.warn(&path, "message without a known role dropped");
+                    continue;
+                }
+            };
+            let Some(content) = message.get("content").and_then(|c| c.as_str()) else {
+                self.warn(&path, "non-text content dropped");
+                continue;
+            };
+            turns.push(((role, self.jinja(content, &path)), path));
+        }
+        Some(turns)
+    }
+
+    fn provider(&mut self, value: &Value, path: &str) -> Option<Provider> {
+        let (id, config) = match value {
+            Value::String(id) => (id.as_str(), Map::new()),
+            Value::Object(object) => match string(object, "id") {
+                Some(id) => (
+                    id,
+                    object
+                        .get("config")
+                        .and_then(|c| c.as_object())
+                        .cloned()
+                        .unwrap_or_default(),
+                ),
+                None => {
+                    self.warn(path, "provider without an id dropped");
+                    return None;
+                }
+            },
+            _ => {
+                self.warn(path, "provider dropped");
+                return None;
+            }
+        };
+        let parts: Vec<&str> = id.splitn(3, ':').collect();
+        let (vendor, mode, model) = match parts.as_slice() {
+            [vendor, mode, model] => (*vendor, Some(mode.to_string()), *model),
+            [vendor, model] => (*vendor, None, *model),
+            _ => {
+                self.warn(path, format!("provider '{}' has no model", id));
+                return None;
+            }
+        };
+        let vendor = match vendor {
+            "vertex" | "google" => "google",
+            other => other,
+        };
+        if Vendor::from_name(vendor).is_none() {
+            self.warn(path, format!("vendor '{}' has no request mapping", vendor));
+        }
+        for key in config.keys().filter(|k| CONNECTION.contains(&k.as_str())) {
+            self.warn(
+                &format!("{}.config.{}", path, key),
+                "connection setting dropped",
+            );
+        }
+        Some(Provider {
+            options: ImportOptions::new(vendor, model),
+            mode,
+            config,
+        })
+    }
+
+    fn promptfoo_prompt(
+        &mut self,
+        raw: &str,
+        provider: &Provider,
+        path: &str,
+    ) -> Result<Prompt, ImportError> {
+        let options = &provider.options;
+        let mut prompt = match self.promptfoo_messages(raw, path) {
+            Some(turns) => Prompt::Chat(self.chat(turns, options)?),
+            None if matches!(provider.mode.as_deref(), Some("chat" | "messages")) => {
+                let text = self.jinja(raw, path);
+                Prompt::Chat(self.chat(vec![((Role::User, text), path.to_string())], options)?)
+            }
+            None => Prompt::Completion(
+                Completion::builder()
+                    .vendor(&options.vendor)
+                    .model(&options.model)
+                    .prompt(self.jinja(raw, path))
+                    .build()?,
+            ),
+        };
+        let parameters = match &mut prompt {
+            Prompt::Completion(completion) => &mut completion.parameters,
+            Prompt::Chat(chat) => &mut chat.parameters,
+            _ => unreachable!(),
+        };
+        for (name, value) in &provider.config {
+            if CONNECTION.contains(&name.as_str()) {
+                continue;
+            }
+            let value = serde_yaml::to_value(value).map_err(|e| invalid(path, &e.to_string()))?;
+            parameters.get_or_insert_with(Vec::new).push(Parameter {
+                name: name.clone(),
+                value,
+            });
+        }
+        Ok(prompt)
+    }
+
+    fn promptfoo(
+        &mut self,
+        config: &Value,
+        options: &ImportOptions,
+    ) -> Result<Vec<Prompt>, ImportError> {
+        let config = config
+            .as_object()
+            .ok_or_else(|| invalid("$", "expected a promptfoo config"))?;
+        let mut providers = Vec::new();
+        for (i, provider) in as_list(config.get("providers")).into_iter().enumerate() {
+            let path = format!("providers[{}]", i);
+            if let Some(provider) = self.provider(provider, &path) {
+                providers.push(provider);
+            }
+        }
+        if providers.is_empty() {
+            providers.push(Provider {
+                options: options.clone(),
+                mode: None,
+                config: Map::new(),
+            });
+        }
+        for key in ["tests", "defaultTest", "scenarios", "assert"] {
+            if config.contains_key(key) {
+                self.warn(key, "evaluation settings are not imported");
+            }
+        }
+        let mut prompts = Vec::new();
+        for (i, entry) in as_list(config.get("prompts")).into_iter().enumerate() {
+            let path = format!("prompts[{}]", i);
+            let (raw, label) = match entry {
+                Value::String(raw) => (raw.as_str(), None),
+                Value::Object(object) => match string(object, "raw") {
+                    Some(raw) => (raw, string(object, "label").or(string(object, "id"))),
+                    None => {
+                        self.warn(&path, "prompt without raw text dropped");
+                        continue;
+                    }
+                },
+                _ => {
+                    self.warn(&path, "prompt dropped");
+                    continue;
+                }
+            };
+            if raw.starts_with("file://") {
+                self.warn(&path, format!("prompt file '{}' is not imported", raw));
+                continue;
+            }
+            let name = label
+                .map(|l| l.to_string())
+                .unwrap_or_else(|| format!("prompt-{}", i + 1));
+            for provider in &providers {
+                let mut prompt = self.promptfoo_prompt(raw, provider, &path)?;
+                if let Some(meta) = prompt.meta_mut() {
+                    meta.name = Some(if providers.len() > 1 {
+                        format!("{}-{}", name, provider.options.model)
+                    } else {
+                        name.clone()
+                    });
+                    meta.description = string(config, "description").map(|d| d.to_string());
+                }
+                prompts.push(prompt);
+            }
+        }
+        Ok(prompts)
+    }
+
+    fn finish(self, mut prompts: Vec<Prompt>) -> Imported {
+        for prompt in &mut prompts {
+            let names = prompt.required_variables().unwrap_or_default();
+            if let Some(meta) = prompt.meta_mut() {
+                if !names.is_empty() {
+                    meta.variables = Some(
+                        names
+                            .into_iter()
+                            .map(|name| (name, VariableSpec::default()))
+                            .collect(),
+                    );
+                }
+            }
+        }
+        Imported {
+            prompts,
+            warnings: self.warnings,
+        }
+    }
+}
+
+pub fn from_langchain(json: &str, options: &ImportOptions) -> Result<Imported, ImportError> {
+    let value: Value =
+        serde_json::from_str(json).map_err(|e| ImportError::InvalidJson(e.to_string()))?;
+    let mut importer = Importer::default();
+    let prompt = importer.lc_prompt(&value, options, "$")?;
+    Ok(importer.finish(vec![prompt]))
+}
+
+pub fn from_promptfoo(yaml: &str, options: &ImportOptions) -> Result<Imported, ImportError> {
+    let document: serde_yaml::Value =
+        serde_yaml::from_str(yaml).map_err(|e| ImportError::InvalidYaml(e.to_string()))?;
+    let value =
+        serde_json::to_value(document).map_err(|e| ImportError::InvalidYaml(e.to_string()))?;
+    let mut importer = Importer::default();
+    let prompts = importer.promptfoo(&value, options)?;
+    Ok(importer.finish(prompts))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn options() -> ImportOptions {
+        ImportOptions::new("openai", "gpt-4o-mini")
+    }
+
+    #[test]
+    fn test_langchain_prompts() {
+        let chat = r#"{
+  "lc": 1, "type": "constructor",
+  "id": ["langchain", "prompts", "chat", "ChatPromptTemplate"],
+  "kwargs": {
+    "input_variables": ["question", "tone"],
+    "messages": [
+      {"lc": 1, "type": "constructor",
+       "id": ["langchain", "prompts", "chat", "SystemMessagePromptTemplate"],
+       "kwargs": {"prompt": {"lc": 1, "type": "constructor",
+         "id": ["langchain", "prompts", "prompt", "PromptTemplate"],
+         "kwargs": {"template": "Answer in a {tone} tone. Use {{json}}.", "template_format": "f-string"}}}},
+      {"lc": 1, "type": "constructor",
+       "id": ["langchain", "prompts", "chat", "MessagesPlaceholder"],
+       "kwargs": {"variable_name": "history"}},
+      ["human", "Hi"],
+      ["ai", "Hello!"],
+      ["human", "{question:>10}"]
+    ]
+  }
+}"#;
+        let imported = from_langchain(chat, &options()).unwrap();
+        let Prompt::Chat(chat) = &imported.prompts[0] else {
+            panic!("expected chat");
+        };
+        assert_eq!(
+            chat.context.as_deref(),
+            Some("Answer in a {{tone}} tone. Use {json}.")
+        );
+        let messages = chat.messages.as_deref().unwrap();
+        assert_eq!(messages[0].output.as_deref(), Some("Hello!"));
+        assert_eq!(messages[1].input, "{{question}}");
+        assert_eq!(
+            imported.prompts[0].required_variables(),
+            Ok(vec!["tone".to_string(), "question".to_string()])
+        );
+        assert_eq!(imported.warnings.len(), 2);
+        assert_eq!(
+            imported.warnings[0].to_string(),
+            "$.messages[1]: messages placeholder 'history' dropped"
+        );
+
+        let few_shot = r#"{
+  "_type": "few_shot",
+  "input_variables": ["word"],
+  "prefix": "Give the antonym of every input.",
+  "suffix": "Input: {{ word }}\nOutput:",
+  "example_separator": "\n---\n",
+  "template_format": "jinja2",
+  "example_prompt": {"_type": "prompt", "input_variables": ["input", "output"],
+                     "template": "Input: {input}\nOutput: {output}"},
+  "examples": [{"input": "happy", "output": "sad"}, {"input": "tall", "output": "short"}]
+}"#;
+        let imported = from_langchain(few_shot, &options()).unwrap();
+        let Prompt::Completion(completion) = &imported.prompts[0] else {
+            panic!("expected completion");
+        };
+        assert_eq!(completion.prompt, "Give the antonym of every input.");
+        assert_eq!(
+            completion.suffix.as_deref(),
+            Some("Input: {{word}}\nOutput:")
+        );
+        assert_eq!(
+            completion.example_format.as_ref().unwrap().separator(),
+            "\n---\n"
+        );
+        let columns = completion.examples.as_deref().unwrap();
+        assert_eq!(columns[0].name, "input");
+        assert_eq!(columns[1].values, vec!["sad", "short"]);
+        assert!(matches!(
+            from_langchain(
+                r#"{"_type": "prompt", "template": "x", "template_format": "mako"}"#,
+                &options()
+            ),
+            Err(ImportError::Unsupported(_))
+        ));
+    }
+
+    #[test]
+    fn test_promptfoo_config() {
+        let config = r#"
+description: Support bot
+prompts:
+  - "Summarize {{ text | trim }} for {{audience}}"
+  - id: chat
+    raw: '[{"role": "system", "content": "Be brief."}, {"role": "user", "content": "{{ question }}"}]'
+  - file://prompts/other.txt
+providers:
+  - id: openai:gpt-4o
+    config:
+      temperature: 0.2
+      max_tokens: 128
+      apiKey: secret
+tests:
+  - vars: { text: hello, audience: kids }
+"#;
+        let imported = from_promptfoo(config, &options()).unwrap();
+        assert_eq!(imported.prompts.len(), 2);
+        let Prompt::Completion(completion) = &imported.prompts[0] else {
+            panic!("expected completion");
+        };
+        assert_eq!(completion.prompt, "Summarize {{text}} for {{audience}}");
+        assert_eq!(completion.model, "gpt-4o");
+        assert_eq!(completion.find_parameter_as_i32("max_tokens"), Some(128));
+        assert!(completion.find_parameter_as_str("apiKey").is_none());
+        assert_eq!(completion.meta.name.as_deref(), Some("prompt-1"));
+        assert_eq!(completion.meta.description.as_deref(), Some("Support bot"));
+        let Prompt::Chat(chat) = &imported.prompts[1] else {
+            panic!("expected chat");
+        };
+        assert_eq!(chat.context.as_deref(), Some("Be brief."));
+        assert_eq!(chat.meta.name.as_deref(), Some("chat"));
+        assert!(chat
+            .meta
+            .variables
+            .as_ref()
+            .unwrap()
+            .contains_key("question"));
+        let paths: Vec<&str> = imported.warnings.iter().map(|w| w.path.as_str()).collect();
+        assert_eq!(
+            paths,
+            vec![
+                "providers[0].config.apiKey",
+                "tests",
+                "prompts[0]",
+                "prompts[2]"
+            ]
+        );
+    }
+}
@@ -0,0 +1,223 @@
+use crate::prompt::{Prompt, PromptError, PromptMeta};
+use crate::template::RenderError;
+use crate::validate::ValidationIssue;
+use serde::de::DeserializeOwned;
+use serde::Serialize;
+use serde_json::Value;
+use std::any::Any;
+use std::collections::HashMap;
+use std::fmt;
+use std::sync::{Arc, OnceLock, RwLock};
+
+pub trait PromptKind: fmt::Debug + Send + Sync {
+    fn kind(&self) -> &str;
+
+    fn to_value(&self) -> Value;
+
+    fn as_any(&self) -> &dyn Any;
+
+    fn meta(&self) -> Option<&PromptMeta> {
+        None
+    }
+
+    fn validate(&self) -> Vec<ValidationIssue> {
+        Vec::new()
+    }
+
+    fn required_variables(&self) -> Result<Vec<String>, RenderError> {
+        Ok(Vec::new())
+    }
+}
+
+impl PartialEq for dyn PromptKind {
+    fn eq(&self, other: &Self) -> bool {
+        self.kind() == other.kind() && self.to_value() == other.to_value()
+    }
+}
+
+type KindParser = dyn Fn(&Value) -> Result<Arc<dyn PromptKind>, String> + Send + Sync;
+
+static PARSERS: OnceLock<RwLock<HashMap<String, Arc<KindParser>>>> = OnceLock::new();
+
+fn parsers() -> &'static RwLock<HashMap<String, Arc<KindParser>>> {
+    PARSERS.get_or_init(|| RwLock::new(HashMap::new()))
+}
+
+pub struct PromptParser;
+
+impl PromptParser {
+    pub fn register<F, K>(kind: &str, parse: F)
+    where
+        F: Fn(&Value) -> Result<K, String> + Send + Sync + 'static,
+        K: PromptKind + 'static,
+    {
+        let parser: Arc<KindParser> =
+            Arc::new(move |document| parse(document).map(|k| Arc::new(k) as Arc<dyn PromptKind>));
+        parsers()
+            .write()
+            .unwrap_or_else(|e| e.into_inner())
+            .insert(kind.to_string(), parser);
+    }
+
+    pub fn register_serde<K>(kind: &str)
+    where
+        K: PromptKind + DeserializeOwned + 'static,
+    {
+        PromptParser::register(kind, |document| {
+            serde_json::from_value::<K>(document.clone()).map_err(|e| e.to_string())
+        });
+    }
+
+    pub fn unregister(kind: &str) -> bool {
+        parsers()
+            .write()
+            .unwrap_or_else(|e| e.into_inner())
+            .remove(kind)
+            .is_some()
+    }
+
+    pub fn is_registered(kind: &str) -> bool {
+        parsers()
+            .read()
+            .unwrap_or_else(|e| e.into_inner())
+            .contains_key(kind)
+    }
+
+    pub(crate) fn parse(kind: &str, document: &Value) -> Result<Prompt, PromptError> {
+        let parser = parsers()
+            .read()
+            .unwrap_or_else(|e| e.into_inner())
+            .get(kind)
+            .cloned()
+            .ok_or_else(|| PromptError::UnknownType(kind.to_string()))?;
+        parser(document)
+            .map(Prompt::Custom)
+            .map_err(|message| PromptError::SchemaMismatch {
+                prompt_type: kind.to_string(),
+                message,
+                location: None,
+            })
+    }
+}
+
+impl Prompt {
+    pub fn custom(kind: impl PromptKind + 'static) -> Prompt {
+        Prompt::Custom(Arc::new(kind))
+    }
+
+    pub fn downcast_ref<K: PromptKind + 'static>(&self) -> Option<&K> {
+        match self {
+            Prompt::Custom(kind) => kind.as_any().downcast_ref(),
+            _ => None,
+        }
+    }
+}
+
+pub(crate) fn serialize_kind<S: serde::Serializer>(
+    kind: &dyn PromptKind,
+    serializer: S,
+) -> Result<S::Ok, S::Error> {
+    kind.to_value().serialize(serializer)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::format::Format;
+    #[cfg(feature = "fs")]
+    use crate::registry::tests::{temp_dir, write};
+    #[cfg(feature = "fs")]
+    use crate::registry::PromptRegistry;
+    use crate::template::Template;
+    use serde::Deserialize;
+
+    #[derive(Debug, Serialize, Deserialize)]
+    struct Embedding {
+        #[serde(flatten)]
+        meta: PromptMeta,
+        #[serde(rename = "type")]
+        prompt_type: String,
+        model: String,
+        input: String,
+    }
+
+    impl PromptKind for Embedding {
+        fn kind(&self) -> &str {
+            &self.prompt_type
+        }
+
+        fn to_value(&self) -> Value {
+            serde_json::to_value(self).unwrap_or(Value::Null)
+        }
+
+        fn as_any(&self) -> &dyn Any {
+            self
+        }
+
+        fn meta(&self) -> Option<&PromptMeta> {
+            Some(&self.meta)
+        }
+
+        fn validate(&self) -> Vec<ValidationIssue> {
+            if self.model.is_empty() {
+                vec![ValidationIssue::error(
+                    "empty-field",
+                    "model",
+                    "'model' must not be empty",
+                )]
+            } else {
+                Vec::new()
+            }
+        }
+
+        fn required_variables(&self) -> Result<Vec<String>, RenderError> {
+            Template::parse(&self.input).map(|t| t.variables())
+        }
+    }
+
+    #[test]
+    fn test_custom_kind() {
+        let yaml = "type: embedding-test\nname: embed\nmodel: ''\ninput: '{{text}}'\n";
+        assert_eq!(
+            Prompt::from_str(yaml, Format::Yaml),
+            Err(PromptError::UnknownType("embedding-test".to_string()))
+        );
+
+        PromptParser::register_serde::<Embedding>("embedding-test");
+        let prompt = Prompt::from_str(yaml, Format::Yaml).unwrap();
+        assert_eq!(prompt.meta().and_then(|m| m.name.as_deref()), Some("embed"));
+        assert_eq!(
+            prompt.downcast_ref::<Embedding>().unwrap().input,
+            "{{text}}"
+        );
+        assert_eq!(prompt.required_variables(), Ok(vec!["text".to_string()]));
+        let codes: Vec<_> = prompt.validate().iter().map(|i| i.code).collect();
+        assert_eq!(codes, vec!["empty-field"]);
+
+        let json = serde_json::to_string(&prompt).unwrap();
+        assert_eq!(Prompt::from_str(&json, Format::Json), Ok(prompt));
+        assert!(matches!(
+            Prompt::from_str("type: embedding-test\nmodel: m\n", Format::Yaml),
+            Err(PromptError::SchemaMismatch { .. })
+        ));
+
+        assert!(PromptParser::unregister("embedding-test"));
+        assert!(!PromptParser::is_registered("embedding-test"));
+    }
+
+    #[cfg(feature = "fs")]
+    #[test]
+    fn test_custom_kind_in_registry() {
+        PromptParser::register_serde::<Embedding>("embedding-registry");
+        let dir = temp_dir("kind");
+        write(
+            &dir,
+            "embed.yaml",
+            "type: embedding-registry\nname: embed\nmodel: e-1\ninput: hi\n",
+        );
+        let registry = PromptRegistry::load(&dir).unwrap();
+        let prompt = registry.get("embed").unwrap();
+        assert_eq!(prompt.downcast_ref::<Embedding>().unwrap().model, "e-1");
+        assert!(prompt.validate().is_empty());
+    }
+}
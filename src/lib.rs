@@ -1 +1,64 @@
+mod base64;
+#[cfg(feature = "exec")]
+pub mod batch;
+pub mod builder;
+#[cfg(feature = "exec")]
+pub mod cache;
+#[cfg(feature = "fs")]
+pub mod compose;
+pub mod content;
+pub mod conversation;
+pub mod convert;
+pub mod cost;
+pub mod dataset;
+pub mod diff;
+#[cfg(feature = "exec")]
+pub mod eval;
+#[cfg(feature = "exec")]
+pub mod exec;
+pub mod fingerprint;
+pub mod format;
+pub mod import;
+pub mod kind;
+pub mod locale;
+pub mod markdown;
+#[cfg(feature = "exec")]
+pub mod mock;
+pub mod models;
+#[cfg(feature = "exec")]
+pub mod observe;
+pub mod output;
+pub mod overrides;
+pub mod pack;
+pub mod parameters;
+mod pattern;
+pub mod pipeline;
+pub mod policy;
 pub mod prompt;
+pub mod registry;
+pub mod request;
+#[cfg(feature = "exec")]
+pub mod retry;
+pub mod sampling;
+pub mod sanitize;
+pub mod scan;
+pub mod schema;
+pub mod select;
+mod sha256;
+#[cfg(feature = "exec")]
+pub mod stream;
+pub mod strict;
+pub mod template;
+#[cfg(feature = "fs")]
+pub mod testing;
+pub mod tokens;
+mod toml;
+pub mod tools;
+pub mod transcript;
+pub mod validate;
+pub mod variables;
+pub mod variant;
+#[cfg(feature = "watch")]
+pub mod watch;
+#[cfg(feature = "exec")]
+pub mod workflow;
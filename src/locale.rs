@@ -0,0 +1,151 @@
+use serde_json::Value;
+use std::collections::BTreeSet;
+
+pub const DEFAULT_LOCALE: &str = "en";
+
+const TEXT_FIELDS: &[&str] = &["prompt", "context", "description"];
+const TURN_FIELDS: &[&str] = &["input", "output", "test"];
+
+pub fn normalize_locale(locale: &str) -> String {
+    locale.trim().replace('_', "-").to_lowercase()
+}
+
+pub fn is_locale_tag(tag: &str) -> bool {
+    let mut parts = tag.split(['-', '_']);
+    let language = parts.next().unwrap_or("");
+    let region = parts.next();
+    (2..=3).contains(&language.len())
+        && language.chars().all(|c| c.is_ascii_alphabetic())
+        && region.is_none_or(|r| {
+            (2..=8).contains(&r.len()) && r.chars().all(|c| c.is_ascii_alphanumeric())
+        })
+        && parts.next().is_none()
+}
+
+pub fn locale_chain(locale: &str, default: &str) -> Vec<String> {
+    let mut chain = Vec::new();
+    let mut push = |tag: String| {
+        if !tag.is_empty() && !chain.contains(&tag) {
+            chain.push(tag);
+        }
+    };
+    let locale = normalize_locale(locale);
+    if let Some((language, _)) = locale.split_once('-') {
+        let language = language.to_string();
+        push(locale);
+        push(language);
+    } else {
+        push(locale);
+    }
+    push(normalize_locale(default));
+    chain
+}
+
+fn is_localized(value: &Value) -> bool {
+    match value.as_object() {
+        Some(map) => !map.is_empty() && map.iter().all(|(k, v)| is_locale_tag(k) && v.is_string()),
+        None => false,
+    }
+}
+
+fn pick(value: &mut Value, chain: &[String], found: &mut BTreeSet<String>) -> bool {
+    let Some(map) = value.as_object().filter(|_| is_localized(value)) else {
+        return false;
+    };
+    found.extend(map.keys().map(|k| normalize_locale(k)));
+    let chosen = chain
+        .iter()
+        .find_map(|tag| map.iter().find(|(k, _)| normalize_locale(k) == *tag))
+        .or_else(|| map.iter().next())
+        .map(|(_, v)| v.clone())
+        .unwrap_or_default();
+    *value = chosen;
+    true
+}
+
+fn walk(document: &mut Value, chain: &[String], found: &mut BTreeSet<String>) -> bool {
+    let Some(object) = document.as_object_mut() else {
+        return false;
+    };
+    let mut changed = false;
+    for field in TEXT_FIELDS {
+        if let Some(value) = object.get_mut(*field) {
+            changed |= pick(value, chain, found);
+        }
+    }
+    for list in ["examples", "messages"] {
+        for item in object
+            .get_mut(list)
+            .and_then(|l| l.as_array_mut())
+            .into_iter()
+            .flatten()
+        {
+            let Some(item) = item.as_object_mut() else {
+                continue;
+            };
+            for field in TURN_FIELDS {
+                if let Some(value) = item.get_mut(*field) {
+                    changed |= pick(value, chain, found);
+                }
+            }
+            for value in item
+                .get_mut("values")
+                .and_then(|v| v.as_array_mut())
+                .into_iter()
+                .flatten()
+            {
+                changed |= pick(value, chain, found);
+            }
+        }
+    }
+    changed
+}
+
+pub fn localize(document: &mut Value, chain: &[String]) -> bool {
+    walk(document, chain, &mut BTreeSet::new())
+}
+
+pub fn document_locales(document: &Value) -> BTreeSet<String> {
+    let mut found = BTreeSet::new();
+    walk(&mut document.clone(), &[], &mut found);
+    found
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::prompt::{deserialize_prompt, Prompt};
+    use serde_json::json;
+
+    #[test]
+    fn test_localize_document() {
+        assert!(is_locale_tag("pt-BR") && is_locale_tag("ja") && !is_locale_tag("old1"));
+        assert_eq!(locale_chain("pt_BR", "en"), vec!["pt-br", "pt", "en"]);
+
+        let mut document = json!({
+            "prompt": { "en": "Hello {{name}}", "de": "Hallo {{name}}", "pt": "Olá {{name}}" },
+            "examples": [{ "name": "input", "values": [{ "en": "hi", "de": "hallo" }] }],
+            "parameters": [{ "name": "logit_bias", "value": { "en": "kept" } }],
+        });
+        assert_eq!(
+            document_locales(&document).into_iter().collect::<Vec<_>>(),
+            vec!["de", "en", "pt"]
+        );
+        let mut german = document.clone();
+        assert!(localize(&mut german, &locale_chain("de-AT", "en")));
+        assert_eq!(german["prompt"], "Hallo {{name}}");
+        assert_eq!(german["examples"][0]["values"][0], "hallo");
+        assert_eq!(german["parameters"][0]["value"], json!({ "en": "kept" }));
+
+        assert!(localize(&mut document, &locale_chain("pt-BR", "en")));
+        assert_eq!(document["prompt"], "Olá {{name}}");
+        assert_eq!(document["examples"][0]["values"][0], "hi");
+
+        let yaml =
+            "type: completion\nvendor: openai\nmodel: gpt\nprompt:\n  de: Hallo\n  en: Hello\n";
+        match deserialize_prompt(yaml) {
+            Prompt::Completion(c) => assert_eq!(c.prompt, "Hello"),
+            other => panic!("Expected Prompt::Completion, got {:?}", other),
+        }
+    }
+}
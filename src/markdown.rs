@@ -0,0 +1,246 @@
+use crate::format::yaml_to_json;
+use crate::prompt::{Location, PromptError};
+use serde_json::{json, Map, Value};
+
+const FENCE: &str = "---";
+
+fn frontmatter_error(message: impl Into<String>, line: usize) -> PromptError {
+    PromptError::InvalidYaml {
+        message: message.into(),
+        location: Some(Location { line, column: 0 }),
+    }
+}
+
+pub(crate) fn split(source: &str) -> Result<(&str, &str), PromptError> {
+    let source = source.trim_start_matches('\u{feff}');
+    let Some(rest) = source
+        .strip_prefix(FENCE)
+        .and_then(|r| r.strip_prefix("\r\n").or_else(|| r.strip_prefix('\n')))
+    else {
+        return Err(frontmatter_error("expected '---' frontmatter", 1));
+    };
+    let mut offset = 0;
+    for line in rest.split_inclusive('\n') {
+        if matches!(line.trim_end(), FENCE | "...") {
+            return Ok((&rest[..offset], &rest[offset + line.len()..]));
+        }
+        offset += line.len();
+    }
+    Err(frontmatter_error(
+        "frontmatter is not closed with '---'",
+        source.lines().count(),
+    ))
+}
+
+#[derive(Debug, PartialEq)]
+enum Role {
+    System,
+    User,
+    Assistant,
+}
+
+fn role(line: &str) -> Option<Role> {
+    let title = line.strip_prefix("## ")?.trim().to_lowercase();
+    match title.as_str() {
+        "system" => Some(Role::System),
+        "user" => Some(Role::User),
+        "assistant" => Some(Role::Assistant),
+        _ => None,
+    }
+}
+
+fn sections(body: &str) -> (String, Vec<(Role, String)>) {
+    let mut preamble = String::new();
+    let mut sections: Vec<(Role, String)> = Vec::new();
+    let mut fenced = false;
+    for line in body.lines() {
+        if line.trim_start().starts_with("```") {
+            fenced = !fenced;
+        }
+        match role(line).filter(|_| !fenced) {
+            Some(role) => sections.push((role, String::new())),
+            None => {
+                let text = match sections.last_mut() {
+                    Some((_, text)) => text,
+                    None => &mut preamble,
+                };
+                text.push_str(line);
+                text.push('\n');
+            }
+        }
+    }
+    let trimmed = sections
+        .into_iter()
+        .map(|(role, text)| (role, text.trim().to_string()))
+        .collect();
+    (preamble.trim().to_string(), trimmed)
+}
+
+fn chat_fields(object: &mut Map<String, Value>, preamble: String, sections: Vec<(Role, String)>) {
+    let mut context = (!preamble.is_empty()).then_some(preamble);
+    let mut messages: Vec<Value> = Vec::new();
+    for (role, text) in sections {
+        match role {
+            Role::System => context = Some(text),
+            Role::User => messages.push(json!({ "input": text })),
+            Role::Assistant => match messages.last_mut() {
+                Some(message) if message.get("output").is_none() => {
+                    message["output"] = Value::String(text);
+                }
+                _ => messages.push(json!({ "input": "", "output": text })),
+            },
+        }
+    }
+    if let Some(context) = context {
+        object.insert("context".to_string(), Value::String(context));
+    }
+    if !messages.is_empty() {
+        object.insert("messages".to_string(), Value::Array(messages));
+    }
+}
+
+pub(crate) fn apply_body(document: &mut Value, body: &str) -> Result<(), PromptError> {
+    if document.is_null() {
+        *document = Value::Object(Map::new());
+    }
+    let Some(object) = document.as_object_mut() else {
+        return Err(frontmatter_error("frontmatter must be a mapping", 2));
+    };
+    let (preamble, sections) = sections(body);
+    if !object.contains_key("type") {
+        let kind = if sections.is_empty() {
+            "completion"
+        } else {
+            "chat"
+        };
+        object.insert("type".to_string(), Value::String(kind.to_string()));
+    }
+    match object.get("type").and_then(|t| t.as_str()) {
+        Some("chat") => chat_fields(object, preamble, sections),
+        Some("embedding") if !preamble.is_empty() => {
+            object.insert("input".to_string(), json!([preamble]));
+        }
+        _ if !body.trim().is_empty() => {
+            object.insert("prompt".to_string(), Value::String(body.trim().to_string()));
+        }
+        _ => {}
+    }
+    Ok(())
+}
+
+pub fn markdown_document(source: &str) -> Result<Value, PromptError> {
+    let (frontmatter, body) = split(source)?;
+    let yaml: serde_yaml::Value =
+        serde_yaml::from_str(frontmatter).map_err(|e| PromptError::InvalidYaml {
+            message: e.to_string(),
+            location: e.location().map(|l| Location {
+                line: l.line() + 1,
+                column: l.column(),
+            }),
+        })?;
+    let mut document = yaml_to_json(yaml);
+    apply_body(&mut document, body)?;
+    Ok(document)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::format::Format;
+    use crate::prompt::Prompt;
+    #[cfg(feature = "fs")]
+    use crate::registry::tests::{temp_dir, write};
+    #[cfg(feature = "fs")]
+    use crate::registry::PromptRegistry;
+    #[cfg(feature = "fs")]
+    use std::fs;
+    use std::path::Path;
+
+    const CHAT: &str = "---
+vendor: openai
+model: gpt-4o
+parameters:
+  - name: temperature
+    value: 0.3
+---
+## System
+You are a **support** agent for {{product}}.
+
+## user
+How do I reset my password?
+
+## assistant
+Use the *Forgot password* link.
+
+```md
+## user
+not a section
+```
+
+## user
+{{question}}
+";
+
+    #[test]
+    fn test_markdown_chat() {
+        let Prompt::Chat(chat) = Prompt::from_str(CHAT, Format::Markdown).unwrap() else {
+            panic!("Expected Prompt::Chat");
+        };
+        assert_eq!(
+            chat.context.as_deref(),
+            Some("You are a **support** agent for {{product}}.")
+        );
+        let messages = chat.messages.as_deref().unwrap();
+        assert_eq!(messages.len(), 2);
+        assert_eq!(messages[0].input, "How do I reset my password?");
+        assert!(messages[0]
+            .output
+            .as_deref()
+            .unwrap()
+            .contains("```md\n## user"));
+        assert_eq!(messages[1].input, "{{question}}");
+        assert_eq!(chat.find_parameter_as_f32("temperature"), Some(0.3));
+
+        #[cfg(feature = "fs")]
+        {
+            let dir = temp_dir("markdown_registry");
+            write(&dir, "README.md", "# Prompts\n");
+            write(
+                &dir,
+                "support.prompt.md",
+                &format!("---\nname: support\n{}", &CHAT[4..]),
+            );
+            let registry = PromptRegistry::load(&dir).unwrap();
+            assert_eq!(registry.list(), vec!["support"]);
+            assert!(matches!(registry.get("support"), Ok(Prompt::Chat(_))));
+            fs::remove_dir_all(&dir).unwrap();
+        }
+    }
+
+    #[test]
+    fn test_markdown_completion() {
+        let source = "---\ntype: completion\nvendor: google\nmodel: text-bison\n---\n\n# Task\n\nWrite a poem about {{topic}}.\n";
+        let Prompt::Completion(completion) = Prompt::from_str(source, Format::Markdown).unwrap()
+        else {
+            panic!("Expected Prompt::Completion");
+        };
+        assert_eq!(completion.prompt, "# Task\n\nWrite a poem about {{topic}}.");
+
+        assert!(matches!(
+            Prompt::from_str("# no frontmatter", Format::Markdown),
+            Err(PromptError::InvalidYaml { .. })
+        ));
+        assert!(matches!(
+            Prompt::from_str("---\nvendor: [\n---\nbody", Format::Markdown),
+            Err(PromptError::InvalidYaml {
+                location: Some(Location { line: 3, .. }),
+                ..
+            })
+        ));
+        assert_eq!(
+            Format::from_path(Path::new("prompts/greet.prompt.md")),
+            Some(Format::Markdown)
+        );
+        assert_eq!(Format::from_path(Path::new("prompts/README.md")), None);
+    }
+}
@@ -0,0 +1,298 @@
+#[cfg(feature = "fs")]
+use crate::cache::{FileCache, ResponseCache};
+use crate::exec::{BoxFuture, ExecError, ExecutionResult, PromptExecutor};
+use crate::prompt::Prompt;
+use serde_json::json;
+use std::collections::HashMap;
+#[cfg(feature = "fs")]
+use std::path::Path;
+use std::sync::{Arc, Mutex};
+
+#[cfg(feature = "fs")]
+pub const REPLAY_ENV: &str = "PROMPT_REPLAY";
+
+type Predicate = dyn Fn(&Prompt, &HashMap<String, String>) -> bool + Send + Sync;
+
+#[derive(Clone)]
+pub enum Matcher {
+    Any,
+    Fingerprint(String),
+    Name(String),
+    Custom(Arc<Predicate>),
+}
+
+impl Matcher {
+    pub fn custom(
+        predicate: impl Fn(&Prompt, &HashMap<String, String>) -> bool + Send + Sync + 'static,
+    ) -> Matcher {
+        Matcher::Custom(Arc::new(predicate))
+    }
+
+    pub fn matches(&self, prompt: &Prompt, vars: &HashMap<String, String>) -> bool {
+        match self {
+            Matcher::Any => true,
+            Matcher::Fingerprint(fingerprint) => {
+                prompt.fingerprint() == *fingerprint
+                    || prompt
+                        .rendered_fingerprint(vars)
+                        .is_ok_and(|rendered| rendered == *fingerprint)
+            }
+            Matcher::Name(name) => prompt_name(prompt) == Some(name.as_str()),
+            Matcher::Custom(predicate) => predicate(prompt, vars),
+        }
+    }
+}
+
+fn prompt_name(prompt: &Prompt) -> Option<&str> {
+    prompt.meta().and_then(|meta| meta.name.as_deref())
+}
+
+fn describe(prompt: &Prompt) -> String {
+    match prompt_name(prompt) {
+        Some(name) => format!("'{}'", name),
+        None => format!("prompt {}", prompt.fingerprint()),
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct MockCall {
+    pub name: Option<String>,
+    pub fingerprint: String,
+    pub vars: HashMap<String, String>,
+}
+
+#[derive(Default)]
+pub struct MockExecutor {
+    responses: Vec<(Matcher, Result<ExecutionResult, ExecError>)>,
+    calls: Mutex<Vec<MockCall>>,
+}
+
+impl MockExecutor {
+    pub fn new() -> MockExecutor {
+        MockExecutor::default()
+    }
+
+    pub fn respond(self, matcher: Matcher, text: impl Into<String>) -> Self {
+        let text = text.into();
+        let raw = json!({ "text": text });
+        self.respond_with(matcher, Ok(ExecutionResult { text, raw }))
+    }
+
+    pub fn respond_with(
+        mut self,
+        matcher: Matcher,
+        result: Result<ExecutionResult, ExecError>,
+    ) -> Self {
+        self.responses.push((matcher, result));
+        self
+    }
+
+    pub fn calls(&self) -> Vec<MockCall> {
+        self.calls.lock().unwrap_or_else(|e| e.into_inner()).clone()
+    }
+
+    pub fn call_count(&self) -> usize {
+        self.calls.lock().unwrap_or_else(|e| e.into_inner()).len()
+    }
+}
+
+impl PromptExecutor for MockExecutor {
+    fn execute<'a>(
+        &'a self,
+        prompt: &'a Prompt,
+        vars: &'a HashMap<String, String>,
+    ) -> BoxFuture<'a, Result<ExecutionResult, ExecError>> {
+        self.calls
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .push(MockCall {
+                name: prompt_name(prompt).map(|n| n.to_string()),
+                fingerprint: prompt.fingerprint(),
+                vars: vars.clone(),
+            });
+        let result = self
+            .responses
+            .iter()
+            .find(|(matcher, _)| matcher.matches(prompt, vars))
+            .map(|(_, result)| result.clone())
+            .unwrap_or_else(|| Err(ExecError::NoResponse(describe(prompt))));
+        Box::pin(async move { result })
+    }
+}
+
+#[cfg(feature = "fs")]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ReplayMode {
+    Record,
+    Replay,
+    Auto,
+}
+
+#[cfg(feature = "fs")]
+impl ReplayMode {
+    pub fn from_name(name: &str) -> Option<ReplayMode> {
+        match name.to_lowercase().as_str() {
+            "record" => Some(ReplayMode::Record),
+            "replay" => Some(ReplayMode::Replay),
+            "auto" => Some(ReplayMode::Auto),
+            _ => None,
+        }
+    }
+
+    pub fn from_env() -> ReplayMode {
+        std::env::var(REPLAY_ENV)
+            .ok()
+            .and_then(|mode| ReplayMode::from_name(&mode))
+            .unwrap_or(ReplayMode::Replay)
+    }
+}
+
+#[cfg(feature = "fs")]
+pub struct ReplayExecutor<E> {
+    inner: E,
+    fixtures: FileCache,
+    mode: ReplayMode,
+}
+
+#[cfg(feature = "fs")]
+impl<E> ReplayExecutor<E> {
+    pub fn new(inner: E, dir: impl AsRef<Path>) -> ReplayExecutor<E> {
+        ReplayExecutor {
+            inner,
+            fixtures: FileCache::new(dir),
+            mode: ReplayMode::from_env(),
+        }
+    }
+
+    pub fn mode(mut self, mode: ReplayMode) -> Self {
+        self.mode = mode;
+        self
+    }
+
+    pub fn fixtures(&self) -> &FileCache {
+        &self.fixtures
+    }
+}
+
+#[cfg(feature = "fs")]
+impl<E: PromptExecutor> PromptExecutor for ReplayExecutor<E> {
+    fn execute<'a>(
+        &'a self,
+        prompt: &'a Prompt,
+        vars: &'a HashMap<String, String>,
+    ) -> BoxFuture<'a, Result<ExecutionResult, ExecError>> {
+        Box::pin(async move {
+            let key = prompt.cache_key(vars)?;
+            if self.mode != ReplayMode::Record {
+                if let Some(result) = self.fixtures.get(&key) {
+                    return Ok(result);
+                }
+                if self.mode == ReplayMode::Replay {
+                    return Err(ExecError::NoResponse(describe(prompt)));
+                }
+            }
+            let result = self.inner.execute(prompt, vars).await?;
+            self.fixtures
+                .put(&key, &result)
+                .map_err(ExecError::Fixture)?;
+            Ok(result)
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::exec::block_on;
+    use crate::prompt::deserialize_prompt;
+    #[cfg(feature = "fs")]
+    use crate::registry::tests::temp_dir;
+
+    const PROMPT: &str =
+        "name: greet\ntype: completion\nvendor: openai\nmodel: gpt-4o\nprompt: Hello {{name}}\n";
+
+    fn vars(name: &str) -> HashMap<String, String> {
+        HashMap::from([("name".to_string(), name.to_string())])
+    }
+
+    #[test]
+    fn test_mock_executor() {
+        let prompt = deserialize_prompt(PROMPT);
+        let other =
+            deserialize_prompt("type: completion\nvendor: openai\nmodel: gpt-4o\nprompt: Bye\n");
+        let mock = MockExecutor::new()
+            .respond(
+                Matcher::custom(|_, vars| vars.get("name").is_some_and(|n| n == "Bob")),
+                "Hi Bob",
+            )
+            .respond(Matcher::Name("greet".to_string()), "Hi there")
+            .respond_with(
+                Matcher::Fingerprint(other.fingerprint()),
+                Err(ExecError::Timeout(Default::default())),
+            );
+        let run = |prompt: &Prompt, vars| block_on(prompt.execute(&mock, &vars));
+        assert_eq!(run(&prompt, vars("Bob")).unwrap().text, "Hi Bob");
+        assert_eq!(run(&prompt, vars("Ann")).unwrap().text, "Hi there");
+        assert!(matches!(
+            run(&other, vars("Ann")),
+            Err(ExecError::Timeout(_))
+        ));
+        assert_eq!(mock.call_count(), 3);
+        assert_eq!(mock.calls()[0].name.as_deref(), Some("greet"));
+
+        let unnamed =
+            deserialize_prompt("type: completion\nvendor: openai\nmodel: gpt-4o\nprompt: Hm\n");
+        assert_eq!(
+            run(&unnamed, vars("Ann")),
+            Err(ExecError::NoResponse(format!(
+                "prompt {}",
+                unnamed.fingerprint()
+            )))
+        );
+    }
+
+    #[cfg(feature = "fs")]
+    #[test]
+    fn test_record_and_replay() {
+        let dir = temp_dir("replay");
+        let prompt = deserialize_prompt(PROMPT);
+        let recorder = ReplayExecutor::new(MockExecutor::new().respond(Matcher::Any, "live"), &dir)
+            .mode(ReplayMode::Auto);
+        assert_eq!(
+            block_on(prompt.execute(&recorder, &vars("Ann")))
+                .unwrap()
+                .text,
+            "live"
+        );
+
+        let replay = ReplayExecutor::new(MockExecutor::new(), &dir).mode(ReplayMode::Replay);
+        assert_eq!(
+            block_on(prompt.execute(&replay, &vars("Ann")))
+                .unwrap()
+                .text,
+            "live"
+        );
+        assert_eq!(
+            block_on(prompt.execute(&replay, &vars("Bob"))),
+            Err(ExecError::NoResponse("'greet'".to_string()))
+        );
+        assert_eq!(recorder.inner.call_count(), 1);
+        assert_eq!(
+            block_on(prompt.execute(&recorder, &vars("Ann")))
+                .unwrap()
+                .text,
+            "live"
+        );
+        assert_eq!(recorder.inner.call_count(), 1);
+        assert_eq!(ReplayMode::from_name("RECORD"), Some(ReplayMode::Record));
+
+        std::fs::write(dir.join("blocked"), "").unwrap();
+        let live = MockExecutor::new().respond(Matcher::Any, "live");
+        let blocked = ReplayExecutor::new(live, dir.join("blocked")).mode(ReplayMode::Record);
+        assert!(matches!(
+            block_on(prompt.execute(&blocked, &vars("Ann"))),
+            Err(ExecError::Fixture(crate::cache::CacheError::Io { .. }))
+        ));
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+}
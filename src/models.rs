@@ -0,0 +1,450 @@
+use crate::cost::same_vendor;
+use std::fmt;
+
+const REASONING_PARAMETERS: &[&str] = &[
+    "max_output_tokens",
+    "max_completion_tokens",
+    "reasoning_effort",
+    "response_format",
+    "seed",
+    "stream",
+    "n",
+    "user",
+    "store",
+    "metadata",
+];
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ModelStatus {
+    Active,
+    Deprecated,
+    Retired,
+}
+
+impl fmt::Display for ModelStatus {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let status = match self {
+            ModelStatus::Active => "active",
+            ModelStatus::Deprecated => "deprecated",
+            ModelStatus::Retired => "retired",
+        };
+        write!(f, "{}", status)
+    }
+}
+
+use ModelStatus::{Active, Deprecated, Retired};
+
+type BuiltinModel = (
+    &'static str,
+    &'static str,
+    usize,
+    bool,
+    bool,
+    ModelStatus,
+    Option<&'static str>,
+);
+
+const BUILTIN_MODELS: &[BuiltinModel] = &[
+    ("openai", "gpt-4o", 128_000, true, true, Active, None),
+    ("openai", "gpt-4o-mini", 128_000, true, true, Active, None),
+    ("openai", "gpt-4.1", 1_047_576, true, true, Active, None),
+    ("openai", "gpt-4-turbo", 128_000, true, true, Active, None),
+    ("openai", "gpt-4", 8_192, true, false, Active, None),
+    (
+        "openai",
+        "gpt-3.5-turbo",
+        16_385,
+        true,
+        false,
+        Deprecated,
+        Some("gpt-4o-mini"),
+    ),
+    (
+        "openai",
+        "gpt-3.5-turbo-instruct",
+        4_096,
+        false,
+        false,
+        Active,
+        None,
+    ),
+    (
+        "openai",
+        "text-davinci",
+        4_097,
+        false,
+        false,
+        Retired,
+        Some("gpt-3.5-turbo-instruct"),
+    ),
+    ("openai", "o1", 200_000, true, true, Active, None),
+    (
+        "openai",
+        "o1-mini",
+        128_000,
+        false,
+        false,
+        Deprecated,
+        Some("o3-mini"),
+    ),
+    (
+        "openai",
+        "o1-preview",
+        128_000,
+        false,
+        false,
+        Retired,
+        Some("o1"),
+    ),
+    ("openai", "o3", 200_000, true, true, Active, None),
+    ("openai", "o3-mini", 200_000, true, false, Active, None),
+    ("openai", "o4-mini", 200_000, true, true, Active, None),
+    (
+        "openai",
+        "text-embedding-3",
+        8_191,
+        false,
+        false,
+        Active,
+        None,
+    ),
+    (
+        "openai",
+        "text-embedding-ada-002",
+        8_191,
+        false,
+        false,
+        Deprecated,
+        Some("text-embedding-3-small"),
+    ),
+    (
+        "anthropic",
+        "claude-3-haiku",
+        200_000,
+        true,
+        true,
+        Active,
+        None,
+    ),
+    (
+        "anthropic",
+        "claude-3-sonnet",
+        200_000,
+        true,
+        true,
+        Retired,
+        Some("claude-sonnet-4"),
+    ),
+    (
+        "anthropic",
+        "claude-3-opus",
+        200_000,
+        true,
+        true,
+        Deprecated,
+        Some("claude-opus-4"),
+    ),
+    (
+        "anthropic",
+        "claude-3-5-haiku",
+        200_000,
+        true,
+        false,
+        Active,
+        None,
+    ),
+    (
+        "anthropic",
+        "claude-3-5-sonnet",
+        200_000,
+        true,
+        true,
+        Deprecated,
+        Some("claude-sonnet-4"),
+    ),
+    (
+        "anthropic",
+        "claude-3-7-sonnet",
+        200_000,
+        true,
+        true,
+        Active,
+        None,
+    ),
+    (
+        "anthropic",
+        "claude-sonnet-4",
+        200_000,
+        true,
+        true,
+        Active,
+        None,
+    ),
+    (
+        "anthropic",
+        "claude-opus-4",
+        200_000,
+        true,
+        true,
+        Active,
+        None,
+    ),
+    (
+        "anthropic",
+        "claude-2",
+        100_000,
+        false,
+        false,
+        Retired,
+        Some("claude-sonnet-4"),
+    ),
+    (
+        "anthropic",
+        "claude-instant",
+        100_000,
+        false,
+        false,
+        Retired,
+        Some("claude-3-5-haiku"),
+    ),
+    (
+        "google",
+        "text-bison",
+        8_192,
+        false,
+        false,
+        Deprecated,
+        Some("gemini-2.0-flash"),
+    ),
+    (
+        "google",
+        "chat-bison",
+        8_192,
+        false,
+        false,
+        Deprecated,
+        Some("gemini-2.0-flash"),
+    ),
+    (
+        "google",
+        "textembedding-gecko",
+        3_072,
+        false,
+        false,
+        Deprecated,
+        Some("text-embedding-004"),
+    ),
+    (
+        "google",
+        "text-embedding-004",
+        2_048,
+        false,
+        false,
+        Active,
+        None,
+    ),
+    (
+        "google",
+        "gemini-1.0-pro",
+        32_760,
+        true,
+        false,
+        Deprecated,
+        Some("gemini-2.0-flash"),
+    ),
+    (
+        "google",
+        "gemini-1.5-flash",
+        1_048_576,
+        true,
+        true,
+        Active,
+        None,
+    ),
+    (
+        "google",
+        "gemini-1.5-pro",
+        2_097_152,
+        true,
+        true,
+        Active,
+        None,
+    ),
+    (
+        "google",
+        "gemini-2.0-flash",
+        1_048_576,
+        true,
+        true,
+        Active,
+        None,
+    ),
+    (
+        "google",
+        "gemini-2.5-flash",
+        1_048_576,
+        true,
+        true,
+        Active,
+        None,
+    ),
+    (
+        "google",
+        "gemini-2.5-pro",
+        1_048_576,
+        true,
+        true,
+        Active,
+        None,
+    ),
+];
+
+const REASONING_MODELS: &[&str] = &["o1", "o1-mini", "o1-preview", "o3", "o3-mini", "o4-mini"];
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ModelInfo {
+    pub vendor: String,
+    pub model: String,
+    pub context_window: usize,
+    pub supports_tools: bool,
+    pub supports_vision: bool,
+    pub parameters: Option<Vec<String>>,
+    pub status: ModelStatus,
+    pub replacement: Option<String>,
+}
+
+impl ModelInfo {
+    pub fn new(
+        vendor: impl Into<String>,
+        model_prefix: impl Into<String>,
+        context_window: usize,
+    ) -> ModelInfo {
+        ModelInfo {
+            vendor: vendor.into(),
+            model: model_prefix.into(),
+            context_window,
+            supports_tools: false,
+            supports_vision: false,
+            parameters: None,
+            status: ModelStatus::Active,
+            replacement: None,
+        }
+    }
+
+    pub fn tools(mut self, supports_tools: bool) -> Self {
+        self.supports_tools = supports_tools;
+        self
+    }
+
+    pub fn vision(mut self, supports_vision: bool) -> Self {
+        self.supports_vision = supports_vision;
+        self
+    }
+
+    pub fn parameters(mut self, parameters: &[&str]) -> Self {
+        self.parameters = Some(parameters.iter().map(|p| p.to_string()).collect());
+        self
+    }
+
+    pub fn status(mut self, status: ModelStatus, replacement: Option<&str>) -> Self {
+        self.status = status;
+        self.replacement = replacement.map(|r| r.to_string());
+        self
+    }
+
+    pub fn supports_parameter(&self, name: &str) -> bool {
+        self.parameters
+            .as_ref()
+            .is_none_or(|parameters| parameters.iter().any(|p| p == name))
+    }
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct ModelRegistry {
+    models: Vec<ModelInfo>,
+}
+
+fn is_version_of(model: &str, prefix: &str) -> bool {
+    model
+        .strip_prefix(prefix)
+        .is_some_and(|rest| rest.is_empty() || rest.starts_with(['-', '@']))
+}
+
+impl ModelRegistry {
+    pub fn new() -> ModelRegistry {
+        ModelRegistry::default()
+    }
+
+    pub fn builtin() -> ModelRegistry {
+        BUILTIN_MODELS.iter().fold(
+            ModelRegistry::new(),
+            |registry, (vendor, model, window, tools, vision, status, replacement)| {
+                let mut info = ModelInfo::new(*vendor, *model, *window)
+                    .tools(*tools)
+                    .vision(*vision)
+                    .status(*status, *replacement);
+                if REASONING_MODELS.contains(model) {
+                    info = info.parameters(REASONING_PARAMETERS);
+                }
+                registry.with_model(info)
+            },
+        )
+    }
+
+    pub fn with_model(mut self, info: ModelInfo) -> Self {
+        self.models
+            .retain(|m| !(same_vendor(&m.vendor, &info.vendor) && m.model == info.model));
+        self.models.push(info);
+        self
+    }
+
+    pub fn model(&self, vendor: &str, model: &str) -> Option<&ModelInfo> {
+        self.models
+            .iter()
+            .filter(|m| same_vendor(&m.vendor, vendor) && is_version_of(model, &m.model))
+            .max_by_key(|m| m.model.len())
+    }
+
+    pub fn models(&self) -> &[ModelInfo] {
+        &self.models
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_model_lookup() {
+        let registry = ModelRegistry::builtin();
+        let mini = registry.model("openai", "gpt-4o-mini-2024-07-18").unwrap();
+        assert_eq!(mini.model, "gpt-4o-mini");
+        assert_eq!(mini.context_window, 128_000);
+        assert!(mini.supports_parameter("temperature"));
+        let o1 = registry.model("openai", "o1-2024-12-17").unwrap();
+        assert!(!o1.supports_parameter("temperature"));
+        assert!(o1.supports_parameter("reasoning_effort"));
+        assert!(o1.supports_parameter("max_completion_tokens"));
+        assert!(!o1.supports_parameter("max_tokens"));
+        assert_eq!(
+            registry.model("openai", "gpt-4-0613").unwrap().model,
+            "gpt-4"
+        );
+        assert_eq!(
+            registry.model("openai", "gpt-4o-2024-08-06").unwrap().model,
+            "gpt-4o"
+        );
+        assert!(registry.model("openai", "gpt-4.5-preview").is_none());
+        assert!(registry.model("openai", "o1x").is_none());
+        assert_eq!(
+            registry.model("vertex", "text-bison@002").map(|m| m.status),
+            Some(ModelStatus::Deprecated)
+        );
+        assert!(registry.model("anthropic", "gpt-4o").is_none());
+
+        let custom = registry.with_model(ModelInfo::new("openai", "gpt-4o", 64_000).tools(true));
+        let gpt = custom.model("openai", "gpt-4o").unwrap();
+        assert_eq!(gpt.context_window, 64_000);
+        assert!(!gpt.supports_vision);
+    }
+}
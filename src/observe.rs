@@ -0,0 +1,389 @@
+use crate::cache::CacheError;
+use crate::cost::Usage;
+use crate::exec::{
+    render_prompt, BoxFuture, ExecError, ExecutionResult, PromptExecutor, RenderedPrompt,
+};
+use crate::prompt::Prompt;
+use crate::stream::{StreamingExecutor, TokenCallback};
+use serde_json::Value;
+use std::collections::HashMap;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+static EXECUTIONS: AtomicU64 = AtomicU64::new(0);
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PromptInfo {
+    pub id: u64,
+    pub name: Option<String>,
+    pub variant: Option<String>,
+    pub fingerprint: String,
+    pub vendor: Option<String>,
+    pub model: Option<String>,
+}
+
+impl PromptInfo {
+    pub fn new(prompt: &Prompt) -> PromptInfo {
+        let (vendor, model) = match prompt {
+            Prompt::Completion(c) => (Some(c.vendor.clone()), Some(c.model.clone())),
+            Prompt::Chat(c) => (Some(c.vendor.clone()), Some(c.model.clone())),
+            Prompt::Embedding(e) => (Some(e.vendor.clone()), Some(e.model.clone())),
+            Prompt::Custom(_) | Prompt::Unknown => (None, None),
+        };
+        PromptInfo {
+            id: EXECUTIONS.fetch_add(1, Ordering::Relaxed),
+            name: prompt.meta().and_then(|m| m.name.clone()),
+            variant: prompt.meta().and_then(|m| m.variant.clone()),
+            fingerprint: prompt.fingerprint(),
+            vendor,
+            model,
+        }
+    }
+}
+
+pub trait PromptObserver: Send + Sync {
+    fn on_render(&self, _info: &PromptInfo, _rendered: &Prompt) {}
+
+    fn on_request(&self, _info: &PromptInfo, _body: &Value) {}
+
+    fn on_response(&self, _info: &PromptInfo, _result: &ExecutionResult) {}
+
+    fn on_tokens(&self, _info: &PromptInfo, _usage: Usage) {}
+
+    fn on_latency(&self, _info: &PromptInfo, _latency: Duration) {}
+
+    fn on_error(&self, _info: &PromptInfo, _error: &ExecError) {}
+
+    fn on_cache_error(&self, _info: &PromptInfo, _error: &CacheError) {}
+}
+
+impl<O: PromptObserver + ?Sized> PromptObserver for Arc<O> {
+    fn on_render(&self, info: &PromptInfo, rendered: &Prompt) {
+        (**self).on_render(info, rendered)
+    }
+
+    fn on_request(&self, info: &PromptInfo, body: &Value) {
+        (**self).on_request(info, body)
+    }
+
+    fn on_response(&self, info: &PromptInfo, result: &ExecutionResult) {
+        (**self).on_response(info, result)
+    }
+
+    fn on_tokens(&self, info: &PromptInfo, usage: Usage) {
+        (**self).on_tokens(info, usage)
+    }
+
+    fn on_latency(&self, info: &PromptInfo, latency: Duration) {
+        (**self).on_latency(info, latency)
+    }
+
+    fn on_error(&self, info: &PromptInfo, error: &ExecError) {
+        (**self).on_error(info, error)
+    }
+
+    fn on_cache_error(&self, info: &PromptInfo, error: &CacheError) {
+        (**self).on_cache_error(info, error)
+    }
+}
+
+fn rendered_prompt(rendered: RenderedPrompt) -> Prompt {
+    match rendered {
+        RenderedPrompt::Completion(c) => Prompt::Completion(c),
+        RenderedPrompt::Chat(c) => Prompt::Chat(c),
+        RenderedPrompt::Embedding(e) => Prompt::Embedding(e),
+    }
+}
+
+pub struct ObservedExecutor<E> {
+    inner: E,
+    observers: Vec<Arc<dyn PromptObserver>>,
+}
+
+impl<E> ObservedExecutor<E> {
+    pub fn new(inner: E) -> ObservedExecutor<E> {
+        ObservedExecutor {
+            inner,
+            observers: Vec::new(),
+        }
+    }
+
+    pub fn observer(mut self, observer: impl PromptObserver + 'static) -> Self {
+        self.observers.push(Arc::new(observer));
+        self
+    }
+
+    fn notify(&self, event: impl Fn(&dyn PromptObserver)) {
+        for observer in &self.observers {
+            event(observer.as_ref());
+        }
+    }
+
+    fn before(
+        &self,
+        prompt: &Prompt,
+        vars: &HashMap<String, String>,
+    ) -> Result<PromptInfo, ExecError> {
+        let info = PromptInfo::new(prompt);
+        match render_prompt(prompt, vars) {
+            Ok(rendered) => {
+                let rendered = rendered_prompt(rendered);
+                self.notify(|o| o.on_render(&info, &rendered));
+                if let Ok(body) = rendered.to_request() {
+                    self.notify(|o| o.on_request(&info, &body));
+                }
+                Ok(info)
+            }
+            Err(error) => {
+                self.notify(|o| o.on_error(&info, &error));
+                self.notify(|o| o.on_latency(&info, Duration::ZERO));
+                Err(error)
+            }
+        }
+    }
+
+    fn after(
+        &self,
+        info: &PromptInfo,
+        started: Instant,
+        result: Result<ExecutionResult, ExecError>,
+    ) -> Result<ExecutionResult, ExecError> {
+        match &result {
+            Ok(result) => {
+                self.notify(|o| o.on_response(info, result));
+                if let Some(usage) = result.usage() {
+                    self.notify(|o| o.on_tokens(info, usage));
+                }
+            }
+            Err(error) => self.notify(|o| o.on_error(info, error)),
+        }
+        let latency = started.elapsed();
+        self.notify(|o| o.on_latency(info, latency));
+        result
+    }
+}
+
+impl<E: PromptExecutor> PromptExecutor for ObservedExecutor<E> {
+    fn execute<'a>(
+        &'a self,
+        prompt: &'a Prompt,
+        vars: &'a HashMap<String, String>,
+    ) -> BoxFuture<'a, Result<ExecutionResult, ExecError>> {
+        Box::pin(async move {
+            let info = self.before(prompt, vars)?;
+            let started = Instant::now();
+            let result = self.inner.execute(prompt, vars).await;
+            self.after(&info, started, result)
+        })
+    }
+}
+
+impl<E: StreamingExecutor> StreamingExecutor for ObservedExecutor<E> {
+    fn execute_streaming<'a>(
+        &'a self,
+        prompt: &'a Prompt,
+        vars: &'a HashMap<String, String>,
+        on_token: TokenCallback<'a>,
+    ) -> BoxFuture<'a, Result<ExecutionResult, ExecError>> {
+        Box::pin(async move {
+            let info = self.before(prompt, vars)?;
+            let started = Instant::now();
+            let result = self.inner.execute_streaming(prompt, vars, on_token).await;
+            self.after(&info, started, result)
+        })
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Span {
+    pub info: PromptInfo,
+    pub usage: Option<Usage>,
+    pub latency: Duration,
+    pub error: Option<String>,
+}
+
+impl Span {
+    pub fn fields(&self) -> Vec<(&'static str, String)> {
+        let mut fields = vec![("prompt.fingerprint", self.info.fingerprint.clone())];
+        let optional = [
+            ("prompt.name", self.info.name.clone()),
+            ("prompt.variant", self.info.variant.clone()),
+            ("llm.vendor", self.info.vendor.clone()),
+            ("llm.model", self.info.model.clone()),
+            (
+                "llm.input_tokens",
+                self.usage.map(|u| u.input_tokens.to_string()),
+            ),
+            (
+                "llm.output_tokens",
+                self.usage.map(|u| u.output_tokens.to_string()),
+            ),
+        ];
+        fields.extend(
+            optional
+                .into_iter()
+                .filter_map(|(key, value)| Some((key, value?))),
+        );
+        fields.push(("latency_ms", self.latency.as_millis().to_string()));
+        if let Some(error) = &self.error {
+            fields.push(("error", error.clone()));
+        }
+        fields
+    }
+}
+
+type SpanSink = Box<dyn Fn(Span) + Send + Sync>;
+
+/// Collects one `Span` per execution and hands it to the sink once it ends.
+pub struct SpanObserver {
+    sink: SpanSink,
+    open: Mutex<HashMap<u64, Span>>,
+}
+
+impl SpanObserver {
+    pub fn new(sink: impl Fn(Span) + Send + Sync + 'static) -> SpanObserver {
+        SpanObserver {
+            sink: Box::new(sink),
+            open: Mutex::new(HashMap::new()),
+        }
+    }
+
+    fn update(&self, info: &PromptInfo, update: impl FnOnce(&mut Span)) {
+        let mut open = self.open.lock().unwrap_or_else(|e| e.into_inner());
+        let span = open.entry(info.id).or_insert_with(|| Span {
+            info: info.clone(),
+            usage: None,
+            latency: Duration::ZERO,
+            error: None,
+        });
+        update(span);
+    }
+}
+
+impl PromptObserver for SpanObserver {
+    fn on_tokens(&self, info: &PromptInfo, usage: Usage) {
+        self.update(info, |span| span.usage = Some(usage));
+    }
+
+    fn on_error(&self, info: &PromptInfo, error: &ExecError) {
+        self.update(info, |span| span.error = Some(error.to_string()));
+    }
+
+    fn on_latency(&self, info: &PromptInfo, latency: Duration) {
+        self.update(info, |span| span.latency = latency);
+        let span = self
+            .open
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .remove(&info.id);
+        if let Some(span) = span {
+            (self.sink)(span);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::exec::tests::MockClient;
+    use crate::exec::{block_on, OpenAiExecutor};
+    use crate::prompt::deserialize_prompt;
+    use serde_json::json;
+
+    #[derive(Default)]
+    struct Events(Mutex<Vec<String>>);
+
+    impl PromptObserver for Events {
+        fn on_render(&self, _: &PromptInfo, rendered: &Prompt) {
+            if let Prompt::Completion(c) = rendered {
+                self.0.lock().unwrap().push(format!("render {}", c.prompt));
+            }
+        }
+
+        fn on_request(&self, _: &PromptInfo, body: &Value) {
+            self.0
+                .lock()
+                .unwrap()
+                .push(format!("request {}", body["model"]));
+        }
+
+        fn on_response(&self, _: &PromptInfo, result: &ExecutionResult) {
+            self.0
+                .lock()
+                .unwrap()
+                .push(format!("response {}", result.text));
+        }
+
+        fn on_tokens(&self, _: &PromptInfo, usage: Usage) {
+            self.0
+                .lock()
+                .unwrap()
+                .push(format!("tokens {}", usage.output_tokens));
+        }
+
+        fn on_latency(&self, _: &PromptInfo, _: Duration) {
+            self.0.lock().unwrap().push("latency".to_string());
+        }
+
+        fn on_error(&self, _: &PromptInfo, error: &ExecError) {
+            self.0.lock().unwrap().push(format!("error {}", error));
+        }
+    }
+
+    #[test]
+    fn test_observed_execution() {
+        let prompt = deserialize_prompt(
+            "name: greet\ntype: completion\nvendor: openai\nmodel: gpt\nprompt: Hi {{name}}\n",
+        );
+        let client = MockClient::replying(&[
+            (
+                200,
+                json!({
+                    "choices": [{ "text": "Hello" }],
+                    "usage": { "prompt_tokens": 3, "completion_tokens": 1 }
+                }),
+            ),
+            (500, json!({ "error": "down" })),
+        ]);
+        let events = Arc::new(Events::default());
+        let spans = Arc::new(Mutex::new(Vec::new()));
+        let sink = spans.clone();
+        let executor = ObservedExecutor::new(OpenAiExecutor::new(client, "key"))
+            .observer(events.clone())
+            .observer(SpanObserver::new(move |span| {
+                sink.lock().unwrap().push(span)
+            }));
+        let vars = HashMap::from([("name".to_string(), "Ada".to_string())]);
+
+        assert_eq!(
+            block_on(prompt.execute(&executor, &vars)).unwrap().text,
+            "Hello"
+        );
+        assert!(block_on(prompt.execute(&executor, &vars)).is_err());
+        assert!(block_on(prompt.execute(&executor, &HashMap::new())).is_err());
+        let events = events.0.lock().unwrap();
+        assert_eq!(
+            events[..5],
+            [
+                "render Hi Ada",
+                "request \"gpt\"",
+                "response Hello",
+                "tokens 1",
+                "latency"
+            ]
+        );
+        assert!(events[7].starts_with("error"));
+        assert_eq!(events.len(), 11);
+
+        let spans = spans.lock().unwrap();
+        assert_eq!(spans.len(), 3);
+        assert_eq!(spans[0].info.name.as_deref(), Some("greet"));
+        assert_eq!(spans[0].info.fingerprint, prompt.fingerprint());
+        let fields = spans[0].fields();
+        assert_eq!(fields[1], ("prompt.name", "greet".to_string()));
+        assert!(fields.contains(&("llm.input_tokens", "3".to_string())));
+        assert!(spans[1].error.is_some());
+        assert_ne!(spans[1].info.id, spans[2].info.id);
+    }
+}
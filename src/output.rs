@@ -0,0 +1,433 @@
+use crate::pattern::Pattern;
+use crate::prompt::{Chat, Completion, Prompt};
+use serde::de::DeserializeOwned;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::fmt;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum OutputFormat {
+    #[default]
+    Text,
+    Json,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize, PartialEq)]
+pub struct OutputSpec {
+    #[serde(default)]
+    pub format: OutputFormat,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub schema: Option<Value>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub pattern: Option<String>,
+    #[serde(rename = "enum", skip_serializing_if = "Option::is_none")]
+    pub allowed: Option<Vec<String>>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SchemaViolation {
+    pub path: String,
+    pub message: String,
+}
+
+impl fmt::Display for SchemaViolation {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}: {}", self.path, self.message)
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum OutputError {
+    InvalidJson(String),
+    SchemaViolation(Vec<SchemaViolation>),
+    PatternMismatch { pattern: String },
+    NotAllowed { value: String, allowed: Vec<String> },
+    InvalidPattern(String),
+    Deserialize(String),
+}
+
+impl fmt::Display for OutputError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            OutputError::InvalidJson(message) => {
+                write!(f, "response is not valid JSON: {}", message)
+            }
+            OutputError::SchemaViolation(violations) => {
+                let violations: Vec<String> = violations.iter().map(|v| v.to_string()).collect();
+                write!(
+                    f,
+                    "response does not match schema: {}",
+                    violations.join("; ")
+                )
+            }
+            OutputError::PatternMismatch { pattern } => {
+                write!(f, "response does not match pattern '{}'", pattern)
+            }
+            OutputError::NotAllowed { value, allowed } => write!(
+                f,
+                "response '{}' is not one of {}",
+                value,
+                allowed.join(", ")
+            ),
+            OutputError::InvalidPattern(message) => write!(f, "invalid pattern: {}", message),
+            OutputError::Deserialize(message) => write!(f, "{}", message),
+        }
+    }
+}
+
+impl std::error::Error for OutputError {}
+
+fn compile(pattern: &str) -> Result<Pattern, OutputError> {
+    Pattern::new(pattern).map_err(|e| OutputError::InvalidPattern(e.to_string()))
+}
+
+fn extract_json(response: &str) -> &str {
+    let trimmed = response.trim();
+    if let Some(start) = trimmed.find("```") {
+        let fenced = &trimmed[start + 3..];
+        let body = fenced.find('\n').map_or(fenced, |n| &fenced[n + 1..]);
+        if let Some(end) = body.find("```") {
+            return body[..end].trim();
+        }
+    }
+    if serde_json::from_str::<Value>(trimmed).is_ok() {
+        return trimmed;
+    }
+    let start = trimmed.find(['{', '[']);
+    let end = trimmed.rfind(['}', ']']);
+    match (start, end) {
+        (Some(start), Some(end)) if start < end => &trimmed[start..=end],
+        _ => trimmed,
+    }
+}
+
+fn type_matches(value: &Value, expected: &str) -> bool {
+    match expected {
+        "string" => value.is_string(),
+        "number" => value.is_number(),
+        "integer" => value.is_i64() || value.is_u64(),
+        "boolean" => value.is_boolean(),
+        "object" => value.is_object(),
+        "array" => value.is_array(),
+        "null" => value.is_null(),
+        _ => true,
+    }
+}
+
+fn check_schema(value: &Value, schema: &Value, path: &str, violations: &mut Vec<SchemaViolation>) {
+    let mut violation = |message: String| {
+        violations.push(SchemaViolation {
+            path: path.to_string(),
+            message,
+        })
+    };
+
+    if let Some(expected) = schema.get("type") {
+        let types: Vec<&str> = match expected {
+            Value::String(t) => vec![t.as_str()],
+            Value::Array(ts) => ts.iter().filter_map(|t| t.as_str()).collect(),
+            _ => Vec::new(),
+        };
+        if !types.is_empty() && !types.iter().any(|t| type_matches(value, t)) {
+            violation(format!("expected {}", types.join(" or ")));
+            return;
+        }
+    }
+    if let Some(allowed) = schema.get("enum").and_then(|e| e.as_array()) {
+        if !allowed.contains(value) {
+            violation(format!("{} is not an allowed value", value));
+        }
+    }
+    if let Some(expected) = schema.get("const") {
+        if value != expected {
+            violation(format!("expected {}", expected));
+        }
+    }
+
+    if let Some(number) = value.as_f64() {
+        if let Some(minimum) = schema.get("minimum").and_then(|m| m.as_f64()) {
+            if number < minimum {
+                violation(format!("{} is less than {}", number, minimum));
+            }
+        }
+        if let Some(maximum) = schema.get("maximum").and_then(|m| m.as_f64()) {
+            if number > maximum {
+                violation(format!("{} is greater than {}", number, maximum));
+            }
+        }
+    }
+
+    if let Some(text) = value.as_str() {
+        let length = text.chars().count() as u64;
+        if let Some(min) = schema.get("minLength").and_then(|m| m.as_u64()) {
+            if length < min {
+                violation(format!("shorter than {} characters", min));
+            }
+        }
+        if let Some(max) = schema.get("maxLength").and_then(|m| m.as_u64()) {
+            if length > max {
+                violation(format!("longer than {} characters", max));
+            }
+        }
+        if let Some(pattern) = schema.get("pattern").and_then(|p| p.as_str()) {
+            match Pattern::new(pattern) {
+                Ok(compiled) if compiled.is_match(text) => {}
+                Ok(_) => violation(format!("does not match pattern '{}'", pattern)),
+                Err(e) => violation(format!("invalid pattern '{}': {}", pattern, e)),
+            }
+        }
+    }
+
+    if let Some(items) = value.as_array() {
+        let count = items.len() as u64;
+        if let Some(min) = schema.get("minItems").and_then(|m| m.as_u64()) {
+            if count < min {
+                violation(format!("fewer than {} items", min));
+            }
+        }
+        if let Some(max) = schema.get("maxItems").and_then(|m| m.as_u64()) {
+            if count > max {
+                violation(format!("more than {} items", max));
+            }
+        }
+        if let Some(item_schema) = schema.get("items") {
+            for (i, item) in items.iter().enumerate() {
+                check_schema(item, item_schema, &format!("{}[{}]", path, i), violations);
+            }
+        }
+    }
+
+    if let Some(object) = value.as_object() {
+        for name in schema
+            .get("required")
+            .and_then(|r| r.as_array())
+            .into_iter()
+            .flatten()
+            .filter_map(|n| n.as_str())
+        {
+            if !object.contains_key(name) {
+                violations.push(SchemaViolation {
+                    path: path.to_string(),
+                    message: format!("missing required property '{}'", name),
+                });
+            }
+        }
+        let properties = schema.get("properties").and_then(|p| p.as_object());
+        for (name, field) in object {
+            let field_path = format!("{}.{}", path, name);
+            match properties.and_then(|p| p.get(name)) {
+                Some(field_schema) => check_schema(field, field_schema, &field_path, violations),
+                None => match schema.get("additionalProperties") {
+                    Some(Value::Bool(false)) => violations.push(SchemaViolation {
+                        path: field_path,
+                        message: "unexpected property".to_string(),
+                    }),
+                    Some(extra @ Value::Object(_)) => {
+                        check_schema(field, extra, &field_path, violations)
+                    }
+                    _ => {}
+                },
+            }
+        }
+    }
+}
+
+pub fn validate_json(value: &Value, schema: &Value) -> Result<(), OutputError> {
+    let mut violations = Vec::new();
+    check_schema(value, schema, "$", &mut violations);
+    if violations.is_empty() {
+        Ok(())
+    } else {
+        Err(OutputError::SchemaViolation(violations))
+    }
+}
+
+impl OutputSpec {
+    pub fn json(schema: Value) -> OutputSpec {
+        OutputSpec {
+            format: OutputFormat::Json,
+            schema: Some(schema),
+            ..OutputSpec::default()
+        }
+    }
+
+    pub fn parse(&self, response: &str) -> Result<Value, OutputError> {
+        match self.format {
+            OutputFormat::Json => {
+                let value: Value = serde_json::from_str(extract_json(response))
+                    .map_err(|e| OutputError::InvalidJson(e.to_string()))?;
+                if let Some(schema) = &self.schema {
+                    validate_json(&value, schema)?;
+                }
+                Ok(value)
+            }
+            OutputFormat::Text => {
+                let text = response.trim();
+                if let Some(pattern) = &self.pattern {
+                    if !compile(pattern)?.is_match(text) {
+                        return Err(OutputError::PatternMismatch {
+                            pattern: pattern.clone(),
+                        });
+                    }
+                }
+                if let Some(allowed) = &self.allowed {
+                    if !allowed.iter().any(|a| a == text) {
+                        return Err(OutputError::NotAllowed {
+                            value: text.to_string(),
+                            allowed: allowed.clone(),
+                        });
+                    }
+                }
+                Ok(Value::String(text.to_string()))
+            }
+        }
+    }
+
+    pub fn parse_as<T: DeserializeOwned>(&self, response: &str) -> Result<T, OutputError> {
+        serde_json::from_value(self.parse(response)?)
+            .map_err(|e| OutputError::Deserialize(e.to_string()))
+    }
+
+    pub fn check(&self) -> Result<(), OutputError> {
+        if let Some(pattern) = &self.pattern {
+            compile(pattern)?;
+        }
+        Ok(())
+    }
+}
+
+fn parse_with(spec: &Option<OutputSpec>, response: &str) -> Result<Value, OutputError> {
+    match spec {
+        Some(spec) => spec.parse(response),
+        None => OutputSpec::default().parse(response),
+    }
+}
+
+impl Completion {
+    pub fn parse_output(&self, response: &str) -> Result<Value, OutputError> {
+        parse_with(&self.output, response)
+    }
+}
+
+impl Chat {
+    pub fn parse_output(&self, response: &str) -> Result<Value, OutputError> {
+        parse_with(&self.output, response)
+    }
+}
+
+impl Prompt {
+    pub fn output(&self) -> Option<&OutputSpec> {
+        match self {
+            Prompt::Completion(completion) => completion.output.as_ref(),
+            Prompt::Chat(chat) => chat.output.as_ref(),
+            Prompt::Embedding(_) | Prompt::Custom(_) | Prompt::Unknown => None,
+        }
+    }
+
+    pub fn parse_output(&self, response: &str) -> Result<Value, OutputError> {
+        parse_with(&self.output().cloned(), response)
+    }
+
+    pub fn parse_output_as<T: DeserializeOwned>(&self, response: &str) -> Result<T, OutputError> {
+        serde_json::from_value(self.parse_output(response)?)
+            .map_err(|e| OutputError::Deserialize(e.to_string()))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::prompt::deserialize_prompt;
+    use serde_json::json;
+
+    const SENTIMENT: &str = r#"
+        type: completion
+        vendor: openai
+        model: gpt-4o-mini
+        prompt: Classify the review
+        output:
+            format: json
+            schema:
+                type: object
+                required: [label, score]
+                additionalProperties: false
+                properties:
+                    label:
+                        enum: [positive, negative]
+                    score:
+                        type: number
+                        minimum: 0
+                        maximum: 1
+    "#;
+
+    #[derive(Debug, Deserialize, PartialEq)]
+    struct Sentiment {
+        label: String,
+        score: f64,
+    }
+
+    #[test]
+    fn test_json_output() {
+        let prompt = deserialize_prompt(SENTIMENT);
+        assert_eq!(prompt.output().unwrap().format, OutputFormat::Json);
+        assert_eq!(
+            prompt.parse_output_as::<Sentiment>(
+                "Sure!\n```json\n{\"label\": \"positive\", \"score\": 0.9}\n```"
+            ),
+            Ok(Sentiment {
+                label: "positive".to_string(),
+                score: 0.9,
+            })
+        );
+        assert_eq!(
+            prompt.parse_output(r#"{"label": "meh", "score": 2, "note": 1}"#),
+            Err(OutputError::SchemaViolation(vec![
+                SchemaViolation {
+                    path: "$.label".to_string(),
+                    message: "\"meh\" is not an allowed value".to_string(),
+                },
+                SchemaViolation {
+                    path: "$.note".to_string(),
+                    message: "unexpected property".to_string(),
+                },
+                SchemaViolation {
+                    path: "$.score".to_string(),
+                    message: "2 is greater than 1".to_string(),
+                },
+            ]))
+        );
+        assert!(matches!(
+            prompt.parse_output("not json"),
+            Err(OutputError::InvalidJson(_))
+        ));
+        assert_eq!(deserialize_prompt(&prompt.to_yaml().unwrap()), prompt);
+    }
+
+    #[test]
+    fn test_text_output() {
+        let spec = OutputSpec {
+            pattern: Some("^(?:yes|no)$".to_string()),
+            allowed: Some(vec!["yes".to_string()]),
+            ..OutputSpec::default()
+        };
+        assert_eq!(spec.parse(" yes\n"), Ok(json!("yes")));
+        assert!(matches!(
+            spec.parse("maybe"),
+            Err(OutputError::PatternMismatch { .. })
+        ));
+        assert!(matches!(
+            spec.parse("no"),
+            Err(OutputError::NotAllowed { .. })
+        ));
+        assert_eq!(
+            deserialize_prompt("type: chat\nvendor: openai\nmodel: gpt-4o\n").parse_output("hi"),
+            Ok(json!("hi"))
+        );
+
+        let invalid = "type: chat\nvendor: openai\nmodel: gpt-4o\nmessages: [{input: hi}]\noutput:\n  pattern: '(yes'\n";
+        let issues = deserialize_prompt(invalid).validate();
+        assert_eq!(issues.len(), 1);
+        assert_eq!(issues[0].code, "invalid-output");
+    }
+}
@@ -0,0 +1,160 @@
+use crate::prompt::{NameStyle, Parameter, Prompt};
+use serde_yaml::Value;
+use std::collections::BTreeMap;
+
+pub const ENV_PREFIX: &str = "PROMPTLIB_PARAM_";
+pub const ENV_NAME: &str = "PROMPTLIB_ENV";
+
+type EnvParameters = Option<BTreeMap<String, Vec<Parameter>>>;
+
+fn apply(parameters: &mut Option<Vec<Parameter>>, name: &str, value: Value) {
+    let canonical = NameStyle::SnakeCase.convert(name);
+    let list = parameters.get_or_insert_with(Vec::new);
+    match list
+        .iter_mut()
+        .find(|p| NameStyle::SnakeCase.convert(&p.name) == canonical)
+    {
+        Some(existing) => existing.value = value,
+        None => list.push(Parameter {
+            name: name.to_string(),
+            value,
+        }),
+    }
+}
+
+fn env_value(raw: &str) -> Value {
+    serde_yaml::from_str(raw).unwrap_or_else(|_| Value::String(raw.to_string()))
+}
+
+impl Prompt {
+    fn parameter_layers(&mut self) -> Option<(&mut Option<Vec<Parameter>>, &EnvParameters)> {
+        match self {
+            Prompt::Completion(c) => Some((&mut c.parameters, &c.parameters_by_env)),
+            Prompt::Chat(c) => Some((&mut c.parameters, &c.parameters_by_env)),
+            Prompt::Embedding(_) | Prompt::Custom(_) | Prompt::Unknown => None,
+        }
+    }
+
+    pub fn with_overrides(&self, overrides: &[(&str, Value)]) -> Prompt {
+        let mut prompt = self.clone();
+        if let Some((parameters, _)) = prompt.parameter_layers() {
+            for (name, value) in overrides {
+                apply(parameters, name, value.clone());
+            }
+        }
+        prompt
+    }
+
+    pub fn for_env(&self, env: &str) -> Prompt {
+        let mut prompt = self.clone();
+        if let Some((parameters, by_env)) = prompt.parameter_layers() {
+            let section = by_env.as_ref().and_then(|b| b.get(env)).cloned();
+            for parameter in section.into_iter().flatten() {
+                apply(parameters, &parameter.name, parameter.value);
+            }
+        }
+        prompt
+    }
+
+    pub fn with_env_overrides_from(
+        &self,
+        vars: impl IntoIterator<Item = (String, String)>,
+    ) -> Prompt {
+        let overrides: Vec<(String, Value)> = vars
+            .into_iter()
+            .filter_map(|(key, raw)| {
+                let name = key.strip_prefix(ENV_PREFIX)?.to_lowercase();
+                Some((name, env_value(&raw)))
+            })
+            .collect();
+        let overrides: Vec<(&str, Value)> = overrides
+            .iter()
+            .map(|(name, value)| (name.as_str(), value.clone()))
+            .collect();
+        self.with_overrides(&overrides)
+    }
+
+    pub fn with_env_overrides(&self) -> Prompt {
+        self.with_env_overrides_from(std::env::vars())
+    }
+
+    // Precedence, lowest first: `parameters`, the `parameters_by_env` section for
+    // `env` (or $PROMPTLIB_ENV), PROMPTLIB_PARAM_* variables, then `overrides`.
+    pub fn resolve_parameters(&self, env: Option<&str>, overrides: &[(&str, Value)]) -> Prompt {
+        let env = env
+            .map(|e| e.to_string())
+            .or_else(|| std::env::var(ENV_NAME).ok());
+        let prompt = match env {
+            Some(env) => self.for_env(&env),
+            None => self.clone(),
+        };
+        prompt.with_env_overrides().with_overrides(overrides)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::prompt::deserialize_prompt;
+
+    const YAML: &str = r#"
+        type: completion
+        vendor: google
+        model: text-bison
+        prompt: hi
+        parameters:
+            - name: temperature
+              value: 0.7
+            - name: maxOutputTokens
+              value: 256
+        parameters_by_env:
+            test:
+                - name: temperature
+                  value: 0
+            prod:
+                - name: topK
+                  value: 40
+    "#;
+
+    fn parameter(prompt: &Prompt, name: &str) -> Option<Value> {
+        match prompt {
+            Prompt::Completion(c) => crate::prompt::find_parameter(&c.parameters, name),
+            _ => None,
+        }
+    }
+
+    #[test]
+    fn test_layers() {
+        let prompt = deserialize_prompt(YAML);
+        let test = prompt.for_env("test");
+        assert_eq!(parameter(&test, "temperature"), Some(Value::from(0)));
+        let prod = prompt.for_env("prod");
+        assert_eq!(parameter(&prod, "temperature"), Some(Value::from(0.7)));
+        assert_eq!(parameter(&prod, "topK"), Some(Value::from(40)));
+        assert_eq!(prompt.for_env("staging"), prompt);
+
+        let overridden = prompt.with_overrides(&[("max_output_tokens", Value::from(64))]);
+        assert_eq!(
+            parameter(&overridden, "maxOutputTokens"),
+            Some(Value::from(64))
+        );
+
+        let from_env = test.with_env_overrides_from(vec![
+            ("PROMPTLIB_PARAM_TEMPERATURE".to_string(), "0.3".to_string()),
+            ("PROMPTLIB_PARAM_STOP".to_string(), "[END]".to_string()),
+            ("HOME".to_string(), "/root".to_string()),
+        ]);
+        assert_eq!(parameter(&from_env, "temperature"), Some(Value::from(0.3)));
+        assert_eq!(
+            parameter(&from_env, "stop"),
+            Some(serde_yaml::from_str("[END]").unwrap())
+        );
+        assert_eq!(
+            parameter(
+                &prompt.resolve_parameters(Some("test"), &[("temperature", Value::from(1))]),
+                "temperature"
+            ),
+            Some(Value::from(1))
+        );
+    }
+}
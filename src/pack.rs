@@ -0,0 +1,207 @@
+use crate::prompt::Prompt;
+use crate::tokens::{HeuristicTokenizer, Tokenizer};
+use std::collections::{HashMap, HashSet};
+
+pub const CONTEXT_VARIABLE: &str = "context";
+
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct PackedContext {
+    pub text: String,
+    pub tokens: usize,
+    pub included: Vec<usize>,
+    pub truncated: Vec<usize>,
+    pub duplicates: Vec<usize>,
+    pub dropped: Vec<usize>,
+}
+
+#[derive(Debug, Clone)]
+pub struct ContextPacker<T = HeuristicTokenizer> {
+    max_tokens: usize,
+    tokenizer: T,
+    separator: String,
+    variable: String,
+}
+
+fn normalize(text: &str) -> String {
+    text.split_whitespace()
+        .collect::<Vec<_>>()
+        .join(" ")
+        .to_lowercase()
+}
+
+fn sentence_ends(text: &str) -> Vec<usize> {
+    let mut ends = Vec::new();
+    let mut chars = text.char_indices().peekable();
+    while let Some((i, c)) = chars.next() {
+        let end = i + c.len_utf8();
+        let boundary = match c {
+            '\n' => true,
+            '.' | '!' | '?' => chars.peek().is_none_or(|(_, next)| next.is_whitespace()),
+            _ => false,
+        };
+        if boundary && !text[..end].trim().is_empty() {
+            ends.push(end);
+        }
+    }
+    ends
+}
+
+impl ContextPacker {
+    pub fn new(max_tokens: usize) -> ContextPacker {
+        ContextPacker {
+            max_tokens,
+            tokenizer: HeuristicTokenizer::default(),
+            separator: "\n\n".to_string(),
+            variable: CONTEXT_VARIABLE.to_string(),
+        }
+    }
+
+    pub fn for_prompt(prompt: &Prompt) -> Option<ContextPacker> {
+        prompt
+            .meta()
+            .and_then(|meta| meta.max_context_tokens)
+            .map(ContextPacker::new)
+    }
+}
+
+impl<T: Tokenizer> ContextPacker<T> {
+    pub fn tokenizer<U: Tokenizer>(self, tokenizer: U) -> ContextPacker<U> {
+        ContextPacker {
+            max_tokens: self.max_tokens,
+            tokenizer,
+            separator: self.separator,
+            variable: self.variable,
+        }
+    }
+
+    pub fn separator(mut self, separator: impl Into<String>) -> Self {
+        self.separator = separator.into();
+        self
+    }
+
+    pub fn variable(mut self, variable: impl Into<String>) -> Self {
+        self.variable = variable.into();
+        self
+    }
+
+    pub fn max_tokens(&self) -> usize {
+        self.max_tokens
+    }
+
+    fn truncate<'c>(&self, chunk: &'c str, budget: usize) -> Option<&'c str> {
+        sentence_ends(chunk)
+            .into_iter()
+            .map(|end| chunk[..end].trim_end())
+            .take_while(|prefix| self.tokenizer.count_tokens(prefix) <= budget)
+            .last()
+    }
+
+    pub fn pack<S: AsRef<str>>(&self, chunks: &[S]) -> PackedContext {
+        let mut packed = PackedContext::default();
+        let mut seen = HashSet::new();
+        let separator = self.tokenizer.count_tokens(&self.separator);
+        let mut parts: Vec<&str> = Vec::new();
+        for (i, chunk) in chunks.iter().enumerate() {
+            let chunk = chunk.as_ref().trim();
+            if chunk.is_empty() {
+                continue;
+            }
+            let key = normalize(chunk);
+            if seen.contains(&key) {
+                packed.duplicates.push(i);
+                continue;
+            }
+            let overhead = if parts.is_empty() { 0 } else { separator };
+            let budget = self.max_tokens.saturating_sub(packed.tokens + overhead);
+            let tokens = self.tokenizer.count_tokens(chunk);
+            let part = if tokens <= budget {
+                chunk
+            } else if let Some(prefix) = self.truncate(chunk, budget) {
+                packed.truncated.push(i);
+                seen.insert(normalize(prefix));
+                prefix
+            } else {
+                packed.dropped.push(i);
+                continue;
+            };
+            packed.tokens += overhead + self.tokenizer.count_tokens(part);
+            packed.included.push(i);
+            parts.push(part);
+            seen.insert(key);
+        }
+        packed.text = parts.join(&self.separator);
+        packed
+    }
+
+    pub fn fill<S: AsRef<str>>(
+        &self,
+        vars: &mut HashMap<String, String>,
+        chunks: &[S],
+    ) -> PackedContext {
+        let packed = self.pack(chunks);
+        vars.insert(self.variable.clone(), packed.text.clone());
+        packed
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::prompt::deserialize_prompt;
+    use crate::tokens::WhitespaceTokenizer;
+
+    #[test]
+    fn test_pack_chunks() {
+        let chunks = [
+            "Rust is a systems language.",
+            "  rust is a SYSTEMS   language. ",
+            "Cargo builds crates. It also runs tests. And it publishes them too.",
+            "The borrow checker enforces ownership rules at compile time.",
+            "Short one.",
+        ];
+        let packer = ContextPacker::new(14)
+            .tokenizer(WhitespaceTokenizer)
+            .separator("\n");
+        let packed = packer.pack(&chunks);
+        assert_eq!(
+            packed.text,
+            "Rust is a systems language.\nCargo builds crates. It also runs tests.\nShort one."
+        );
+        assert_eq!(packed.tokens, 14);
+        assert_eq!(packed.included, vec![0, 2, 4]);
+        assert_eq!(packed.truncated, vec![2]);
+        assert_eq!(packed.duplicates, vec![1]);
+        assert_eq!(packed.dropped, vec![3]);
+
+        let overlapping = [
+            "Cargo builds crates.",
+            "Cargo builds crates. It also runs tests.",
+            "cargo builds crates.",
+        ];
+        let packer = ContextPacker::new(100).tokenizer(WhitespaceTokenizer);
+        assert_eq!(packer.pack(&overlapping).duplicates, vec![2]);
+        let reversed: Vec<&str> = overlapping.iter().rev().copied().collect();
+        assert_eq!(packer.pack(&reversed).duplicates, vec![2]);
+        let packer = ContextPacker::new(3).tokenizer(WhitespaceTokenizer);
+        let packed = packer.pack(&[overlapping[1], overlapping[0]]);
+        assert_eq!(packed.truncated, vec![0]);
+        assert_eq!(packed.duplicates, vec![1]);
+
+        let prompt = deserialize_prompt(
+            "type: completion\nvendor: openai\nmodel: gpt-4o\nmax_context_tokens: 6\nprompt: \"Use {{context}} to answer.\"\n",
+        );
+        let packer = ContextPacker::for_prompt(&prompt)
+            .unwrap()
+            .tokenizer(WhitespaceTokenizer);
+        assert_eq!(packer.max_tokens(), 6);
+        let mut vars = HashMap::new();
+        packer.fill(&mut vars, &chunks);
+        let Prompt::Completion(completion) = prompt else {
+            panic!("Expected Prompt::Completion");
+        };
+        assert_eq!(
+            completion.render(&vars),
+            Ok("Use Rust is a systems language. to answer.".to_string())
+        );
+    }
+}
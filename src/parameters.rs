@@ -0,0 +1,394 @@
+use crate::prompt::{Chat, Completion, NameStyle, Parameter, Prompt};
+use crate::request::Vendor;
+use serde::de::DeserializeOwned;
+use serde_yaml::Value;
+use std::collections::HashMap;
+use std::fmt;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ParameterKind {
+    Integer,
+    Float,
+    String,
+    Bool,
+    StringList,
+    Map,
+}
+
+impl ParameterKind {
+    fn matches(&self, value: &Value) -> bool {
+        match self {
+            ParameterKind::Integer => value.as_i64().is_some(),
+            ParameterKind::Float => value.as_f64().is_some(),
+            ParameterKind::String => value.is_string(),
+            ParameterKind::Bool => value.is_bool(),
+            ParameterKind::StringList => value
+                .as_sequence()
+                .map(|items| items.iter().all(|i| i.is_string()))
+                .unwrap_or(false),
+            ParameterKind::Map => value.is_mapping(),
+        }
+    }
+}
+
+impl fmt::Display for ParameterKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let name = match self {
+            ParameterKind::Integer => "integer",
+            ParameterKind::Float => "number",
+            ParameterKind::String => "string",
+            ParameterKind::Bool => "boolean",
+            ParameterKind::StringList => "list of strings",
+            ParameterKind::Map => "mapping",
+        };
+        write!(f, "{}", name)
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct KnownParameter {
+    pub name: &'static str,
+    pub kind: ParameterKind,
+    pub min: Option<f64>,
+    pub max: Option<f64>,
+}
+
+const fn known(
+    name: &'static str,
+    kind: ParameterKind,
+    min: Option<f64>,
+    max: Option<f64>,
+) -> KnownParameter {
+    KnownParameter {
+        name,
+        kind,
+        min,
+        max,
+    }
+}
+
+const OPENAI_PARAMETERS: &[KnownParameter] = &[
+    known("temperature", ParameterKind::Float, Some(0.0), Some(2.0)),
+    known("max_tokens", ParameterKind::Integer, Some(1.0), None),
+    known("max_output_tokens", ParameterKind::Integer, Some(1.0), None),
+    known("top_p", ParameterKind::Float, Some(0.0), Some(1.0)),
+    known("stop", ParameterKind::StringList, None, None),
+    known(
+        "presence_penalty",
+        ParameterKind::Float,
+        Some(-2.0),
+        Some(2.0),
+    ),
+    known(
+        "frequency_penalty",
+        ParameterKind::Float,
+        Some(-2.0),
+        Some(2.0),
+    ),
+    known("logit_bias", ParameterKind::Map, None, None),
+    known("seed", ParameterKind::Integer, None, None),
+    known("stream", ParameterKind::Bool, None, None),
+];
+
+const ANTHROPIC_PARAMETERS: &[KnownParameter] = &[
+    known("temperature", ParameterKind::Float, Some(0.0), Some(1.0)),
+    known("max_tokens", ParameterKind::Integer, Some(1.0), None),
+    known("max_output_tokens", ParameterKind::Integer, Some(1.0), None),
+    known("top_p", ParameterKind::Float, Some(0.0), Some(1.0)),
+    known("top_k", ParameterKind::Integer, Some(1.0), None),
+    known("stop_sequences", ParameterKind::StringList, None, None),
+    known("stream", ParameterKind::Bool, None, None),
+];
+
+const GOOGLE_PARAMETERS: &[KnownParameter] = &[
+    known("temperature", ParameterKind::Float, Some(0.0), Some(1.0)),
+    known("max_output_tokens", ParameterKind::Integer, Some(1.0), None),
+    known("top_p", ParameterKind::Float, Some(0.0), Some(1.0)),
+    known("top_k", ParameterKind::Integer, Some(1.0), None),
+    known("stop_sequences", ParameterKind::StringList, None, None),
+    known("stream", ParameterKind::Bool, None, None),
+];
+
+pub fn known_parameters(vendor: Vendor) -> &'static [KnownParameter] {
+    match vendor {
+        Vendor::OpenAi => OPENAI_PARAMETERS,
+        Vendor::Anthropic => ANTHROPIC_PARAMETERS,
+        Vendor::Google => GOOGLE_PARAMETERS,
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum ParameterError {
+    Missing(String),
+    WrongType {
+        name: String,
+        expected: ParameterKind,
+        found: Value,
+    },
+    OutOfRange {
+        name: String,
+        value: f64,
+        min: Option<f64>,
+        max: Option<f64>,
+    },
+    Invalid {
+        name: String,
+        message: String,
+    },
+}
+
+impl fmt::Display for ParameterError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ParameterError::Missing(name) => write!(f, "missing parameter '{}'", name),
+            ParameterError::WrongType {
+                name,
+                expected,
+                found,
+            } => write!(
+                f,
+                "parameter '{}' should be a {}, found {:?}",
+                name, expected, found
+            ),
+            ParameterError::OutOfRange {
+                name,
+                value,
+                min,
+                max,
+            } => {
+                write!(f, "parameter '{}' = {} is out of range", name, value)?;
+                match (min, max) {
+                    (Some(min), Some(max)) => write!(f, " [{}, {}]", min, max),
+                    (Some(min), None) => write!(f, " (min {})", min),
+                    (None, Some(max)) => write!(f, " (max {})", max),
+                    (None, None) => Ok(()),
+                }
+            }
+            ParameterError::Invalid { name, message } => {
+                write!(f, "parameter '{}' is invalid: {}", name, message)
+            }
+        }
+    }
+}
+
+impl std::error::Error for ParameterError {}
+
+#[derive(Debug, Clone, Copy)]
+pub struct Parameters<'a>(&'a [Parameter]);
+
+impl<'a> Parameters<'a> {
+    pub fn new(parameters: &'a [Parameter]) -> Parameters<'a> {
+        Parameters(parameters)
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = &'a Parameter> {
+        self.0.iter()
+    }
+
+    pub fn contains(&self, name: &str) -> bool {
+        self.0.iter().any(|p| p.name == name)
+    }
+
+    pub fn value(&self, name: &str) -> Result<&'a Value, ParameterError> {
+        self.0
+            .iter()
+            .find(|p| p.name == name)
+            .map(|p| &p.value)
+            .ok_or_else(|| ParameterError::Missing(name.to_string()))
+    }
+
+    fn typed<T>(
+        &self,
+        name: &str,
+        expected: ParameterKind,
+        convert: impl Fn(&Value) -> Option<T>,
+    ) -> Result<T, ParameterError> {
+        let value = self.value(name)?;
+        convert(value).ok_or_else(|| ParameterError::WrongType {
+            name: name.to_string(),
+            expected,
+            found: value.clone(),
+        })
+    }
+
+    pub fn get_i64(&self, name: &str) -> Result<i64, ParameterError> {
+        self.typed(name, ParameterKind::Integer, |v| v.as_i64())
+    }
+
+    pub fn get_f64(&self, name: &str) -> Result<f64, ParameterError> {
+        self.typed(name, ParameterKind::Float, |v| v.as_f64())
+    }
+
+    pub fn get_str(&self, name: &str) -> Result<&'a str, ParameterError> {
+        let value = self.value(name)?;
+        value.as_str().ok_or_else(|| ParameterError::WrongType {
+            name: name.to_string(),
+            expected: ParameterKind::String,
+            found: value.clone(),
+        })
+    }
+
+    pub fn get_bool(&self, name: &str) -> Result<bool, ParameterError> {
+        self.typed(name, ParameterKind::Bool, |v| v.as_bool())
+    }
+
+    pub fn get_vec_str(&self, name: &str) -> Result<Vec<String>, ParameterError> {
+        self.typed(name, ParameterKind::StringList, |v| {
+            v.as_sequence()?
+                .iter()
+                .map(|i| i.as_str().map(|s| s.to_string()))
+                .collect()
+        })
+    }
+
+    pub fn get_map(&self, name: &str) -> Result<HashMap<String, Value>, ParameterError> {
+        self.typed(name, ParameterKind::Map, |v| {
+            v.as_mapping()?
+                .iter()
+                .map(|(k, v)| k.as_str().map(|k| (k.to_string(), v.clone())))
+                .collect()
+        })
+    }
+
+    pub fn get<T: DeserializeOwned>(&self, name: &str) -> Result<T, ParameterError> {
+        let value = self.value(name)?;
+        serde_yaml::from_value(value.clone()).map_err(|e| ParameterError::Invalid {
+            name: name.to_string(),
+            message: e.to_string(),
+        })
+    }
+
+    pub fn validate(&self, schema: &[KnownParameter]) -> Vec<ParameterError> {
+        let mut errors = Vec::new();
+        for parameter in self.0 {
+            let canonical = NameStyle::SnakeCase.convert(&parameter.name);
+            let Some(known) = schema.iter().find(|k| k.name == canonical) else {
+                continue;
+            };
+            if !known.kind.matches(&parameter.value) {
+                errors.push(ParameterError::WrongType {
+                    name: parameter.name.clone(),
+                    expected: known.kind,
+                    found: parameter.value.clone(),
+                });
+                continue;
+            }
+            if let Some(value) = parameter.value.as_f64() {
+                let below = known.min.map(|min| value < min).unwrap_or(false);
+                let above = known.max.map(|max| value > max).unwrap_or(false);
+                if below || above {
+                    errors.push(ParameterError::OutOfRange {
+                        name: parameter.name.clone(),
+                        value,
+                        min: known.min,
+                        max: known.max,
+                    });
+                }
+            }
+        }
+        errors
+    }
+}
+
+fn validate_for_vendor(parameters: Parameters, vendor: Option<Vendor>) -> Vec<ParameterError> {
+    match vendor {
+        Some(vendor) => parameters.validate(known_parameters(vendor)),
+        None => Vec::new(),
+    }
+}
+
+impl Completion {
+    pub fn params(&self) -> Parameters<'_> {
+        Parameters::new(self.parameters.as_deref().unwrap_or(&[]))
+    }
+
+    pub fn validate_parameters(&self) -> Vec<ParameterError> {
+        validate_for_vendor(self.params(), self.meta.endpoint.api_vendor(&self.vendor))
+    }
+}
+
+impl Chat {
+    pub fn params(&self) -> Parameters<'_> {
+        Parameters::new(self.parameters.as_deref().unwrap_or(&[]))
+    }
+
+    pub fn validate_parameters(&self) -> Vec<ParameterError> {
+        validate_for_vendor(self.params(), self.meta.endpoint.api_vendor(&self.vendor))
+    }
+}
+
+impl Prompt {
+    pub fn validate_parameters(&self) -> Vec<ParameterError> {
+        match self {
+            Prompt::Completion(completion) => completion.validate_parameters(),
+            Prompt::Chat(chat) => chat.validate_parameters(),
+            Prompt::Embedding(_) | Prompt::Custom(_) | Prompt::Unknown => Vec::new(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::prompt::deserialize_prompt;
+
+    const YAML: &str = r#"
+        type: completion
+        vendor: google
+        model: text-bison
+        prompt: hi
+        parameters:
+            - name: maxOutputTokens
+              value: 256
+            - name: temperature
+              value: hot
+            - name: topP
+              value: 1.5
+            - name: stopSequences
+              value: ["."]
+    "#;
+
+    #[test]
+    fn test_typed_getters() {
+        if let Prompt::Completion(completion) = deserialize_prompt(YAML) {
+            let params = completion.params();
+            assert_eq!(params.get_i64("maxOutputTokens"), Ok(256));
+            assert_eq!(params.get_f64("maxOutputTokens"), Ok(256.0));
+            assert_eq!(params.get_str("temperature"), Ok("hot"));
+            assert_eq!(
+                params.get_vec_str("stopSequences"),
+                Ok(vec![".".to_string()])
+            );
+            assert_eq!(params.get::<Vec<String>>("stopSequences").unwrap().len(), 1);
+            assert_eq!(
+                params.get_f64("temperature"),
+                Err(ParameterError::WrongType {
+                    name: "temperature".to_string(),
+                    expected: ParameterKind::Float,
+                    found: Value::from("hot"),
+                })
+            );
+            assert_eq!(
+                params.get_bool("stream"),
+                Err(ParameterError::Missing("stream".to_string()))
+            );
+            assert_eq!(completion.find_parameter_as_f32("temperature"), None);
+        } else {
+            panic!("Expected Prompt::Completion");
+        }
+    }
+
+    #[test]
+    fn test_validate_against_vendor_schema() {
+        let errors = deserialize_prompt(YAML).validate_parameters();
+        assert_eq!(errors.len(), 2);
+        assert!(matches!(
+            &errors[0],
+            ParameterError::WrongType { name, .. } if name == "temperature"
+        ));
+        assert!(matches!(
+            &errors[1],
+            ParameterError::OutOfRange { name, .. } if name == "topP"
+        ));
+    }
+}
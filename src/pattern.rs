@@ -0,0 +1,617 @@
+use std::fmt;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PatternError {
+    pub position: usize,
+    pub message: String,
+}
+
+impl fmt::Display for PatternError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{} at position {}", self.message, self.position)
+    }
+}
+
+impl std::error::Error for PatternError {}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum ClassItem {
+    Range(char, char),
+    Digit(bool),
+    Word(bool),
+    Space(bool),
+}
+
+impl ClassItem {
+    fn matches(&self, c: char) -> bool {
+        match *self {
+            ClassItem::Range(low, high) => low <= c && c <= high,
+            ClassItem::Digit(negated) => c.is_ascii_digit() != negated,
+            ClassItem::Word(negated) => is_word(c) != negated,
+            ClassItem::Space(negated) => c.is_whitespace() != negated,
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum Node {
+    Char(char),
+    Any,
+    Class {
+        items: Vec<ClassItem>,
+        negated: bool,
+    },
+    Start,
+    End,
+    WordBoundary(bool),
+    Group(Vec<Vec<Node>>),
+    Repeat {
+        node: Box<Node>,
+        min: usize,
+        max: Option<usize>,
+        greedy: bool,
+    },
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum Inst {
+    Consume(Node),
+    Start,
+    End,
+    WordBoundary(bool),
+    Split(usize, usize),
+    Jmp(usize),
+    Match,
+}
+
+const REPEAT_LIMIT: usize = 1000;
+const PROGRAM_LIMIT: usize = 100_000;
+
+fn compile_alternatives(alternatives: &[Vec<Node>], program: &mut Vec<Inst>) {
+    let mut jumps = Vec::new();
+    for (i, nodes) in alternatives.iter().enumerate() {
+        if i + 1 == alternatives.len() {
+            compile_nodes(nodes, program);
+            break;
+        }
+        let split = program.len();
+        program.push(Inst::Split(split + 1, 0));
+        compile_nodes(nodes, program);
+        jumps.push(program.len());
+        program.push(Inst::Jmp(0));
+        program[split] = Inst::Split(split + 1, program.len());
+    }
+    let end = program.len();
+    for jump in jumps {
+        program[jump] = Inst::Jmp(end);
+    }
+}
+
+fn compile_nodes(nodes: &[Node], program: &mut Vec<Inst>) {
+    for node in nodes {
+        if program.len() >= PROGRAM_LIMIT {
+            return;
+        }
+        compile_node(node, program);
+    }
+}
+
+fn branch(greedy: bool, body: usize, out: usize) -> Inst {
+    if greedy {
+        Inst::Split(body, out)
+    } else {
+        Inst::Split(out, body)
+    }
+}
+
+fn compile_node(node: &Node, program: &mut Vec<Inst>) {
+    match node {
+        Node::Start => program.push(Inst::Start),
+        Node::End => program.push(Inst::End),
+        Node::WordBoundary(negated) => program.push(Inst::WordBoundary(*negated)),
+        Node::Group(alternatives) => compile_alternatives(alternatives, program),
+        Node::Repeat {
+            node,
+            min,
+            max,
+            greedy,
+        } => {
+            compile_nodes(&vec![(**node).clone(); *min], program);
+            match max {
+                None => {
+                    let split = program.len();
+                    program.push(Inst::Jmp(0));
+                    compile_node(node, program);
+                    program.push(Inst::Jmp(split));
+                    program[split] = branch(*greedy, split + 1, program.len());
+                }
+                Some(max) => {
+                    let mut splits = Vec::new();
+                    for _ in *min..*max {
+                        if program.len() >= PROGRAM_LIMIT {
+                            return;
+                        }
+                        splits.push(program.len());
+                        program.push(Inst::Jmp(0));
+                        compile_node(node, program);
+                    }
+                    let out = program.len();
+                    for split in splits {
+                        program[split] = branch(*greedy, split + 1, out);
+                    }
+                }
+            }
+        }
+        single => program.push(Inst::Consume(single.clone())),
+    }
+}
+
+fn is_word(c: char) -> bool {
+    c.is_alphanumeric() || c == '_'
+}
+
+struct Vm<'a> {
+    program: &'a [Inst],
+    seen: Vec<usize>,
+    stack: Vec<usize>,
+    input: &'a [char],
+}
+
+impl Vm<'_> {
+    fn at_boundary(&self, pos: usize) -> bool {
+        let before = pos > 0 && is_word(self.input[pos - 1]);
+        let after = self.input.get(pos).is_some_and(|&c| is_word(c));
+        before != after
+    }
+
+    fn add(&mut self, threads: &mut Vec<(usize, usize)>, pos: usize, pc: usize, start: usize) {
+        self.stack.push(pc);
+        while let Some(pc) = self.stack.pop() {
+            if self.seen[pc] == pos {
+                continue;
+            }
+            self.seen[pc] = pos;
+            match self.program[pc] {
+                Inst::Jmp(to) => self.stack.push(to),
+                Inst::Split(first, second) => {
+                    self.stack.push(second);
+                    self.stack.push(first);
+                }
+                Inst::Start if pos == 0 => self.stack.push(pc + 1),
+                Inst::End if pos == self.input.len() => self.stack.push(pc + 1),
+                Inst::WordBoundary(negated) if self.at_boundary(pos) != negated => {
+                    self.stack.push(pc + 1)
+                }
+                Inst::Start | Inst::End | Inst::WordBoundary(_) => {}
+                _ => threads.push((pc, start)),
+            }
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Pattern {
+    source: String,
+    program: Vec<Inst>,
+    case_insensitive: bool,
+}
+
+struct Parser<'a> {
+    chars: &'a [char],
+    pos: usize,
+}
+
+impl Parser<'_> {
+    fn error(&self, message: &str) -> PatternError {
+        PatternError {
+            position: self.pos,
+            message: message.to_string(),
+        }
+    }
+
+    fn peek(&self) -> Option<char> {
+        self.chars.get(self.pos).copied()
+    }
+
+    fn eat(&mut self, c: char) -> bool {
+        if self.peek() == Some(c) {
+            self.pos += 1;
+            true
+        } else {
+            false
+        }
+    }
+
+    fn alternatives(&mut self) -> Result<Vec<Vec<Node>>, PatternError> {
+        let mut alternatives = vec![self.sequence()?];
+        while self.eat('|') {
+            alternatives.push(self.sequence()?);
+        }
+        Ok(alternatives)
+    }
+
+    fn sequence(&mut self) -> Result<Vec<Node>, PatternError> {
+        let mut nodes = Vec::new();
+        while let Some(c) = self.peek() {
+            if c == '|' || c == ')' {
+                break;
+            }
+            let atom = self.atom()?;
+            nodes.push(self.quantified(atom)?);
+        }
+        Ok(nodes)
+    }
+
+    fn atom(&mut self) -> Result<Node, PatternError> {
+        let c = self.peek().ok_or_else(|| self.error("unexpected end"))?;
+        self.pos += 1;
+        Ok(match c {
+            '(' => {
+                if self.eat('?') && !self.eat(':') {
+                    return Err(self.error("unsupported group syntax"));
+                }
+                let alternatives = self.alternatives()?;
+                if !self.eat(')') {
+                    return Err(self.error("unclosed group"));
+                }
+                Node::Group(alternatives)
+            }
+            '[' => self.class()?,
+            '.' => Node::Any,
+            '^' => Node::Start,
+            '$' => Node::End,
+            '\\' if self.eat('b') => Node::WordBoundary(false),
+            '\\' if self.eat('B') => Node::WordBoundary(true),
+            '\\' => match self.escape()? {
+                Ok(item) => Node::Class {
+                    items: vec![item],
+                    negated: false,
+                },
+                Err(c) => Node::Char(c),
+            },
+            '*' | '+' | '?' | '{' => return Err(self.error("nothing to repeat")),
+            c => Node::Char(c),
+        })
+    }
+
+    fn escape(&mut self) -> Result<Result<ClassItem, char>, PatternError> {
+        let c = self
+            .peek()
+            .ok_or_else(|| self.error("trailing backslash"))?;
+        self.pos += 1;
+        Ok(match c {
+            'd' => Ok(ClassItem::Digit(false)),
+            'D' => Ok(ClassItem::Digit(true)),
+            'w' => Ok(ClassItem::Word(false)),
+            'W' => Ok(ClassItem::Word(true)),
+            's' => Ok(ClassItem::Space(false)),
+            'S' => Ok(ClassItem::Space(true)),
+            'n' => Err('\n'),
+            't' => Err('\t'),
+            'r' => Err('\r'),
+            c if c.is_alphanumeric() => {
+                self.pos -= 2;
+                return Err(self.error(&format!("unsupported escape '\\{}'", c)));
+            }
+            c => Err(c),
+        })
+    }
+
+    fn class(&mut self) -> Result<Node, PatternError> {
+        let negated = self.eat('^');
+        let mut items = Vec::new();
+        let mut first = true;
+        loop {
+            let c = self.peek().ok_or_else(|| self.error("unclosed class"))?;
+            self.pos += 1;
+            if c == ']' && !first {
+                break;
+            }
+            first = false;
+            let low = match c {
+                '\\' => match self.escape()? {
+                    Ok(item) => {
+                        items.push(item);
+                        continue;
+                    }
+                    Err(c) => c,
+                },
+                c => c,
+            };
+            if self.peek() == Some('-') && self.chars.get(self.pos + 1).is_some_and(|&c| c != ']') {
+                self.pos += 1;
+                let mut high = self.chars[self.pos];
+                self.pos += 1;
+                if high == '\\' {
+                    high = match self.escape()? {
+                        Err(c) => c,
+                        Ok(_) => return Err(self.error("invalid class range")),
+                    };
+                }
+                if high < low {
+                    return Err(self.error("invalid class range"));
+                }
+                items.push(ClassItem::Range(low, high));
+            } else {
+                items.push(ClassItem::Range(low, low));
+            }
+        }
+        Ok(Node::Class { items, negated })
+    }
+
+    fn number(&mut self) -> Option<usize> {
+        let start = self.pos;
+        while self.peek().is_some_and(|c| c.is_ascii_digit()) {
+            self.pos += 1;
+        }
+        self.chars[start..self.pos]
+            .iter()
+            .collect::<String>()
+            .parse()
+            .ok()
+    }
+
+    fn quantified(&mut self, node: Node) -> Result<Node, PatternError> {
+        let (min, max) = match self.peek() {
+            Some('*') => (0, None),
+            Some('+') => (1, None),
+            Some('?') => (0, Some(1)),
+            Some('{') => {
+                self.pos += 1;
+                let min = self
+                    .number()
+                    .ok_or_else(|| self.error("invalid repetition"))?;
+                let max = if self.eat(',') {
+                    self.number()
+                } else {
+                    Some(min)
+                };
+                if self.peek() != Some('}') || max.is_some_and(|max| max < min) {
+                    return Err(self.error("invalid repetition"));
+                }
+                if max.unwrap_or(min) > REPEAT_LIMIT {
+                    return Err(self.error("repetition count too large"));
+                }
+                (min, max)
+            }
+            _ => return Ok(node),
+        };
+        if matches!(node, Node::Start | Node::End | Node::WordBoundary(_)) {
+            return Err(self.error("nothing to repeat"));
+        }
+        self.pos += 1;
+        let greedy = !self.eat('?');
+        Ok(Node::Repeat {
+            node: Box::new(node),
+            min,
+            max,
+            greedy,
+        })
+    }
+}
+
+impl Pattern {
+    pub fn new(source: &str) -> Result<Pattern, PatternError> {
+        let (body, case_insensitive) = match source.strip_prefix("(?i)") {
+            Some(body) => (body, true),
+            None => (source, false),
+        };
+        let chars: Vec<char> = body.chars().collect();
+        let mut parser = Parser {
+            chars: &chars,
+            pos: 0,
+        };
+        let alternatives = parser.alternatives()?;
+        if parser.pos < chars.len() {
+            return Err(parser.error("unmatched ')'"));
+        }
+        let mut program = Vec::new();
+        compile_alternatives(&alternatives, &mut program);
+        if program.len() >= PROGRAM_LIMIT {
+            return Err(parser.error("pattern too large"));
+        }
+        program.push(Inst::Match);
+        Ok(Pattern {
+            source: source.to_string(),
+            program,
+            case_insensitive,
+        })
+    }
+
+    pub fn is_match(&self, text: &str) -> bool {
+        self.find(text).is_some()
+    }
+
+    pub fn find(&self, text: &str) -> Option<(usize, usize)> {
+        let (chars, offsets) = self.decode(text);
+        self.find_from(&chars, 0)
+            .map(|(start, end)| (offsets[start], offsets[end]))
+    }
+
+    pub fn find_iter(&self, text: &str) -> Vec<(usize, usize)> {
+        let (chars, offsets) = self.decode(text);
+        let mut matches = Vec::new();
+        let mut from = 0;
+        while let Some((start, end)) = self.find_from(&chars, from) {
+            matches.push((offsets[start], offsets[end]));
+            from = if end > start {
+                end
+            } else if end < chars.len() {
+                end + 1
+            } else {
+                break;
+            };
+        }
+        matches
+    }
+
+    pub fn replace_all(&self, text: &str, replacement: &str) -> String {
+        let mut output = String::new();
+        let mut last = 0;
+        for (start, end) in self.find_iter(text) {
+            output.push_str(&text[last..start]);
+            output.push_str(replacement);
+            last = end;
+        }
+        output.push_str(&text[last..]);
+        output
+    }
+
+    fn find_from(&self, input: &[char], from: usize) -> Option<(usize, usize)> {
+        let mut vm = Vm {
+            program: &self.program,
+            seen: vec![usize::MAX; self.program.len()],
+            stack: Vec::new(),
+            input,
+        };
+        let mut current = Vec::new();
+        let mut next = Vec::new();
+        let mut matched = None;
+        for pos in from..=input.len() {
+            if matched.is_none() {
+                vm.add(&mut current, pos, 0, pos);
+            } else if current.is_empty() {
+                break;
+            }
+            for &(pc, start) in &current {
+                match &self.program[pc] {
+                    Inst::Match => {
+                        matched = Some((start, pos));
+                        break;
+                    }
+                    Inst::Consume(node)
+                        if pos < input.len() && self.single_matches(node, input[pos]) =>
+                    {
+                        vm.add(&mut next, pos + 1, pc + 1, start);
+                    }
+                    _ => {}
+                }
+            }
+            std::mem::swap(&mut current, &mut next);
+            next.clear();
+        }
+        matched
+    }
+
+    fn decode(&self, text: &str) -> (Vec<char>, Vec<usize>) {
+        let mut chars = Vec::new();
+        let mut offsets = Vec::new();
+        for (offset, c) in text.char_indices() {
+            offsets.push(offset);
+            chars.push(if self.case_insensitive {
+                c.to_lowercase().next().unwrap_or(c)
+            } else {
+                c
+            });
+        }
+        offsets.push(text.len());
+        (chars, offsets)
+    }
+
+    fn char_matches(&self, expected: char, actual: char) -> bool {
+        if self.case_insensitive {
+            expected.to_lowercase().next() == Some(actual)
+        } else {
+            expected == actual
+        }
+    }
+
+    fn single_matches(&self, node: &Node, c: char) -> bool {
+        match node {
+            Node::Char(expected) => self.char_matches(*expected, c),
+            Node::Any => c != '\n',
+            Node::Class { items, negated } => {
+                let hit = items.iter().any(|item| match item {
+                    ClassItem::Range(low, high) if self.case_insensitive => {
+                        item.matches(c) || c.to_uppercase().any(|u| *low <= u && u <= *high)
+                    }
+                    _ => item.matches(c),
+                });
+                hit != *negated
+            }
+            _ => false,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_matching() {
+        let date = Pattern::new(r"^\d{4}-\d{2}-\d{2}$").unwrap();
+        assert!(date.is_match("2024-01-05"));
+        assert!(!date.is_match("2024-1-05"));
+
+        let answer = Pattern::new("(?i)^(yes|no)[.!]?$").unwrap();
+        assert!(answer.is_match("YES!"));
+        assert!(!answer.is_match("maybe"));
+
+        let word = Pattern::new(r"[a-z]+@[a-z]+\.com").unwrap();
+        assert_eq!(word.find("mail bob@example.com now"), Some((5, 20)));
+        assert_eq!(Pattern::new("a.*?b").unwrap().find("axbxb"), Some((0, 3)));
+        assert_eq!(Pattern::new("a.*b").unwrap().find("axbxb"), Some((0, 5)));
+
+        let digits = Pattern::new(r"\d+").unwrap();
+        assert_eq!(
+            digits.find_iter("a1 b22 c333"),
+            vec![(1, 2), (4, 6), (8, 11)]
+        );
+        assert_eq!(digits.replace_all("call 555-1234", "#"), "call #-#");
+        assert_eq!(Pattern::new("x*").unwrap().replace_all("ab", "-"), "-a-b-");
+    }
+
+    #[test]
+    fn test_errors() {
+        assert!(Pattern::new("(ab").is_err());
+        assert!(Pattern::new("ab)").is_err());
+        assert!(Pattern::new("*a").is_err());
+        assert!(Pattern::new("[z-a]").is_err());
+        assert!(Pattern::new("a{3,1}").is_err());
+        assert!(Pattern::new("a{5000}").is_err());
+        assert!(Pattern::new("((a{1000}){1000}){1000}").is_err());
+        for source in [r"\A", r"\z", r"\x41", r"\u{41}", r"\p{L}", r"[\b]", r"\b+"] {
+            assert!(Pattern::new(source).is_err(), "{}", source);
+        }
+        assert_eq!(
+            Pattern::new(r"a\x41").unwrap_err().to_string(),
+            "unsupported escape '\\x' at position 1"
+        );
+        assert!(Pattern::new(r"\.\$\\").unwrap().is_match(".$\\"));
+    }
+
+    #[test]
+    fn test_word_boundaries() {
+        let cat = Pattern::new(r"\bcat\b").unwrap();
+        assert_eq!(cat.find("a cat, concat"), Some((2, 5)));
+        assert!(!cat.is_match("concatenate"));
+        assert!(cat.is_match("cat"));
+        assert_eq!(
+            Pattern::new(r"\Bcat").unwrap().find("cat concat"),
+            Some((7, 10))
+        );
+        assert_eq!(
+            Pattern::new(r"\b").unwrap().find_iter("ab cd"),
+            vec![(0, 0), (2, 2), (3, 3), (5, 5)]
+        );
+    }
+
+    #[test]
+    fn test_large_input() {
+        let digits = "7".repeat(1 << 20);
+        let pattern = Pattern::new(r"\d+").unwrap();
+        assert_eq!(pattern.find(&digits), Some((0, 1 << 20)));
+        assert_eq!(
+            Pattern::new("(7|8)*?$").unwrap().find(&digits),
+            Some((0, 1 << 20))
+        );
+
+        let words = "ab ".repeat(1 << 18);
+        assert_eq!(
+            Pattern::new("[a-z]+").unwrap().find_iter(&words).len(),
+            1 << 18
+        );
+        assert_eq!(pattern.replace_all(&words, "#"), words);
+    }
+}
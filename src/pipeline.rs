@@ -0,0 +1,438 @@
+use crate::pattern::Pattern;
+use crate::prompt::{Chat, Completion, Prompt};
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+use std::collections::HashMap;
+use std::fmt;
+use std::sync::Arc;
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(try_from = "Value", into = "Value")]
+pub enum Step {
+    Trim,
+    StripFences,
+    ExtractJson,
+    TrimSentences(usize),
+    Redact {
+        pattern: String,
+        replacement: String,
+    },
+    Custom(String),
+}
+
+const DEFAULT_REPLACEMENT: &str = "[REDACTED]";
+
+impl TryFrom<Value> for Step {
+    type Error = String;
+
+    fn try_from(value: Value) -> Result<Step, String> {
+        let (name, argument) = match &value {
+            Value::String(name) => (name.as_str(), None),
+            Value::Object(map) if map.len() == 1 => {
+                let (name, argument) = map.iter().next().unwrap();
+                (name.as_str(), Some(argument))
+            }
+            _ => return Err("post-processing step must be a name or a single-key map".to_string()),
+        };
+        match (name, argument) {
+            ("trim", None) => Ok(Step::Trim),
+            ("strip_fences", None) => Ok(Step::StripFences),
+            ("extract_json", None) => Ok(Step::ExtractJson),
+            ("trim_sentences", Some(count)) => count
+                .as_u64()
+                .map(|count| Step::TrimSentences(count as usize))
+                .ok_or_else(|| "trim_sentences expects a sentence count".to_string()),
+            ("redact", Some(Value::String(pattern))) => Ok(Step::Redact {
+                pattern: pattern.clone(),
+                replacement: DEFAULT_REPLACEMENT.to_string(),
+            }),
+            ("redact", Some(Value::Object(options))) => {
+                let field = |key: &str| options.get(key).and_then(|v| v.as_str());
+                Ok(Step::Redact {
+                    pattern: field("pattern")
+                        .ok_or_else(|| "redact expects a pattern".to_string())?
+                        .to_string(),
+                    replacement: field("replacement")
+                        .unwrap_or(DEFAULT_REPLACEMENT)
+                        .to_string(),
+                })
+            }
+            ("custom", Some(Value::String(name))) => Ok(Step::Custom(name.clone())),
+            (name, _) => Err(format!("invalid post-processing step '{}'", name)),
+        }
+    }
+}
+
+impl From<Step> for Value {
+    fn from(step: Step) -> Value {
+        match step {
+            Step::Trim => json!("trim"),
+            Step::StripFences => json!("strip_fences"),
+            Step::ExtractJson => json!("extract_json"),
+            Step::TrimSentences(count) => json!({ "trim_sentences": count }),
+            Step::Redact {
+                pattern,
+                replacement,
+            } => json!({ "redact": { "pattern": pattern, "replacement": replacement } }),
+            Step::Custom(name) => json!({ "custom": name }),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum PipelineError {
+    UnknownStep(String),
+    InvalidPattern(String),
+    NoJson,
+    Step { name: String, message: String },
+}
+
+impl fmt::Display for PipelineError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            PipelineError::UnknownStep(name) => write!(f, "unknown post-processor '{}'", name),
+            PipelineError::InvalidPattern(message) => write!(f, "invalid pattern: {}", message),
+            PipelineError::NoJson => write!(f, "response contains no JSON value"),
+            PipelineError::Step { name, message } => write!(f, "{}: {}", name, message),
+        }
+    }
+}
+
+impl std::error::Error for PipelineError {}
+
+pub trait PostProcessor: Send + Sync {
+    fn name(&self) -> &str;
+
+    fn process(&self, text: String) -> Result<String, PipelineError>;
+}
+
+pub type Processors = HashMap<String, Arc<dyn PostProcessor>>;
+
+fn strip_fences(text: &str) -> String {
+    let trimmed = text.trim();
+    let Some(start) = trimmed.find("```") else {
+        return trimmed.to_string();
+    };
+    let fenced = &trimmed[start + 3..];
+    let body = fenced.find('\n').map_or(fenced, |n| &fenced[n + 1..]);
+    match body.find("```") {
+        Some(end) => body[..end].trim().to_string(),
+        None => body.trim().to_string(),
+    }
+}
+
+fn first_json(text: &str) -> Option<&str> {
+    for (start, _) in text.char_indices().filter(|(_, c)| matches!(c, '{' | '[')) {
+        let mut depth = 0usize;
+        let mut in_string = false;
+        let mut escaped = false;
+        for (offset, c) in text[start..].char_indices() {
+            match c {
+                _ if escaped => escaped = false,
+                '\\' if in_string => escaped = true,
+                '"' => in_string = !in_string,
+                '{' | '[' if !in_string => depth += 1,
+                '}' | ']' if !in_string => {
+                    depth -= 1;
+                    if depth == 0 {
+                        let candidate = &text[start..start + offset + 1];
+                        if serde_json::from_str::<serde_json::Value>(candidate).is_ok() {
+                            return Some(candidate);
+                        }
+                        break;
+                    }
+                }
+                _ => {}
+            }
+        }
+    }
+    None
+}
+
+fn trim_sentences(text: &str, count: usize) -> String {
+    let mut seen = 0;
+    let mut chars = text.char_indices().peekable();
+    while let Some((i, c)) = chars.next() {
+        if matches!(c, '.' | '!' | '?') && chars.peek().is_none_or(|(_, n)| n.is_whitespace()) {
+            seen += 1;
+            if seen == count {
+                return text[..i + c.len_utf8()].trim().to_string();
+            }
+        }
+    }
+    text.trim().to_string()
+}
+
+struct Builtin {
+    name: &'static str,
+    run: Box<dyn Fn(String) -> Result<String, PipelineError> + Send + Sync>,
+}
+
+impl PostProcessor for Builtin {
+    fn name(&self) -> &str {
+        self.name
+    }
+
+    fn process(&self, text: String) -> Result<String, PipelineError> {
+        (self.run)(text)
+    }
+}
+
+impl Step {
+    fn processor(&self, custom: &Processors) -> Result<Arc<dyn PostProcessor>, PipelineError> {
+        let builtin =
+            |name, run: Box<dyn Fn(String) -> Result<String, PipelineError> + Send + Sync>| {
+                Ok(Arc::new(Builtin { name, run }) as Arc<dyn PostProcessor>)
+            };
+        match self {
+            Step::Trim => builtin("trim", Box::new(|text| Ok(text.trim().to_string()))),
+            Step::StripFences => builtin("strip_fences", Box::new(|text| Ok(strip_fences(&text)))),
+            Step::ExtractJson => builtin(
+                "extract_json",
+                Box::new(|text| {
+                    first_json(&text)
+                        .map(|json| json.to_string())
+                        .ok_or(PipelineError::NoJson)
+                }),
+            ),
+            Step::TrimSentences(count) => {
+                let count = *count;
+                builtin(
+                    "trim_sentences",
+                    Box::new(move |text| Ok(trim_sentences(&text, count))),
+                )
+            }
+            Step::Redact {
+                pattern,
+                replacement,
+            } => {
+                let compiled = Pattern::new(pattern)
+                    .map_err(|e| PipelineError::InvalidPattern(e.to_string()))?;
+                let replacement = replacement.clone();
+                builtin(
+                    "redact",
+                    Box::new(move |text| Ok(compiled.replace_all(&text, &replacement))),
+                )
+            }
+            Step::Custom(name) => custom
+                .get(name)
+                .cloned()
+                .ok_or_else(|| PipelineError::UnknownStep(name.clone())),
+        }
+    }
+}
+
+#[derive(Clone, Default)]
+pub struct Pipeline {
+    steps: Vec<Arc<dyn PostProcessor>>,
+}
+
+impl Pipeline {
+    pub fn new() -> Pipeline {
+        Pipeline::default()
+    }
+
+    pub fn from_steps(steps: &[Step], custom: &Processors) -> Result<Pipeline, PipelineError> {
+        steps.iter().try_fold(Pipeline::new(), |pipeline, step| {
+            Ok(pipeline.step_arc(step.processor(custom)?))
+        })
+    }
+
+    pub fn step(self, processor: impl PostProcessor + 'static) -> Self {
+        self.step_arc(Arc::new(processor))
+    }
+
+    fn step_arc(mut self, processor: Arc<dyn PostProcessor>) -> Self {
+        self.steps.push(processor);
+        self
+    }
+
+    pub fn len(&self) -> usize {
+        self.steps.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.steps.is_empty()
+    }
+
+    pub fn run(&self, text: impl Into<String>) -> Result<String, PipelineError> {
+        self.steps
+            .iter()
+            .try_fold(text.into(), |text, step| step.process(text))
+    }
+}
+
+impl fmt::Debug for Pipeline {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let names: Vec<&str> = self.steps.iter().map(|s| s.name()).collect();
+        f.debug_struct("Pipeline").field("steps", &names).finish()
+    }
+}
+
+impl Completion {
+    pub fn pipeline(&self, custom: &Processors) -> Result<Pipeline, PipelineError> {
+        Pipeline::from_steps(self.post_process.as_deref().unwrap_or_default(), custom)
+    }
+}
+
+impl Chat {
+    pub fn pipeline(&self, custom: &Processors) -> Result<Pipeline, PipelineError> {
+        Pipeline::from_steps(self.post_process.as_deref().unwrap_or_default(), custom)
+    }
+}
+
+impl Prompt {
+    pub fn pipeline(&self, custom: &Processors) -> Result<Pipeline, PipelineError> {
+        match self {
+            Prompt::Completion(completion) => completion.pipeline(custom),
+            Prompt::Chat(chat) => chat.pipeline(custom),
+            Prompt::Embedding(_) | Prompt::Custom(_) | Prompt::Unknown => Ok(Pipeline::new()),
+        }
+    }
+}
+
+#[cfg(feature = "exec")]
+mod exec {
+    use super::Processors;
+    use crate::exec::{ExecError, ExecutionResult, PromptExecutor};
+    use crate::prompt::Prompt;
+    use std::collections::HashMap;
+
+    impl Prompt {
+        pub async fn execute_parsed(
+            &self,
+            executor: &(impl PromptExecutor + ?Sized),
+            vars: &HashMap<String, String>,
+        ) -> Result<ExecutionResult, ExecError> {
+            self.execute_parsed_with(executor, vars, &Processors::new())
+                .await
+        }
+
+        pub async fn execute_parsed_with(
+            &self,
+            executor: &(impl PromptExecutor + ?Sized),
+            vars: &HashMap<String, String>,
+            custom: &Processors,
+        ) -> Result<ExecutionResult, ExecError> {
+            let pipeline = self.pipeline(custom)?;
+            let mut result = executor.execute(self, vars).await?;
+            result.text = pipeline.run(result.text)?;
+            Ok(result)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::prompt::deserialize_prompt;
+
+    struct Shout;
+
+    impl PostProcessor for Shout {
+        fn name(&self) -> &str {
+            "shout"
+        }
+
+        fn process(&self, text: String) -> Result<String, PipelineError> {
+            Ok(text.to_uppercase())
+        }
+    }
+
+    #[test]
+    fn test_declared_pipeline() {
+        let yaml = r#"
+            type: completion
+            vendor: openai
+            model: gpt
+            prompt: hi
+            post_process:
+                - strip_fences
+                - trim_sentences: 2
+                - redact: { pattern: '\d{3}-\d{4}' }
+                - custom: shout
+        "#;
+        let prompt = deserialize_prompt(yaml);
+        assert_eq!(
+            prompt.pipeline(&Processors::new()).unwrap_err(),
+            PipelineError::UnknownStep("shout".to_string())
+        );
+
+        let custom = Processors::from([(
+            "shout".to_string(),
+            Arc::new(Shout) as Arc<dyn PostProcessor>,
+        )]);
+        let pipeline = prompt.pipeline(&custom).unwrap();
+        assert_eq!(
+            format!("{:?}", pipeline),
+            r#"Pipeline { steps: ["strip_fences", "trim_sentences", "redact", "shout"] }"#
+        );
+        assert_eq!(
+            pipeline
+                .run("```text\nCall 555-1234 now. It is v1.5 ready! Bye.\n```")
+                .unwrap(),
+            "CALL [REDACTED] NOW. IT IS V1.5 READY!"
+        );
+    }
+
+    #[test]
+    fn test_step_serde() {
+        let steps: Vec<Step> =
+            serde_yaml::from_str("[trim, {redact: 'a+'}, {trim_sentences: 1}]").unwrap();
+        assert_eq!(
+            steps[1],
+            Step::Redact {
+                pattern: "a+".to_string(),
+                replacement: DEFAULT_REPLACEMENT.to_string(),
+            }
+        );
+        let json = serde_json::to_string(&steps).unwrap();
+        assert_eq!(serde_json::from_str::<Vec<Step>>(&json).unwrap(), steps);
+        assert!(serde_yaml::from_str::<Vec<Step>>("[{trim_sentences: x}]").is_err());
+    }
+
+    #[test]
+    fn test_extract_json() {
+        let pipeline = Pipeline::from_steps(&[Step::ExtractJson], &Processors::new()).unwrap();
+        assert_eq!(
+            pipeline
+                .run(r#"Sure: {"a": "}", "b": [1, 2]} and also {"c": 3}"#)
+                .unwrap(),
+            r#"{"a": "}", "b": [1, 2]}"#
+        );
+        assert_eq!(pipeline.run("no json {here"), Err(PipelineError::NoJson));
+        assert!(matches!(
+            Pipeline::from_steps(
+                &[Step::Redact {
+                    pattern: "(".to_string(),
+                    replacement: String::new(),
+                }],
+                &Processors::new(),
+            ),
+            Err(PipelineError::InvalidPattern(_))
+        ));
+
+        let yaml = "type: chat\nvendor: openai\nmodel: gpt\nmessages: [{input: hi}]\npost_process: [{redact: {pattern: '['}}]";
+        let issues = deserialize_prompt(yaml).validate();
+        assert_eq!(issues[0].code, "invalid-post-process");
+        assert_eq!(issues[0].field, "post_process[0]");
+    }
+
+    #[test]
+    fn test_redact_large_response() {
+        let redact = |pattern: &str| {
+            let step = Step::Redact {
+                pattern: pattern.to_string(),
+                replacement: "#".to_string(),
+            };
+            Pipeline::from_steps(&[step], &Processors::new()).unwrap()
+        };
+        let text = "a".repeat(1 << 20);
+        assert_eq!(redact("(a|aa)*b").run(&text).unwrap(), text);
+
+        let text = format!("{}555-1234{}", "x ".repeat(1 << 19), " y".repeat(1 << 19));
+        let redacted = redact(r"\d{3}-\d{4}").run(&text).unwrap();
+        assert_eq!(redacted.len(), text.len() - 7);
+        assert!(redacted.contains(" # "));
+    }
+}
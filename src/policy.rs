@@ -0,0 +1,89 @@
+use crate::prompt::{Chat, Completion, Prompt};
+use serde::{Deserialize, Serialize};
+use std::time::Duration;
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Backoff {
+    Constant,
+    Linear,
+    #[default]
+    Exponential,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ExecutionPolicy {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub max_retries: Option<u32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub backoff: Option<Backoff>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub timeout_ms: Option<u64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub rate_limit_rpm: Option<u32>,
+}
+
+impl ExecutionPolicy {
+    pub fn timeout(&self) -> Option<Duration> {
+        self.timeout_ms.map(Duration::from_millis)
+    }
+
+    pub fn delay(&self, base: Duration, attempt: u32) -> Duration {
+        match self.backoff.unwrap_or_default() {
+            Backoff::Constant => base,
+            Backoff::Linear => base.saturating_mul(attempt + 1),
+            Backoff::Exponential => base.saturating_mul(2u32.saturating_pow(attempt)),
+        }
+    }
+
+    pub fn or(&self, fallback: &ExecutionPolicy) -> ExecutionPolicy {
+        ExecutionPolicy {
+            max_retries: self.max_retries.or(fallback.max_retries),
+            backoff: self.backoff.or(fallback.backoff),
+            timeout_ms: self.timeout_ms.or(fallback.timeout_ms),
+            rate_limit_rpm: self.rate_limit_rpm.or(fallback.rate_limit_rpm),
+        }
+    }
+}
+
+impl Prompt {
+    pub fn policy(&self) -> Option<&ExecutionPolicy> {
+        match self {
+            Prompt::Completion(Completion { policy, .. }) | Prompt::Chat(Chat { policy, .. }) => {
+                policy.as_ref()
+            }
+            Prompt::Embedding(_) | Prompt::Custom(_) | Prompt::Unknown => None,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::prompt::deserialize_prompt;
+
+    #[test]
+    fn test_policy_declaration() {
+        let yaml = r#"
+            type: completion
+            vendor: openai
+            model: gpt
+            prompt: hi
+            policy: { max_retries: 3, backoff: linear, timeout_ms: 30000, rate_limit_rpm: 60 }
+        "#;
+        let prompt = deserialize_prompt(yaml);
+        let policy = prompt.policy().unwrap();
+        assert_eq!(policy.max_retries, Some(3));
+        assert_eq!(policy.timeout(), Some(Duration::from_secs(30)));
+        let base = Duration::from_millis(100);
+        assert_eq!(policy.delay(base, 2), Duration::from_millis(300));
+        assert_eq!(
+            ExecutionPolicy::default().delay(base, 3),
+            Duration::from_millis(800)
+        );
+
+        let merged = ExecutionPolicy::default().or(policy);
+        assert_eq!(&merged, policy);
+        assert!(prompt.to_yaml().unwrap().contains("rate_limit_rpm: 60"));
+    }
+}
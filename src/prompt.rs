@@ -1,46 +1,442 @@
-use serde::Deserialize;
+use crate::content::ContentPart;
+use crate::format::{prompt_from_value, yaml_to_json};
+use crate::kind::{serialize_kind, PromptKind, PromptParser};
+use crate::locale::{localize, DEFAULT_LOCALE};
+use crate::output::OutputSpec;
+use crate::pipeline::Step;
+use crate::policy::ExecutionPolicy;
+use crate::request::Endpoint;
+use crate::sampling::Sampling;
+use crate::strict::UnknownField;
+use crate::template::TemplateCache;
+use crate::tools::{Tool, ToolCall, ToolChoice, ToolResult};
+use crate::variables::Variables;
+use crate::variant::{ChangelogEntry, Variant};
+use serde::{Deserialize, Serialize, Serializer};
 use serde_yaml::Value;
+use std::collections::{BTreeMap, HashMap};
+use std::fmt;
+use std::sync::Arc;
 
-#[derive(Debug, Deserialize)]
-struct PromptType {
-    #[serde(rename = "type")]
-    prompt_type: String,
-}
-
-#[derive(Debug, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
 pub struct CompletionExampleColumn {
     pub name: String,
     pub values: Vec<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
     pub test: Option<String>,
 }
 
-#[derive(Debug, Deserialize)]
+#[derive(Debug, Clone, Copy, Default, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum LabelStyle {
+    #[default]
+    Colon,
+    Newline,
+    Bracket,
+    None,
+}
+
+impl LabelStyle {
+    fn line(self, name: &str, value: &str) -> String {
+        match self {
+            LabelStyle::Colon => format!("{}: {}\n", name, value),
+            LabelStyle::Newline => format!("{}\n{}\n", name, value),
+            LabelStyle::Bracket => format!("[{}] {}\n", name, value),
+            LabelStyle::None => format!("{}\n", value),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize, PartialEq)]
+#[serde(try_from = "RawExampleFormat", into = "RawExampleFormat")]
+pub struct ExampleFormat {
+    pub separator: Option<String>,
+    pub labels: LabelStyle,
+    pub template: Option<String>,
+}
+
+#[derive(Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct RawExampleFormat {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    separator: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    labels: Option<LabelStyle>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    template: Option<String>,
+}
+
+impl From<ExampleFormat> for RawExampleFormat {
+    fn from(format: ExampleFormat) -> RawExampleFormat {
+        RawExampleFormat {
+            labels: Some(format.labels).filter(|_| format.template.is_none()),
+            separator: format.separator,
+            template: format.template,
+        }
+    }
+}
+
+impl TryFrom<RawExampleFormat> for ExampleFormat {
+    type Error = String;
+
+    fn try_from(raw: RawExampleFormat) -> Result<ExampleFormat, String> {
+        if raw.labels.is_some() && raw.template.is_some() {
+            return Err("example_format takes either labels or a template, not both".to_string());
+        }
+        Ok(ExampleFormat {
+            separator: raw.separator,
+            labels: raw.labels.unwrap_or_default(),
+            template: raw.template,
+        })
+    }
+}
+
+impl ExampleFormat {
+    pub fn separator(&self) -> &str {
+        self.separator.as_deref().unwrap_or("\n")
+    }
+
+    fn fill<'a>(
+        template: &str,
+        columns: &'a [CompletionExampleColumn],
+        value: impl Fn(&'a CompletionExampleColumn) -> Option<&'a str>,
+    ) -> String {
+        let mut out = String::new();
+        let mut rest = template;
+        while let Some(start) = rest.find('{') {
+            out.push_str(&rest[..start]);
+            let tail = &rest[start..];
+            let column = tail.find('}').and_then(|end| {
+                let column = columns.iter().find(|c| c.name == tail[1..end])?;
+                Some((column, end + 1))
+            });
+            let Some((column, len)) = column else {
+                out.push('{');
+                rest = &tail[1..];
+                continue;
+            };
+            match value(column) {
+                Some(text) => out.push_str(text),
+                None => return out,
+            }
+            rest = &tail[len..];
+        }
+        out.push_str(rest);
+        out
+    }
+
+    pub fn render_row<'a>(
+        &self,
+        columns: &'a [CompletionExampleColumn],
+        value: impl Fn(&'a CompletionExampleColumn) -> Option<&'a str>,
+    ) -> String {
+        match &self.template {
+            Some(template) => ExampleFormat::fill(template, columns, value),
+            None => columns
+                .iter()
+                .map(|c| self.labels.line(&c.name, value(c).unwrap_or("")))
+                .collect(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
 pub struct ChatExample {
     pub input: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
     pub output: Option<String>,
 }
 
-#[derive(Debug, Deserialize)]
+#[derive(Debug, Clone, Default, Serialize, Deserialize, PartialEq)]
 pub struct Message {
+    #[serde(default)]
     pub input: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub content: Option<Vec<ContentPart>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub tool_calls: Option<Vec<ToolCall>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub tool_results: Option<Vec<ToolResult>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
     pub output: Option<String>,
 }
 
-#[derive(Debug, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
 pub struct Parameter {
     pub name: String,
     pub value: Value,
 }
 
-#[derive(Debug, Deserialize)]
+pub(crate) fn deserialize_version<'de, D>(deserializer: D) -> Result<Option<String>, D::Error>
+where
+    D: serde::Deserializer<'de>,
+{
+    Ok(match Option::<Value>::deserialize(deserializer)? {
+        None | Some(Value::Null) => None,
+        Some(Value::String(version)) => Some(version),
+        Some(Value::Number(version)) => Some(version.to_string()),
+        Some(other) => {
+            return Err(serde::de::Error::custom(format!(
+                "invalid version {:?}",
+                other
+            )))
+        }
+    })
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize, PartialEq)]
+pub struct PromptMeta {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub name: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub description: Option<String>,
+    #[serde(
+        default,
+        deserialize_with = "deserialize_version",
+        skip_serializing_if = "Option::is_none"
+    )]
+    pub version: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub tags: Option<Vec<String>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub author: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub created: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub updated: Option<String>,
+    #[serde(
+        default,
+        deserialize_with = "crate::variables::deserialize_variables",
+        skip_serializing_if = "Option::is_none"
+    )]
+    pub variables: Option<Variables>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub changelog: Option<Vec<ChangelogEntry>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub variant: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub variants: Option<Vec<Variant>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub max_context_tokens: Option<usize>,
+    #[serde(flatten)]
+    pub endpoint: Endpoint,
+    #[serde(skip)]
+    pub unknown_fields: Vec<UnknownField>,
+    #[serde(skip)]
+    pub(crate) templates: TemplateCache,
+}
+
+impl PromptMeta {
+    pub fn templates(&self) -> &TemplateCache {
+        &self.templates
+    }
+
+    pub fn has_tag(&self, tag: &str) -> bool {
+        self.tags.iter().flatten().any(|t| t == tag)
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
 pub struct Completion {
+    #[serde(flatten)]
+    pub meta: PromptMeta,
     #[serde(rename = "type")]
     pub prompt_type: String,
     pub vendor: String,
     pub model: String,
     pub prompt: String,
+    #[serde(alias = "instructions", skip_serializing_if = "Option::is_none")]
+    pub system: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub suffix: Option<String>,
+    #[serde(flatten)]
+    pub sampling: Sampling,
+    #[serde(skip_serializing_if = "Option::is_none")]
     pub parameters: Option<Vec<Parameter>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub parameters_by_env: Option<BTreeMap<String, Vec<Parameter>>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
     pub examples: Option<Vec<CompletionExampleColumn>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub example_format: Option<ExampleFormat>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub output: Option<OutputSpec>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub policy: Option<ExecutionPolicy>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub post_process: Option<Vec<Step>>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Location {
+    pub line: usize,
+    pub column: usize,
+}
+
+impl fmt::Display for Location {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "line {} column {}", self.line, self.column)
+    }
+}
+
+pub(crate) fn error_location(error: &serde_yaml::Error) -> Option<Location> {
+    error.location().map(|l| Location {
+        line: l.line(),
+        column: l.column(),
+    })
+}
+
+fn write_location(f: &mut fmt::Formatter<'_>, location: &Option<Location>) -> fmt::Result {
+    match location {
+        Some(location) => write!(f, " at {}", location),
+        None => Ok(()),
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum PromptError {
+    InvalidYaml {
+        message: String,
+        location: Option<Location>,
+    },
+    InvalidJson {
+        message: String,
+        location: Option<Location>,
+    },
+    InvalidToml {
+        message: String,
+        location: Option<Location>,
+    },
+    MissingType,
+    UnknownType(String),
+    SchemaMismatch {
+        prompt_type: String,
+        message: String,
+        location: Option<Location>,
+    },
+    MissingColumn(String),
+    EmptyOutput {
+        column: String,
+        row: usize,
+    },
+    Serialization(String),
+    UnknownFields(Vec<UnknownField>),
+}
+
+impl fmt::Display for PromptError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            PromptError::InvalidYaml { message, location } => {
+                write!(f, "invalid yaml: {}", message)?;
+                write_location(f, location)
+            }
+            PromptError::InvalidJson { message, location } => {
+                write!(f, "invalid json: {}", message)?;
+                write_location(f, location)
+            }
+            PromptError::InvalidToml { message, location } => {
+                write!(f, "invalid toml: {}", message)?;
+                write_location(f, location)
+            }
+            PromptError::MissingType => write!(f, "missing 'type' field"),
+            PromptError::UnknownType(prompt_type) => {
+                write!(f, "unknown prompt type '{}'", prompt_type)
+            }
+            PromptError::SchemaMismatch {
+                prompt_type,
+                message,
+                location,
+            } => {
+                write!(f, "invalid {} prompt: {}", prompt_type, message)?;
+                write_location(f, location)
+            }
+            PromptError::MissingColumn(name) => write!(f, "missing example column '{}'", name),
+            PromptError::EmptyOutput { column, row } => {
+                write!(f, "example row {} has an empty '{}' value", row, column)
+            }
+            PromptError::Serialization(message) => {
+                write!(f, "cannot serialize prompt: {}", message)
+            }
+            PromptError::UnknownFields(fields) => {
+                let fields: Vec<String> = fields.iter().map(|u| u.to_string()).collect();
+                write!(f, "{}", fields.join(", "))
+            }
+        }
+    }
+}
+
+impl std::error::Error for PromptError {}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum NameStyle {
+    CamelCase,
+    SnakeCase,
+}
+
+impl NameStyle {
+    pub fn convert(&self, name: &str) -> String {
+        match self {
+            NameStyle::CamelCase => to_camel_case(name),
+            NameStyle::SnakeCase => to_snake_case(name),
+        }
+    }
+}
+
+fn to_snake_case(name: &str) -> String {
+    let mut result = String::new();
+    let mut prev_lower = false;
+    for c in name.chars() {
+        if c == '-' || c == ' ' || c == '_' {
+            if !result.is_empty() && !result.ends_with('_') {
+                result.push('_');
+            }
+            prev_lower = false;
+        } else if c.is_uppercase() {
+            if prev_lower && !result.ends_with('_') {
+                result.push('_');
+            }
+            result.extend(c.to_lowercase());
+            prev_lower = false;
+        } else {
+            result.push(c);
+            prev_lower = c.is_lowercase() || c.is_ascii_digit();
+        }
+    }
+    result.trim_end_matches('_').to_string()
+}
+
+fn to_camel_case(name: &str) -> String {
+    let mut result = String::new();
+    let mut upper_next = false;
+    for c in name.chars() {
+        if c == '_' || c == '-' || c == ' ' {
+            upper_next = !result.is_empty();
+        } else if upper_next {
+            result.extend(c.to_uppercase());
+            upper_next = false;
+        } else if result.is_empty() {
+            result.extend(c.to_lowercase());
+        } else {
+            result.push(c);
+        }
+    }
+    result
+}
+
+fn value_as_vec_str(value: &Value) -> Option<Vec<String>> {
+    value
+        .as_sequence()?
+        .iter()
+        .map(|v| v.as_str().map(|s| s.to_string()))
+        .collect()
+}
+
+fn value_as_map(value: &Value) -> Option<HashMap<String, Value>> {
+    value
+        .as_mapping()?
+        .iter()
+        .map(|(k, v)| k.as_str().map(|k| (k.to_string(), v.clone())))
+        .collect()
 }
 
 pub fn find_parameter(
@@ -70,99 +466,489 @@ impl Completion {
         max_length
     }
 
+    pub fn has_examples(&self) -> bool {
+        self.example_count() > 0
+    }
+
+    fn example_block(&self) -> String {
+        let columns = self.examples.as_deref().unwrap_or_default();
+        let default = ExampleFormat::default();
+        let format = self.example_format.as_ref().unwrap_or(&default);
+        let mut rows: Vec<String> = (0..self.example_count())
+            .map(|i| {
+                format.render_row(columns, |c| {
+                    Some(c.values.get(i).map_or("", |v| v.as_str()))
+                })
+            })
+            .collect();
+        rows.push(format.render_row(columns, |c| c.test.as_deref()));
+        rows.join(format.separator())
+    }
+
     pub fn final_prompt(&self) -> String {
+        let mut prompt = match self.system.as_deref().filter(|s| !s.is_empty()) {
+            Some(system) => format!("{}\n\n{}", system, self.prompt),
+            None => self.prompt.clone(),
+        };
+        if self.has_examples() {
+            prompt.push_str("\n\n");
+            prompt.push_str(&self.example_block());
+        }
+        if let Some(suffix) = self.suffix.as_deref().filter(|s| !s.is_empty()) {
+            prompt.push_str(if prompt.ends_with('\n') { "\n" } else { "\n\n" });
+            prompt.push_str(suffix);
+        }
+        prompt
+    }
+
+    pub fn render_qa(
+        &self,
+        question_col: &str,
+        answer_col: &str,
+        q_label: &str,
+        a_label: &str,
+    ) -> Result<String, PromptError> {
+        let question = self.find_column(question_col)?;
+        let answer = self.find_column(answer_col)?;
         let mut prompt = self.prompt.clone();
         prompt.push_str("\n\n");
-        if let Some(columns) = &self.examples {
-            for i in 0..self.example_count() {
-                for column in columns {
-                    let line: String = format!(
+        for i in 0..self.example_count() {
+            prompt.push_str(&format!(
+                "{}: {}\n{}: {}\n\n",
+                q_label,
+                question.values.get(i).map(|v| v.as_str()).unwrap_or(""),
+                a_label,
+                answer.values.get(i).map(|v| v.as_str()).unwrap_or("")
+            ));
+        }
+        prompt.push_str(&format!(
+            "{}: {}\n{}: {}",
+            q_label,
+            question.test.as_deref().unwrap_or(""),
+            a_label,
+            answer.test.as_deref().unwrap_or("")
+        ));
+        Ok(prompt)
+    }
+
+    pub fn render_grouped(&self, category_col: &str) -> Result<String, PromptError> {
+        let category = self.find_column(category_col)?;
+        let columns: Vec<&CompletionExampleColumn> = self
+            .examples
+            .iter()
+            .flatten()
+            .filter(|c| c.name != category_col)
+            .collect();
+
+        let mut groups: Vec<(&str, Vec<usize>)> = Vec::new();
+        for i in 0..self.example_count() {
+            let key = category.values.get(i).map(|v| v.as_str()).unwrap_or("");
+            match groups.iter_mut().find(|(k, _)| *k == key) {
+                Some((_, rows)) => rows.push(i),
+                None => groups.push((key, vec![i])),
+            }
+        }
+
+        let mut prompt = self.prompt.clone();
+        prompt.push_str("\n\n");
+        for (key, rows) in groups {
+            prompt.push_str(&format!("### {}\n", key));
+            for i in rows {
+                for column in &columns {
+                    prompt.push_str(&format!(
                         "{}: {}\n",
                         column.name,
-                        column.values.get(i).unwrap_or(&"".to_string())
-                    );
-                    prompt.push_str(&line);
+                        column.values.get(i).map(|v| v.as_str()).unwrap_or("")
+                    ));
                 }
                 prompt.push('\n');
             }
-            for column in columns {
-                let line: String = format!(
-                    "{}: {}\n",
-                    column.name,
-                    column.test.as_ref().unwrap_or(&"".to_string())
-                );
-                prompt.push_str(&line);
+        }
+        for column in &columns {
+            prompt.push_str(&format!(
+                "{}: {}\n",
+                column.name,
+                column.test.as_deref().unwrap_or("")
+            ));
+        }
+        Ok(prompt)
+    }
+
+    pub fn find_column(&self, name: &str) -> Result<&CompletionExampleColumn, PromptError> {
+        self.examples
+            .iter()
+            .flatten()
+            .find(|c| c.name == name)
+            .ok_or_else(|| PromptError::MissingColumn(name.to_string()))
+    }
+
+    pub fn validate_outputs_present(&self, output_column: &str) -> Result<(), PromptError> {
+        let column = self.find_column(output_column)?;
+        for row in 0..self.example_count() {
+            let empty = column
+                .values
+                .get(row)
+                .map(|v| v.trim().is_empty())
+                .unwrap_or(true);
+            if empty {
+                return Err(PromptError::EmptyOutput {
+                    column: output_column.to_string(),
+                    row,
+                });
+            }
+        }
+        Ok(())
+    }
+
+    pub fn normalize_parameter_names(&mut self, style: NameStyle) {
+        if let Some(parameters) = &mut self.parameters {
+            for parameter in parameters {
+                parameter.name = style.convert(&parameter.name);
             }
         }
-        prompt.to_string()
     }
 
     pub fn find_parameter_as_i32(&self, name: &str) -> Option<i32> {
-        find_parameter(&self.parameters, name).map(|p| p.as_i64().unwrap() as i32)
+        find_parameter(&self.parameters, name).and_then(|p| p.as_i64().map(|v| v as i32))
     }
 
     pub fn find_parameter_as_f32(&self, name: &str) -> Option<f32> {
-        find_parameter(&self.parameters, name).map(|p| p.as_f64().unwrap() as f32)
+        find_parameter(&self.parameters, name).and_then(|p| p.as_f64().map(|v| v as f32))
     }
 
     pub fn find_parameter_as_str(&self, name: &str) -> Option<String> {
-        find_parameter(&self.parameters, name).map(|p| p.as_str().unwrap().to_string())
+        find_parameter(&self.parameters, name).and_then(|p| p.as_str().map(|v| v.to_string()))
     }
 
     pub fn find_parameter_as_bool(&self, name: &str) -> Option<bool> {
-        find_parameter(&self.parameters, name).map(|p| p.as_bool().unwrap())
+        find_parameter(&self.parameters, name).and_then(|p| p.as_bool())
+    }
+
+    pub fn is_streaming(&self) -> bool {
+        self.find_parameter_as_bool("stream").unwrap_or(false)
+    }
+
+    pub fn find_parameter_as_vec_str(&self, name: &str) -> Option<Vec<String>> {
+        find_parameter(&self.parameters, name).and_then(|p| value_as_vec_str(&p))
+    }
+
+    pub fn find_parameter_as_map(&self, name: &str) -> Option<HashMap<String, Value>> {
+        find_parameter(&self.parameters, name).and_then(|p| value_as_map(&p))
     }
 }
 
-#[derive(Debug, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
 pub struct Chat {
+    #[serde(flatten)]
+    pub meta: PromptMeta,
     #[serde(rename = "type")]
     pub prompt_type: String,
     pub vendor: String,
     pub model: String,
+    #[serde(flatten)]
+    pub sampling: Sampling,
+    #[serde(skip_serializing_if = "Option::is_none")]
     pub parameters: Option<Vec<Parameter>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub parameters_by_env: Option<BTreeMap<String, Vec<Parameter>>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
     pub examples: Option<Vec<ChatExample>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
     pub context: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
     pub messages: Option<Vec<Message>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub tools: Option<Vec<Tool>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub tool_choice: Option<ToolChoice>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub output: Option<OutputSpec>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub policy: Option<ExecutionPolicy>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub post_process: Option<Vec<Step>>,
 }
 
 impl Chat {
     pub fn find_parameter_as_i32(&self, name: &str) -> Option<i32> {
-        find_parameter(&self.parameters, name).map(|p| p.as_i64().unwrap() as i32)
+        find_parameter(&self.parameters, name).and_then(|p| p.as_i64().map(|v| v as i32))
     }
 
     pub fn find_parameter_as_f32(&self, name: &str) -> Option<f32> {
-        find_parameter(&self.parameters, name).map(|p| p.as_f64().unwrap() as f32)
+        find_parameter(&self.parameters, name).and_then(|p| p.as_f64().map(|v| v as f32))
     }
 
     pub fn find_parameter_as_str(&self, name: &str) -> Option<String> {
-        find_parameter(&self.parameters, name).map(|p| p.as_str().unwrap().to_string())
+        find_parameter(&self.parameters, name).and_then(|p| p.as_str().map(|v| v.to_string()))
     }
 
     pub fn find_parameter_as_bool(&self, name: &str) -> Option<bool> {
-        find_parameter(&self.parameters, name).map(|p| p.as_bool().unwrap())
+        find_parameter(&self.parameters, name).and_then(|p| p.as_bool())
     }
+
+    pub fn is_streaming(&self) -> bool {
+        self.find_parameter_as_bool("stream").unwrap_or(false)
+    }
+
+    pub fn find_parameter_as_vec_str(&self, name: &str) -> Option<Vec<String>> {
+        find_parameter(&self.parameters, name).and_then(|p| value_as_vec_str(&p))
+    }
+
+    pub fn find_parameter_as_map(&self, name: &str) -> Option<HashMap<String, Value>> {
+        find_parameter(&self.parameters, name).and_then(|p| value_as_map(&p))
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum EncodingFormat {
+    Float,
+    Base64,
 }
 
-#[derive(Debug, Deserialize)]
+fn deserialize_inputs<'de, D>(deserializer: D) -> Result<Vec<String>, D::Error>
+where
+    D: serde::Deserializer<'de>,
+{
+    #[derive(Deserialize)]
+    #[serde(untagged)]
+    enum Inputs {
+        One(String),
+        Many(Vec<String>),
+    }
+    Ok(match Inputs::deserialize(deserializer)? {
+        Inputs::One(input) => vec![input],
+        Inputs::Many(inputs) => inputs,
+    })
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct Embedding {
+    #[serde(flatten)]
+    pub meta: PromptMeta,
+    #[serde(rename = "type")]
+    pub prompt_type: String,
+    pub vendor: String,
+    pub model: String,
+    #[serde(deserialize_with = "deserialize_inputs")]
+    pub input: Vec<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub dimensions: Option<u32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub encoding_format: Option<EncodingFormat>,
+}
+
+#[derive(Debug, Clone, Deserialize, PartialEq)]
 pub enum Prompt {
     Completion(Completion),
     Chat(Chat),
+    Embedding(Embedding),
+    #[serde(skip_deserializing)]
+    Custom(Arc<dyn PromptKind>),
     Unknown,
 }
 
-pub fn deserialize_prompt(yaml: &str) -> Prompt {
-    let prompt_type: PromptType = serde_yaml::from_str(&yaml).unwrap();
-    match prompt_type.prompt_type.as_str() {
-        "completion" => {
-            let completion: Completion = serde_yaml::from_str(&yaml).unwrap();
-            Prompt::Completion(completion)
+fn collect_value_text(path: String, value: &Value, fields: &mut Vec<(String, String)>) {
+    match value {
+        Value::String(text) => fields.push((path, text.clone())),
+        Value::Sequence(items) => {
+            for (i, item) in items.iter().enumerate() {
+                collect_value_text(format!("{}[{}]", path, i), item, fields);
+            }
+        }
+        Value::Mapping(mapping) => {
+            for (key, item) in mapping {
+                let key = key.as_str().map(|k| k.to_string()).unwrap_or_default();
+                collect_value_text(format!("{}.{}", path, key), item, fields);
+            }
+        }
+        _ => {}
+    }
+}
+
+fn collect_parameter_text(parameters: &Option<Vec<Parameter>>, fields: &mut Vec<(String, String)>) {
+    for (i, parameter) in parameters.iter().flatten().enumerate() {
+        fields.push((format!("parameters[{}].name", i), parameter.name.clone()));
+        collect_value_text(format!("parameters[{}].value", i), &parameter.value, fields);
+    }
+}
+
+impl Serialize for Prompt {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        match self {
+            Prompt::Completion(completion) => completion.serialize(serializer),
+            Prompt::Chat(chat) => chat.serialize(serializer),
+            Prompt::Embedding(embedding) => embedding.serialize(serializer),
+            Prompt::Custom(kind) => serialize_kind(kind.as_ref(), serializer),
+            Prompt::Unknown => serializer.serialize_unit(),
+        }
+    }
+}
+
+pub fn to_yaml<T: Serialize>(value: &T) -> Result<String, PromptError> {
+    serde_yaml::to_string(value).map_err(|e| PromptError::Serialization(e.to_string()))
+}
+
+impl Completion {
+    pub fn to_yaml(&self) -> Result<String, PromptError> {
+        to_yaml(self)
+    }
+}
+
+impl Chat {
+    pub fn to_yaml(&self) -> Result<String, PromptError> {
+        to_yaml(self)
+    }
+}
+
+impl Embedding {
+    pub fn to_yaml(&self) -> Result<String, PromptError> {
+        to_yaml(self)
+    }
+}
+
+impl Prompt {
+    pub fn meta(&self) -> Option<&PromptMeta> {
+        match self {
+            Prompt::Completion(completion) => Some(&completion.meta),
+            Prompt::Chat(chat) => Some(&chat.meta),
+            Prompt::Embedding(embedding) => Some(&embedding.meta),
+            Prompt::Custom(kind) => kind.meta(),
+            Prompt::Unknown => None,
         }
-        "chat" => {
-            let chat: Chat = serde_yaml::from_str(&yaml).unwrap();
-            Prompt::Chat(chat)
+    }
+
+    pub fn meta_mut(&mut self) -> Option<&mut PromptMeta> {
+        match self {
+            Prompt::Completion(completion) => Some(&mut completion.meta),
+            Prompt::Chat(chat) => Some(&mut chat.meta),
+            Prompt::Embedding(embedding) => Some(&mut embedding.meta),
+            Prompt::Custom(_) | Prompt::Unknown => None,
+        }
+    }
+
+    pub fn is_streaming(&self) -> bool {
+        match self {
+            Prompt::Completion(completion) => completion.is_streaming(),
+            Prompt::Chat(chat) => chat.is_streaming(),
+            Prompt::Embedding(_) | Prompt::Custom(_) | Prompt::Unknown => false,
+        }
+    }
+
+    pub fn to_yaml(&self) -> Result<String, PromptError> {
+        to_yaml(self)
+    }
+
+    pub fn text_fields(&self) -> Vec<(String, String)> {
+        let mut fields = Vec::new();
+        match self {
+            Prompt::Completion(completion) => {
+                fields.push(("vendor".to_string(), completion.vendor.clone()));
+                fields.push(("model".to_string(), completion.model.clone()));
+                fields.push(("prompt".to_string(), completion.prompt.clone()));
+                collect_parameter_text(&completion.parameters, &mut fields);
+                for (i, column) in completion.examples.iter().flatten().enumerate() {
+                    fields.push((format!("examples[{}].name", i), column.name.clone()));
+                    for (j, value) in column.values.iter().enumerate() {
+                        fields.push((format!("examples[{}].values[{}]", i, j), value.clone()));
+                    }
+                    if let Some(test) = &column.test {
+                        fields.push((format!("examples[{}].test", i), test.clone()));
+                    }
+                }
+            }
+            Prompt::Chat(chat) => {
+                fields.push(("vendor".to_string(), chat.vendor.clone()));
+                fields.push(("model".to_string(), chat.model.clone()));
+                collect_parameter_text(&chat.parameters, &mut fields);
+                if let Some(context) = &chat.context {
+                    fields.push(("context".to_string(), context.clone()));
+                }
+                for (i, example) in chat.examples.iter().flatten().enumerate() {
+                    fields.push((format!("examples[{}].input", i), example.input.clone()));
+                    if let Some(output) = &example.output {
+                        fields.push((format!("examples[{}].output", i), output.clone()));
+                    }
+                }
+                for (i, message) in chat.messages.iter().flatten().enumerate() {
+                    fields.push((format!("messages[{}].input", i), message.input.clone()));
+                    if let Some(output) = &message.output {
+                        fields.push((format!("messages[{}].output", i), output.clone()));
+                    }
+                }
+            }
+            Prompt::Embedding(embedding) => {
+                fields.push(("vendor".to_string(), embedding.vendor.clone()));
+                fields.push(("model".to_string(), embedding.model.clone()));
+                for (i, input) in embedding.input.iter().enumerate() {
+                    fields.push((format!("input[{}]", i), input.clone()));
+                }
+            }
+            Prompt::Custom(_) | Prompt::Unknown => {}
         }
-        _ => Prompt::Unknown,
+        fields
+    }
+
+    pub fn replacement_char_fields(&self) -> Vec<String> {
+        self.text_fields()
+            .into_iter()
+            .filter(|(_, text)| text.contains(char::REPLACEMENT_CHARACTER))
+            .map(|(path, _)| path)
+            .collect()
+    }
+
+    pub fn has_replacement_chars(&self) -> bool {
+        !self.replacement_char_fields().is_empty()
+    }
+}
+
+fn parse_typed<T: serde::de::DeserializeOwned>(
+    yaml: &str,
+    prompt_type: &str,
+) -> Result<T, PromptError> {
+    serde_yaml::from_str(yaml).map_err(|e| PromptError::SchemaMismatch {
+        prompt_type: prompt_type.to_string(),
+        message: e.to_string(),
+        location: error_location(&e),
+    })
+}
+
+fn from_document<T: serde::de::DeserializeOwned>(
+    document: Value,
+    yaml: &str,
+    prompt_type: &str,
+) -> Result<T, PromptError> {
+    serde_yaml::from_value(document).or_else(|_| parse_typed(yaml, prompt_type))
+}
+
+pub fn try_deserialize_prompt(yaml: &str) -> Result<Prompt, PromptError> {
+    let document: Value = serde_yaml::from_str(yaml).map_err(|e| PromptError::InvalidYaml {
+        message: e.to_string(),
+        location: error_location(&e),
+    })?;
+    let prompt_type = document
+        .get("type")
+        .and_then(|t| t.as_str())
+        .ok_or(PromptError::MissingType)?
+        .to_string();
+    let mut json = yaml_to_json(document.clone());
+    if localize(&mut json, &[DEFAULT_LOCALE.to_string()]) {
+        return prompt_from_value(json);
+    }
+    let prompt_type = prompt_type.as_str();
+    match prompt_type {
+        "completion" => from_document(document, yaml, prompt_type).map(Prompt::Completion),
+        "chat" => from_document(document, yaml, prompt_type).map(Prompt::Chat),
+        "embedding" => from_document(document, yaml, prompt_type).map(Prompt::Embedding),
+        other => PromptParser::parse(other, &json),
+    }
+}
+
+pub fn deserialize_prompt(yaml: &str) -> Prompt {
+    match try_deserialize_prompt(yaml) {
+        Ok(prompt) => prompt,
+        Err(PromptError::UnknownType(_)) => Prompt::Unknown,
+        Err(e) => panic!("{}", e),
     }
 }
 
@@ -332,4 +1118,444 @@ output:
             panic!("Expected Prompt::Unkwon, got {:?}", prompt);
         }
     }
+
+    #[test]
+    fn test_normalize_parameter_names() {
+        let yaml = r#"
+            type: completion
+            vendor: google
+            model: text-bison
+            prompt: Write a hello world in java
+            parameters:
+                - name: maxOutputTokens
+                  value: 256
+                - name: top_k
+                  value: 40
+        "#;
+
+        if let Prompt::Completion(mut completion) = deserialize_prompt(yaml) {
+            completion.normalize_parameter_names(NameStyle::SnakeCase);
+            assert_eq!(
+                completion.find_parameter_as_i32("max_output_tokens"),
+                Some(256)
+            );
+            assert_eq!(completion.find_parameter_as_i32("top_k"), Some(40));
+
+            completion.normalize_parameter_names(NameStyle::CamelCase);
+            assert_eq!(
+                completion.find_parameter_as_i32("maxOutputTokens"),
+                Some(256)
+            );
+            assert_eq!(completion.find_parameter_as_i32("topK"), Some(40));
+        } else {
+            panic!("Expected Prompt::Completion");
+        }
+    }
+
+    #[test]
+    fn test_final_prompt_sections() {
+        let yaml = r#"
+            type: completion
+            vendor: openai
+            model: davinci-002
+            instructions: You translate English to French.
+            prompt: Translate the sentence.
+            suffix: "Answer:"
+            examples:
+              - name: english
+                values: [cat, dog]
+                test: bird
+              - name: french
+                values: [chat, chien]
+        "#;
+        let Prompt::Completion(mut completion) = deserialize_prompt(yaml) else {
+            panic!("Expected Prompt::Completion");
+        };
+        assert_eq!(
+            completion.final_prompt(),
+            "You translate English to French.\n\nTranslate the sentence.\n\n\
+             english: cat\nfrench: chat\n\nenglish: dog\nfrench: chien\n\n\
+             english: bird\nfrench: \n\nAnswer:"
+        );
+
+        completion.suffix = None;
+        completion.example_format = Some(ExampleFormat {
+            separator: Some("\n\n".to_string()),
+            labels: LabelStyle::Colon,
+            template: Some("{english} => {french} {unknown}".to_string()),
+        });
+        assert_eq!(
+            completion.final_prompt(),
+            "You translate English to French.\n\nTranslate the sentence.\n\n\
+             cat => chat {unknown}\n\ndog => chien {unknown}\n\nbird => "
+        );
+
+        completion.example_format = Some(ExampleFormat {
+            labels: LabelStyle::Bracket,
+            ..Default::default()
+        });
+        assert!(completion
+            .final_prompt()
+            .ends_with("[english] bird\n[french] \n"));
+    }
+
+    #[test]
+    fn test_example_format_fields() {
+        let format: ExampleFormat =
+            serde_yaml::from_str("template: '{q} => {a}'\nseparator: '---'").unwrap();
+        assert_eq!(format.template.as_deref(), Some("{q} => {a}"));
+        assert_eq!(
+            serde_yaml::from_value::<ExampleFormat>(serde_yaml::to_value(&format).unwrap())
+                .unwrap(),
+            format
+        );
+
+        let error =
+            serde_yaml::from_str::<ExampleFormat>("labels: bracket\ntemplate: '{q}'").unwrap_err();
+        assert!(error.to_string().contains("either labels or a template"));
+        let error = serde_yaml::from_str::<ExampleFormat>("label: bracket").unwrap_err();
+        assert!(error.to_string().contains("unknown field `label`"));
+    }
+
+    #[test]
+    fn test_final_prompt_without_examples() {
+        let yaml = r#"
+            type: completion
+            vendor: google
+            model: text-bison
+            prompt: Write a hello world in java
+        "#;
+
+        if let Prompt::Completion(completion) = deserialize_prompt(yaml) {
+            assert!(!completion.has_examples());
+            assert_eq!(completion.example_count(), 0);
+            assert_eq!(completion.final_prompt(), "Write a hello world in java");
+        } else {
+            panic!("Expected Prompt::Completion");
+        }
+    }
+
+    #[test]
+    fn test_validate_outputs_present() {
+        let yaml = r#"
+            type: completion
+            vendor: google
+            model: text-bison
+            prompt: Translate to french
+            examples:
+                - name: input
+                  values:
+                    - cat
+                    - dog
+                    - bird
+                - name: output
+                  values:
+                    - chat
+                    - ""
+        "#;
+
+        if let Prompt::Completion(completion) = deserialize_prompt(yaml) {
+            assert_eq!(
+                completion.validate_outputs_present("output"),
+                Err(PromptError::EmptyOutput {
+                    column: "output".to_string(),
+                    row: 1
+                })
+            );
+            assert_eq!(
+                completion.validate_outputs_present("answer"),
+                Err(PromptError::MissingColumn("answer".to_string()))
+            );
+            assert_eq!(completion.validate_outputs_present("input"), Ok(()));
+        } else {
+            panic!("Expected Prompt::Completion");
+        }
+    }
+
+    #[test]
+    fn test_find_structured_parameters() {
+        let yaml = r#"
+            type: completion
+            vendor: openai
+            model: gpt-3.5-turbo-instruct
+            prompt: Say hi
+            parameters:
+                - name: stop_sequences
+                  value: [".", "\n"]
+                - name: logit_bias
+                  value:
+                    "50256": -100
+                    "198": 5
+                - name: temperature
+                  value: 0.4
+        "#;
+
+        if let Prompt::Completion(completion) = deserialize_prompt(yaml) {
+            assert_eq!(
+                completion.find_parameter_as_vec_str("stop_sequences"),
+                Some(vec![".".to_string(), "\n".to_string()])
+            );
+            let bias = completion.find_parameter_as_map("logit_bias").unwrap();
+            assert_eq!(bias.len(), 2);
+            assert_eq!(bias["50256"], -100);
+            assert_eq!(bias["198"], 5);
+
+            assert_eq!(completion.find_parameter_as_vec_str("temperature"), None);
+            assert_eq!(completion.find_parameter_as_map("stop_sequences"), None);
+            assert_eq!(completion.find_parameter_as_vec_str("missing"), None);
+        } else {
+            panic!("Expected Prompt::Completion");
+        }
+    }
+
+    #[test]
+    fn test_render_qa() {
+        let yaml = r#"
+            type: completion
+            vendor: google
+            model: text-bison
+            prompt: Answer the capital city question
+            examples:
+                - name: country
+                  values:
+                    - France
+                    - Japan
+                  test: Italy
+                - name: capital
+                  values:
+                    - Paris
+                    - Tokyo
+        "#;
+
+        let expected = r#"Answer the capital city question
+
+Q: France
+A: Paris
+
+Q: Japan
+A: Tokyo
+
+Q: Italy
+A: "#;
+        if let Prompt::Completion(completion) = deserialize_prompt(yaml) {
+            assert_eq!(
+                completion.render_qa("country", "capital", "Q", "A"),
+                Ok(expected.to_string())
+            );
+            assert_eq!(
+                completion.render_qa("question", "capital", "Q", "A"),
+                Err(PromptError::MissingColumn("question".to_string()))
+            );
+        } else {
+            panic!("Expected Prompt::Completion");
+        }
+    }
+
+    #[test]
+    fn test_replacement_char_fields() {
+        let yaml = "
+            type: chat
+            vendor: google
+            model: chat-bison
+            context: caf\u{FFFD} owner
+            messages:
+                - input: hello
+                  output: bonjour \u{FFFD}
+        ";
+
+        let prompt = deserialize_prompt(yaml);
+        assert!(prompt.has_replacement_chars());
+        assert_eq!(
+            prompt.replacement_char_fields(),
+            vec!["context".to_string(), "messages[0].output".to_string()]
+        );
+
+        let clean = deserialize_prompt(
+            r#"
+            type: completion
+            vendor: google
+            model: text-bison
+            prompt: Write a hello world in java
+        "#,
+        );
+        assert!(!clean.has_replacement_chars());
+    }
+
+    #[test]
+    fn test_render_grouped() {
+        let yaml = r#"
+            type: completion
+            vendor: google
+            model: text-bison
+            prompt: Solve the puzzle
+            examples:
+                - name: difficulty
+                  values:
+                    - easy
+                    - hard
+                    - easy
+                - name: input
+                  values:
+                    - a
+                    - b
+                    - c
+                  test: d
+                - name: output
+                  values:
+                    - x
+                    - y
+                    - z
+        "#;
+
+        let expected = r#"Solve the puzzle
+
+### easy
+input: a
+output: x
+
+input: c
+output: z
+
+### hard
+input: b
+output: y
+
+input: d
+output: 
+"#;
+        if let Prompt::Completion(completion) = deserialize_prompt(yaml) {
+            assert_eq!(
+                completion.render_grouped("difficulty"),
+                Ok(expected.to_string())
+            );
+            assert_eq!(
+                completion.render_grouped("topic"),
+                Err(PromptError::MissingColumn("topic".to_string()))
+            );
+        } else {
+            panic!("Expected Prompt::Completion");
+        }
+    }
+
+    #[test]
+    fn test_try_deserialize_prompt_errors() {
+        match try_deserialize_prompt("type: [completion") {
+            Err(PromptError::InvalidYaml { location, .. }) => assert!(location.is_some()),
+            other => panic!("Expected InvalidYaml, got {:?}", other),
+        }
+
+        assert_eq!(
+            try_deserialize_prompt("vendor: google"),
+            Err(PromptError::MissingType)
+        );
+        assert_eq!(
+            try_deserialize_prompt("type: rerank"),
+            Err(PromptError::UnknownType("rerank".to_string()))
+        );
+        assert!(matches!(
+            try_deserialize_prompt("type: embedding"),
+            Err(PromptError::SchemaMismatch { prompt_type, .. }) if prompt_type == "embedding"
+        ));
+
+        let missing_model = "type: completion\nvendor: google\nprompt: hi\n";
+        match try_deserialize_prompt(missing_model) {
+            Err(PromptError::SchemaMismatch {
+                prompt_type,
+                message,
+                ..
+            }) => {
+                assert_eq!(prompt_type, "completion");
+                assert!(message.contains("model"));
+            }
+            other => panic!("Expected SchemaMismatch, got {:?}", other),
+        }
+
+        let wrong_shape = "type: chat\nvendor: google\nmodel: chat-bison\nmessages: 3\n";
+        match try_deserialize_prompt(wrong_shape) {
+            Err(PromptError::SchemaMismatch { location, .. }) => {
+                assert_eq!(location.map(|l| l.line), Some(4))
+            }
+            other => panic!("Expected SchemaMismatch, got {:?}", other),
+        }
+
+        let numeric_keys = "type: completion\nvendor: openai\nmodel: gpt\nprompt: hi\n1: x\n";
+        assert!(matches!(
+            try_deserialize_prompt(numeric_keys),
+            Ok(Prompt::Completion(c)) if c.prompt == "hi"
+        ));
+        let localized = format!("{}description: {{en: Hi, de: Hallo}}\n", numeric_keys);
+        assert!(matches!(
+            try_deserialize_prompt(&localized),
+            Ok(Prompt::Completion(c)) if c.meta.description.as_deref() == Some("Hi")
+        ));
+    }
+
+    #[test]
+    fn test_prompt_meta() {
+        let yaml = r#"
+            name: support/greet
+            description: Greets a customer
+            version: 1.2
+            tags: [support, greeting]
+            author: jane
+            created: 2024-01-05
+            type: chat
+            vendor: google
+            model: chat-bison
+        "#;
+
+        let prompt = deserialize_prompt(yaml);
+        let meta = prompt.meta().unwrap();
+        assert_eq!(meta.name.as_deref(), Some("support/greet"));
+        assert_eq!(meta.version.as_deref(), Some("1.2"));
+        assert_eq!(meta.created.as_deref(), Some("2024-01-05"));
+        assert!(meta.has_tag("greeting"));
+        assert!(!meta.has_tag("billing"));
+        assert_eq!(meta.updated, None);
+        assert_eq!(deserialize_prompt(&prompt.to_yaml().unwrap()), prompt);
+        assert_eq!(Prompt::Unknown.meta(), None);
+    }
+
+    #[test]
+    fn test_yaml_round_trip() {
+        let completion = r#"
+            type: completion
+            vendor: google
+            model: text-bison
+            prompt: Write a hello world in java
+            parameters:
+                - name: maxOutputTokens
+                  value: 256
+                - name: stop
+                  value: [".", "\n"]
+            examples:
+                - name: input
+                  values:
+                    - a
+                  test: c
+                - name: output
+                  values:
+                    - x
+        "#;
+        let chat = r#"
+            type: chat
+            vendor: google
+            model: chat-bison
+            context: Be nice
+            examples:
+                - input: who are u?
+                  output: I'm google
+            messages:
+                - input: what's your name?
+        "#;
+
+        for yaml in [completion, chat] {
+            let prompt = deserialize_prompt(yaml);
+            let emitted = prompt.to_yaml().unwrap();
+            assert!(!emitted.contains("null"));
+            let reparsed = deserialize_prompt(&emitted);
+            assert_eq!(prompt, reparsed);
+            assert_eq!(emitted, reparsed.to_yaml().unwrap());
+        }
+    }
 }
@@ -0,0 +1,677 @@
+#[cfg(feature = "fs")]
+use crate::compose::{has_references, load_localized, load_source};
+#[cfg(feature = "fs")]
+use crate::format::{document, Format};
+#[cfg(feature = "fs")]
+use crate::locale::{
+    document_locales, is_locale_tag, locale_chain, normalize_locale, DEFAULT_LOCALE,
+};
+#[cfg(feature = "fs")]
+use crate::prompt::Prompt;
+use crate::prompt::PromptError;
+#[cfg(feature = "fs")]
+use crate::scan::{scan, FrontMatter};
+#[cfg(feature = "fs")]
+use crate::strict::ParseOptions;
+#[cfg(feature = "fs")]
+use std::collections::{BTreeMap, BTreeSet, HashMap};
+use std::fmt;
+#[cfg(feature = "fs")]
+use std::fs;
+#[cfg(feature = "fs")]
+use std::path::Path;
+use std::path::PathBuf;
+#[cfg(feature = "fs")]
+use std::sync::atomic::{AtomicU64, Ordering};
+#[cfg(feature = "fs")]
+use std::sync::{Arc, OnceLock, RwLock};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum LoadMode {
+    Eager,
+    Lazy,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum RegistryError {
+    Io {
+        path: PathBuf,
+        message: String,
+    },
+    Parse {
+        path: PathBuf,
+        error: PromptError,
+    },
+    DuplicateName {
+        name: String,
+        paths: (PathBuf, PathBuf),
+    },
+    NotFound(String),
+    Cycle(Vec<PathBuf>),
+    InvalidReference {
+        path: PathBuf,
+        message: String,
+    },
+}
+
+impl fmt::Display for RegistryError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            RegistryError::Io { path, message } => write!(f, "{}: {}", path.display(), message),
+            RegistryError::Parse { path, error } => write!(f, "{}: {}", path.display(), error),
+            RegistryError::DuplicateName { name, paths } => write!(
+                f,
+                "prompt '{}' is defined by both {} and {}",
+                name,
+                paths.0.display(),
+                paths.1.display()
+            ),
+            RegistryError::NotFound(name) => write!(f, "prompt '{}' not found", name),
+            RegistryError::Cycle(paths) => {
+                let paths: Vec<String> = paths.iter().map(|p| p.display().to_string()).collect();
+                write!(f, "reference cycle: {}", paths.join(" -> "))
+            }
+            RegistryError::InvalidReference { path, message } => {
+                write!(f, "{}: {}", path.display(), message)
+            }
+        }
+    }
+}
+
+impl std::error::Error for RegistryError {}
+
+#[cfg(feature = "fs")]
+const LOCALIZED_CACHE_LIMIT: usize = 32;
+
+#[cfg(feature = "fs")]
+type Localized = (Arc<Prompt>, AtomicU64);
+
+#[cfg(feature = "fs")]
+#[derive(Debug)]
+struct Entry {
+    path: PathBuf,
+    format: Format,
+    source: String,
+    front: FrontMatter,
+    prompt: OnceLock<Result<Arc<Prompt>, RegistryError>>,
+    fingerprint: OnceLock<String>,
+    locales: OnceLock<BTreeSet<String>>,
+    references: OnceLock<bool>,
+    localized: RwLock<HashMap<Vec<String>, Localized>>,
+    clock: AtomicU64,
+}
+
+#[cfg(feature = "fs")]
+impl Entry {
+    fn new(path: PathBuf, format: Format, source: String, front: FrontMatter) -> Entry {
+        Entry {
+            path,
+            format,
+            source,
+            front,
+            prompt: OnceLock::new(),
+            fingerprint: OnceLock::new(),
+            locales: OnceLock::new(),
+            references: OnceLock::new(),
+            localized: RwLock::new(HashMap::new()),
+            clock: AtomicU64::new(0),
+        }
+    }
+
+    fn has_references(&self) -> bool {
+        *self
+            .references
+            .get_or_init(|| has_references(&self.source, self.format))
+    }
+
+    fn parsed(&self) -> &Result<Arc<Prompt>, RegistryError> {
+        self.prompt.get_or_init(|| {
+            load_source(
+                &self.path,
+                &self.source,
+                self.format,
+                ParseOptions::default(),
+            )
+            .map(Arc::new)
+        })
+    }
+
+    fn localized(&self, chain: &[String]) -> Result<Arc<Prompt>, RegistryError> {
+        let chain: Vec<String> = if self.has_references() {
+            chain.to_vec()
+        } else {
+            let locales = self.locales();
+            chain
+                .iter()
+                .filter(|tag| locales.contains(*tag))
+                .cloned()
+                .collect()
+        };
+        let tick = self.clock.fetch_add(1, Ordering::Relaxed);
+        let cached = self.localized.read().unwrap_or_else(|e| e.into_inner());
+        if let Some((prompt, used)) = cached.get(&chain) {
+            used.store(tick, Ordering::Relaxed);
+            return Ok(prompt.clone());
+        }
+        drop(cached);
+        let prompt = Arc::new(load_localized(
+            &self.path,
+            &self.source,
+            self.format,
+            &chain,
+        )?);
+        let mut cached = self.localized.write().unwrap_or_else(|e| e.into_inner());
+        if cached.len() >= LOCALIZED_CACHE_LIMIT {
+            let oldest = cached
+                .iter()
+                .min_by_key(|(_, (_, used))| used.load(Ordering::Relaxed))
+                .map(|(chain, _)| chain.clone());
+            if let Some(oldest) = oldest {
+                cached.remove(&oldest);
+            }
+        }
+        cached.insert(chain, (prompt.clone(), AtomicU64::new(tick)));
+        Ok(prompt)
+    }
+
+    fn locales(&self) -> &BTreeSet<String> {
+        self.locales.get_or_init(|| {
+            document(&self.source, self.format)
+                .map(|d| document_locales(&d))
+                .unwrap_or_default()
+        })
+    }
+}
+
+#[cfg(feature = "fs")]
+#[derive(Debug)]
+pub struct PromptRegistry {
+    root: PathBuf,
+    mode: LoadMode,
+    entries: BTreeMap<String, Entry>,
+    variants: BTreeMap<String, BTreeMap<String, Entry>>,
+    default_locale: String,
+}
+
+#[cfg(feature = "fs")]
+fn io_error(path: &Path, error: std::io::Error) -> RegistryError {
+    RegistryError::Io {
+        path: path.to_path_buf(),
+        message: error.to_string(),
+    }
+}
+
+#[cfg(feature = "fs")]
+pub(crate) fn collect_files(dir: &Path, files: &mut Vec<PathBuf>) -> Result<(), RegistryError> {
+    let mut children = Vec::new();
+    for entry in fs::read_dir(dir).map_err(|e| io_error(dir, e))? {
+        children.push(entry.map_err(|e| io_error(dir, e))?.path());
+    }
+    children.sort();
+    for path in children {
+        if path.is_dir() {
+            collect_files(&path, files)?;
+        } else if Format::from_path(&path).is_some() {
+            files.push(path);
+        }
+    }
+    Ok(())
+}
+
+#[cfg(feature = "fs")]
+fn path_identity(root: &Path, path: &Path) -> String {
+    let relative = path.strip_prefix(root).unwrap_or(path).with_extension("");
+    relative
+        .components()
+        .map(|c| c.as_os_str().to_string_lossy().into_owned())
+        .collect::<Vec<_>>()
+        .join("/")
+}
+
+#[cfg(feature = "fs")]
+impl PromptRegistry {
+    pub fn load(root: impl AsRef<Path>) -> Result<PromptRegistry, RegistryError> {
+        PromptRegistry::with_mode(root, LoadMode::Eager)
+    }
+
+    pub fn load_lazy(root: impl AsRef<Path>) -> Result<PromptRegistry, RegistryError> {
+        PromptRegistry::with_mode(root, LoadMode::Lazy)
+    }
+
+    pub fn with_mode(
+        root: impl AsRef<Path>,
+        mode: LoadMode,
+    ) -> Result<PromptRegistry, RegistryError> {
+        let mut registry = PromptRegistry {
+            root: root.as_ref().to_path_buf(),
+            mode,
+            entries: BTreeMap::new(),
+            variants: BTreeMap::new(),
+            default_locale: DEFAULT_LOCALE.to_string(),
+        };
+        registry.reload()?;
+        Ok(registry)
+    }
+
+    pub fn reload(&mut self) -> Result<(), RegistryError> {
+        let mut files = Vec::new();
+        collect_files(&self.root, &mut files)?;
+
+        let mut entries: BTreeMap<String, Entry> = BTreeMap::new();
+        let mut named = BTreeSet::new();
+        for path in files {
+            let source = fs::read_to_string(&path).map_err(|e| io_error(&path, e))?;
+            let format = Format::from_path(&path).unwrap_or(Format::Yaml);
+            let front = scan(&source, format).unwrap_or_default();
+            if front.is_abstract {
+                continue;
+            }
+            let name = match front.name.clone() {
+                Some(name) => {
+                    named.insert(name.clone());
+                    name
+                }
+                None => path_identity(&self.root, &path),
+            };
+            let entry = Entry::new(path, format, source, front);
+            if self.mode == LoadMode::Eager {
+                if let Err(error) = entry.parsed() {
+                    return Err(error.clone());
+                }
+            }
+            if let Some(existing) = entries.get(&name) {
+                return Err(RegistryError::DuplicateName {
+                    name,
+                    paths: (existing.path.clone(), entry.path),
+                });
+            }
+            entries.insert(name, entry);
+        }
+
+        let mut variants: BTreeMap<String, BTreeMap<String, Entry>> = BTreeMap::new();
+        let sibling_names: Vec<String> = entries
+            .keys()
+            .filter(|name| !named.contains(*name))
+            .filter(|name| match name.rsplit_once('.') {
+                Some((base, tag)) => is_locale_tag(tag) && entries.contains_key(base),
+                None => false,
+            })
+            .cloned()
+            .collect();
+        for name in sibling_names {
+            let (base, tag) = name.rsplit_once('.').unwrap_or_default();
+            if let Some(entry) = entries.remove(&name) {
+                variants
+                    .entry(base.to_string())
+                    .or_default()
+                    .insert(normalize_locale(tag), entry);
+            }
+        }
+        self.entries = entries;
+        self.variants = variants;
+        Ok(())
+    }
+
+    pub fn default_locale(&self) -> &str {
+        &self.default_locale
+    }
+
+    pub fn with_default_locale(mut self, locale: impl Into<String>) -> Self {
+        self.default_locale = locale.into();
+        self
+    }
+
+    pub fn locales(&self, name: &str) -> Vec<String> {
+        let mut locales: BTreeSet<String> = self
+            .entries
+            .get(name)
+            .map(|e| e.locales().clone())
+            .unwrap_or_default();
+        locales.extend(
+            self.variants
+                .get(name)
+                .into_iter()
+                .flat_map(|v| v.keys().cloned()),
+        );
+        locales.into_iter().collect()
+    }
+
+    pub fn get_localized(&self, name: &str, locale: &str) -> Result<Prompt, RegistryError> {
+        self.get_localized_shared(name, locale)
+            .map(|prompt| prompt.as_ref().clone())
+    }
+
+    pub fn get_localized_shared(
+        &self,
+        name: &str,
+        locale: &str,
+    ) -> Result<Arc<Prompt>, RegistryError> {
+        let chain = locale_chain(locale, &self.default_locale);
+        let base = self.entries.get(name);
+        let in_file = base.map(|e| e.locales().clone()).unwrap_or_default();
+        for (i, tag) in chain.iter().enumerate() {
+            if in_file.contains(tag) {
+                break;
+            }
+            if let Some(entry) = self.variants.get(name).and_then(|v| v.get(tag)) {
+                return entry.localized(&chain[i..]);
+            }
+        }
+        match base {
+            Some(entry) => entry.localized(&chain),
+            None => Err(RegistryError::NotFound(name.to_string())),
+        }
+    }
+
+    pub fn root(&self) -> &Path {
+        &self.root
+    }
+
+    pub fn mode(&self) -> LoadMode {
+        self.mode
+    }
+
+    fn entry(&self, name: &str) -> Result<&Entry, RegistryError> {
+        self.entries
+            .get(name)
+            .ok_or_else(|| RegistryError::NotFound(name.to_string()))
+    }
+
+    pub fn get(&self, name: &str) -> Result<&Prompt, RegistryError> {
+        self.get_arc(name).map(|prompt| prompt.as_ref())
+    }
+
+    pub fn get_shared(&self, name: &str) -> Result<Arc<Prompt>, RegistryError> {
+        self.get_arc(name).cloned()
+    }
+
+    fn get_arc(&self, name: &str) -> Result<&Arc<Prompt>, RegistryError> {
+        self.entry(name)?
+            .parsed()
+            .as_ref()
+            .map_err(|error| error.clone())
+    }
+
+    pub fn fingerprint(&self, name: &str) -> Result<String, RegistryError> {
+        let entry = self.entry(name)?;
+        let prompt = entry.parsed().as_ref().map_err(|error| error.clone())?;
+        Ok(entry
+            .fingerprint
+            .get_or_init(|| prompt.fingerprint())
+            .clone())
+    }
+
+    pub fn source(&self, name: &str) -> Option<&str> {
+        self.entries.get(name).map(|e| e.source.as_str())
+    }
+
+    pub fn path(&self, name: &str) -> Option<&Path> {
+        self.entries.get(name).map(|e| e.path.as_path())
+    }
+
+    pub fn front_matter(&self, name: &str) -> Option<&FrontMatter> {
+        self.entries.get(name).map(|e| &e.front)
+    }
+
+    pub fn list(&self) -> Vec<&str> {
+        self.entries.keys().map(|k| k.as_str()).collect()
+    }
+
+    pub fn tagged(&self, tag: &str) -> Vec<&str> {
+        self.entries
+            .iter()
+            .filter(|(_, entry)| {
+                if !entry.has_references() {
+                    return entry.front.has_tag(tag);
+                }
+                match entry.parsed() {
+                    Ok(prompt) => prompt.meta().is_some_and(|m| m.has_tag(tag)),
+                    Err(_) => false,
+                }
+            })
+            .map(|(name, _)| name.as_str())
+            .collect()
+    }
+
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+}
+
+#[cfg(all(test, feature = "fs"))]
+pub(crate) mod tests {
+    use super::*;
+
+    pub(crate) fn temp_dir(name: &str) -> PathBuf {
+        let dir = std::env::temp_dir().join(format!("prompt_def_{}_{}", name, std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(&dir).unwrap();
+        dir
+    }
+
+    pub(crate) fn write(dir: &Path, relative: &str, content: &str) {
+        let path = dir.join(relative);
+        fs::create_dir_all(path.parent().unwrap()).unwrap();
+        fs::write(path, content).unwrap();
+    }
+
+    const COMPLETION: &str = "type: completion\nvendor: google\nmodel: text-bison\nprompt: hi\n";
+
+    #[test]
+    fn test_load_and_get() {
+        let dir = temp_dir("registry_load");
+        write(&dir, "summarize.yaml", COMPLETION);
+        write(&dir, "support/greet.yml", COMPLETION);
+        write(
+            &dir,
+            "other/whatever.yaml",
+            "name: custom/chat\ntype: chat\nvendor: google\nmodel: chat-bison\n",
+        );
+        write(
+            &dir,
+            "support/farewell.json",
+            r#"{"type": "completion", "vendor": "google", "model": "text-bison", "prompt": "bye"}"#,
+        );
+        write(
+            &dir,
+            "support/thanks.toml",
+            "type = 'completion'\nvendor = 'google'\nmodel = 'text-bison'\nprompt = 'thanks'\n",
+        );
+        write(&dir, "notes.txt", "ignored");
+        write(&dir, "shared/base.yaml", "abstract: true\nvendor: google\n");
+        write(
+            &dir,
+            "support/derived.yaml",
+            "extends: ../shared/base.yaml\ntype: completion\nmodel: text-bison\nprompt: hi\n",
+        );
+        write(
+            &dir,
+            "support/tagged.yaml",
+            "type: completion\nvendor: google\nmodel: text-bison\nprompt: hi\ntags: [support]\n",
+        );
+
+        let registry = PromptRegistry::load(&dir).unwrap();
+        assert_eq!(
+            registry.list(),
+            vec![
+                "custom/chat",
+                "summarize",
+                "support/derived",
+                "support/farewell",
+                "support/greet",
+                "support/tagged",
+                "support/thanks"
+            ]
+        );
+        assert_eq!(registry.tagged("support"), vec!["support/tagged"]);
+        assert!(matches!(
+            registry.get("support/greet"),
+            Ok(Prompt::Completion(_))
+        ));
+        assert!(matches!(registry.get("custom/chat"), Ok(Prompt::Chat(_))));
+        assert_eq!(
+            registry.get("missing").unwrap_err(),
+            RegistryError::NotFound("missing".to_string())
+        );
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn test_lazy_and_reload() {
+        let dir = temp_dir("registry_lazy");
+        write(&dir, "good.yaml", COMPLETION);
+        write(&dir, "broken.yaml", "type: completion\n");
+
+        assert!(matches!(
+            PromptRegistry::load(&dir),
+            Err(RegistryError::Parse { .. })
+        ));
+
+        let mut registry = PromptRegistry::load_lazy(&dir).unwrap();
+        let front = registry.front_matter("broken").unwrap();
+        assert_eq!(front.prompt_type.as_deref(), Some("completion"));
+        assert!(registry.entries["broken"].prompt.get().is_none());
+        assert!(registry.get("good").is_ok());
+        assert!(matches!(
+            registry.get("broken"),
+            Err(RegistryError::Parse { .. })
+        ));
+
+        write(&dir, "broken.yaml", COMPLETION);
+        write(&dir, "new.yaml", COMPLETION);
+        registry.reload().unwrap();
+        assert_eq!(registry.list(), vec!["broken", "good", "new"]);
+        assert!(registry.get("broken").is_ok());
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn test_localized() {
+        let dir = temp_dir("registry_locale");
+        write(
+            &dir,
+            "greet.yaml",
+            "type: completion\nvendor: openai\nmodel: gpt\nprompt:\n  en: Hello\n  pt-BR: Olá\n",
+        );
+        write(
+            &dir,
+            "greet.de.yaml",
+            "type: completion\nvendor: openai\nmodel: gpt\nprompt: Hallo\n",
+        );
+        write(&dir, "release.old.yaml", COMPLETION);
+
+        let registry = PromptRegistry::load(&dir).unwrap();
+        assert_eq!(registry.list(), vec!["greet", "release.old"]);
+        assert_eq!(registry.locales("greet"), vec!["de", "en", "pt-br"]);
+        assert_eq!(text_of(&registry, "de-CH"), "Hallo");
+        assert_eq!(text_of(&registry, "pt_BR"), "Olá");
+        assert_eq!(text_of(&registry, "ja"), "Hello");
+        assert!(matches!(registry.get("greet"), Ok(Prompt::Completion(c)) if c.prompt == "Hello"));
+
+        let registry = registry.with_default_locale("de");
+        assert_eq!(text_of(&registry, "fr"), "Hallo");
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    fn assert_send_sync<T: Send + Sync>() {}
+
+    #[test]
+    fn test_shared_across_threads() {
+        assert_send_sync::<PromptRegistry>();
+        assert_send_sync::<Prompt>();
+        assert_send_sync::<RegistryError>();
+        #[cfg(feature = "exec")]
+        {
+            assert_send_sync::<crate::exec::VendorExecutor>();
+            assert_send_sync::<crate::exec::ExecutionResult>();
+        }
+
+        let dir = temp_dir("registry_shared");
+        write(
+            &dir,
+            "greet.yaml",
+            "type: completion\nvendor: openai\nmodel: gpt\nprompt:\n  en: Hi {{name}}\n  de: Hallo {{name}}\n",
+        );
+        let registry = Arc::new(PromptRegistry::load(&dir).unwrap());
+        let handles: Vec<_> = (0..4)
+            .map(|i| {
+                let registry = registry.clone();
+                std::thread::spawn(move || {
+                    let prompt = registry.get_shared("greet").unwrap();
+                    let Prompt::Completion(completion) = prompt.as_ref() else {
+                        panic!("expected a completion");
+                    };
+                    let vars = HashMap::from([("name".to_string(), i.to_string())]);
+                    assert_eq!(completion.render(&vars).unwrap(), format!("Hi {}", i));
+                    (prompt, registry.fingerprint("greet").unwrap())
+                })
+            })
+            .collect();
+        let results: Vec<_> = handles.into_iter().map(|h| h.join().unwrap()).collect();
+        for (prompt, fingerprint) in &results {
+            assert!(Arc::ptr_eq(prompt, &results[0].0));
+            assert_eq!(*fingerprint, prompt.fingerprint());
+        }
+        assert_eq!(results[0].0.meta().unwrap().templates().len(), 1);
+        let german = registry.get_localized_shared("greet", "de").unwrap();
+        assert!(Arc::ptr_eq(
+            &german,
+            &registry.get_localized_shared("greet", "de").unwrap()
+        ));
+        for i in 0..100 {
+            registry
+                .get_localized_shared("greet", &format!("de-x{}", i))
+                .unwrap();
+        }
+        let cached = registry.entries["greet"].localized.read().unwrap();
+        assert_eq!(cached.len(), 1);
+        drop(cached);
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn test_localized_cache_evicts_least_recent() {
+        let dir = temp_dir("registry_localized_lru");
+        let tags: Vec<String> = (0..LOCALIZED_CACHE_LIMIT as u8 + 8)
+            .map(|i| format!("{}{}", (b'a' + i / 26) as char, (b'a' + i % 26) as char))
+            .collect();
+        let texts: String = tags
+            .iter()
+            .map(|t| format!("  {}: Hi {}\n", t, t))
+            .collect();
+        write(
+            &dir,
+            "greet.yaml",
+            &format!(
+                "type: completion\nvendor: openai\nmodel: gpt\nprompt:\n  en: Hi\n  de: Hallo\n{}",
+                texts
+            ),
+        );
+        let registry = PromptRegistry::load(&dir).unwrap();
+        let german = registry.get_localized_shared("greet", "de").unwrap();
+        for tag in &tags {
+            registry.get_localized_shared("greet", tag).unwrap();
+            let again = registry.get_localized_shared("greet", "de").unwrap();
+            assert!(Arc::ptr_eq(&german, &again));
+        }
+        let cached = registry.entries["greet"].localized.read().unwrap();
+        assert_eq!(cached.len(), LOCALIZED_CACHE_LIMIT);
+        drop(cached);
+        assert_eq!(
+            text_of(&registry, &tags[tags.len() - 1]),
+            format!("Hi {}", tags[tags.len() - 1])
+        );
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    fn text_of(registry: &PromptRegistry, locale: &str) -> String {
+        match registry.get_localized("greet", locale).unwrap() {
+            Prompt::Completion(c) => c.prompt,
+            other => panic!("Expected Prompt::Completion, got {:?}", other),
+        }
+    }
+}
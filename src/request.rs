@@ -0,0 +1,645 @@
+use crate::prompt::{Chat, Completion, Embedding, NameStyle, Parameter, Prompt};
+use crate::sampling::Sampling;
+use crate::tools::{Tool, ToolChoice};
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Map, Value};
+use std::fmt;
+
+const DEFAULT_ANTHROPIC_MAX_TOKENS: i64 = 1024;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Vendor {
+    OpenAi,
+    Anthropic,
+    Google,
+}
+
+impl Vendor {
+    pub fn from_name(name: &str) -> Option<Vendor> {
+        match name.to_lowercase().as_str() {
+            "openai" => Some(Vendor::OpenAi),
+            "anthropic" => Some(Vendor::Anthropic),
+            "google" | "vertex" | "vertexai" => Some(Vendor::Google),
+            _ => None,
+        }
+    }
+
+    fn parameter_name(&self, canonical: &str) -> String {
+        let mapped = match (self, canonical) {
+            (Vendor::OpenAi | Vendor::Anthropic, "max_output_tokens") => "max_tokens",
+            (Vendor::OpenAi, "stop_sequences") => "stop",
+            (Vendor::Anthropic, "stop") => "stop_sequences",
+            (Vendor::Google, "max_tokens") => "max_output_tokens",
+            (Vendor::Google, "stop") => "stop_sequences",
+            _ => canonical,
+        };
+        match self {
+            Vendor::Google => NameStyle::CamelCase.convert(mapped),
+            Vendor::OpenAi | Vendor::Anthropic => mapped.to_string(),
+        }
+    }
+}
+
+pub fn is_gemini(model: &str) -> bool {
+    model.to_lowercase().starts_with("gemini")
+}
+
+pub fn is_azure(vendor: &str) -> bool {
+    matches!(
+        vendor.to_lowercase().as_str(),
+        "azure" | "azure_openai" | "azure-openai"
+    )
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Endpoint {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub base_url: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub api_style: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub deployment: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub api_version: Option<String>,
+}
+
+impl Endpoint {
+    pub fn is_empty(&self) -> bool {
+        self == &Endpoint::default()
+    }
+
+    pub fn api_vendor(&self, vendor: &str) -> Option<Vendor> {
+        match &self.api_style {
+            Some(style) => Vendor::from_name(style),
+            None if is_azure(vendor) => Some(Vendor::OpenAi),
+            None => Vendor::from_name(vendor),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum RequestError {
+    UnsupportedVendor(String),
+    UnsupportedPrompt,
+}
+
+impl fmt::Display for RequestError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            RequestError::UnsupportedVendor(vendor) => write!(f, "unsupported vendor '{}'", vendor),
+            RequestError::UnsupportedPrompt => write!(f, "prompt type cannot be sent to a vendor"),
+        }
+    }
+}
+
+impl std::error::Error for RequestError {}
+
+fn vendor_parameters(
+    parameters: &Option<Vec<Parameter>>,
+    sampling: &Sampling,
+    vendor: Vendor,
+) -> Map<String, Value> {
+    let mut mapped = Map::new();
+    for parameter in parameters.iter().flatten() {
+        let canonical = NameStyle::SnakeCase.convert(&parameter.name);
+        if vendor == Vendor::Google && canonical == "stream" {
+            continue;
+        }
+        let value = serde_json::to_value(&parameter.value).unwrap_or(Value::Null);
+        mapped.insert(vendor.parameter_name(&canonical), value);
+    }
+    for (name, value) in sampling.vendor_parameters(vendor) {
+        mapped.insert(vendor.parameter_name(name), value);
+    }
+    mapped
+}
+
+fn insert_tools(
+    body: &mut Map<String, Value>,
+    chat: &Chat,
+    tool: fn(&Tool) -> Value,
+    choice: fn(&ToolChoice) -> Value,
+) {
+    if let Some(tools) = chat.tools.as_ref().filter(|t| !t.is_empty()) {
+        body.insert(
+            "tools".to_string(),
+            Value::Array(tools.iter().map(tool).collect()),
+        );
+        if let Some(tool_choice) = &chat.tool_choice {
+            body.insert("tool_choice".to_string(), choice(tool_choice));
+        }
+    }
+}
+
+fn with_parameters(mut body: Map<String, Value>, parameters: Map<String, Value>) -> Value {
+    body.extend(parameters);
+    Value::Object(body)
+}
+
+impl Completion {
+    pub fn to_openai_request(&self) -> Value {
+        let mut body = Map::new();
+        body.insert("model".to_string(), json!(self.model));
+        body.insert("prompt".to_string(), json!(self.final_prompt()));
+        with_parameters(
+            body,
+            vendor_parameters(&self.parameters, &self.sampling, Vendor::OpenAi),
+        )
+    }
+
+    pub fn to_anthropic_messages_request(&self) -> Value {
+        let mut body = Map::new();
+        body.insert("model".to_string(), json!(self.model));
+        body.insert(
+            "max_tokens".to_string(),
+            json!(DEFAULT_ANTHROPIC_MAX_TOKENS),
+        );
+        body.insert(
+            "messages".to_string(),
+            json!([{ "role": "user", "content": self.final_prompt() }]),
+        );
+        with_parameters(
+            body,
+            vendor_parameters(&self.parameters, &self.sampling, Vendor::Anthropic),
+        )
+    }
+
+    pub fn to_gemini_request(&self) -> Value {
+        let mut body = Map::new();
+        body.insert(
+            "contents".to_string(),
+            json!([{ "role": "user", "parts": [{ "text": self.final_prompt() }] }]),
+        );
+        let config = vendor_parameters(&self.parameters, &self.sampling, Vendor::Google);
+        if !config.is_empty() {
+            body.insert("generationConfig".to_string(), Value::Object(config));
+        }
+        Value::Object(body)
+    }
+
+    pub fn to_vertex_request(&self) -> Value {
+        json!({
+            "instances": [{ "prompt": self.final_prompt() }],
+            "parameters": vendor_parameters(&self.parameters, &self.sampling, Vendor::Google),
+        })
+    }
+
+    pub fn to_google_request(&self) -> Value {
+        if is_gemini(&self.model) {
+            self.to_gemini_request()
+        } else {
+            self.to_vertex_request()
+        }
+    }
+
+    pub fn to_request(&self) -> Result<Value, RequestError> {
+        match self.meta.endpoint.api_vendor(&self.vendor) {
+            Some(Vendor::OpenAi) => Ok(self.to_openai_request()),
+            Some(Vendor::Anthropic) => Ok(self.to_anthropic_messages_request()),
+            Some(Vendor::Google) => Ok(self.to_google_request()),
+            None => Err(RequestError::UnsupportedVendor(self.vendor.clone())),
+        }
+    }
+}
+
+impl Chat {
+    pub fn to_openai_chat_request(&self) -> Value {
+        let messages = self.to_vendor_messages(Vendor::OpenAi).messages;
+
+        let mut body = Map::new();
+        body.insert("model".to_string(), json!(self.model));
+        body.insert("messages".to_string(), Value::Array(messages));
+        insert_tools(&mut body, self, Tool::to_openai, ToolChoice::to_openai);
+        with_parameters(
+            body,
+            vendor_parameters(&self.parameters, &self.sampling, Vendor::OpenAi),
+        )
+    }
+
+    pub fn to_anthropic_messages_request(&self) -> Value {
+        let mut body = Map::new();
+        body.insert("model".to_string(), json!(self.model));
+        body.insert(
+            "max_tokens".to_string(),
+            json!(DEFAULT_ANTHROPIC_MAX_TOKENS),
+        );
+        let converted = self.to_vendor_messages(Vendor::Anthropic);
+        if let Some(system) = converted.system {
+            body.insert("system".to_string(), system);
+        }
+        body.insert("messages".to_string(), Value::Array(converted.messages));
+        insert_tools(
+            &mut body,
+            self,
+            Tool::to_anthropic,
+            ToolChoice::to_anthropic,
+        );
+        with_parameters(
+            body,
+            vendor_parameters(&self.parameters, &self.sampling, Vendor::Anthropic),
+        )
+    }
+
+    pub fn to_gemini_request(&self) -> Value {
+        let converted = self.to_vendor_messages(Vendor::Google);
+        let mut body = Map::new();
+        body.insert("contents".to_string(), Value::Array(converted.messages));
+        if let Some(system) = converted.system {
+            body.insert("systemInstruction".to_string(), system);
+        }
+        if let Some(tools) = self.tools.as_ref().filter(|t| !t.is_empty()) {
+            let declarations: Vec<Value> = tools.iter().map(Tool::to_gemini).collect();
+            body.insert(
+                "tools".to_string(),
+                json!([{ "functionDeclarations": declarations }]),
+            );
+            if let Some(tool_choice) = &self.tool_choice {
+                body.insert("toolConfig".to_string(), tool_choice.to_gemini());
+            }
+        }
+        let config = vendor_parameters(&self.parameters, &self.sampling, Vendor::Google);
+        if !config.is_empty() {
+            body.insert("generationConfig".to_string(), Value::Object(config));
+        }
+        Value::Object(body)
+    }
+
+    pub fn to_vertex_request(&self) -> Value {
+        let examples: Vec<Value> = self
+            .examples
+            .iter()
+            .flatten()
+            .map(|e| {
+                json!({
+                    "input": { "content": e.input },
+                    "output": { "content": e.output.clone().unwrap_or_default() },
+                })
+            })
+            .collect();
+        let mut messages = Vec::new();
+        for message in self.messages.iter().flatten() {
+            let mut content = message.input.clone();
+            for text in message.content.iter().flatten().filter_map(|p| p.text()) {
+                if !content.is_empty() {
+                    content.push('\n');
+                }
+                content.push_str(text);
+            }
+            messages.push(json!({ "author": "user", "content": content }));
+            if let Some(output) = &message.output {
+                messages.push(json!({ "author": "bot", "content": output }));
+            }
+        }
+
+        let mut instance = Map::new();
+        if let Some(context) = &self.context {
+            instance.insert("context".to_string(), json!(context));
+        }
+        instance.insert("examples".to_string(), Value::Array(examples));
+        instance.insert("messages".to_string(), Value::Array(messages));
+        json!({
+            "instances": [instance],
+            "parameters": vendor_parameters(&self.parameters, &self.sampling, Vendor::Google),
+        })
+    }
+
+    pub fn to_google_request(&self) -> Value {
+        if is_gemini(&self.model) {
+            self.to_gemini_request()
+        } else {
+            self.to_vertex_request()
+        }
+    }
+
+    pub fn to_request(&self) -> Result<Value, RequestError> {
+        match self.meta.endpoint.api_vendor(&self.vendor) {
+            Some(Vendor::OpenAi) => Ok(self.to_openai_chat_request()),
+            Some(Vendor::Anthropic) => Ok(self.to_anthropic_messages_request()),
+            Some(Vendor::Google) => Ok(self.to_google_request()),
+            None => Err(RequestError::UnsupportedVendor(self.vendor.clone())),
+        }
+    }
+}
+
+impl Embedding {
+    pub fn to_openai_embedding_request(&self) -> Value {
+        let mut body = Map::new();
+        body.insert("model".to_string(), json!(self.model));
+        body.insert("input".to_string(), json!(self.input));
+        if let Some(dimensions) = self.dimensions {
+            body.insert("dimensions".to_string(), json!(dimensions));
+        }
+        if let Some(format) = self.encoding_format {
+            body.insert("encoding_format".to_string(), json!(format));
+        }
+        Value::Object(body)
+    }
+
+    pub fn to_vertex_embedding_request(&self) -> Value {
+        let instances: Vec<Value> = self.input.iter().map(|i| json!({ "content": i })).collect();
+        let mut parameters = Map::new();
+        if let Some(dimensions) = self.dimensions {
+            parameters.insert("outputDimensionality".to_string(), json!(dimensions));
+        }
+        json!({ "instances": instances, "parameters": parameters })
+    }
+
+    pub fn to_request(&self) -> Result<Value, RequestError> {
+        match self.meta.endpoint.api_vendor(&self.vendor) {
+            Some(Vendor::OpenAi) => Ok(self.to_openai_embedding_request()),
+            Some(Vendor::Google) => Ok(self.to_vertex_embedding_request()),
+            Some(Vendor::Anthropic) | None => {
+                Err(RequestError::UnsupportedVendor(self.vendor.clone()))
+            }
+        }
+    }
+}
+
+impl Prompt {
+    pub fn to_request(&self) -> Result<Value, RequestError> {
+        match self {
+            Prompt::Completion(completion) => completion.to_request(),
+            Prompt::Chat(chat) => chat.to_request(),
+            Prompt::Embedding(embedding) => embedding.to_request(),
+            Prompt::Custom(_) | Prompt::Unknown => Err(RequestError::UnsupportedPrompt),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::prompt::deserialize_prompt;
+
+    const CHAT: &str = r#"
+        type: chat
+        vendor: openai
+        model: gpt-4o
+        context: You are terse
+        parameters:
+            - name: maxOutputTokens
+              value: 256
+            - name: temperature
+              value: 0.4
+        examples:
+            - input: who are u?
+              output: a bot
+        messages:
+            - input: what's your name?
+    "#;
+
+    fn chat() -> Chat {
+        match deserialize_prompt(CHAT) {
+            Prompt::Chat(chat) => chat,
+            other => panic!("Expected Prompt::Chat, got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn test_openai_chat_request() {
+        assert_eq!(
+            chat().to_openai_chat_request(),
+            json!({
+                "model": "gpt-4o",
+                "max_tokens": 256,
+                "temperature": 0.4,
+                "messages": [
+                    { "role": "system", "content": "You are terse" },
+                    { "role": "user", "content": "who are u?" },
+                    { "role": "assistant", "content": "a bot" },
+                    { "role": "user", "content": "what's your name?" },
+                ],
+            })
+        );
+        assert_eq!(
+            deserialize_prompt(CHAT).to_request().unwrap(),
+            chat().to_openai_chat_request()
+        );
+    }
+
+    #[test]
+    fn test_anthropic_and_vertex_chat_request() {
+        let anthropic = chat().to_anthropic_messages_request();
+        assert_eq!(anthropic["system"], "You are terse");
+        assert_eq!(anthropic["max_tokens"], 256);
+        assert_eq!(anthropic["messages"].as_array().unwrap().len(), 3);
+
+        let vertex = chat().to_vertex_request();
+        assert_eq!(vertex["parameters"]["maxOutputTokens"], 256);
+        assert_eq!(vertex["instances"][0]["context"], "You are terse");
+        assert_eq!(
+            vertex["instances"][0]["examples"][0]["output"]["content"],
+            "a bot"
+        );
+        assert_eq!(
+            vertex["instances"][0]["messages"],
+            json!([{ "author": "user", "content": "what's your name?" }])
+        );
+    }
+
+    #[test]
+    fn test_completion_request() {
+        let yaml = r#"
+            type: completion
+            vendor: google
+            model: text-bison
+            prompt: Write a hello world in java
+            parameters:
+                - name: max_output_tokens
+                  value: 128
+                - name: topK
+                  value: 40
+        "#;
+
+        let prompt = deserialize_prompt(yaml);
+        assert_eq!(
+            prompt.to_request().unwrap(),
+            json!({
+                "instances": [{ "prompt": "Write a hello world in java" }],
+                "parameters": { "maxOutputTokens": 128, "topK": 40 },
+            })
+        );
+        if let Prompt::Completion(completion) = prompt {
+            let openai = completion.to_openai_request();
+            assert_eq!(openai["max_tokens"], 128);
+            assert_eq!(openai["top_k"], 40);
+            assert_eq!(openai["prompt"], "Write a hello world in java");
+        }
+
+        let gemini = deserialize_prompt(&yaml.replace("text-bison", "gemini-1.5-pro"));
+        assert_eq!(
+            gemini.to_request().unwrap(),
+            json!({
+                "contents": [{ "role": "user", "parts": [{ "text": "Write a hello world in java" }] }],
+                "generationConfig": { "maxOutputTokens": 128, "topK": 40 },
+            })
+        );
+        let mut chat = chat();
+        chat.vendor = "google".to_string();
+        chat.model = "gemini-2.0-flash".to_string();
+        assert_eq!(chat.to_request().unwrap(), chat.to_gemini_request());
+    }
+
+    #[test]
+    fn test_tool_requests() {
+        let yaml = r#"
+            type: chat
+            vendor: openai
+            model: gpt-4o
+            tools:
+                - name: get_weather
+                  description: Current weather for a city
+                  parameters:
+                      type: object
+                      properties:
+                          city: { type: string }
+                      required: [city]
+            tool_choice: required
+            messages:
+                - input: weather in Paris?
+                  tool_calls:
+                      - id: call_1
+                        name: get_weather
+                        arguments: { city: Paris }
+                  tool_results:
+                      - id: call_1
+                        content: "18C, sunny"
+                  output: It's 18C and sunny.
+        "#;
+
+        let chat = match deserialize_prompt(yaml) {
+            Prompt::Chat(chat) => chat,
+            other => panic!("Expected Prompt::Chat, got {:?}", other),
+        };
+        let openai = chat.to_openai_chat_request();
+        assert_eq!(openai["tools"][0]["function"]["name"], "get_weather");
+        assert_eq!(openai["tool_choice"], "required");
+        assert_eq!(
+            openai["messages"][1],
+            json!({
+                "role": "assistant",
+                "content": null,
+                "tool_calls": [{
+                    "id": "call_1",
+                    "type": "function",
+                    "function": { "name": "get_weather", "arguments": "{\"city\":\"Paris\"}" },
+                }],
+            })
+        );
+        assert_eq!(
+            openai["messages"][2],
+            json!({ "role": "tool", "tool_call_id": "call_1", "content": "18C, sunny" })
+        );
+
+        let anthropic = chat.to_anthropic_messages_request();
+        assert_eq!(
+            anthropic["tools"][0]["input_schema"]["required"],
+            json!(["city"])
+        );
+        assert_eq!(anthropic["tool_choice"], json!({ "type": "any" }));
+        assert_eq!(
+            anthropic["messages"][1]["content"][0],
+            json!({ "type": "tool_use", "id": "call_1", "name": "get_weather", "input": { "city": "Paris" } })
+        );
+        assert_eq!(
+            anthropic["messages"][2],
+            json!({
+                "role": "user",
+                "content": [{ "type": "tool_result", "tool_use_id": "call_1", "content": "18C, sunny" }],
+            })
+        );
+        assert_eq!(anthropic["messages"][3]["content"], "It's 18C and sunny.");
+    }
+
+    #[test]
+    fn test_sampling_mapping() {
+        let yaml = r#"
+            type: chat
+            vendor: openai
+            model: gpt-4o
+            stop: [END]
+            top_p: 0.5
+            top_k: 20
+            seed: 42
+            logit_bias: { "1234": 10 }
+            parameters:
+                - name: top_p
+                  value: 0.1
+            messages:
+                - input: hi
+        "#;
+        let Prompt::Chat(chat) = deserialize_prompt(yaml) else {
+            panic!("expected a chat prompt");
+        };
+        let openai = chat.to_openai_chat_request();
+        assert_eq!(openai["stop"], json!(["END"]));
+        assert_eq!(openai["top_p"], json!(0.5));
+        assert_eq!(openai["seed"], json!(42));
+        assert_eq!(openai["logit_bias"], json!({ "1234": 10.0 }));
+        assert!(openai.get("top_k").is_none());
+
+        let anthropic = chat.to_anthropic_messages_request();
+        assert_eq!(anthropic["stop_sequences"], json!(["END"]));
+        assert_eq!(anthropic["top_k"], json!(20));
+        assert!(anthropic.get("seed").is_none());
+        assert!(anthropic.get("logit_bias").is_none());
+
+        let gemini = chat.to_gemini_request();
+        assert_eq!(
+            gemini["generationConfig"],
+            json!({ "topP": 0.5, "stopSequences": ["END"], "topK": 20, "seed": 42 })
+        );
+    }
+
+    #[test]
+    fn test_embedding_requests() {
+        let yaml = r#"
+            type: embedding
+            vendor: openai
+            model: text-embedding-3-small
+            input: ['first {{doc}}', second]
+            dimensions: 256
+            encoding_format: base64
+        "#;
+        let prompt = deserialize_prompt(yaml);
+        assert_eq!(
+            prompt.to_request().unwrap(),
+            json!({
+                "model": "text-embedding-3-small",
+                "input": ["first {{doc}}", "second"],
+                "dimensions": 256,
+                "encoding_format": "base64",
+            })
+        );
+
+        let Prompt::Embedding(embedding) = prompt else {
+            panic!("expected an embedding prompt");
+        };
+        assert_eq!(
+            embedding.to_vertex_embedding_request(),
+            json!({
+                "instances": [{ "content": "first {{doc}}" }, { "content": "second" }],
+                "parameters": { "outputDimensionality": 256 },
+            })
+        );
+
+        let single = "type: embedding\nvendor: anthropic\nmodel: m\ninput: just one\n";
+        let Prompt::Embedding(single) = deserialize_prompt(single) else {
+            panic!("expected an embedding prompt");
+        };
+        assert_eq!(single.input, vec!["just one".to_string()]);
+        assert_eq!(
+            single.to_request(),
+            Err(RequestError::UnsupportedVendor("anthropic".to_string()))
+        );
+    }
+
+    #[test]
+    fn test_unsupported_vendor() {
+        let yaml = "type: completion\nvendor: acme\nmodel: m\nprompt: hi\n";
+        assert_eq!(
+            deserialize_prompt(yaml).to_request(),
+            Err(RequestError::UnsupportedVendor("acme".to_string()))
+        );
+    }
+}
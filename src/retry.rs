@@ -0,0 +1,310 @@
+use crate::exec::{BoxFuture, ExecError, ExecutionResult, PromptExecutor};
+use crate::policy::ExecutionPolicy;
+use crate::prompt::Prompt;
+use crate::select::split_mix;
+use crate::stream::{StreamingExecutor, TokenCallback};
+use std::collections::{HashMap, VecDeque};
+use std::future::Future;
+use std::pin::Pin;
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+use std::sync::Mutex;
+use std::task::{Context, Poll};
+use std::thread;
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
+
+const DEFAULT_BASE_DELAY: Duration = Duration::from_millis(500);
+const RATE_WINDOW: Duration = Duration::from_secs(60);
+
+pub(crate) struct Sleep {
+    deadline: Instant,
+    waiting: bool,
+}
+
+pub(crate) fn sleep(duration: Duration) -> Sleep {
+    Sleep {
+        deadline: Instant::now() + duration,
+        waiting: false,
+    }
+}
+
+impl Future for Sleep {
+    type Output = ();
+
+    fn poll(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<()> {
+        let now = Instant::now();
+        if now >= self.deadline {
+            return Poll::Ready(());
+        }
+        if !self.waiting {
+            self.waiting = true;
+            let waker = cx.waker().clone();
+            let remaining = self.deadline - now;
+            thread::spawn(move || {
+                thread::sleep(remaining);
+                waker.wake();
+            });
+        }
+        Poll::Pending
+    }
+}
+
+struct Timeout<'a, T> {
+    future: BoxFuture<'a, T>,
+    sleep: Option<Sleep>,
+}
+
+impl<T> Future for Timeout<'_, T> {
+    type Output = Option<T>;
+
+    fn poll(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<T>> {
+        if let Poll::Ready(output) = self.future.as_mut().poll(cx) {
+            return Poll::Ready(Some(output));
+        }
+        match self.sleep.as_mut().map(|s| Pin::new(s).poll(cx)) {
+            Some(Poll::Ready(())) => Poll::Ready(None),
+            _ => Poll::Pending,
+        }
+    }
+}
+
+async fn with_timeout<'a>(
+    future: BoxFuture<'a, Result<ExecutionResult, ExecError>>,
+    timeout: Option<Duration>,
+) -> Result<ExecutionResult, ExecError> {
+    let timeout_future = Timeout {
+        future,
+        sleep: timeout.map(sleep),
+    };
+    timeout_future
+        .await
+        .unwrap_or_else(|| Err(ExecError::Timeout(timeout.unwrap_or_default())))
+}
+
+fn is_retryable(error: &ExecError) -> bool {
+    match error {
+        ExecError::Status { status, .. } => *status == 429 || (500..600).contains(status),
+        ExecError::Timeout(_) => true,
+        _ => false,
+    }
+}
+
+fn jitter(delay: Duration) -> Duration {
+    static COUNTER: AtomicU64 = AtomicU64::new(0);
+    let nanos = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map_or(0, |d| d.subsec_nanos() as u64);
+    let mut state = nanos ^ COUNTER.fetch_add(1, Ordering::Relaxed);
+    let fraction = (split_mix(&mut state) >> 11) as f64 / (1u64 << 53) as f64;
+    delay.mul_f64(0.5 + fraction / 2.0)
+}
+
+pub struct RetryExecutor<E> {
+    inner: E,
+    policy: ExecutionPolicy,
+    base_delay: Duration,
+    requests: Mutex<VecDeque<Instant>>,
+}
+
+impl<E> RetryExecutor<E> {
+    pub fn new(inner: E) -> RetryExecutor<E> {
+        RetryExecutor {
+            inner,
+            policy: ExecutionPolicy::default(),
+            base_delay: DEFAULT_BASE_DELAY,
+            requests: Mutex::new(VecDeque::new()),
+        }
+    }
+
+    pub fn policy(mut self, policy: ExecutionPolicy) -> Self {
+        self.policy = policy;
+        self
+    }
+
+    pub fn base_delay(mut self, base_delay: Duration) -> Self {
+        self.base_delay = base_delay;
+        self
+    }
+
+    fn policy_for(&self, prompt: &Prompt) -> ExecutionPolicy {
+        match prompt.policy() {
+            Some(policy) => policy.or(&self.policy),
+            None => self.policy.clone(),
+        }
+    }
+
+    fn reserve(&self, rpm: u32) -> Option<Duration> {
+        let mut requests = self.requests.lock().unwrap_or_else(|e| e.into_inner());
+        let now = Instant::now();
+        while requests
+            .front()
+            .is_some_and(|t| now.duration_since(*t) >= RATE_WINDOW)
+        {
+            requests.pop_front();
+        }
+        if requests.len() < rpm as usize {
+            requests.push_back(now);
+            return None;
+        }
+        requests
+            .front()
+            .map(|t| RATE_WINDOW - now.duration_since(*t))
+    }
+
+    async fn throttle(&self, policy: &ExecutionPolicy) {
+        if let Some(rpm) = policy.rate_limit_rpm.filter(|rpm| *rpm > 0) {
+            while let Some(wait) = self.reserve(rpm) {
+                sleep(wait).await;
+            }
+        }
+    }
+
+    fn retry_delay(
+        &self,
+        policy: &ExecutionPolicy,
+        attempt: u32,
+        error: &ExecError,
+    ) -> Option<Duration> {
+        if attempt < policy.max_retries.unwrap_or(0) && is_retryable(error) {
+            Some(jitter(policy.delay(self.base_delay, attempt)))
+        } else {
+            None
+        }
+    }
+}
+
+impl<E: PromptExecutor> PromptExecutor for RetryExecutor<E> {
+    fn execute<'a>(
+        &'a self,
+        prompt: &'a Prompt,
+        vars: &'a HashMap<String, String>,
+    ) -> BoxFuture<'a, Result<ExecutionResult, ExecError>> {
+        Box::pin(async move {
+            let policy = self.policy_for(prompt);
+            let mut attempt = 0;
+            loop {
+                self.throttle(&policy).await;
+                let result = with_timeout(self.inner.execute(prompt, vars), policy.timeout()).await;
+                match result {
+                    Err(error) => match self.retry_delay(&policy, attempt, &error) {
+                        Some(delay) => sleep(delay).await,
+                        None => return Err(error),
+                    },
+                    ok => return ok,
+                }
+                attempt += 1;
+            }
+        })
+    }
+}
+
+impl<E: StreamingExecutor> StreamingExecutor for RetryExecutor<E> {
+    fn execute_streaming<'a>(
+        &'a self,
+        prompt: &'a Prompt,
+        vars: &'a HashMap<String, String>,
+        on_token: TokenCallback<'a>,
+    ) -> BoxFuture<'a, Result<ExecutionResult, ExecError>> {
+        Box::pin(async move {
+            let policy = self.policy_for(prompt);
+            let emitted = AtomicBool::new(false);
+            let mut forward = |token: &str| {
+                emitted.store(true, Ordering::Relaxed);
+                on_token(token);
+            };
+            let mut attempt = 0;
+            loop {
+                self.throttle(&policy).await;
+                let future = self.inner.execute_streaming(prompt, vars, &mut forward);
+                let result = with_timeout(future, policy.timeout()).await;
+                match result {
+                    Err(error) if !emitted.load(Ordering::Relaxed) => {
+                        match self.retry_delay(&policy, attempt, &error) {
+                            Some(delay) => sleep(delay).await,
+                            None => return Err(error),
+                        }
+                    }
+                    other => return other,
+                }
+                attempt += 1;
+            }
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::exec::tests::MockClient;
+    use crate::exec::{block_on, OpenAiExecutor};
+    use crate::prompt::deserialize_prompt;
+    use serde_json::json;
+
+    fn prompt(policy: &str) -> Prompt {
+        deserialize_prompt(&format!(
+            "type: completion\nvendor: openai\nmodel: gpt\nprompt: hi\npolicy: {}\n",
+            policy
+        ))
+    }
+
+    #[test]
+    fn test_retries_transient_errors() {
+        let ok = json!({ "choices": [{ "text": "done" }] });
+        let client = MockClient::replying(&[
+            (429, json!({ "error": "slow down" })),
+            (503, json!({ "error": "unavailable" })),
+            (200, ok.clone()),
+        ]);
+        let executor = RetryExecutor::new(OpenAiExecutor::new(client.clone(), "key"))
+            .base_delay(Duration::from_millis(1));
+        let none = HashMap::new();
+
+        let result = block_on(prompt("{ max_retries: 3 }").execute(&executor, &none));
+        assert_eq!(result.unwrap().text, "done");
+        assert_eq!(client.requests.lock().unwrap().len(), 3);
+
+        let client = MockClient::replying(&[(500, json!({})), (200, ok)]);
+        let executor = RetryExecutor::new(OpenAiExecutor::new(client.clone(), "key"));
+        assert!(matches!(
+            block_on(prompt("{ backoff: constant }").execute(&executor, &none)),
+            Err(ExecError::Status { status: 500, .. })
+        ));
+
+        let client = MockClient::replying(&[(400, json!({}))]);
+        let executor = RetryExecutor::new(OpenAiExecutor::new(client.clone(), "key")).policy(
+            ExecutionPolicy {
+                max_retries: Some(5),
+                ..ExecutionPolicy::default()
+            },
+        );
+        assert!(block_on(prompt("{}").execute(&executor, &none)).is_err());
+        assert_eq!(client.requests.lock().unwrap().len(), 1);
+    }
+
+    struct Stalled;
+
+    impl PromptExecutor for Stalled {
+        fn execute<'a>(
+            &'a self,
+            _: &'a Prompt,
+            _: &'a HashMap<String, String>,
+        ) -> BoxFuture<'a, Result<ExecutionResult, ExecError>> {
+            Box::pin(std::future::pending())
+        }
+    }
+
+    #[test]
+    fn test_timeout_and_rate_limit() {
+        let none = HashMap::new();
+        let executor = RetryExecutor::new(Stalled).base_delay(Duration::from_millis(1));
+        assert_eq!(
+            block_on(prompt("{ timeout_ms: 20, max_retries: 1 }").execute(&executor, &none)),
+            Err(ExecError::Timeout(Duration::from_millis(20)))
+        );
+
+        let executor = RetryExecutor::new(Stalled);
+        assert_eq!(executor.reserve(2), None);
+        assert_eq!(executor.reserve(2), None);
+        assert!(executor.reserve(2).unwrap() > Duration::from_secs(59));
+        assert!(jitter(Duration::from_millis(100)) >= Duration::from_millis(50));
+    }
+}
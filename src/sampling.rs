@@ -0,0 +1,257 @@
+use crate::parameters::ParameterError;
+use crate::prompt::{Chat, Completion, NameStyle, Parameter};
+use crate::request::Vendor;
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+use std::collections::BTreeMap;
+
+const OPENAI_MAX_STOP: usize = 4;
+
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct Sampling {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub stop: Option<Vec<String>>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub top_p: Option<f64>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub top_k: Option<u32>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub frequency_penalty: Option<f64>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub presence_penalty: Option<f64>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub logit_bias: Option<BTreeMap<String, f64>>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub seed: Option<i64>,
+}
+
+pub fn supports(vendor: Vendor, field: &str) -> bool {
+    match vendor {
+        Vendor::OpenAi => field != "top_k",
+        Vendor::Anthropic => matches!(field, "stop" | "top_p" | "top_k"),
+        Vendor::Google => field != "logit_bias",
+    }
+}
+
+fn out_of_range(name: &str, value: f64, min: Option<f64>, max: Option<f64>) -> ParameterError {
+    ParameterError::OutOfRange {
+        name: name.to_string(),
+        value,
+        min,
+        max,
+    }
+}
+
+fn check_range(
+    errors: &mut Vec<ParameterError>,
+    name: &str,
+    value: Option<f64>,
+    min: f64,
+    max: Option<f64>,
+) {
+    if let Some(value) = value {
+        if value < min || max.is_some_and(|max| value > max) {
+            errors.push(out_of_range(name, value, Some(min), max));
+        }
+    }
+}
+
+impl Sampling {
+    pub fn is_empty(&self) -> bool {
+        self.fields().is_empty()
+    }
+
+    pub fn fields(&self) -> Vec<(&'static str, Value)> {
+        let fields = [
+            ("stop", self.stop.as_ref().map(|v| json!(v))),
+            ("top_p", self.top_p.map(|v| json!(v))),
+            ("top_k", self.top_k.map(|v| json!(v))),
+            (
+                "frequency_penalty",
+                self.frequency_penalty.map(|v| json!(v)),
+            ),
+            ("presence_penalty", self.presence_penalty.map(|v| json!(v))),
+            ("logit_bias", self.logit_bias.as_ref().map(|v| json!(v))),
+            ("seed", self.seed.map(|v| json!(v))),
+        ];
+        fields
+            .into_iter()
+            .filter_map(|(name, value)| Some((name, value?)))
+            .collect()
+    }
+
+    pub fn vendor_parameters(&self, vendor: Vendor) -> Vec<(&'static str, Value)> {
+        self.fields()
+            .into_iter()
+            .filter(|(name, _)| supports(vendor, name))
+            .collect()
+    }
+
+    pub fn validate(&self, vendor: Option<Vendor>) -> Vec<ParameterError> {
+        let mut errors = Vec::new();
+        check_range(&mut errors, "top_p", self.top_p, 0.0, Some(1.0));
+        check_range(&mut errors, "top_k", self.top_k.map(f64::from), 1.0, None);
+        check_range(
+            &mut errors,
+            "frequency_penalty",
+            self.frequency_penalty,
+            -2.0,
+            Some(2.0),
+        );
+        check_range(
+            &mut errors,
+            "presence_penalty",
+            self.presence_penalty,
+            -2.0,
+            Some(2.0),
+        );
+        for (token, bias) in self.logit_bias.iter().flatten() {
+            if token.parse::<u32>().is_err() {
+                errors.push(ParameterError::Invalid {
+                    name: "logit_bias".to_string(),
+                    message: format!("'{}' is not a token id", token),
+                });
+            }
+            check_range(&mut errors, "logit_bias", Some(*bias), -100.0, Some(100.0));
+        }
+        let stop = self.stop.as_deref().unwrap_or_default();
+        if stop.iter().any(|s| s.is_empty()) {
+            errors.push(ParameterError::Invalid {
+                name: "stop".to_string(),
+                message: "stop sequences must not be empty".to_string(),
+            });
+        }
+        if vendor == Some(Vendor::OpenAi) && stop.len() > OPENAI_MAX_STOP {
+            errors.push(ParameterError::Invalid {
+                name: "stop".to_string(),
+                message: format!("openai accepts at most {} stop sequences", OPENAI_MAX_STOP),
+            });
+        }
+        errors
+    }
+
+    pub fn unsupported(&self, vendor: Vendor) -> Vec<&'static str> {
+        self.fields()
+            .into_iter()
+            .map(|(name, _)| name)
+            .filter(|name| !supports(vendor, name))
+            .collect()
+    }
+
+    pub fn conflicts(&self, parameters: &Option<Vec<Parameter>>) -> Vec<&'static str> {
+        let aliases = |name: &str| match name {
+            "stop_sequences" => "stop".to_string(),
+            other => other.to_string(),
+        };
+        let generic: Vec<String> = parameters
+            .iter()
+            .flatten()
+            .map(|p| aliases(&NameStyle::SnakeCase.convert(&p.name)))
+            .collect();
+        self.fields()
+            .into_iter()
+            .map(|(name, _)| name)
+            .filter(|name| generic.iter().any(|g| g == name))
+            .collect()
+    }
+}
+
+impl Completion {
+    pub fn sampling(&self) -> &Sampling {
+        &self.sampling
+    }
+}
+
+impl Chat {
+    pub fn sampling(&self) -> &Sampling {
+        &self.sampling
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::prompt::{deserialize_prompt, Prompt};
+
+    #[test]
+    fn test_sampling_fields() {
+        let yaml = r#"
+            type: chat
+            vendor: anthropic
+            model: claude-3-haiku
+            stop: ["\n\n", "END"]
+            top_p: 0.9
+            top_k: 40
+            seed: 7
+            logit_bias: { "50256": -100 }
+            messages:
+                - input: hi
+        "#;
+        let Prompt::Chat(chat) = deserialize_prompt(yaml) else {
+            panic!("expected a chat prompt");
+        };
+        assert_eq!(chat.sampling().top_k, Some(40));
+        assert_eq!(
+            chat.sampling().logit_bias,
+            Some(BTreeMap::from([("50256".to_string(), -100.0)]))
+        );
+        assert_eq!(
+            chat.sampling().unsupported(Vendor::Anthropic),
+            vec!["logit_bias", "seed"]
+        );
+        assert!(chat.sampling().validate(Some(Vendor::Anthropic)).is_empty());
+
+        let names: Vec<&str> = chat
+            .sampling()
+            .vendor_parameters(Vendor::Anthropic)
+            .into_iter()
+            .map(|(name, _)| name)
+            .collect();
+        assert_eq!(names, vec!["stop", "top_p", "top_k"]);
+        assert!(serde_yaml::to_string(&chat).unwrap().contains("top_k: 40"));
+    }
+
+    #[test]
+    fn test_sampling_validation() {
+        let sampling = Sampling {
+            stop: Some(
+                vec!["a", "b", "c", "d", ""]
+                    .into_iter()
+                    .map(String::from)
+                    .collect(),
+            ),
+            top_p: Some(1.5),
+            top_k: Some(0),
+            presence_penalty: Some(-3.0),
+            logit_bias: Some(BTreeMap::from([("abc".to_string(), 5.0)])),
+            ..Sampling::default()
+        };
+        let errors = sampling.validate(Some(Vendor::OpenAi));
+        let names: Vec<String> = errors
+            .iter()
+            .map(|e| match e {
+                ParameterError::OutOfRange { name, .. } | ParameterError::Invalid { name, .. } => {
+                    name.clone()
+                }
+                other => panic!("unexpected {:?}", other),
+            })
+            .collect();
+        assert_eq!(
+            names,
+            vec![
+                "top_p",
+                "top_k",
+                "presence_penalty",
+                "logit_bias",
+                "stop",
+                "stop"
+            ]
+        );
+
+        let parameters = Some(vec![Parameter {
+            name: "stopSequences".to_string(),
+            value: serde_yaml::Value::from(vec!["x"]),
+        }]);
+        assert_eq!(sampling.conflicts(&parameters), vec!["stop"]);
+    }
+}
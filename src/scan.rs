@@ -0,0 +1,205 @@
+use crate::format::{document, Format};
+use crate::markdown::split;
+use serde_json::Value;
+use std::collections::BTreeSet;
+
+const SCANNED_KEYS: &[&str] = &["type", "name", "vendor", "model", "tags", "abstract"];
+
+pub(crate) fn is_abstract(document: &Value) -> bool {
+    document.get("abstract").and_then(|a| a.as_bool()) == Some(true)
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct FrontMatter {
+    pub prompt_type: Option<String>,
+    pub name: Option<String>,
+    pub vendor: Option<String>,
+    pub model: Option<String>,
+    pub tags: Vec<String>,
+    pub is_abstract: bool,
+}
+
+impl FrontMatter {
+    pub fn from_document(document: &Value) -> FrontMatter {
+        let text = |key: &str| document.get(key)?.as_str().map(|s| s.to_string());
+        FrontMatter {
+            prompt_type: text("type"),
+            name: text("name"),
+            vendor: text("vendor"),
+            model: text("model"),
+            tags: tags(document.get("tags")),
+            is_abstract: is_abstract(document),
+        }
+    }
+
+    pub fn has_tag(&self, tag: &str) -> bool {
+        self.tags.iter().any(|t| t == tag)
+    }
+}
+
+fn tags(value: Option<&Value>) -> Vec<String> {
+    value
+        .and_then(|t| t.as_array())
+        .into_iter()
+        .flatten()
+        .filter_map(|t| t.as_str().map(|s| s.to_string()))
+        .collect()
+}
+
+fn scalar(text: &str) -> Option<Value> {
+    let text = text.trim();
+    if text.starts_with(['|', '>', '&', '*', '!']) {
+        return None;
+    }
+    let value: serde_yaml::Value = serde_yaml::from_str(text).ok()?;
+    serde_json::to_value(value).ok()
+}
+
+fn key_of(line: &str) -> Option<(&str, &str)> {
+    let (key, rest) = match line.strip_prefix(['"', '\'']) {
+        Some(quoted) => {
+            let end = quoted.find(['"', '\''])?;
+            let rest = quoted[end + 1..].trim_start().strip_prefix(':')?;
+            (&quoted[..end], rest)
+        }
+        None => {
+            let end = line
+                .find(": ")
+                .or_else(|| line.strip_suffix(':').map(|k| k.len()))?;
+            (line[..end].trim_end(), &line[end + 1..])
+        }
+    };
+    Some((key, rest))
+}
+
+pub fn scan_yaml(source: &str) -> Option<FrontMatter> {
+    let source = source.trim_start_matches('\u{feff}');
+    let mut fields = serde_json::Map::new();
+    let mut started = false;
+    let mut block: Option<&str> = None;
+    let mut after_scalar = false;
+    let mut keys = BTreeSet::new();
+    for line in source.lines() {
+        let trimmed = line.trim_end();
+        if trimmed.is_empty() || trimmed.trim_start().starts_with('#') {
+            continue;
+        }
+        if matches!(trimmed, "---" | "...") {
+            if started {
+                return None;
+            }
+            started = true;
+            continue;
+        }
+        started = true;
+        if line.starts_with([' ', '\t', '-']) {
+            if after_scalar {
+                return None;
+            }
+            match (block, trimmed.trim_start().strip_prefix("- ")) {
+                (None, _) => {}
+                (Some("tags"), Some(item)) => {
+                    fields.get_mut("tags")?.as_array_mut()?.push(scalar(item)?);
+                }
+                _ => return None,
+            }
+            continue;
+        }
+        if trimmed.starts_with(['{', '[', '?', '&', '*', '!', '%']) {
+            return None;
+        }
+        let (key, rest) = key_of(trimmed)?;
+        block = None;
+        after_scalar = false;
+        if key == "<<" || !keys.insert(key) {
+            return None;
+        }
+        if !SCANNED_KEYS.contains(&key) {
+            continue;
+        }
+        let rest = rest.split(" #").next().unwrap_or("");
+        if rest.trim().is_empty() {
+            block = Some(key);
+            let empty = if key == "tags" {
+                Value::Array(Vec::new())
+            } else {
+                Value::Null
+            };
+            fields.insert(key.to_string(), empty);
+            continue;
+        }
+        fields.insert(key.to_string(), scalar(rest)?);
+        after_scalar = true;
+    }
+    Some(FrontMatter::from_document(&Value::Object(fields)))
+}
+
+pub fn scan(source: &str, format: Format) -> Option<FrontMatter> {
+    let scanned = match format {
+        Format::Yaml => scan_yaml(source),
+        Format::Markdown => split(source)
+            .ok()
+            .and_then(|(frontmatter, _)| scan_yaml(frontmatter))
+            .filter(|front| front.prompt_type.is_some()),
+        Format::Json | Format::Toml => None,
+    };
+    scanned.or_else(|| document(source, format).map(|d| FrontMatter::from_document(&d)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const YAML: &str = r#"# support prompt
+name: "support"
+type: chat  # inline comment
+vendor: openai
+model: gpt-4o
+tags:
+  - support
+  - 'tier-1'
+context: |
+  name: not a key
+  tags: [nope]
+messages:
+  - input: hi
+    tags: [inner]
+"#;
+
+    #[test]
+    fn test_scan_matches_full_parse() {
+        let sources = [
+            YAML,
+            "---\ntype: completion\nabstract: true\ntags: [a, b]\nprompt: >\n  x\n",
+            "type: embedding\nname: 42\ninput: [x]\n",
+        ];
+        for source in sources {
+            let scanned = scan_yaml(source).expect(source);
+            let full = FrontMatter::from_document(&document(source, Format::Yaml).unwrap());
+            assert_eq!(scanned, full, "{}", source);
+        }
+        let front = scan_yaml(YAML).unwrap();
+        assert_eq!(front.name.as_deref(), Some("support"));
+        assert_eq!(front.tags, vec!["support", "tier-1"]);
+
+        for fallback in [
+            "name: &n a\n",
+            "<<: *base\n",
+            "name:\n  a\n",
+            "name: foo\n  bar\n",
+            "name: a\nprompt: x\nname: b\n",
+            "name: |\n  a\n",
+            "{type: chat}\n",
+        ] {
+            assert_eq!(scan_yaml(fallback), None, "{}", fallback);
+        }
+        assert_eq!(scan("name: a\nprompt: x\nprompt: y\n", Format::Yaml), None);
+        let json = r#"{"type": "chat", "name": "j", "tags": ["x"]}"#;
+        assert_eq!(scan(json, Format::Json).unwrap().tags, vec!["x"]);
+        let markdown = "---\ntype: chat\nname: md\n---\n## user\nhi\n";
+        assert_eq!(
+            scan(markdown, Format::Markdown).unwrap().name.as_deref(),
+            Some("md")
+        );
+    }
+}
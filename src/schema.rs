@@ -0,0 +1,488 @@
+use crate::prompt::Prompt;
+use crate::strict::{
+    CHAT_EXAMPLE_FIELDS, CHAT_FIELDS, COLUMN_FIELDS, COMPLETION_FIELDS, COMPOSE_FIELDS,
+    EMBEDDING_FIELDS, MESSAGE_FIELDS, META_FIELDS, PARAMETER_FIELDS, SAMPLING_FIELDS,
+};
+use serde_json::{json, Map, Value};
+
+pub const SCHEMA_DIALECT: &str = "https://json-schema.org/draft/2020-12/schema";
+
+const KINDS: &[&str] = &["completion", "chat", "embedding"];
+
+fn text() -> Value {
+    json!({
+        "type": ["string", "object"],
+        "additionalProperties": { "type": "string" }
+    })
+}
+
+fn strings() -> Value {
+    json!({ "type": "array", "items": { "type": "string" } })
+}
+
+fn object(fields: &[&str], required: &[&str], field: impl Fn(&str) -> Option<Value>) -> Value {
+    let properties: Map<String, Value> = fields
+        .iter()
+        .map(|name| (name.to_string(), field(name).unwrap_or(json!({}))))
+        .collect();
+    let mut schema = json!({
+        "type": "object",
+        "properties": properties,
+        "additionalProperties": false
+    });
+    if !required.is_empty() {
+        schema["required"] = json!(required);
+    }
+    schema
+}
+
+fn named_steps(names: &[&str], arguments: Value) -> Value {
+    json!({
+        "type": ["string", "object"],
+        "anyOf": [
+            { "type": "string", "enum": names },
+            {
+                "type": "object",
+                "properties": arguments,
+                "additionalProperties": false,
+                "minProperties": 1,
+                "maxProperties": 1
+            }
+        ]
+    })
+}
+
+fn redact() -> Value {
+    json!({
+        "anyOf": [
+            { "type": "string" },
+            {
+                "type": "object",
+                "properties": {
+                    "pattern": { "type": "string" },
+                    "replacement": { "type": "string" }
+                },
+                "required": ["pattern"],
+                "additionalProperties": false
+            }
+        ]
+    })
+}
+
+fn parameters() -> Value {
+    let parameter = object(PARAMETER_FIELDS, PARAMETER_FIELDS, |name| match name {
+        "name" => Some(json!({ "type": "string" })),
+        "value" => Some(json!({})),
+        _ => None,
+    });
+    json!({ "type": "array", "items": parameter })
+}
+
+fn example_field(kind: &str, name: &str) -> Option<Value> {
+    match (kind, name) {
+        ("completion", "name") => Some(json!({ "type": "string" })),
+        ("completion", "values") => Some(strings()),
+        ("completion", "test") | ("chat", "input" | "output") => Some(text()),
+        _ => None,
+    }
+}
+
+fn message_field(name: &str) -> Option<Value> {
+    let media = json!({ "type": "string" });
+    Some(match name {
+        "input" | "output" => text(),
+        "content" => json!({
+            "type": "array",
+            "items": {
+                "type": "object",
+                "properties": { "text": { "type": "string" }, "image": media, "document": media },
+                "additionalProperties": false,
+                "minProperties": 1,
+                "maxProperties": 1
+            }
+        }),
+        "tool_calls" => json!({
+            "type": "array",
+            "items": {
+                "type": "object",
+                "properties": {
+                    "id": { "type": "string" },
+                    "name": { "type": "string" },
+                    "arguments": {}
+                },
+                "required": ["id", "name"],
+                "additionalProperties": false
+            }
+        }),
+        "tool_results" => json!({
+            "type": "array",
+            "items": {
+                "type": "object",
+                "properties": { "id": { "type": "string" }, "content": { "type": "string" } },
+                "required": ["id", "content"],
+                "additionalProperties": false
+            }
+        }),
+        _ => return None,
+    })
+}
+
+fn field_schema(kind: &str, name: &str) -> Option<Value> {
+    Some(match name {
+        "type" => json!({ "type": "string", "const": kind }),
+        "name" | "author" | "created" | "updated" | "variant" | "extends" | "deployment"
+        | "api_version" => json!({ "type": "string" }),
+        "base_url" => json!({ "type": "string", "pattern": "^https?://" }),
+        "api_style" => json!({ "type": "string", "enum": ["openai", "anthropic", "google"] }),
+        "vendor" => json!({ "type": "string", "examples": ["openai", "anthropic", "google"] }),
+        "model" => json!({ "type": "string", "minLength": 1 }),
+        "description" | "prompt" | "context" | "system" | "instructions" | "suffix" => text(),
+        "version" => json!({ "type": ["string", "number"] }),
+        "tags" | "includes" | "stop" => strings(),
+        "abstract" => json!({ "type": "boolean" }),
+        "max_context_tokens" | "top_k" => json!({ "type": "integer", "minimum": 0 }),
+        "top_p" => json!({ "type": "number", "minimum": 0, "maximum": 1 }),
+        "frequency_penalty" | "presence_penalty" => json!({ "type": "number" }),
+        "seed" => json!({ "type": "integer" }),
+        "logit_bias" => json!({ "type": "object", "additionalProperties": { "type": "number" } }),
+        "variables" => {
+            let rule = named_steps(
+                &["strip_injection", "flag_injection", "redact_pii"],
+                json!({
+                    "redact": redact(),
+                    "max_len": { "type": "integer", "minimum": 0 },
+                    "allow_chars": { "type": "string" },
+                    "deny_chars": { "type": "string" }
+                }),
+            );
+            let kinds = [
+                "string", "str", "int", "integer", "float", "number", "bool", "boolean", "list",
+                "array", "object",
+            ];
+            let properties = json!({
+                "type": { "type": "string", "enum": kinds },
+                "required": { "type": "boolean" },
+                "default": {},
+                "description": { "type": "string" },
+                "sanitize": { "type": "array", "items": rule }
+            });
+            let mut named = properties.clone();
+            named["name"] = json!({ "type": "string" });
+            json!({
+                "type": ["object", "array"],
+                "additionalProperties": {
+                    "type": "object",
+                    "properties": properties,
+                    "additionalProperties": false
+                },
+                "items": {
+                    "type": ["string", "object"],
+                    "properties": named,
+                    "required": ["name"],
+                    "additionalProperties": false
+                }
+            })
+        }
+        "changelog" => json!({
+            "type": "array",
+            "items": {
+                "type": "object",
+                "properties": {
+                    "version": { "type": ["string", "number", "null"] },
+                    "date": { "type": "string" },
+                    "notes": { "type": "string" }
+                },
+                "required": ["version"],
+                "additionalProperties": false
+            }
+        }),
+        "variants" => json!({
+            "type": "array",
+            "items": {
+                "type": "object",
+                "properties": {
+                    "name": { "type": "string" },
+                    "weight": { "type": "number", "minimum": 0 }
+                },
+                "required": ["name"]
+            }
+        }),
+        "parameters" => parameters(),
+        "parameters_by_env" => json!({ "type": "object", "additionalProperties": parameters() }),
+        "examples" => {
+            let fields = match kind {
+                "completion" => COLUMN_FIELDS,
+                _ => CHAT_EXAMPLE_FIELDS,
+            };
+            let required: &[&str] = match kind {
+                "completion" => &["name", "values"],
+                _ => &["input"],
+            };
+            let example = object(fields, required, |field| example_field(kind, field));
+            json!({ "type": "array", "items": example })
+        }
+        "example_format" => json!({
+            "type": "object",
+            "properties": {
+                "separator": { "type": "string" },
+                "labels": { "type": "string", "enum": ["colon", "newline", "bracket", "none"] },
+                "template": { "type": "string" }
+            },
+            "additionalProperties": false,
+            "not": { "required": ["labels", "template"] }
+        }),
+        "messages" => json!({
+            "type": "array",
+            "items": object(MESSAGE_FIELDS, &[], message_field)
+        }),
+        "tools" => json!({
+            "type": "array",
+            "items": {
+                "type": "object",
+                "properties": {
+                    "name": { "type": "string" },
+                    "description": { "type": "string" },
+                    "parameters": { "type": "object" }
+                },
+                "required": ["name"],
+                "additionalProperties": false
+            }
+        }),
+        "tool_choice" => json!({
+            "type": ["string", "object"],
+            "anyOf": [
+                { "type": "string", "enum": ["auto", "none", "required"] },
+                {
+                    "type": "object",
+                    "properties": { "name": { "type": "string" } },
+                    "required": ["name"],
+                    "additionalProperties": false
+                }
+            ]
+        }),
+        "output" => json!({
+            "type": "object",
+            "properties": {
+                "format": { "type": "string", "enum": ["text", "json"] },
+                "schema": { "type": "object" },
+                "pattern": { "type": "string" },
+                "enum": strings()
+            },
+            "additionalProperties": false
+        }),
+        "policy" => json!({
+            "type": "object",
+            "properties": {
+                "max_retries": { "type": "integer", "minimum": 0 },
+                "backoff": { "type": "string", "enum": ["constant", "linear", "exponential"] },
+                "timeout_ms": { "type": "integer", "minimum": 0 },
+                "rate_limit_rpm": { "type": "integer", "minimum": 0 }
+            },
+            "additionalProperties": false
+        }),
+        "post_process" => {
+            let step = named_steps(
+                &["trim", "strip_fences", "extract_json"],
+                json!({
+                    "trim_sentences": { "type": "integer", "minimum": 0 },
+                    "redact": redact(),
+                    "custom": { "type": "string" }
+                }),
+            );
+            json!({ "type": "array", "items": step })
+        }
+        "input" => json!({ "type": ["string", "array"], "items": { "type": "string" } }),
+        "dimensions" => json!({ "type": "integer", "minimum": 1 }),
+        "encoding_format" => json!({ "type": "string", "enum": ["float", "base64"] }),
+        _ => return None,
+    })
+}
+
+fn kind_fields(kind: &str) -> Option<Vec<&'static str>> {
+    let (top, sampling) = match kind {
+        "completion" => (COMPLETION_FIELDS, true),
+        "chat" => (CHAT_FIELDS, true),
+        "embedding" => (EMBEDDING_FIELDS, false),
+        _ => return None,
+    };
+    let mut fields = [top, META_FIELDS, COMPOSE_FIELDS].concat();
+    if sampling {
+        fields.extend_from_slice(SAMPLING_FIELDS);
+    }
+    Some(fields)
+}
+
+pub fn prompt_schema(kind: &str) -> Option<Value> {
+    let fields = kind_fields(kind)?;
+    let required: &[&str] = match kind {
+        "completion" => &["type", "vendor", "model", "prompt"],
+        "embedding" => &["type", "vendor", "model", "input"],
+        _ => &["type", "vendor", "model"],
+    };
+    let mut schema = object(&fields, &[], |name| field_schema(kind, name));
+    schema["title"] = json!(format!("{} prompt", kind));
+    schema["anyOf"] = json!([{ "required": required }, { "required": ["extends"] }]);
+    Some(schema)
+}
+
+impl Prompt {
+    pub fn json_schema() -> Value {
+        let kinds: Vec<Value> = KINDS
+            .iter()
+            .filter_map(|kind| {
+                Some(json!({
+                    "if": {
+                        "properties": { "type": { "const": kind } },
+                        "required": ["type"]
+                    },
+                    "then": prompt_schema(kind)?
+                }))
+            })
+            .collect();
+        json!({
+            "$schema": SCHEMA_DIALECT,
+            "title": "prompt",
+            "type": "object",
+            "properties": {
+                "type": { "type": "string", "examples": KINDS }
+            },
+            "allOf": kinds
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::output::validate_json;
+    use crate::prompt::{
+        try_deserialize_prompt, Chat, Completion, Embedding, ExampleFormat, LabelStyle, Parameter,
+        PromptMeta,
+    };
+    use crate::request::Endpoint;
+    use crate::sampling::Sampling;
+
+    #[test]
+    fn test_schema_covers_fields() {
+        for kind in KINDS {
+            for field in kind_fields(kind).unwrap() {
+                assert!(field_schema(kind, field).is_some(), "{}.{}", kind, field);
+            }
+            for field in CHAT_EXAMPLE_FIELDS.iter().chain(COLUMN_FIELDS) {
+                let covered = example_field("completion", field).is_some()
+                    || example_field("chat", field).is_some();
+                assert!(covered, "examples.{}", field);
+            }
+            for field in MESSAGE_FIELDS {
+                assert!(message_field(field).is_some(), "messages.{}", field);
+            }
+        }
+        let schema = Prompt::json_schema();
+        assert_eq!(
+            schema["allOf"].as_array().map(|a| a.len()),
+            Some(KINDS.len())
+        );
+        assert_eq!(prompt_schema("rerank"), None);
+    }
+
+    #[test]
+    fn test_schema_matches_structs() {
+        let meta = PromptMeta {
+            name: Some("full".to_string()),
+            description: Some("every field".to_string()),
+            version: Some("1.0".to_string()),
+            tags: Some(vec!["a".to_string()]),
+            author: Some("me".to_string()),
+            created: Some("2024-01-01".to_string()),
+            updated: Some("2024-01-02".to_string()),
+            variables: Some(Default::default()),
+            changelog: Some(Vec::new()),
+            variant: Some("a".to_string()),
+            variants: Some(Vec::new()),
+            max_context_tokens: Some(100),
+            endpoint: Endpoint {
+                base_url: Some("http://localhost:8000".to_string()),
+                api_style: Some("openai".to_string()),
+                deployment: Some("gpt4o-prod".to_string()),
+                api_version: Some("2024-06-01".to_string()),
+            },
+            unknown_fields: Vec::new(),
+            templates: Default::default(),
+        };
+        let sampling = Sampling {
+            stop: Some(vec!["\n".to_string()]),
+            top_p: Some(0.5),
+            top_k: Some(4),
+            frequency_penalty: Some(0.1),
+            presence_penalty: Some(0.1),
+            logit_bias: Some(Default::default()),
+            seed: Some(7),
+        };
+        let parameters = Some(vec![Parameter {
+            name: "temperature".to_string(),
+            value: 0.2.into(),
+        }]);
+        let source = "type: chat\nvendor: openai\nmodel: gpt-4o\npost_process: [trim]\n\
+            output:\n  format: json\npolicy:\n  max_retries: 2\n";
+        let Ok(Prompt::Chat(filled)) = try_deserialize_prompt(source) else {
+            panic!("expected a chat prompt");
+        };
+        let prompts = [
+            Prompt::Completion(Completion {
+                meta: meta.clone(),
+                prompt_type: "completion".to_string(),
+                vendor: "openai".to_string(),
+                model: "gpt-3.5-turbo-instruct".to_string(),
+                prompt: "Hi {{name}}".to_string(),
+                system: Some("Be brief.".to_string()),
+                suffix: Some("Answer:".to_string()),
+                sampling: sampling.clone(),
+                parameters: parameters.clone(),
+                parameters_by_env: Some(Default::default()),
+                examples: Some(Vec::new()),
+                example_format: Some(ExampleFormat {
+                    separator: Some("\n###\n".to_string()),
+                    labels: LabelStyle::Bracket,
+                    template: Some("Q: {question}\nA: {answer}".to_string()),
+                }),
+                output: filled.output.clone(),
+                policy: filled.policy.clone(),
+                post_process: filled.post_process.clone(),
+            }),
+            Prompt::Chat(Chat {
+                meta: meta.clone(),
+                prompt_type: "chat".to_string(),
+                vendor: "openai".to_string(),
+                model: "gpt-4o".to_string(),
+                sampling,
+                parameters,
+                parameters_by_env: Some(Default::default()),
+                examples: Some(Vec::new()),
+                context: Some("Be brief".to_string()),
+                messages: Some(Vec::new()),
+                tools: Some(Vec::new()),
+                tool_choice: Some(serde_json::from_value(json!("auto")).unwrap()),
+                output: filled.output,
+                policy: filled.policy,
+                post_process: filled.post_process,
+            }),
+            Prompt::Embedding(Embedding {
+                meta,
+                prompt_type: "embedding".to_string(),
+                vendor: "openai".to_string(),
+                model: "text-embedding-3-small".to_string(),
+                input: vec!["a".to_string()],
+                dimensions: Some(256),
+                encoding_format: Some(crate::prompt::EncodingFormat::Float),
+            }),
+        ];
+        for prompt in prompts {
+            let document = serde_json::to_value(&prompt).unwrap();
+            let kind = document["type"].as_str().unwrap().to_string();
+            let schema = prompt_schema(&kind).unwrap();
+            assert_eq!(validate_json(&document, &schema), Ok(()), "{}", kind);
+        }
+        let document = json!({ "type": "embedding", "vendor": "openai", "model": "m", "input": "a", "top_p": 1 });
+        assert!(validate_json(&document, &prompt_schema("embedding").unwrap()).is_err());
+    }
+}
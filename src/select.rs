@@ -0,0 +1,263 @@
+use crate::prompt::{Chat, Completion};
+use crate::tokens::Tokenizer;
+use crate::transcript::Transcript;
+
+pub trait ExampleSelector {
+    fn select(&self, query: &str, examples: &[String]) -> Vec<usize>;
+}
+
+impl<T: ExampleSelector + ?Sized> ExampleSelector for &T {
+    fn select(&self, query: &str, examples: &[String]) -> Vec<usize> {
+        (**self).select(query, examples)
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct FirstK(pub usize);
+
+impl ExampleSelector for FirstK {
+    fn select(&self, _: &str, examples: &[String]) -> Vec<usize> {
+        (0..examples.len().min(self.0)).collect()
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct RandomK {
+    pub k: usize,
+    pub seed: u64,
+}
+
+impl RandomK {
+    pub fn new(k: usize, seed: u64) -> RandomK {
+        RandomK { k, seed }
+    }
+}
+
+pub(crate) fn split_mix(state: &mut u64) -> u64 {
+    *state = state.wrapping_add(0x9e37_79b9_7f4a_7c15);
+    let mut z = *state;
+    z = (z ^ (z >> 30)).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+    z = (z ^ (z >> 27)).wrapping_mul(0x94d0_49bb_1331_11eb);
+    z ^ (z >> 31)
+}
+
+impl ExampleSelector for RandomK {
+    fn select(&self, _: &str, examples: &[String]) -> Vec<usize> {
+        let mut indices: Vec<usize> = (0..examples.len()).collect();
+        let mut state = self.seed;
+        for i in (1..indices.len()).rev() {
+            let j = (split_mix(&mut state) % (i as u64 + 1)) as usize;
+            indices.swap(i, j);
+        }
+        indices.truncate(self.k);
+        indices.sort_unstable();
+        indices
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct TokenBudget<T> {
+    pub max_tokens: usize,
+    pub tokenizer: T,
+}
+
+impl<T: Tokenizer> TokenBudget<T> {
+    pub fn new(max_tokens: usize, tokenizer: T) -> TokenBudget<T> {
+        TokenBudget {
+            max_tokens,
+            tokenizer,
+        }
+    }
+}
+
+impl<T: Tokenizer> ExampleSelector for TokenBudget<T> {
+    fn select(&self, _: &str, examples: &[String]) -> Vec<usize> {
+        let mut remaining = self.max_tokens;
+        let mut selected = Vec::new();
+        for (i, example) in examples.iter().enumerate() {
+            let cost = self.tokenizer.count_tokens(example);
+            if cost <= remaining {
+                remaining -= cost;
+                selected.push(i);
+            }
+        }
+        selected
+    }
+}
+
+fn keep<T>(items: &mut Vec<T>, selected: &[usize]) {
+    let mut index = 0;
+    items.retain(|_| {
+        index += 1;
+        selected.contains(&(index - 1))
+    });
+}
+
+impl Completion {
+    fn example_texts(&self) -> Vec<String> {
+        let columns = self.examples.as_deref().unwrap_or_default();
+        (0..self.example_count())
+            .map(|i| {
+                columns
+                    .iter()
+                    .map(|c| {
+                        let value = c.values.get(i).map(|v| v.as_str()).unwrap_or("");
+                        format!("{}: {}\n", c.name, value)
+                    })
+                    .collect()
+            })
+            .collect()
+    }
+
+    fn example_query(&self) -> String {
+        self.examples
+            .iter()
+            .flatten()
+            .filter_map(|c| c.test.as_ref().map(|t| format!("{}: {}\n", c.name, t)))
+            .collect()
+    }
+
+    pub fn select_examples(&self, selector: &impl ExampleSelector) -> Completion {
+        let selected = selector.select(&self.example_query(), &self.example_texts());
+        let count = self.example_count();
+        let mut completion = self.clone();
+        for column in completion.examples.iter_mut().flatten() {
+            column.values.resize(count, String::new());
+            keep(&mut column.values, &selected);
+        }
+        completion
+    }
+
+    pub fn final_prompt_with(&self, selector: &impl ExampleSelector) -> String {
+        self.select_examples(selector).final_prompt()
+    }
+}
+
+impl Chat {
+    fn example_texts(&self) -> Vec<String> {
+        self.examples
+            .iter()
+            .flatten()
+            .map(|e| match &e.output {
+                Some(output) => format!("{}\n{}", e.input, output),
+                None => e.input.clone(),
+            })
+            .collect()
+    }
+
+    fn example_query(&self) -> String {
+        self.messages
+            .iter()
+            .flatten()
+            .last()
+            .map(|m| m.input.clone())
+            .unwrap_or_default()
+    }
+
+    pub fn select_examples(&self, selector: &impl ExampleSelector) -> Chat {
+        let selected = selector.select(&self.example_query(), &self.example_texts());
+        let mut chat = self.clone();
+        if let Some(examples) = &mut chat.examples {
+            keep(examples, &selected);
+        }
+        chat
+    }
+
+    pub fn to_transcript_with(&self, selector: &impl ExampleSelector) -> Transcript {
+        self.select_examples(selector).to_transcript()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::prompt::{deserialize_prompt, Prompt};
+    use crate::tokens::WhitespaceTokenizer;
+
+    const COMPLETION: &str = r#"
+        type: completion
+        vendor: google
+        model: text-bison
+        prompt: Translate
+        examples:
+            - name: en
+              values: [one, two, three, four]
+              test: five
+            - name: fr
+              values: [un, deux, trois, quatre]
+    "#;
+
+    fn completion() -> Completion {
+        match deserialize_prompt(COMPLETION) {
+            Prompt::Completion(completion) => completion,
+            other => panic!("Expected Prompt::Completion, got {:?}", other),
+        }
+    }
+
+    struct Matching;
+
+    impl ExampleSelector for Matching {
+        fn select(&self, query: &str, examples: &[String]) -> Vec<usize> {
+            let word = query.split_whitespace().last().unwrap_or("");
+            (0..examples.len())
+                .filter(|&i| examples[i].contains(word))
+                .collect()
+        }
+    }
+
+    #[test]
+    fn test_completion_selectors() {
+        let completion = completion();
+        assert_eq!(
+            completion.final_prompt_with(&FirstK(1)),
+            "Translate\n\nen: one\nfr: un\n\nen: five\nfr: \n"
+        );
+        assert_eq!(
+            completion.final_prompt_with(&FirstK(10)),
+            completion.final_prompt()
+        );
+
+        let random = completion.select_examples(&RandomK::new(2, 7));
+        assert_eq!(random.example_count(), 2);
+        assert_eq!(random, completion.select_examples(&RandomK::new(2, 7)));
+        let en = &random.examples.as_ref().unwrap()[0].values;
+        let fr = &random.examples.as_ref().unwrap()[1].values;
+        for (word, translation) in en.iter().zip(fr) {
+            let row = ["one", "two", "three", "four"]
+                .iter()
+                .position(|w| w == word)
+                .unwrap();
+            assert_eq!(translation, ["un", "deux", "trois", "quatre"][row]);
+        }
+
+        let budget = completion.select_examples(&TokenBudget::new(9, WhitespaceTokenizer));
+        assert_eq!(budget.example_count(), 2);
+    }
+
+    #[test]
+    fn test_chat_custom_selector() {
+        let yaml = r#"
+            type: chat
+            vendor: openai
+            model: gpt-4o
+            examples:
+                - input: capital of france
+                  output: paris
+                - input: capital of spain
+                  output: madrid
+            messages:
+                - input: what about spain
+        "#;
+
+        if let Prompt::Chat(chat) = deserialize_prompt(yaml) {
+            let selected = chat.select_examples(&Matching);
+            assert_eq!(
+                selected.examples.unwrap()[0].output.as_deref(),
+                Some("madrid")
+            );
+            assert_eq!(chat.to_transcript_with(&FirstK(0)).len(), 1);
+        } else {
+            panic!("Expected Prompt::Chat");
+        }
+    }
+}
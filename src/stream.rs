@@ -0,0 +1,274 @@
+use crate::exec::{
+    check_status, AnthropicExecutor, AzureOpenAiExecutor, BoxFuture, ExecError, ExecutionResult,
+    HttpClient, HttpRequest, OpenAiExecutor, PromptExecutor, VendorExecutor, VertexExecutor,
+};
+use crate::prompt::Prompt;
+use serde_json::Value;
+use std::collections::HashMap;
+
+pub type TokenCallback<'a> = &'a mut (dyn FnMut(&str) + Send);
+
+pub trait StreamingExecutor: PromptExecutor {
+    fn execute_streaming<'a>(
+        &'a self,
+        prompt: &'a Prompt,
+        vars: &'a HashMap<String, String>,
+        on_token: TokenCallback<'a>,
+    ) -> BoxFuture<'a, Result<ExecutionResult, ExecError>> {
+        Box::pin(async move {
+            let result = self.execute(prompt, vars).await?;
+            on_token(&result.text);
+            Ok(result)
+        })
+    }
+}
+
+impl Prompt {
+    pub async fn execute_streaming(
+        &self,
+        executor: &(impl StreamingExecutor + ?Sized),
+        vars: &HashMap<String, String>,
+        on_token: TokenCallback<'_>,
+    ) -> Result<ExecutionResult, ExecError> {
+        executor.execute_streaming(self, vars, on_token).await
+    }
+}
+
+#[derive(Debug, Default)]
+pub struct SseParser {
+    buffer: String,
+}
+
+impl SseParser {
+    pub fn feed(&mut self, chunk: &str, mut on_event: impl FnMut(Value)) {
+        self.buffer.push_str(chunk);
+        while let Some(end) = self.buffer.find('\n') {
+            let line: String = self.buffer.drain(..=end).collect();
+            if let Some(data) = line.trim().strip_prefix("data:") {
+                let data = data.trim();
+                if data == "[DONE]" {
+                    continue;
+                }
+                if let Ok(event) = serde_json::from_str(data) {
+                    on_event(event);
+                }
+            }
+        }
+    }
+
+    pub fn finish(&mut self, on_event: impl FnMut(Value)) {
+        if !self.buffer.is_empty() {
+            self.feed("\n", on_event);
+        }
+    }
+}
+
+async fn stream_sse(
+    client: &dyn HttpClient,
+    request: HttpRequest,
+    extract: fn(&Value) -> Option<&str>,
+    on_token: TokenCallback<'_>,
+) -> Result<ExecutionResult, ExecError> {
+    let mut parser = SseParser::default();
+    let mut text = String::new();
+    let mut events = Vec::new();
+    let mut handle_event = |event: Value| {
+        if let Some(token) = extract(&event) {
+            on_token(token);
+            text.push_str(token);
+        }
+        events.push(event);
+    };
+    let response = {
+        let mut on_chunk = |chunk: &str| parser.feed(chunk, &mut handle_event);
+        client.post_stream(request, &mut on_chunk).await?
+    };
+    parser.finish(&mut handle_event);
+    check_status(response)?;
+    Ok(ExecutionResult {
+        text,
+        raw: Value::Array(events),
+    })
+}
+
+fn openai_delta(event: &Value) -> Option<&str> {
+    let choice = event.pointer("/choices/0")?;
+    choice
+        .pointer("/delta/content")
+        .or_else(|| choice.get("text"))
+        .and_then(|t| t.as_str())
+}
+
+fn anthropic_delta(event: &Value) -> Option<&str> {
+    if event.get("type")?.as_str()? != "content_block_delta" {
+        return None;
+    }
+    event.pointer("/delta/text")?.as_str()
+}
+
+impl StreamingExecutor for OpenAiExecutor {
+    fn execute_streaming<'a>(
+        &'a self,
+        prompt: &'a Prompt,
+        vars: &'a HashMap<String, String>,
+        on_token: TokenCallback<'a>,
+    ) -> BoxFuture<'a, Result<ExecutionResult, ExecError>> {
+        Box::pin(async move {
+            let (request, _) = self.build_request(prompt, vars, true)?;
+            stream_sse(self.client.as_ref(), request, openai_delta, on_token).await
+        })
+    }
+}
+
+impl StreamingExecutor for AzureOpenAiExecutor {
+    fn execute_streaming<'a>(
+        &'a self,
+        prompt: &'a Prompt,
+        vars: &'a HashMap<String, String>,
+        on_token: TokenCallback<'a>,
+    ) -> BoxFuture<'a, Result<ExecutionResult, ExecError>> {
+        Box::pin(async move {
+            let (request, _) = self.build_request(prompt, vars, true)?;
+            stream_sse(self.client.as_ref(), request, openai_delta, on_token).await
+        })
+    }
+}
+
+impl StreamingExecutor for AnthropicExecutor {
+    fn execute_streaming<'a>(
+        &'a self,
+        prompt: &'a Prompt,
+        vars: &'a HashMap<String, String>,
+        on_token: TokenCallback<'a>,
+    ) -> BoxFuture<'a, Result<ExecutionResult, ExecError>> {
+        Box::pin(async move {
+            let request = self.build_request(prompt, vars, true)?;
+            stream_sse(self.client.as_ref(), request, anthropic_delta, on_token).await
+        })
+    }
+}
+
+impl StreamingExecutor for VertexExecutor {}
+
+impl StreamingExecutor for VendorExecutor {
+    fn execute_streaming<'a>(
+        &'a self,
+        prompt: &'a Prompt,
+        vars: &'a HashMap<String, String>,
+        on_token: TokenCallback<'a>,
+    ) -> BoxFuture<'a, Result<ExecutionResult, ExecError>> {
+        Box::pin(async move {
+            let executor = self.executor_for(prompt)?;
+            executor.execute_streaming(prompt, vars, on_token).await
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::exec::tests::MockClient;
+    use crate::exec::{block_on, HttpResponse};
+    use crate::prompt::deserialize_prompt;
+    use crate::request::Vendor;
+    use serde_json::json;
+    use std::sync::{Arc, Mutex};
+
+    struct ChunkedClient {
+        body: String,
+        requests: Mutex<Vec<HttpRequest>>,
+    }
+
+    impl HttpClient for ChunkedClient {
+        fn post(&self, _: HttpRequest) -> BoxFuture<'_, Result<HttpResponse, ExecError>> {
+            Box::pin(async { Err(ExecError::Http("streaming only".to_string())) })
+        }
+
+        fn post_stream<'a>(
+            &'a self,
+            request: HttpRequest,
+            on_chunk: &'a mut (dyn FnMut(&str) + Send),
+        ) -> BoxFuture<'a, Result<HttpResponse, ExecError>> {
+            self.requests.lock().unwrap().push(request);
+            Box::pin(async move {
+                for chunk in self.body.as_bytes().chunks(7) {
+                    on_chunk(std::str::from_utf8(chunk).unwrap());
+                }
+                Ok(HttpResponse {
+                    status: 200,
+                    body: String::new(),
+                })
+            })
+        }
+    }
+
+    #[test]
+    fn test_openai_streaming() {
+        let body = [
+            json!({ "choices": [{ "delta": { "role": "assistant" } }] }),
+            json!({ "choices": [{ "delta": { "content": "Hel" } }] }),
+            json!({ "choices": [{ "delta": { "content": "lo" } }] }),
+        ]
+        .iter()
+        .map(|e| format!("data: {}\n\n", e))
+        .collect::<String>()
+            + "data: [DONE]\n\n";
+        let client = Arc::new(ChunkedClient {
+            body,
+            requests: Mutex::new(Vec::new()),
+        });
+        let executor = OpenAiExecutor::new(client.clone(), "key");
+        let prompt = deserialize_prompt(
+            "type: chat\nvendor: openai\nmodel: gpt-4o\nparameters:\n  - name: stream\n    value: true\nmessages:\n  - input: hi\n",
+        );
+        assert!(prompt.is_streaming());
+
+        let mut tokens = Vec::new();
+        let mut on_token = |t: &str| tokens.push(t.to_string());
+        let result =
+            block_on(prompt.execute_streaming(&executor, &HashMap::new(), &mut on_token)).unwrap();
+        assert_eq!(result.text, "Hello");
+        assert_eq!(tokens, vec!["Hel", "lo"]);
+        assert_eq!(client.requests.lock().unwrap()[0].body["stream"], true);
+    }
+
+    #[test]
+    fn test_anthropic_streaming_and_fallback() {
+        let anthropic = MockClient::replying(&[]);
+        anthropic.responses.lock().unwrap().push(HttpResponse {
+            status: 200,
+            body: concat!(
+                "event: content_block_delta\n",
+                "data: {\"type\":\"content_block_delta\",\"delta\":{\"type\":\"text_delta\",\"text\":\"Hi \"}}\n\n",
+                "event: content_block_delta\n",
+                "data: {\"type\":\"content_block_delta\",\"delta\":{\"type\":\"text_delta\",\"text\":\"there\"}}\n\n",
+                "event: message_stop\n",
+                "data: {\"type\":\"message_stop\"}"
+            )
+            .to_string(),
+        });
+        let vertex =
+            MockClient::replying(&[(200, json!({ "predictions": [{ "content": "Yo" }] }))]);
+        let executor = VendorExecutor::new()
+            .with(Vendor::Anthropic, AnthropicExecutor::new(anthropic, "key"))
+            .with(
+                Vendor::Google,
+                VertexExecutor::new(vertex, "token", "proj", "us-central1"),
+            );
+
+        let claude = deserialize_prompt(
+            "type: completion\nvendor: anthropic\nmodel: claude-3-haiku\nprompt: hi\n",
+        );
+        let bison =
+            deserialize_prompt("type: completion\nvendor: google\nmodel: text-bison\nprompt: hi\n");
+        let none = HashMap::new();
+
+        let mut tokens = Vec::new();
+        let mut on_token = |t: &str| tokens.push(t.to_string());
+        let result = block_on(claude.execute_streaming(&executor, &none, &mut on_token)).unwrap();
+        assert_eq!(result.text, "Hi there");
+        let result = block_on(bison.execute_streaming(&executor, &none, &mut on_token)).unwrap();
+        assert_eq!(result.text, "Yo");
+        assert_eq!(tokens, vec!["Hi ", "there", "Yo"]);
+    }
+}
@@ -0,0 +1,580 @@
+use crate::format::{document, prompt_from_value, Format};
+use crate::prompt::{Location, Prompt, PromptError};
+use serde_json::Value;
+use std::fmt;
+use std::path::PathBuf;
+
+pub(crate) const META_FIELDS: &[&str] = &[
+    "name",
+    "description",
+    "version",
+    "tags",
+    "author",
+    "created",
+    "updated",
+    "variables",
+    "changelog",
+    "variant",
+    "variants",
+    "max_context_tokens",
+    "base_url",
+    "api_style",
+    "deployment",
+    "api_version",
+];
+pub(crate) const COMPOSE_FIELDS: &[&str] = &["extends", "includes", "abstract"];
+pub(crate) const SAMPLING_FIELDS: &[&str] = &[
+    "stop",
+    "top_p",
+    "top_k",
+    "frequency_penalty",
+    "presence_penalty",
+    "logit_bias",
+    "seed",
+];
+pub(crate) const COMPLETION_FIELDS: &[&str] = &[
+    "type",
+    "vendor",
+    "model",
+    "prompt",
+    "system",
+    "instructions",
+    "suffix",
+    "parameters",
+    "parameters_by_env",
+    "examples",
+    "example_format",
+    "output",
+    "policy",
+    "post_process",
+];
+pub(crate) const CHAT_FIELDS: &[&str] = &[
+    "type",
+    "vendor",
+    "model",
+    "parameters",
+    "parameters_by_env",
+    "examples",
+    "context",
+    "messages",
+    "tools",
+    "tool_choice",
+    "output",
+    "policy",
+    "post_process",
+];
+pub(crate) const EMBEDDING_FIELDS: &[&str] = &[
+    "type",
+    "vendor",
+    "model",
+    "input",
+    "dimensions",
+    "encoding_format",
+];
+pub(crate) const PARAMETER_FIELDS: &[&str] = &["name", "value"];
+pub(crate) const COLUMN_FIELDS: &[&str] = &["name", "values", "test"];
+pub(crate) const CHAT_EXAMPLE_FIELDS: &[&str] = &["input", "output"];
+pub(crate) const MESSAGE_FIELDS: &[&str] =
+    &["input", "content", "tool_calls", "tool_results", "output"];
+pub(crate) const TOOL_FIELDS: &[&str] = &["name", "description", "parameters"];
+pub(crate) const OUTPUT_FIELDS: &[&str] = &["format", "schema", "pattern", "enum"];
+pub(crate) const POLICY_FIELDS: &[&str] =
+    &["max_retries", "backoff", "timeout_ms", "rate_limit_rpm"];
+pub(crate) const EXAMPLE_FORMAT_FIELDS: &[&str] = &["separator", "labels", "template"];
+pub(crate) const STEP_FIELDS: &[&str] = &[
+    "trim",
+    "strip_fences",
+    "extract_json",
+    "trim_sentences",
+    "redact",
+    "custom",
+];
+pub(crate) const REDACT_FIELDS: &[&str] = &["pattern", "replacement"];
+pub(crate) const VARIABLE_FIELDS: &[&str] =
+    &["type", "required", "default", "description", "sanitize"];
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct ParseOptions {
+    pub strict: bool,
+}
+
+impl ParseOptions {
+    pub fn strict() -> ParseOptions {
+        ParseOptions { strict: true }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct UnknownField {
+    pub path: String,
+    pub location: Option<Location>,
+    pub file: Option<PathBuf>,
+}
+
+impl fmt::Display for UnknownField {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "unknown field '{}'", self.path)?;
+        if let Some(file) = &self.file {
+            write!(f, " in {}", file.display())?;
+        }
+        if let Some(location) = &self.location {
+            write!(f, " at {}", location)?;
+        }
+        Ok(())
+    }
+}
+
+fn check_object(value: &Value, path: &str, known: &[&[&str]], unknown: &mut Vec<String>) {
+    for key in value.as_object().into_iter().flat_map(|o| o.keys()) {
+        if !known.iter().any(|fields| fields.contains(&key.as_str())) {
+            unknown.push(match path {
+                "" => key.clone(),
+                _ => format!("{}.{}", path, key),
+            });
+        }
+    }
+}
+
+fn check_items(value: Option<&Value>, path: &str, known: &[&str], unknown: &mut Vec<String>) {
+    let items = value.and_then(|v| v.as_array()).into_iter().flatten();
+    for (i, item) in items.enumerate() {
+        check_object(item, &format!("{}[{}]", path, i), &[known], unknown);
+    }
+}
+
+fn check_field(document: &Value, key: &str, known: &[&str], unknown: &mut Vec<String>) {
+    if let Some(value) = document.get(key) {
+        check_object(value, key, &[known], unknown);
+    }
+}
+
+fn check_variables(variables: Option<&Value>, unknown: &mut Vec<String>) {
+    match variables {
+        Some(Value::Object(declarations)) => {
+            for (name, spec) in declarations {
+                let path = format!("variables.{}", name);
+                check_object(spec, &path, &[VARIABLE_FIELDS], unknown);
+            }
+        }
+        Some(Value::Array(declarations)) => {
+            for (i, declaration) in declarations.iter().enumerate() {
+                let path = format!("variables[{}]", i);
+                check_object(declaration, &path, &[&["name"], VARIABLE_FIELDS], unknown);
+            }
+        }
+        _ => {}
+    }
+}
+
+pub fn unknown_fields(document: &Value) -> Vec<String> {
+    let top: &[&str] = match document.get("type").and_then(|t| t.as_str()) {
+        Some("completion") => COMPLETION_FIELDS,
+        Some("chat") => CHAT_FIELDS,
+        Some("embedding") => EMBEDDING_FIELDS,
+        _ => return Vec::new(),
+    };
+    let mut known = vec![top, META_FIELDS, COMPOSE_FIELDS];
+    if top != EMBEDDING_FIELDS {
+        known.push(SAMPLING_FIELDS);
+    }
+    let mut unknown = Vec::new();
+    check_object(document, "", &known, &mut unknown);
+    check_items(
+        document.get("parameters"),
+        "parameters",
+        PARAMETER_FIELDS,
+        &mut unknown,
+    );
+    for (env, parameters) in document
+        .get("parameters_by_env")
+        .and_then(|p| p.as_object())
+        .into_iter()
+        .flatten()
+    {
+        let path = format!("parameters_by_env.{}", env);
+        check_items(Some(parameters), &path, PARAMETER_FIELDS, &mut unknown);
+    }
+    let examples = if top == COMPLETION_FIELDS {
+        COLUMN_FIELDS
+    } else {
+        CHAT_EXAMPLE_FIELDS
+    };
+    check_items(document.get("examples"), "examples", examples, &mut unknown);
+    check_items(
+        document.get("messages"),
+        "messages",
+        MESSAGE_FIELDS,
+        &mut unknown,
+    );
+    check_items(document.get("tools"), "tools", TOOL_FIELDS, &mut unknown);
+    check_field(document, "output", OUTPUT_FIELDS, &mut unknown);
+    check_field(document, "policy", POLICY_FIELDS, &mut unknown);
+    check_field(
+        document,
+        "example_format",
+        EXAMPLE_FORMAT_FIELDS,
+        &mut unknown,
+    );
+    let steps = document.get("post_process").and_then(|p| p.as_array());
+    for (i, step) in steps.into_iter().flatten().enumerate() {
+        let path = format!("post_process[{}]", i);
+        check_object(step, &path, &[STEP_FIELDS], &mut unknown);
+        if let Some(options) = step.get("redact") {
+            check_object(
+                options,
+                &format!("{}.redact", path),
+                &[REDACT_FIELDS],
+                &mut unknown,
+            );
+        }
+    }
+    check_variables(document.get("variables"), &mut unknown);
+    unknown
+}
+
+fn is_key_at(rest: &str, key: &str) -> bool {
+    let rest = rest.trim_start_matches(['"', '\'']);
+    let Some(after) = rest.strip_prefix(key) else {
+        return false;
+    };
+    let after = after.trim_start_matches(['"', '\'']).trim_start();
+    after.starts_with(':') || after.starts_with('=')
+}
+
+enum Segment<'a> {
+    Key(&'a str),
+    Index(usize),
+}
+
+fn segments(path: &str) -> Option<Vec<Segment<'_>>> {
+    let mut segments = Vec::new();
+    for part in path.split('.') {
+        let (key, indexes) = part.split_at(part.find('[').unwrap_or(part.len()));
+        if !key.is_empty() {
+            segments.push(Segment::Key(key));
+        }
+        for index in indexes.split(['[', ']']).filter(|i| !i.is_empty()) {
+            segments.push(Segment::Index(index.parse().ok()?));
+        }
+    }
+    Some(segments)
+}
+
+fn depths(source: &str) -> Vec<Option<usize>> {
+    let mut depths = Vec::with_capacity(source.len());
+    let (mut depth, mut quoted, mut escaped) = (0usize, false, false);
+    for byte in source.bytes() {
+        depths.push((!quoted).then_some(depth));
+        match byte {
+            _ if escaped => escaped = false,
+            b'\\' if quoted => escaped = true,
+            b'"' => quoted = !quoted,
+            b'[' | b'{' if !quoted => depth += 1,
+            b']' | b'}' if !quoted => depth = depth.saturating_sub(1),
+            _ => {}
+        }
+    }
+    depths
+}
+
+fn line_start(source: &str, offset: usize) -> usize {
+    source[..offset].rfind('\n').map_or(0, |n| n + 1)
+}
+
+fn find_key(
+    source: &str,
+    depths: &[Option<usize>],
+    region: (usize, usize),
+    key: &str,
+) -> Option<usize> {
+    let mut found: Option<((usize, usize), usize)> = None;
+    let mut start = line_start(source, region.0);
+    for line in source[start..region.1].split_inclusive('\n') {
+        for (column, _) in line.char_indices() {
+            let offset = start + column;
+            let Some(depth) = depths[offset].filter(|_| offset >= region.0) else {
+                continue;
+            };
+            let before = line[..column].trim_start_matches([' ', '\t', '-', '{', '[', ',']);
+            let boundary = before.is_empty() || line[..column].ends_with([' ', '{', ',']);
+            if !boundary || !is_key_at(&source[offset..region.1], key) {
+                continue;
+            }
+            if found.is_none_or(|(level, _)| (depth, column) < level) {
+                found = Some(((depth, column), offset));
+            }
+        }
+        start += line.len();
+    }
+    found.map(|(_, offset)| offset)
+}
+
+fn closing(source: &str, depths: &[Option<usize>], open: usize, end: usize) -> usize {
+    let inner = depths[open].map(|d| d + 1);
+    (open + 1..end)
+        .find(|&i| matches!(source.as_bytes()[i], b']' | b'}') && depths[i] == inner)
+        .map_or(end, |i| i + 1)
+}
+
+fn value_region(source: &str, depths: &[Option<usize>], key: usize, end: usize) -> (usize, usize) {
+    let colon = source[key..end]
+        .find([':', '='])
+        .map_or(end, |n| key + n + 1);
+    let line_end = source[colon..end].find('\n').map_or(end, |n| colon + n);
+    let inline = source[colon..line_end].trim_start();
+    let first = line_end - inline.len();
+    if inline.starts_with(['[', '{']) {
+        return (first, closing(source, depths, first, end));
+    }
+    if !inline.is_empty() && !inline.starts_with(['|', '>', '#']) {
+        return (first, line_end);
+    }
+    let column = key - line_start(source, key);
+    let mut offset = line_end;
+    for line in source[line_end..end].split_inclusive('\n') {
+        let trimmed = line.trim_start();
+        let indent = line.len() - trimmed.len();
+        let blank = trimmed.trim().is_empty() || trimmed.starts_with('#');
+        if !blank && (indent < column || (indent == column && !trimmed.starts_with('-'))) {
+            break;
+        }
+        offset += line.len();
+    }
+    (line_end, offset.min(end))
+}
+
+fn item_region(
+    source: &str,
+    depths: &[Option<usize>],
+    region: (usize, usize),
+    index: usize,
+) -> Option<(usize, usize)> {
+    let (start, end) = region;
+    let mut starts = Vec::new();
+    if source[start..end].starts_with('[') {
+        let inner = depths[start].map(|d| d + 1);
+        let close = closing(source, depths, start, end) - 1;
+        let separators = (start..close)
+            .filter(|&i| i == start || (source.as_bytes()[i] == b',' && depths[i] == inner));
+        for separator in separators {
+            let rest = &source[separator + 1..close];
+            starts.push(close - rest.trim_start().len());
+        }
+        starts.push(close);
+    } else {
+        let mut offset = start;
+        let mut items = Vec::new();
+        for line in source[start..end].split_inclusive('\n') {
+            let trimmed = line.trim_start();
+            if trimmed.starts_with('-') {
+                items.push((
+                    line.len() - trimmed.len(),
+                    offset + line.len() - trimmed.len(),
+                ));
+            }
+            offset += line.len();
+        }
+        let indent = items.iter().map(|(indent, _)| *indent).min()?;
+        starts = items
+            .into_iter()
+            .filter(|(i, _)| *i == indent)
+            .map(|(_, start)| start)
+            .collect();
+        starts.push(end);
+    }
+    Some((*starts.get(index)?, *starts.get(index + 1)?))
+}
+
+fn locate(source: &str, path: &str) -> Option<Location> {
+    let depths = depths(source);
+    let mut region = (0, source.len());
+    let mut key = None;
+    for segment in segments(path)? {
+        match segment {
+            Segment::Key(name) => {
+                let offset = find_key(source, &depths, region, name)?;
+                region = value_region(source, &depths, offset, region.1);
+                key = Some(offset);
+            }
+            Segment::Index(index) => region = item_region(source, &depths, region, index)?,
+        }
+    }
+    let offset = key?;
+    let start = line_start(source, offset);
+    Some(Location {
+        line: source[..start].matches('\n').count() + 1,
+        column: offset - start + 1,
+    })
+}
+
+pub(crate) fn located_fields(document: &Value, source: &str) -> Vec<UnknownField> {
+    unknown_fields(document)
+        .into_iter()
+        .map(|path| UnknownField {
+            location: locate(source, &path),
+            path,
+            file: None,
+        })
+        .collect()
+}
+
+pub(crate) fn check_fields(
+    mut prompt: Prompt,
+    unknown: Vec<UnknownField>,
+    options: ParseOptions,
+) -> Result<Prompt, PromptError> {
+    if unknown.is_empty() {
+        return Ok(prompt);
+    }
+    if options.strict {
+        return Err(PromptError::UnknownFields(unknown));
+    }
+    if let Some(meta) = prompt.meta_mut() {
+        meta.unknown_fields = unknown;
+    }
+    Ok(prompt)
+}
+
+impl Prompt {
+    pub fn parse_with(
+        source: &str,
+        format: Format,
+        options: ParseOptions,
+    ) -> Result<Prompt, PromptError> {
+        let Some(document) = document(source, format) else {
+            return Prompt::from_str(source, format);
+        };
+        if options.strict {
+            let unknown = located_fields(&document, source);
+            if !unknown.is_empty() {
+                return Err(PromptError::UnknownFields(unknown));
+            }
+        }
+        let prompt =
+            prompt_from_value(document.clone()).or_else(|_| Prompt::from_str(source, format))?;
+        check_fields(prompt, located_fields(&document, source), options)
+    }
+
+    pub fn unknown_fields(&self) -> &[UnknownField] {
+        self.meta().map_or(&[], |m| m.unknown_fields.as_slice())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const YAML: &str = r#"type: chat
+vendor: openai
+model: gpt-4o
+parmeters:
+  - name: temperature
+    vaule: 0.2
+messages:
+  - input: hi
+    outptu: hello
+"#;
+
+    #[test]
+    fn test_unknown_fields() {
+        let prompt = Prompt::parse_with(YAML, Format::Yaml, ParseOptions::default()).unwrap();
+        let unknown = prompt.unknown_fields();
+        assert_eq!(unknown.len(), 2);
+        assert_eq!(unknown[0].path, "parmeters");
+        assert_eq!(unknown[0].location, Some(Location { line: 4, column: 1 }));
+        assert_eq!(unknown[1].path, "messages[0].outptu");
+        assert_eq!(unknown[1].location.map(|l| l.line), Some(9));
+        assert_eq!(prompt.validate()[0].code, "unknown-field");
+
+        match Prompt::parse_with(YAML, Format::Yaml, ParseOptions::strict()) {
+            Err(PromptError::UnknownFields(fields)) => assert_eq!(fields.len(), 2),
+            other => panic!("Expected unknown fields, got {:?}", other),
+        }
+
+        let json = r#"{"type": "completion", "vendor": "google", "model": "m", "prompt": "hi",
+            "parameters": [{"name": "temperature", "vaule": 1}], "top_p": 0.5}"#;
+        let error = Prompt::parse_with(json, Format::Json, ParseOptions::strict()).unwrap_err();
+        assert_eq!(
+            error.to_string(),
+            "unknown field 'parameters[0].vaule' at line 2 column 52"
+        );
+
+        let at = |line, column| Some(Location { line, column });
+        let located = |source: &str| -> Vec<(String, Option<Location>)> {
+            let prompt = Prompt::parse_with(source, Format::Yaml, ParseOptions::default()).unwrap();
+            let fields = prompt.unknown_fields().iter();
+            fields.map(|f| (f.path.clone(), f.location)).collect()
+        };
+        let source = "type: chat\nvendor: openai\nmodel: gpt-4o\nparameters:\n  - name: temperature\n    value: 0.2\n    vaule: 0.2\nmessages:\n  - input: hi\n  - input: there\n    outptu: hello\nvaule: 1\n";
+        assert_eq!(
+            located(source),
+            vec![
+                ("vaule".to_string(), at(12, 1)),
+                ("parameters[0].vaule".to_string(), at(7, 5)),
+                ("messages[1].outptu".to_string(), at(11, 5)),
+            ]
+        );
+        let flow = "type: chat\nvendor: openai\nmodel: gpt-4o\nmessages: [{input: hi, output: x}, {input: there, outptu: x}]\noutptu: 1\n";
+        assert_eq!(
+            located(flow),
+            vec![
+                ("outptu".to_string(), at(5, 1)),
+                ("messages[1].outptu".to_string(), at(4, 51)),
+            ]
+        );
+        assert!(Prompt::parse_with(
+            "type = 'embedding'\nvendor = 'openai'\nmodel = 'e'\ninput = 'x'\n",
+            Format::Toml,
+            ParseOptions::strict()
+        )
+        .is_ok());
+    }
+
+    #[test]
+    fn test_unknown_nested_fields() {
+        let source = r#"type: chat
+vendor: openai
+model: gpt-4o
+variables:
+  city:
+    type: string
+    requried: true
+tools:
+  - name: weather
+    parmeters: {}
+output:
+  format: json
+  shema: {}
+policy:
+  max_retires: 3
+post_process:
+  - trim
+  - redact:
+      pattern: secret
+      replacment: x
+messages:
+  - input: hi
+"#;
+        let prompt = Prompt::parse_with(source, Format::Yaml, ParseOptions::default()).unwrap();
+        let fields: Vec<(&str, Option<usize>)> = prompt
+            .unknown_fields()
+            .iter()
+            .map(|f| (f.path.as_str(), f.location.map(|l| l.line)))
+            .collect();
+        assert_eq!(
+            fields,
+            vec![
+                ("tools[0].parmeters", Some(10)),
+                ("output.shema", Some(13)),
+                ("policy.max_retires", Some(15)),
+                ("post_process[1].redact.replacment", Some(20)),
+                ("variables.city.requried", Some(7)),
+            ]
+        );
+        let format = "type: completion\nvendor: openai\nmodel: gpt\nprompt: hi\nvariables: [{name: city, typ: string}]\n";
+        assert_eq!(
+            unknown_fields(&document(format, Format::Yaml).unwrap()),
+            vec!["variables[0].typ"]
+        );
+    }
+}
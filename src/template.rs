@@ -0,0 +1,811 @@
+use crate::content::ContentPart;
+use crate::prompt::{Chat, Completion, Embedding, Prompt};
+use crate::sanitize::SanitizeError;
+use crate::variables::{prepare_context, prepare_vars, VariableType};
+use serde_json::{Map, Value};
+use std::borrow::Cow;
+use std::collections::HashMap;
+use std::fmt;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, RwLock};
+
+pub type Context = Map<String, Value>;
+
+const TEMPLATE_CACHE_LIMIT: usize = 256;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum MissingVariable {
+    #[default]
+    Error,
+    Empty,
+    Keep,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum RenderError {
+    MissingVariable(String),
+    UnclosedTag {
+        position: usize,
+    },
+    EmptyTag {
+        position: usize,
+    },
+    UnexpectedTag {
+        tag: String,
+        position: usize,
+    },
+    UnclosedSection {
+        section: String,
+        position: usize,
+    },
+    Sanitize {
+        variable: String,
+        error: SanitizeError,
+    },
+    InvalidVariable {
+        name: String,
+        expected: VariableType,
+    },
+}
+
+impl fmt::Display for RenderError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            RenderError::MissingVariable(name) => write!(f, "missing variable '{}'", name),
+            RenderError::UnclosedTag { position } => {
+                write!(f, "unclosed '{{{{' at byte {}", position)
+            }
+            RenderError::EmptyTag { position } => write!(f, "empty tag at byte {}", position),
+            RenderError::UnexpectedTag { tag, position } => {
+                write!(f, "unexpected '{{{{{}}}}}' at byte {}", tag, position)
+            }
+            RenderError::UnclosedSection { section, position } => {
+                write!(f, "unclosed '{{{{#{}}}}}' at byte {}", section, position)
+            }
+            RenderError::Sanitize { variable, error } => {
+                write!(f, "variable '{}': {}", variable, error)
+            }
+            RenderError::InvalidVariable { name, expected } => {
+                write!(f, "variable '{}' must be a {}", name, expected)
+            }
+        }
+    }
+}
+
+impl std::error::Error for RenderError {}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum SectionKind {
+    If,
+    Unless,
+    Each,
+}
+
+impl SectionKind {
+    fn keyword(&self) -> &'static str {
+        match self {
+            SectionKind::If => "if",
+            SectionKind::Unless => "unless",
+            SectionKind::Each => "each",
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum Node {
+    Text(String),
+    Variable {
+        name: String,
+        raw: String,
+    },
+    Section {
+        kind: SectionKind,
+        name: String,
+        body: Vec<Node>,
+        otherwise: Vec<Node>,
+    },
+}
+
+struct Frame {
+    kind: SectionKind,
+    name: String,
+    position: usize,
+    body: Vec<Node>,
+    otherwise: Option<Vec<Node>>,
+}
+
+fn current<'a>(root: &'a mut Vec<Node>, stack: &'a mut [Frame]) -> &'a mut Vec<Node> {
+    match stack.last_mut() {
+        Some(Frame {
+            otherwise: Some(nodes),
+            ..
+        }) => nodes,
+        Some(frame) => &mut frame.body,
+        None => root,
+    }
+}
+
+fn standalone(text: &str, from: usize, start: usize, after: usize) -> Option<(usize, usize)> {
+    let line_start = text[..start].rfind('\n').map_or(0, |i| i + 1);
+    let blank = |s: &str| s.chars().all(|c| c == ' ' || c == '\t');
+    if line_start < from || !blank(&text[line_start..start]) {
+        return None;
+    }
+    let rest = &text[after..];
+    let trimmed = rest.trim_start_matches([' ', '\t']);
+    let skipped = rest.len() - trimmed.len();
+    if trimmed.is_empty() {
+        Some((line_start, text.len()))
+    } else if trimmed.starts_with("\r\n") {
+        Some((line_start, after + skipped + 2))
+    } else if trimmed.starts_with('\n') {
+        Some((line_start, after + skipped + 1))
+    } else {
+        None
+    }
+}
+
+fn is_local(name: &str) -> bool {
+    name == "this" || name.starts_with("this.") || name.starts_with('@')
+}
+
+fn truthy(value: Option<&Value>) -> bool {
+    match value {
+        None | Some(Value::Null) => false,
+        Some(Value::Bool(b)) => *b,
+        Some(Value::Number(n)) => n.as_f64() != Some(0.0),
+        Some(Value::String(s)) => !matches!(s.as_str(), "" | "false" | "0"),
+        Some(Value::Array(items)) => !items.is_empty(),
+        Some(Value::Object(fields)) => !fields.is_empty(),
+    }
+}
+
+fn value_text(value: &Value) -> Cow<'_, str> {
+    match value {
+        Value::String(s) => Cow::Borrowed(s),
+        Value::Null => Cow::Borrowed(""),
+        other => Cow::Owned(other.to_string()),
+    }
+}
+
+fn path<'a>(mut value: &'a Value, path: &str) -> Option<&'a Value> {
+    for segment in path.split('.') {
+        value = match value {
+            Value::Object(fields) => fields.get(segment)?,
+            Value::Array(items) => items.get(segment.parse::<usize>().ok()?)?,
+            _ => return None,
+        };
+    }
+    Some(value)
+}
+
+struct Item<'a> {
+    value: &'a Value,
+    key: Option<&'a str>,
+    index: usize,
+    count: usize,
+}
+
+struct Scope<'a> {
+    context: &'a Context,
+    item: Option<&'a Item<'a>>,
+}
+
+impl<'a> Scope<'a> {
+    fn lookup(&self, name: &str) -> Option<Cow<'a, Value>> {
+        if let Some(item) = self.item {
+            match name {
+                "this" => return Some(Cow::Borrowed(item.value)),
+                "@index" => return Some(Cow::Owned(Value::from(item.index))),
+                "@first" => return Some(Cow::Owned(Value::Bool(item.index == 0))),
+                "@last" => return Some(Cow::Owned(Value::Bool(item.index + 1 == item.count))),
+                "@key" => return item.key.map(|k| Cow::Owned(Value::from(k))),
+                _ => {}
+            }
+            if let Some(field) = name.strip_prefix("this.") {
+                return path(item.value, field).map(Cow::Borrowed);
+            }
+        }
+        if let Some(value) = self.context.get(name) {
+            return Some(Cow::Borrowed(value));
+        }
+        let (root, rest) = name.split_once('.')?;
+        path(self.context.get(root)?, rest).map(Cow::Borrowed)
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Template {
+    nodes: Vec<Node>,
+}
+
+impl Template {
+    pub fn parse(text: &str) -> Result<Template, RenderError> {
+        let mut root = Vec::new();
+        let mut stack: Vec<Frame> = Vec::new();
+        let mut pos = 0;
+        while let Some(found) = text[pos..].find("{{") {
+            let start = pos + found;
+            let end = text[start..]
+                .find("}}")
+                .map(|e| start + e)
+                .ok_or(RenderError::UnclosedTag { position: start })?;
+            let tag = text[start + 2..end].trim();
+            if tag.is_empty() {
+                return Err(RenderError::EmptyTag { position: start });
+            }
+            let block = tag.starts_with('#') || tag.starts_with('/') || tag == "else";
+            let (text_end, next) = match standalone(text, pos, start, end + 2) {
+                Some(bounds) if block => bounds,
+                _ => (start, end + 2),
+            };
+            if text_end > pos {
+                current(&mut root, &mut stack).push(Node::Text(text[pos..text_end].to_string()));
+            }
+            let unexpected = || RenderError::UnexpectedTag {
+                tag: tag.to_string(),
+                position: start,
+            };
+            if !block {
+                current(&mut root, &mut stack).push(Node::Variable {
+                    name: tag.to_string(),
+                    raw: text[start..end + 2].to_string(),
+                });
+            } else if let Some(open) = tag.strip_prefix('#') {
+                let (keyword, name) = open
+                    .split_once(char::is_whitespace)
+                    .map_or((open, ""), |(k, n)| (k, n.trim()));
+                let kind = match keyword {
+                    "if" => SectionKind::If,
+                    "unless" => SectionKind::Unless,
+                    "each" => SectionKind::Each,
+                    _ => return Err(unexpected()),
+                };
+                if name.is_empty() {
+                    return Err(RenderError::EmptyTag { position: start });
+                }
+                stack.push(Frame {
+                    kind,
+                    name: name.to_string(),
+                    position: start,
+                    body: Vec::new(),
+                    otherwise: None,
+                });
+            } else if tag == "else" {
+                match stack.last_mut() {
+                    Some(frame) if frame.otherwise.is_none() => frame.otherwise = Some(Vec::new()),
+                    _ => return Err(unexpected()),
+                }
+            } else {
+                let frame = match stack.pop() {
+                    Some(frame) if frame.kind.keyword() == tag[1..].trim() => frame,
+                    _ => return Err(unexpected()),
+                };
+                current(&mut root, &mut stack).push(Node::Section {
+                    kind: frame.kind,
+                    name: frame.name,
+                    body: frame.body,
+                    otherwise: frame.otherwise.unwrap_or_default(),
+                });
+            }
+            pos = next;
+        }
+        if let Some(frame) = stack.pop() {
+            return Err(RenderError::UnclosedSection {
+                section: frame.kind.keyword().to_string(),
+                position: frame.position,
+            });
+        }
+        if pos < text.len() {
+            root.push(Node::Text(text[pos..].to_string()));
+        }
+        Ok(Template { nodes: root })
+    }
+
+    pub fn variables(&self) -> Vec<String> {
+        fn collect(nodes: &[Node], names: &mut Vec<String>) {
+            for node in nodes {
+                let name = match node {
+                    Node::Text(_) => continue,
+                    Node::Variable { name, .. } | Node::Section { name, .. } => name,
+                };
+                if !is_local(name) && !names.contains(name) {
+                    names.push(name.clone());
+                }
+                if let Node::Section {
+                    body, otherwise, ..
+                } = node
+                {
+                    collect(body, names);
+                    collect(otherwise, names);
+                }
+            }
+        }
+        let mut names: Vec<String> = Vec::new();
+        collect(&self.nodes, &mut names);
+        names
+    }
+
+    pub fn render(
+        &self,
+        vars: &HashMap<String, String>,
+        missing: MissingVariable,
+    ) -> Result<String, RenderError> {
+        self.render_context(&context(vars), missing)
+    }
+
+    pub fn render_context(
+        &self,
+        context: &Context,
+        missing: MissingVariable,
+    ) -> Result<String, RenderError> {
+        let mut output = String::new();
+        let scope = Scope {
+            context,
+            item: None,
+        };
+        render_nodes(&self.nodes, &scope, missing, &mut output)?;
+        Ok(output)
+    }
+}
+
+fn render_nodes(
+    nodes: &[Node],
+    scope: &Scope<'_>,
+    missing: MissingVariable,
+    output: &mut String,
+) -> Result<(), RenderError> {
+    for node in nodes {
+        match node {
+            Node::Text(text) => output.push_str(text),
+            Node::Variable { name, raw } => match (scope.lookup(name), missing) {
+                (Some(value), _) => output.push_str(&value_text(&value)),
+                (None, MissingVariable::Error) => {
+                    return Err(RenderError::MissingVariable(name.clone()))
+                }
+                (None, MissingVariable::Empty) => {}
+                (None, MissingVariable::Keep) => output.push_str(raw),
+            },
+            Node::Section {
+                kind,
+                name,
+                body,
+                otherwise,
+            } => {
+                let value = scope.lookup(name);
+                let branch = match kind {
+                    SectionKind::If if truthy(value.as_deref()) => body,
+                    SectionKind::Unless if !truthy(value.as_deref()) => body,
+                    SectionKind::If | SectionKind::Unless => otherwise,
+                    SectionKind::Each => {
+                        let items: Vec<(Option<&str>, &Value)> = match value.as_deref() {
+                            None | Some(Value::Null) => Vec::new(),
+                            Some(Value::Array(items)) => items.iter().map(|v| (None, v)).collect(),
+                            Some(Value::Object(fields)) => {
+                                fields.iter().map(|(k, v)| (Some(k.as_str()), v)).collect()
+                            }
+                            Some(Value::String(s)) if s.is_empty() => Vec::new(),
+                            Some(other) => vec![(None, other)],
+                        };
+                        if items.is_empty() {
+                            render_nodes(otherwise, scope, missing, output)?;
+                        }
+                        let count = items.len();
+                        for (index, (key, value)) in items.into_iter().enumerate() {
+                            let item = Item {
+                                value,
+                                key,
+                                index,
+                                count,
+                            };
+                            let scope = Scope {
+                                context: scope.context,
+                                item: Some(&item),
+                            };
+                            render_nodes(body, &scope, missing, output)?;
+                        }
+                        continue;
+                    }
+                };
+                render_nodes(branch, scope, missing, output)?;
+            }
+        }
+    }
+    Ok(())
+}
+
+#[derive(Default)]
+pub struct TemplateCache {
+    templates: RwLock<HashMap<String, (Arc<Template>, AtomicU64)>>,
+    clock: AtomicU64,
+}
+
+impl Clone for TemplateCache {
+    fn clone(&self) -> TemplateCache {
+        TemplateCache::default()
+    }
+}
+
+impl TemplateCache {
+    pub fn parse(&self, text: &str) -> Result<Arc<Template>, RenderError> {
+        let tick = self.clock.fetch_add(1, Ordering::Relaxed);
+        let cached = self.templates.read().unwrap_or_else(|e| e.into_inner());
+        if let Some((template, used)) = cached.get(text) {
+            used.store(tick, Ordering::Relaxed);
+            return Ok(template.clone());
+        }
+        drop(cached);
+        let template = Arc::new(Template::parse(text)?);
+        let mut templates = self.templates.write().unwrap_or_else(|e| e.into_inner());
+        if templates.len() >= TEMPLATE_CACHE_LIMIT {
+            let oldest = templates
+                .iter()
+                .min_by_key(|(_, (_, used))| used.load(Ordering::Relaxed))
+                .map(|(text, _)| text.clone());
+            if let Some(oldest) = oldest {
+                templates.remove(&oldest);
+            }
+        }
+        templates.insert(text.to_string(), (template.clone(), AtomicU64::new(tick)));
+        Ok(template)
+    }
+
+    pub fn len(&self) -> usize {
+        self.templates.read().map_or(0, |t| t.len())
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+}
+
+impl fmt::Debug for TemplateCache {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "TemplateCache({} templates)", self.len())
+    }
+}
+
+impl PartialEq for TemplateCache {
+    fn eq(&self, _: &TemplateCache) -> bool {
+        true
+    }
+}
+
+pub fn context(vars: &HashMap<String, String>) -> Context {
+    vars.iter()
+        .map(|(name, value)| (name.clone(), Value::String(value.clone())))
+        .collect()
+}
+
+pub fn render_template(
+    text: &str,
+    vars: &HashMap<String, String>,
+    missing: MissingVariable,
+) -> Result<String, RenderError> {
+    Template::parse(text)?.render(vars, missing)
+}
+
+pub fn render_template_context(
+    text: &str,
+    context: &Context,
+    missing: MissingVariable,
+) -> Result<String, RenderError> {
+    Template::parse(text)?.render_context(context, missing)
+}
+
+fn collect_variables(texts: &[&str]) -> Result<Vec<String>, RenderError> {
+    let mut names: Vec<String> = Vec::new();
+    for text in texts {
+        for name in Template::parse(text)?.variables() {
+            if !names.contains(&name) {
+                names.push(name);
+            }
+        }
+    }
+    Ok(names)
+}
+
+impl Completion {
+    pub fn required_variables(&self) -> Result<Vec<String>, RenderError> {
+        collect_variables(&[&self.final_prompt()])
+    }
+
+    pub fn render(&self, vars: &HashMap<String, String>) -> Result<String, RenderError> {
+        self.render_with(vars, MissingVariable::Error)
+    }
+
+    pub fn render_with(
+        &self,
+        vars: &HashMap<String, String>,
+        missing: MissingVariable,
+    ) -> Result<String, RenderError> {
+        let context = prepare_vars(self.meta.variables.as_ref(), vars)?;
+        let template = self.meta.templates().parse(&self.final_prompt())?;
+        template.render_context(&context, missing)
+    }
+
+    pub fn render_context(&self, context: &Context) -> Result<String, RenderError> {
+        let context = prepare_context(self.meta.variables.as_ref(), context)?;
+        let template = self.meta.templates().parse(&self.final_prompt())?;
+        template.render_context(&context, MissingVariable::Error)
+    }
+}
+
+impl Chat {
+    fn template_texts(&self) -> Vec<&str> {
+        let mut texts: Vec<&str> = Vec::new();
+        if let Some(context) = &self.context {
+            texts.push(context);
+        }
+        for example in self.examples.iter().flatten() {
+            texts.push(&example.input);
+            texts.extend(example.output.as_deref());
+        }
+        for message in self.messages.iter().flatten() {
+            texts.push(&message.input);
+            texts.extend(message.content.iter().flatten().filter_map(|p| p.text()));
+            texts.extend(message.output.as_deref());
+        }
+        texts
+    }
+
+    pub fn required_variables(&self) -> Result<Vec<String>, RenderError> {
+        collect_variables(&self.template_texts())
+    }
+
+    pub fn render(&self, vars: &HashMap<String, String>) -> Result<Chat, RenderError> {
+        self.render_with(vars, MissingVariable::Error)
+    }
+
+    pub fn render_with(
+        &self,
+        vars: &HashMap<String, String>,
+        missing: MissingVariable,
+    ) -> Result<Chat, RenderError> {
+        let context = prepare_vars(self.meta.variables.as_ref(), vars)?;
+        let templates = &self.meta.templates();
+        self.render_fields(|text| templates.parse(text)?.render_context(&context, missing))
+    }
+
+    pub fn render_context(&self, context: &Context) -> Result<Chat, RenderError> {
+        let context = prepare_context(self.meta.variables.as_ref(), context)?;
+        let templates = &self.meta.templates();
+        self.render_fields(|text| {
+            templates
+                .parse(text)?
+                .render_context(&context, MissingVariable::Error)
+        })
+    }
+
+    fn render_fields(
+        &self,
+        render: impl Fn(&str) -> Result<String, RenderError>,
+    ) -> Result<Chat, RenderError> {
+        let mut chat = self.clone();
+        if let Some(context) = &mut chat.context {
+            *context = render(context)?;
+        }
+        for example in chat.examples.iter_mut().flatten() {
+            example.input = render(&example.input)?;
+            if let Some(output) = &mut example.output {
+                *output = render(output)?;
+            }
+        }
+        for message in chat.messages.iter_mut().flatten() {
+            message.input = render(&message.input)?;
+            for part in message.content.iter_mut().flatten() {
+                if let ContentPart::Text(text) = part {
+                    *text = render(text)?;
+                }
+            }
+            if let Some(output) = &mut message.output {
+                *output = render(output)?;
+            }
+        }
+        Ok(chat)
+    }
+}
+
+impl Embedding {
+    pub fn required_variables(&self) -> Result<Vec<String>, RenderError> {
+        let texts: Vec<&str> = self.input.iter().map(|i| i.as_str()).collect();
+        collect_variables(&texts)
+    }
+
+    pub fn render(&self, vars: &HashMap<String, String>) -> Result<Embedding, RenderError> {
+        self.render_with(vars, MissingVariable::Error)
+    }
+
+    pub fn render_with(
+        &self,
+        vars: &HashMap<String, String>,
+        missing: MissingVariable,
+    ) -> Result<Embedding, RenderError> {
+        let context = prepare_vars(self.meta.variables.as_ref(), vars)?;
+        let mut embedding = self.clone();
+        for input in &mut embedding.input {
+            *input = self
+                .meta
+                .templates()
+                .parse(input)?
+                .render_context(&context, missing)?;
+        }
+        Ok(embedding)
+    }
+
+    pub fn render_context(&self, context: &Context) -> Result<Embedding, RenderError> {
+        let context = prepare_context(self.meta.variables.as_ref(), context)?;
+        let mut embedding = self.clone();
+        for input in &mut embedding.input {
+            *input = self
+                .meta
+                .templates()
+                .parse(input)?
+                .render_context(&context, MissingVariable::Error)?;
+        }
+        Ok(embedding)
+    }
+}
+
+impl Prompt {
+    pub fn required_variables(&self) -> Result<Vec<String>, RenderError> {
+        match self {
+            Prompt::Completion(completion) => completion.required_variables(),
+            Prompt::Chat(chat) => chat.required_variables(),
+            Prompt::Embedding(embedding) => embedding.required_variables(),
+            Prompt::Custom(kind) => kind.required_variables(),
+            Prompt::Unknown => Ok(Vec::new()),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::prompt::deserialize_prompt;
+
+    fn vars(pairs: &[(&str, &str)]) -> HashMap<String, String> {
+        pairs
+            .iter()
+            .map(|(k, v)| (k.to_string(), v.to_string()))
+            .collect()
+    }
+
+    #[test]
+    fn test_render_completion() {
+        let yaml = r#"
+            type: completion
+            vendor: google
+            model: text-bison
+            prompt: "Summarize the following {{language}} code: {{ code }}"
+        "#;
+
+        if let Prompt::Completion(completion) = deserialize_prompt(yaml) {
+            assert_eq!(
+                completion.required_variables(),
+                Ok(vec!["language".to_string(), "code".to_string()])
+            );
+            assert_eq!(
+                completion.render(&vars(&[("language", "rust"), ("code", "fn main() {}")])),
+                Ok("Summarize the following rust code: fn main() {}".to_string())
+            );
+            let partial = vars(&[("language", "rust")]);
+            assert_eq!(
+                completion.render(&partial),
+                Err(RenderError::MissingVariable("code".to_string()))
+            );
+            assert_eq!(
+                completion.render_with(&partial, MissingVariable::Empty),
+                Ok("Summarize the following rust code: ".to_string())
+            );
+            assert_eq!(
+                completion.render_with(&partial, MissingVariable::Keep),
+                Ok("Summarize the following rust code: {{ code }}".to_string())
+            );
+        } else {
+            panic!("Expected Prompt::Completion");
+        }
+    }
+
+    #[test]
+    fn test_render_chat() {
+        let yaml = r#"
+            type: chat
+            vendor: google
+            model: chat-bison
+            context: You are a {{role}}
+            messages:
+                - input: Tell me about {{topic}}
+        "#;
+
+        let prompt = deserialize_prompt(yaml);
+        assert_eq!(
+            prompt.required_variables(),
+            Ok(vec!["role".to_string(), "topic".to_string()])
+        );
+        if let Prompt::Chat(chat) = prompt {
+            let rendered = chat
+                .render(&vars(&[("role", "tutor"), ("topic", "rust")]))
+                .unwrap();
+            assert_eq!(rendered.context, Some("You are a tutor".to_string()));
+            assert_eq!(rendered.messages.unwrap()[0].input, "Tell me about rust");
+            assert_eq!(chat.meta.templates().len(), 2);
+            assert!(rendered.meta.templates().is_empty());
+            assert!(chat.clone().meta.templates().is_empty());
+
+            let cache = TemplateCache::default();
+            for i in 0..TEMPLATE_CACHE_LIMIT + 10 {
+                cache.parse("{{role}}").unwrap();
+                cache.parse(&format!("user text {}", i)).unwrap();
+            }
+            assert_eq!(cache.len(), TEMPLATE_CACHE_LIMIT);
+            assert!(cache.templates.read().unwrap().contains_key("{{role}}"));
+            assert!(cache
+                .templates
+                .read()
+                .unwrap()
+                .contains_key(&format!("user text {}", TEMPLATE_CACHE_LIMIT + 9)));
+        } else {
+            panic!("Expected Prompt::Chat");
+        }
+    }
+
+    #[test]
+    fn test_parse_errors() {
+        assert_eq!(
+            Template::parse("Hello {{name"),
+            Err(RenderError::UnclosedTag { position: 6 })
+        );
+        assert_eq!(
+            Template::parse("Hello {{ }}"),
+            Err(RenderError::EmptyTag { position: 6 })
+        );
+        assert_eq!(
+            Template::parse("{{#if a}}x{{#each b}}y{{/if}}"),
+            Err(RenderError::UnexpectedTag {
+                tag: "/if".to_string(),
+                position: 22
+            })
+        );
+        assert_eq!(
+            Template::parse("ok {{#unless a}}x"),
+            Err(RenderError::UnclosedSection {
+                section: "unless".to_string(),
+                position: 3
+            })
+        );
+    }
+
+    #[test]
+    fn test_sections() {
+        let template = Template::parse(
+            "Hi {{name}}.\n{{#if premium_user}}\nPriority support.\n{{else}}\nUpgrade today.\n{{/if}}\n{{#each items}}- {{this.title}}{{#unless @last}},{{/unless}}\n{{else}}No items.\n{{/each}}",
+        )
+        .unwrap();
+        assert_eq!(template.variables(), vec!["name", "premium_user", "items"]);
+        let context: Context = serde_json::from_value(serde_json::json!({
+            "name": "Ann",
+            "premium_user": true,
+            "items": [{ "title": "Tea" }, { "title": "Cake" }],
+        }))
+        .unwrap();
+        assert_eq!(
+            template.render_context(&context, MissingVariable::Error),
+            Ok("Hi Ann.\nPriority support.\n- Tea,\n- Cake\n".to_string())
+        );
+        assert_eq!(
+            template.render(
+                &vars(&[("name", "Bob"), ("premium_user", "false")]),
+                MissingVariable::Error
+            ),
+            Ok("Hi Bob.\nUpgrade today.\nNo items.\n".to_string())
+        );
+        let list = Template::parse("{{#each tags}}[{{@index}}:{{this}}]{{/each}}").unwrap();
+        let context: Context =
+            serde_json::from_value(serde_json::json!({ "tags": ["a", 2] })).unwrap();
+        assert_eq!(
+            list.render_context(&context, MissingVariable::Error),
+            Ok("[0:a][1:2]".to_string())
+        );
+    }
+}
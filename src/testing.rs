@@ -0,0 +1,368 @@
+use crate::content::ContentPart;
+use crate::diff::{line_diff, LineChange};
+use crate::prompt::{Chat, Parameter, Prompt};
+use crate::sampling::Sampling;
+use crate::template::RenderError;
+use std::collections::HashMap;
+use std::fmt;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+pub const UPDATE_ENV: &str = "PROMPT_SNAPSHOT_UPDATE";
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum SnapshotError {
+    Render(RenderError),
+    Unnamed,
+    Io {
+        path: PathBuf,
+        message: String,
+    },
+    Missing(PathBuf),
+    Mismatch {
+        path: PathBuf,
+        lines: Vec<LineChange>,
+    },
+}
+
+impl fmt::Display for SnapshotError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            SnapshotError::Render(error) => write!(f, "{}", error),
+            SnapshotError::Unnamed => write!(f, "prompt has no name, pass a snapshot name"),
+            SnapshotError::Io { path, message } => write!(f, "{}: {}", path.display(), message),
+            SnapshotError::Missing(path) => write!(
+                f,
+                "snapshot {} does not exist, set {}=1 to create it",
+                path.display(),
+                UPDATE_ENV
+            ),
+            SnapshotError::Mismatch { path, lines } => {
+                writeln!(f, "snapshot {} does not match:", path.display())?;
+                for line in lines {
+                    match line {
+                        LineChange::Same(line) => writeln!(f, "  {}", line)?,
+                        LineChange::Added(line) => writeln!(f, "+ {}", line)?,
+                        LineChange::Removed(line) => writeln!(f, "- {}", line)?,
+                    }
+                }
+                write!(f, "set {}=1 to accept the new rendering", UPDATE_ENV)
+            }
+        }
+    }
+}
+
+impl std::error::Error for SnapshotError {}
+
+impl From<RenderError> for SnapshotError {
+    fn from(error: RenderError) -> Self {
+        SnapshotError::Render(error)
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SnapshotOutcome {
+    Matched,
+    Created,
+    Updated,
+}
+
+pub fn normalize(text: &str) -> String {
+    let mut lines: Vec<&str> = Vec::new();
+    for line in text.lines().map(|l| l.trim_end()) {
+        if line.is_empty() && lines.last().is_none_or(|l| l.is_empty()) {
+            continue;
+        }
+        lines.push(line);
+    }
+    while lines.last().is_some_and(|l| l.is_empty()) {
+        lines.pop();
+    }
+    let mut normalized = lines.join("\n");
+    normalized.push('\n');
+    normalized
+}
+
+fn header(
+    lines: &mut Vec<String>,
+    vendor: &str,
+    model: &str,
+    parameters: &Option<Vec<Parameter>>,
+    sampling: Option<&Sampling>,
+) {
+    lines.push(format!("vendor: {}", vendor));
+    lines.push(format!("model: {}", model));
+    for parameter in parameters.iter().flatten() {
+        let value = serde_json::to_string(&parameter.value).unwrap_or_default();
+        lines.push(format!("{}: {}", parameter.name, value));
+    }
+    for (name, value) in sampling.map(|s| s.fields()).unwrap_or_default() {
+        lines.push(format!("{}: {}", name, value));
+    }
+}
+
+fn section(lines: &mut Vec<String>, role: &str, text: &str) {
+    if lines.last().is_some_and(|l| l != "---") {
+        lines.push(String::new());
+    }
+    lines.push(format!("## {}", role));
+    lines.push(text.to_string());
+}
+
+fn chat_text(chat: &Chat, lines: &mut Vec<String>) {
+    let tools: Vec<&str> = chat
+        .tools
+        .iter()
+        .flatten()
+        .map(|t| t.name.as_str())
+        .collect();
+    if !tools.is_empty() {
+        lines.push(format!("tools: {}", tools.join(", ")));
+    }
+    lines.push("---".to_string());
+    if let Some(context) = &chat.context {
+        section(lines, "system", context);
+    }
+    for example in chat.examples.iter().flatten() {
+        section(lines, "user", &example.input);
+        section(lines, "assistant", example.output.as_deref().unwrap_or(""));
+    }
+    for message in chat.messages.iter().flatten() {
+        let mut input = vec![message.input.clone()];
+        for part in message.content.iter().flatten() {
+            input.push(match part {
+                ContentPart::Text(text) => text.clone(),
+                ContentPart::Image(source) => format!("[image: {}]", source.reference()),
+                ContentPart::Document(source) => format!("[document: {}]", source.reference()),
+            });
+        }
+        input.retain(|part| !part.is_empty());
+        section(lines, "user", &input.join("\n"));
+        if let Some(output) = &message.output {
+            section(lines, "assistant", output);
+        }
+    }
+}
+
+pub fn snapshot_text(
+    prompt: &Prompt,
+    vars: &HashMap<String, String>,
+) -> Result<String, RenderError> {
+    let mut lines = Vec::new();
+    match prompt {
+        Prompt::Completion(completion) => {
+            let rendered = completion.render(vars)?;
+            let (vendor, model) = (&completion.vendor, &completion.model);
+            header(
+                &mut lines,
+                vendor,
+                model,
+                &completion.parameters,
+                Some(&completion.sampling),
+            );
+            lines.push("---".to_string());
+            lines.push(rendered);
+        }
+        Prompt::Chat(chat) => {
+            let chat = chat.render(vars)?;
+            header(
+                &mut lines,
+                &chat.vendor,
+                &chat.model,
+                &chat.parameters,
+                Some(&chat.sampling),
+            );
+            chat_text(&chat, &mut lines);
+        }
+        Prompt::Embedding(embedding) => {
+            let embedding = embedding.render(vars)?;
+            header(&mut lines, &embedding.vendor, &embedding.model, &None, None);
+            lines.push("---".to_string());
+            for input in &embedding.input {
+                section(&mut lines, "input", input);
+            }
+        }
+        Prompt::Custom(kind) => {
+            lines.push(format!("type: {}", kind.kind()));
+            lines.push("---".to_string());
+            let value = serde_json::to_string_pretty(&kind.to_value()).unwrap_or_default();
+            lines.push(value);
+        }
+        Prompt::Unknown => lines.push("type: unknown".to_string()),
+    }
+    Ok(normalize(&lines.join("\n")))
+}
+
+pub fn snapshot_name(prompt: &Prompt) -> Result<String, SnapshotError> {
+    prompt
+        .meta()
+        .and_then(|meta| meta.name.clone())
+        .ok_or(SnapshotError::Unnamed)
+}
+
+pub fn snapshot_path(dir: impl AsRef<Path>, module: &str, name: &str) -> PathBuf {
+    let file: String = format!("{}__{}", module.replace("::", "__"), name)
+        .chars()
+        .map(|c| match c {
+            c if c.is_alphanumeric() || c == '_' || c == '-' || c == '.' => c,
+            _ => '_',
+        })
+        .collect();
+    dir.as_ref().join(format!("{}.snap", file))
+}
+
+pub fn update_requested() -> bool {
+    std::env::var(UPDATE_ENV).is_ok_and(|v| matches!(v.as_str(), "1" | "true" | "always"))
+}
+
+pub fn check_snapshot(
+    path: impl AsRef<Path>,
+    prompt: &Prompt,
+    vars: &HashMap<String, String>,
+    update: bool,
+) -> Result<SnapshotOutcome, SnapshotError> {
+    let path = path.as_ref();
+    let io_error = |path: &Path, error: std::io::Error| SnapshotError::Io {
+        path: path.to_path_buf(),
+        message: error.to_string(),
+    };
+    let actual = snapshot_text(prompt, vars)?;
+    let expected = match fs::read_to_string(path) {
+        Ok(expected) => Some(normalize(&expected)),
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => None,
+        Err(e) => return Err(io_error(path, e)),
+    };
+    let outcome = match &expected {
+        Some(expected) if *expected == actual => return Ok(SnapshotOutcome::Matched),
+        Some(expected) if !update => {
+            return Err(SnapshotError::Mismatch {
+                path: path.to_path_buf(),
+                lines: line_diff(expected, &actual),
+            })
+        }
+        Some(_) => SnapshotOutcome::Updated,
+        None if !update => return Err(SnapshotError::Missing(path.to_path_buf())),
+        None => SnapshotOutcome::Created,
+    };
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent).map_err(|e| io_error(parent, e))?;
+    }
+    fs::write(path, actual).map_err(|e| io_error(path, e))?;
+    Ok(outcome)
+}
+
+#[macro_export]
+macro_rules! assert_prompt_snapshot {
+    ($prompt:expr, $vars:expr) => {{
+        let prompt = &$prompt;
+        match $crate::testing::snapshot_name(prompt) {
+            Ok(name) => $crate::assert_prompt_snapshot!(name, prompt, $vars),
+            Err(error) => panic!("{}", error),
+        }
+    }};
+    ($name:expr, $prompt:expr, $vars:expr) => {{
+        $crate::assert_prompt_snapshot!(
+            dir = ::std::path::Path::new(env!("CARGO_MANIFEST_DIR"))
+                .join("tests")
+                .join("snapshots"),
+            $name,
+            $prompt,
+            $vars
+        )
+    }};
+    (dir = $dir:expr, $name:expr, $prompt:expr, $vars:expr) => {{
+        let path = $crate::testing::snapshot_path($dir, module_path!(), &$name);
+        let update = $crate::testing::update_requested();
+        if let Err(error) = $crate::testing::check_snapshot(&path, &$prompt, &$vars, update) {
+            panic!("{}", error);
+        }
+    }};
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::prompt::deserialize_prompt;
+    use crate::registry::tests::temp_dir;
+
+    const CHAT: &str = r#"
+name: greeting
+type: chat
+vendor: openai
+model: gpt-4o
+parameters:
+  - name: temperature
+    value: 0.2
+context: "You greet {{name}}.   "
+messages:
+  - input: Hi!
+    output: Hello {{name}}
+
+
+  - input: "{{question}}"
+"#;
+
+    fn vars(question: &str) -> HashMap<String, String> {
+        HashMap::from([
+            ("name".to_string(), "Ann".to_string()),
+            ("question".to_string(), question.to_string()),
+        ])
+    }
+
+    #[test]
+    fn test_prompt_snapshot() {
+        let dir = temp_dir("snapshot_macro");
+        let prompt = deserialize_prompt(CHAT);
+        let path = snapshot_path(&dir, module_path!(), "greeting-weather");
+        check_snapshot(&path, &prompt, &vars("Is it sunny?"), true).unwrap();
+        assert!(fs::read_to_string(&path)
+            .unwrap()
+            .ends_with("## user\nIs it sunny?\n"));
+        assert_prompt_snapshot!(dir = &dir, "greeting-weather", prompt, vars("Is it sunny?"));
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn test_check_snapshot() {
+        let dir = temp_dir("snapshot");
+        let path = snapshot_path(&dir, "my_crate::tests", "greeting");
+        assert!(path.ends_with("my_crate__tests__greeting.snap"));
+        let prompt = deserialize_prompt(CHAT);
+        assert_eq!(
+            check_snapshot(&path, &prompt, &vars("a"), false),
+            Err(SnapshotError::Missing(path.clone()))
+        );
+        assert!(!path.exists());
+        assert_eq!(
+            check_snapshot(&path, &prompt, &vars("a"), true),
+            Ok(SnapshotOutcome::Created)
+        );
+        fs::write(
+            &path,
+            fs::read_to_string(&path).unwrap().replace('\n', "  \r\n") + "\n\n",
+        )
+        .unwrap();
+        assert_eq!(
+            check_snapshot(&path, &prompt, &vars("a"), false),
+            Ok(SnapshotOutcome::Matched)
+        );
+        let error = check_snapshot(&path, &prompt, &vars("b"), false).unwrap_err();
+        let SnapshotError::Mismatch { lines, .. } = &error else {
+            panic!("expected a mismatch");
+        };
+        assert!(lines.contains(&LineChange::Removed("a".to_string())));
+        assert!(lines.contains(&LineChange::Added("b".to_string())));
+        assert!(error
+            .to_string()
+            .ends_with("set PROMPT_SNAPSHOT_UPDATE=1 to accept the new rendering"));
+        assert_eq!(
+            check_snapshot(&path, &prompt, &vars("b"), true),
+            Ok(SnapshotOutcome::Updated)
+        );
+        assert_eq!(
+            check_snapshot(&path, &prompt, &vars("b"), false),
+            Ok(SnapshotOutcome::Matched)
+        );
+        fs::remove_dir_all(&dir).unwrap();
+    }
+}
@@ -0,0 +1,438 @@
+use crate::base64;
+use crate::prompt::{Chat, Completion, Embedding};
+use std::collections::HashMap;
+use std::fmt;
+
+const MESSAGE_OVERHEAD: usize = 4;
+
+pub trait Tokenizer {
+    fn count_tokens(&self, text: &str) -> usize;
+}
+
+#[derive(Debug, Clone, Copy, Default)]
+pub struct WhitespaceTokenizer;
+
+impl Tokenizer for WhitespaceTokenizer {
+    fn count_tokens(&self, text: &str) -> usize {
+        text.split_whitespace().count()
+    }
+}
+
+#[derive(Debug, Clone, Copy)]
+pub struct HeuristicTokenizer {
+    pub chars_per_token: f32,
+}
+
+impl Default for HeuristicTokenizer {
+    fn default() -> Self {
+        HeuristicTokenizer {
+            chars_per_token: 4.0,
+        }
+    }
+}
+
+impl Tokenizer for HeuristicTokenizer {
+    fn count_tokens(&self, text: &str) -> usize {
+        let by_chars = (text.chars().count() as f32 / self.chars_per_token).ceil() as usize;
+        by_chars.max(text.split_whitespace().count())
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum TokenError {
+    InvalidVocabulary { line: usize },
+    BudgetExceeded { required: usize, max_tokens: usize },
+}
+
+impl fmt::Display for TokenError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            TokenError::InvalidVocabulary { line } => {
+                write!(f, "invalid vocabulary entry on line {}", line)
+            }
+            TokenError::BudgetExceeded {
+                required,
+                max_tokens,
+            } => write!(
+                f,
+                "prompt needs {} tokens but the budget is {}",
+                required, max_tokens
+            ),
+        }
+    }
+}
+
+impl std::error::Error for TokenError {}
+
+#[derive(Debug, Clone, Default)]
+pub struct BpeTokenizer {
+    ranks: HashMap<Vec<u8>, u32>,
+}
+
+#[derive(PartialEq, Clone, Copy)]
+enum CharClass {
+    Letter,
+    Digit,
+    Space,
+    Other,
+}
+
+fn char_class(c: char) -> CharClass {
+    if c.is_alphabetic() {
+        CharClass::Letter
+    } else if c.is_numeric() {
+        CharClass::Digit
+    } else if c.is_whitespace() {
+        CharClass::Space
+    } else {
+        CharClass::Other
+    }
+}
+
+fn pre_tokenize(text: &str) -> Vec<&str> {
+    let mut pieces = Vec::new();
+    let mut start = 0;
+    let mut previous: Option<CharClass> = None;
+    for (i, c) in text.char_indices() {
+        let class = char_class(c);
+        let split = match previous {
+            None => false,
+            Some(CharClass::Space) => class == CharClass::Space && c == '\n',
+            Some(prev) => prev != class,
+        };
+        if split {
+            pieces.push(&text[start..i]);
+            start = i;
+        }
+        previous = Some(class);
+    }
+    if start < text.len() {
+        pieces.push(&text[start..]);
+    }
+    pieces
+}
+
+impl BpeTokenizer {
+    pub fn new(ranks: HashMap<Vec<u8>, u32>) -> BpeTokenizer {
+        BpeTokenizer { ranks }
+    }
+
+    pub fn from_tiktoken(data: &str) -> Result<BpeTokenizer, TokenError> {
+        let mut ranks = HashMap::new();
+        for (i, line) in data.lines().enumerate() {
+            if line.trim().is_empty() {
+                continue;
+            }
+            let invalid = TokenError::InvalidVocabulary { line: i + 1 };
+            let (token, rank) = line.split_once(' ').ok_or(invalid.clone())?;
+            let token = base64::decode(token).ok_or(invalid.clone())?;
+            let rank = rank.trim().parse().map_err(|_| invalid)?;
+            ranks.insert(token, rank);
+        }
+        Ok(BpeTokenizer { ranks })
+    }
+
+    fn count_piece(&self, piece: &[u8]) -> usize {
+        if self.ranks.contains_key(piece) {
+            return 1;
+        }
+        let mut bounds: Vec<usize> = (0..=piece.len()).collect();
+        while bounds.len() > 2 {
+            let best = (0..bounds.len() - 2)
+                .filter_map(|i| {
+                    self.ranks
+                        .get(&piece[bounds[i]..bounds[i + 2]])
+                        .map(|rank| (*rank, i))
+                })
+                .min();
+            match best {
+                Some((_, i)) => {
+                    bounds.remove(i + 1);
+                }
+                None => break,
+            }
+        }
+        bounds.len() - 1
+    }
+}
+
+impl Tokenizer for BpeTokenizer {
+    fn count_tokens(&self, text: &str) -> usize {
+        pre_tokenize(text)
+            .into_iter()
+            .map(|piece| self.count_piece(piece.as_bytes()))
+            .sum()
+    }
+}
+
+#[derive(Default)]
+pub struct ModelTokenizers {
+    tokenizers: Vec<(String, Box<dyn Tokenizer + Send + Sync>)>,
+    fallback: HeuristicTokenizer,
+}
+
+impl ModelTokenizers {
+    pub fn new() -> ModelTokenizers {
+        ModelTokenizers::default()
+    }
+
+    pub fn register(
+        mut self,
+        model_prefix: impl Into<String>,
+        tokenizer: impl Tokenizer + Send + Sync + 'static,
+    ) -> Self {
+        self.tokenizers
+            .push((model_prefix.into(), Box::new(tokenizer)));
+        self
+    }
+
+    pub fn for_model(&self, model: &str) -> &dyn Tokenizer {
+        self.tokenizers
+            .iter()
+            .filter(|(prefix, _)| model.starts_with(prefix.as_str()))
+            .max_by_key(|(prefix, _)| prefix.len())
+            .map(|(_, tokenizer)| tokenizer.as_ref() as &dyn Tokenizer)
+            .unwrap_or(&self.fallback)
+    }
+}
+
+impl<T: Tokenizer + ?Sized> Tokenizer for &T {
+    fn count_tokens(&self, text: &str) -> usize {
+        (**self).count_tokens(text)
+    }
+}
+
+impl Completion {
+    pub fn with_example_row(&self, row: &HashMap<String, String>) -> Completion {
+        let mut extended = self.clone();
+        let count = self.example_count();
+        if let Some(columns) = &mut extended.examples {
+            for column in columns {
+                column.values.resize(count, String::new());
+                column
+                    .values
+                    .push(row.get(&column.name).cloned().unwrap_or_default());
+            }
+        }
+        extended
+    }
+
+    pub fn estimate_tokens(&self, tokenizer: &impl Tokenizer) -> usize {
+        tokenizer.count_tokens(&self.final_prompt())
+    }
+
+    fn drop_oldest_example(&mut self) -> bool {
+        if !self.has_examples() {
+            return false;
+        }
+        for column in self.examples.iter_mut().flatten() {
+            if !column.values.is_empty() {
+                column.values.remove(0);
+            }
+        }
+        true
+    }
+
+    pub fn fit_to_budget(
+        &mut self,
+        max_tokens: usize,
+        tokenizer: &impl Tokenizer,
+    ) -> Result<(), TokenError> {
+        loop {
+            let required = self.estimate_tokens(tokenizer);
+            if required <= max_tokens {
+                return Ok(());
+            }
+            if !self.drop_oldest_example() {
+                return Err(TokenError::BudgetExceeded {
+                    required,
+                    max_tokens,
+                });
+            }
+        }
+    }
+
+    pub fn cost_of_adding(
+        &self,
+        row: &HashMap<String, String>,
+        tokenizer: &impl Tokenizer,
+    ) -> usize {
+        let before = tokenizer.count_tokens(&self.final_prompt());
+        let after = tokenizer.count_tokens(&self.with_example_row(row).final_prompt());
+        after.saturating_sub(before)
+    }
+}
+
+impl Embedding {
+    pub fn estimate_tokens(&self, tokenizer: &impl Tokenizer) -> usize {
+        self.input.iter().map(|i| tokenizer.count_tokens(i)).sum()
+    }
+}
+
+impl Chat {
+    pub fn estimate_tokens(&self, tokenizer: &impl Tokenizer) -> usize {
+        self.to_transcript()
+            .messages
+            .iter()
+            .map(|m| tokenizer.count_tokens(&m.content) + MESSAGE_OVERHEAD)
+            .sum()
+    }
+
+    fn drop_oldest_turn(&mut self) -> bool {
+        if let Some(examples) = &mut self.examples {
+            if !examples.is_empty() {
+                examples.remove(0);
+                return true;
+            }
+        }
+        match &mut self.messages {
+            Some(messages) if messages.len() > 1 => {
+                messages.remove(0);
+                true
+            }
+            _ => false,
+        }
+    }
+
+    pub fn fit_to_budget(
+        &mut self,
+        max_tokens: usize,
+        tokenizer: &impl Tokenizer,
+    ) -> Result<(), TokenError> {
+        loop {
+            let required = self.estimate_tokens(tokenizer);
+            if required <= max_tokens {
+                return Ok(());
+            }
+            if !self.drop_oldest_turn() {
+                return Err(TokenError::BudgetExceeded {
+                    required,
+                    max_tokens,
+                });
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::prompt::{deserialize_prompt, Prompt};
+
+    #[test]
+    fn test_cost_of_adding() {
+        let yaml = r#"
+            type: completion
+            vendor: google
+            model: text-bison
+            prompt: Translate to french
+            examples:
+                - name: input
+                  values:
+                    - cat
+                  test: bird
+                - name: output
+                  values:
+                    - chat
+        "#;
+
+        if let Prompt::Completion(completion) = deserialize_prompt(yaml) {
+            let row = HashMap::from([
+                ("input".to_string(), "big dog".to_string()),
+                ("output".to_string(), "grand chien".to_string()),
+            ]);
+            assert_eq!(completion.cost_of_adding(&row, &WhitespaceTokenizer), 6);
+
+            let extended = completion.with_example_row(&row);
+            assert_eq!(extended.example_count(), 2);
+            assert_eq!(completion.example_count(), 1);
+        } else {
+            panic!("Expected Prompt::Completion");
+        }
+    }
+
+    #[test]
+    fn test_bpe_tokenizer() {
+        // "a", "b", "ab", "abab" encoded as base64 with their ranks.
+        let tokenizer =
+            BpeTokenizer::from_tiktoken("YQ== 0\nYg== 1\nYWI= 2\nYWJhYg== 3\n").unwrap();
+        assert_eq!(tokenizer.count_tokens("abab"), 1);
+        assert_eq!(tokenizer.count_tokens("ababa"), 2);
+        assert_eq!(tokenizer.count_tokens("ab ab"), 3);
+        assert_eq!(
+            BpeTokenizer::from_tiktoken("YQ== x").unwrap_err(),
+            TokenError::InvalidVocabulary { line: 1 }
+        );
+        assert_eq!(HeuristicTokenizer::default().count_tokens("abcdefghi"), 3);
+
+        let tokenizers = ModelTokenizers::new().register("gpt-4", tokenizer);
+        assert_eq!(tokenizers.for_model("gpt-4o").count_tokens("abab"), 1);
+        assert_eq!(tokenizers.for_model("text-bison").count_tokens("abab"), 1);
+        assert_eq!(
+            tokenizers.for_model("text-bison").count_tokens("ababababa"),
+            3
+        );
+    }
+
+    #[test]
+    fn test_fit_completion_to_budget() {
+        let yaml = r#"
+            type: completion
+            vendor: google
+            model: text-bison
+            prompt: Translate to french
+            examples:
+                - name: input
+                  values: [cat, dog, bird]
+                  test: fish
+                - name: output
+                  values: [chat, chien, oiseau]
+        "#;
+
+        if let Prompt::Completion(mut completion) = deserialize_prompt(yaml) {
+            assert_eq!(completion.estimate_tokens(&WhitespaceTokenizer), 18);
+            completion.fit_to_budget(12, &WhitespaceTokenizer).unwrap();
+            assert_eq!(completion.example_count(), 1);
+            assert_eq!(
+                completion.find_column("input").unwrap().values,
+                vec!["bird"]
+            );
+            assert_eq!(
+                completion.fit_to_budget(2, &WhitespaceTokenizer),
+                Err(TokenError::BudgetExceeded {
+                    required: 3,
+                    max_tokens: 2
+                })
+            );
+        } else {
+            panic!("Expected Prompt::Completion");
+        }
+    }
+
+    #[test]
+    fn test_fit_chat_to_budget() {
+        let yaml = r#"
+            type: chat
+            vendor: openai
+            model: gpt-4o
+            context: Be brief
+            examples:
+                - input: one
+                  output: two
+            messages:
+                - input: three
+                  output: four
+                - input: five
+        "#;
+
+        if let Prompt::Chat(mut chat) = deserialize_prompt(yaml) {
+            assert_eq!(chat.estimate_tokens(&WhitespaceTokenizer), 31);
+            chat.fit_to_budget(15, &WhitespaceTokenizer).unwrap();
+            assert_eq!(chat.examples, Some(vec![]));
+            assert_eq!(chat.messages.as_ref().unwrap().len(), 1);
+            assert_eq!(chat.messages.unwrap()[0].input, "five");
+        } else {
+            panic!("Expected Prompt::Chat");
+        }
+    }
+}
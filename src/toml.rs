@@ -0,0 +1,474 @@
+use serde_json::{Map, Value};
+use std::fmt;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TomlError {
+    pub line: usize,
+    pub message: String,
+}
+
+impl fmt::Display for TomlError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{} at line {}", self.message, self.line)
+    }
+}
+
+struct Parser {
+    chars: Vec<char>,
+    pos: usize,
+}
+
+pub fn parse(source: &str) -> Result<Value, TomlError> {
+    Parser {
+        chars: source.chars().collect(),
+        pos: 0,
+    }
+    .document()
+}
+
+impl Parser {
+    fn line(&self) -> usize {
+        self.chars[..self.pos.min(self.chars.len())]
+            .iter()
+            .filter(|c| **c == '\n')
+            .count()
+            + 1
+    }
+
+    fn error<T>(&self, message: impl Into<String>) -> Result<T, TomlError> {
+        Err(TomlError {
+            line: self.line(),
+            message: message.into(),
+        })
+    }
+
+    fn peek(&self) -> Option<char> {
+        self.chars.get(self.pos).copied()
+    }
+
+    fn starts_with(&self, text: &str) -> bool {
+        text.chars()
+            .enumerate()
+            .all(|(i, c)| self.chars.get(self.pos + i) == Some(&c))
+    }
+
+    fn skip_inline_space(&mut self) {
+        while matches!(self.peek(), Some(' ') | Some('\t')) {
+            self.pos += 1;
+        }
+    }
+
+    fn skip_comment(&mut self) {
+        if self.peek() == Some('#') {
+            while !matches!(self.peek(), None | Some('\n')) {
+                self.pos += 1;
+            }
+        }
+    }
+
+    fn skip_blank(&mut self) {
+        loop {
+            self.skip_inline_space();
+            self.skip_comment();
+            match self.peek() {
+                Some('\n') | Some('\r') => self.pos += 1,
+                _ => return,
+            }
+        }
+    }
+
+    fn end_of_line(&mut self) -> Result<(), TomlError> {
+        self.skip_inline_space();
+        self.skip_comment();
+        match self.peek() {
+            None | Some('\n') => Ok(()),
+            Some('\r') if self.chars.get(self.pos + 1) == Some(&'\n') => Ok(()),
+            Some(c) => self.error(format!("unexpected '{}' after value", c)),
+        }
+    }
+
+    fn document(&mut self) -> Result<Value, TomlError> {
+        let mut root = Map::new();
+        let mut current: Vec<String> = Vec::new();
+        loop {
+            self.skip_blank();
+            match self.peek() {
+                None => break,
+                Some('[') => {
+                    let array = self.starts_with("[[");
+                    self.pos += if array { 2 } else { 1 };
+                    self.skip_inline_space();
+                    let path = self.key()?;
+                    self.skip_inline_space();
+                    let close = if array { "]]" } else { "]" };
+                    if !self.starts_with(close) {
+                        return self.error(format!("expected '{}'", close));
+                    }
+                    self.pos += close.len();
+                    self.end_of_line()?;
+                    if array {
+                        let (last, parents) = path.split_last().unwrap();
+                        let table = self.table_at(&mut root, parents)?;
+                        let entry = table
+                            .entry(last.clone())
+                            .or_insert_with(|| Value::Array(Vec::new()));
+                        match entry {
+                            Value::Array(items) => items.push(Value::Object(Map::new())),
+                            _ => return self.error(format!("'{}' is not an array", last)),
+                        }
+                    } else {
+                        self.table_at(&mut root, &path)?;
+                    }
+                    current = path;
+                }
+                Some(_) => {
+                    let key = self.key()?;
+                    self.skip_inline_space();
+                    if self.peek() != Some('=') {
+                        return self.error("expected '='");
+                    }
+                    self.pos += 1;
+                    self.skip_inline_space();
+                    let value = self.value()?;
+                    self.end_of_line()?;
+                    let table = self.table_at(&mut root, &current)?;
+                    self.insert(table, &key, value)?;
+                }
+            }
+        }
+        Ok(Value::Object(root))
+    }
+
+    fn table_at<'m>(
+        &self,
+        root: &'m mut Map<String, Value>,
+        path: &[String],
+    ) -> Result<&'m mut Map<String, Value>, TomlError> {
+        let mut table = root;
+        for segment in path {
+            let entry = table
+                .entry(segment.clone())
+                .or_insert_with(|| Value::Object(Map::new()));
+            table = match entry {
+                Value::Object(map) => map,
+                Value::Array(items) => match items.last_mut() {
+                    Some(Value::Object(map)) => map,
+                    _ => return self.error(format!("'{}' is not a table", segment)),
+                },
+                _ => return self.error(format!("'{}' is not a table", segment)),
+            };
+        }
+        Ok(table)
+    }
+
+    fn insert(
+        &self,
+        table: &mut Map<String, Value>,
+        key: &[String],
+        value: Value,
+    ) -> Result<(), TomlError> {
+        let (last, parents) = key.split_last().unwrap();
+        let table = self.table_at(table, parents)?;
+        if table.contains_key(last) {
+            return self.error(format!("duplicate key '{}'", last));
+        }
+        table.insert(last.clone(), value);
+        Ok(())
+    }
+
+    fn key(&mut self) -> Result<Vec<String>, TomlError> {
+        let mut parts = Vec::new();
+        loop {
+            self.skip_inline_space();
+            let part = match self.peek() {
+                Some('"') => self.basic_string()?,
+                Some('\'') => self.literal_string()?,
+                _ => {
+                    let start = self.pos;
+                    while matches!(self.peek(), Some(c) if c.is_ascii_alphanumeric() || c == '_' || c == '-')
+                    {
+                        self.pos += 1;
+                    }
+                    if start == self.pos {
+                        return self.error("expected a key");
+                    }
+                    self.chars[start..self.pos].iter().collect()
+                }
+            };
+            parts.push(part);
+            self.skip_inline_space();
+            if self.peek() == Some('.') {
+                self.pos += 1;
+            } else {
+                return Ok(parts);
+            }
+        }
+    }
+
+    fn value(&mut self) -> Result<Value, TomlError> {
+        match self.peek() {
+            Some('"') if self.starts_with("\"\"\"") => self.multiline_basic().map(Value::String),
+            Some('\'') if self.starts_with("'''") => self.multiline_literal().map(Value::String),
+            Some('"') => self.basic_string().map(Value::String),
+            Some('\'') => self.literal_string().map(Value::String),
+            Some('[') => self.array(),
+            Some('{') => self.inline_table(),
+            Some(_) if self.starts_with("true") => {
+                self.pos += 4;
+                Ok(Value::Bool(true))
+            }
+            Some(_) if self.starts_with("false") => {
+                self.pos += 5;
+                Ok(Value::Bool(false))
+            }
+            Some(_) => self.scalar(),
+            None => self.error("expected a value"),
+        }
+    }
+
+    fn scalar(&mut self) -> Result<Value, TomlError> {
+        let start = self.pos;
+        while matches!(self.peek(), Some(c) if !matches!(c, ',' | ']' | '}' | '#' | '\n' | '\r')) {
+            self.pos += 1;
+        }
+        let raw: String = self.chars[start..self.pos].iter().collect();
+        let raw = raw.trim();
+        let cleaned = raw.replace('_', "");
+        if let Ok(integer) = cleaned.parse::<i64>() {
+            return Ok(Value::from(integer));
+        }
+        if let Some(float) = cleaned
+            .parse::<f64>()
+            .ok()
+            .and_then(serde_json::Number::from_f64)
+        {
+            return Ok(Value::Number(float));
+        }
+        if raw.starts_with(|c: char| c.is_ascii_digit()) && raw.contains('-') {
+            return Ok(Value::String(raw.to_string()));
+        }
+        self.error(format!("invalid value '{}'", raw))
+    }
+
+    fn array(&mut self) -> Result<Value, TomlError> {
+        self.pos += 1;
+        let mut items = Vec::new();
+        loop {
+            self.skip_blank();
+            if self.peek() == Some(']') {
+                self.pos += 1;
+                return Ok(Value::Array(items));
+            }
+            items.push(self.value()?);
+            self.skip_blank();
+            match self.peek() {
+                Some(',') => self.pos += 1,
+                Some(']') => {}
+                _ => return self.error("expected ',' or ']' in array"),
+            }
+        }
+    }
+
+    fn inline_table(&mut self) -> Result<Value, TomlError> {
+        self.pos += 1;
+        let mut table = Map::new();
+        loop {
+            self.skip_inline_space();
+            if self.peek() == Some('}') {
+                self.pos += 1;
+                return Ok(Value::Object(table));
+            }
+            let key = self.key()?;
+            self.skip_inline_space();
+            if self.peek() != Some('=') {
+                return self.error("expected '='");
+            }
+            self.pos += 1;
+            self.skip_inline_space();
+            let value = self.value()?;
+            self.insert(&mut table, &key, value)?;
+            self.skip_inline_space();
+            match self.peek() {
+                Some(',') => self.pos += 1,
+                Some('}') => {}
+                _ => return self.error("expected ',' or '}' in inline table"),
+            }
+        }
+    }
+
+    fn escape(&mut self) -> Result<char, TomlError> {
+        let c = self.peek();
+        self.pos += 1;
+        let escaped = match c {
+            Some('n') => '\n',
+            Some('t') => '\t',
+            Some('r') => '\r',
+            Some('b') => '\u{8}',
+            Some('f') => '\u{c}',
+            Some('"') => '"',
+            Some('\\') => '\\',
+            Some(u @ ('u' | 'U')) => {
+                let len = if u == 'u' { 4 } else { 8 };
+                let end = self.pos + len;
+                if end > self.chars.len() {
+                    return self.error("truncated unicode escape");
+                }
+                let hex: String = self.chars[self.pos..end].iter().collect();
+                self.pos = end;
+                match u32::from_str_radix(&hex, 16).ok().and_then(char::from_u32) {
+                    Some(c) => c,
+                    None => return self.error(format!("invalid unicode escape '{}'", hex)),
+                }
+            }
+            _ => return self.error("invalid escape sequence"),
+        };
+        Ok(escaped)
+    }
+
+    fn basic_string(&mut self) -> Result<String, TomlError> {
+        self.pos += 1;
+        let mut text = String::new();
+        loop {
+            match self.peek() {
+                None | Some('\n') => return self.error("unterminated string"),
+                Some('"') => {
+                    self.pos += 1;
+                    return Ok(text);
+                }
+                Some('\\') => {
+                    self.pos += 1;
+                    text.push(self.escape()?);
+                }
+                Some(c) => {
+                    text.push(c);
+                    self.pos += 1;
+                }
+            }
+        }
+    }
+
+    fn literal_string(&mut self) -> Result<String, TomlError> {
+        self.pos += 1;
+        let start = self.pos;
+        loop {
+            match self.peek() {
+                None | Some('\n') => return self.error("unterminated string"),
+                Some('\'') => {
+                    let text = self.chars[start..self.pos].iter().collect();
+                    self.pos += 1;
+                    return Ok(text);
+                }
+                Some(_) => self.pos += 1,
+            }
+        }
+    }
+
+    fn skip_leading_newline(&mut self) {
+        if self.starts_with("\r\n") {
+            self.pos += 2;
+        } else if self.peek() == Some('\n') {
+            self.pos += 1;
+        }
+    }
+
+    fn multiline_basic(&mut self) -> Result<String, TomlError> {
+        self.pos += 3;
+        self.skip_leading_newline();
+        let mut text = String::new();
+        loop {
+            if self.starts_with("\"\"\"") {
+                self.pos += 3;
+                return Ok(text);
+            }
+            match self.peek() {
+                None => return self.error("unterminated string"),
+                Some('\\') => {
+                    self.pos += 1;
+                    if matches!(self.peek(), Some('\n') | Some('\r') | Some(' ')) {
+                        while matches!(self.peek(), Some(c) if c.is_whitespace()) {
+                            self.pos += 1;
+                        }
+                    } else {
+                        text.push(self.escape()?);
+                    }
+                }
+                Some(c) => {
+                    text.push(c);
+                    self.pos += 1;
+                }
+            }
+        }
+    }
+
+    fn multiline_literal(&mut self) -> Result<String, TomlError> {
+        self.pos += 3;
+        self.skip_leading_newline();
+        let start = self.pos;
+        while !self.starts_with("'''") {
+            if self.peek().is_none() {
+                return self.error("unterminated string");
+            }
+            self.pos += 1;
+        }
+        let text = self.chars[start..self.pos].iter().collect();
+        self.pos += 3;
+        Ok(text)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_parse_toml() {
+        let source = r#"
+# a prompt
+type = "completion"
+prompt = """
+Line one
+Line two"""
+literal = 'C:\path'
+numbers = [1, 2.5, -3_000]
+inline = { a = true, "b c" = "d" }
+dotted.key = "x"
+
+[[examples]]
+name = "input"
+values = [
+    "a",
+    "b",
+]
+
+[[examples]]
+name = "output"
+
+[nested.table]
+released = 2024-01-01
+"#;
+        assert_eq!(
+            parse(source).unwrap(),
+            json!({
+                "type": "completion",
+                "prompt": "Line one\nLine two",
+                "literal": "C:\\path",
+                "numbers": [1, 2.5, -3000],
+                "inline": { "a": true, "b c": "d" },
+                "dotted": { "key": "x" },
+                "examples": [{ "name": "input", "values": ["a", "b"] }, { "name": "output" }],
+                "nested": { "table": { "released": "2024-01-01" } },
+            })
+        );
+    }
+
+    #[test]
+    fn test_parse_errors() {
+        assert_eq!(parse("a = 1\nb = \"open\n").unwrap_err().line, 2);
+        assert_eq!(
+            parse("a = 1\na = 2\n").unwrap_err().message,
+            "duplicate key 'a'"
+        );
+        assert!(parse("a = [1 2]").is_err());
+    }
+}
@@ -0,0 +1,112 @@
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct Tool {
+    pub name: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub description: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub parameters: Option<Value>,
+}
+
+impl Tool {
+    pub fn new(name: impl Into<String>) -> Tool {
+        Tool {
+            name: name.into(),
+            description: None,
+            parameters: None,
+        }
+    }
+
+    fn schema(&self) -> Value {
+        self.parameters
+            .clone()
+            .unwrap_or_else(|| json!({ "type": "object", "properties": {} }))
+    }
+
+    pub(crate) fn to_openai(&self) -> Value {
+        let mut function = json!({ "name": self.name, "parameters": self.schema() });
+        if let Some(description) = &self.description {
+            function["description"] = json!(description);
+        }
+        json!({ "type": "function", "function": function })
+    }
+
+    pub(crate) fn to_anthropic(&self) -> Value {
+        let mut tool = json!({ "name": self.name, "input_schema": self.schema() });
+        if let Some(description) = &self.description {
+            tool["description"] = json!(description);
+        }
+        tool
+    }
+
+    pub(crate) fn to_gemini(&self) -> Value {
+        let mut declaration = json!({ "name": self.name, "parameters": self.schema() });
+        if let Some(description) = &self.description {
+            declaration["description"] = json!(description);
+        }
+        declaration
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ToolMode {
+    Auto,
+    None,
+    Required,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(untagged)]
+pub enum ToolChoice {
+    Mode(ToolMode),
+    Tool { name: String },
+}
+
+impl ToolChoice {
+    pub(crate) fn to_openai(&self) -> Value {
+        match self {
+            ToolChoice::Mode(ToolMode::Auto) => json!("auto"),
+            ToolChoice::Mode(ToolMode::None) => json!("none"),
+            ToolChoice::Mode(ToolMode::Required) => json!("required"),
+            ToolChoice::Tool { name } => {
+                json!({ "type": "function", "function": { "name": name } })
+            }
+        }
+    }
+
+    pub(crate) fn to_anthropic(&self) -> Value {
+        match self {
+            ToolChoice::Mode(ToolMode::Auto) => json!({ "type": "auto" }),
+            ToolChoice::Mode(ToolMode::None) => json!({ "type": "none" }),
+            ToolChoice::Mode(ToolMode::Required) => json!({ "type": "any" }),
+            ToolChoice::Tool { name } => json!({ "type": "tool", "name": name }),
+        }
+    }
+
+    pub(crate) fn to_gemini(&self) -> Value {
+        let config = match self {
+            ToolChoice::Mode(ToolMode::Auto) => json!({ "mode": "AUTO" }),
+            ToolChoice::Mode(ToolMode::None) => json!({ "mode": "NONE" }),
+            ToolChoice::Mode(ToolMode::Required) => json!({ "mode": "ANY" }),
+            ToolChoice::Tool { name } => json!({ "mode": "ANY", "allowedFunctionNames": [name] }),
+        };
+        json!({ "functionCallingConfig": config })
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+pub struct ToolCall {
+    pub id: String,
+    pub name: String,
+    #[serde(default)]
+    pub arguments: Value,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+pub struct ToolResult {
+    pub id: String,
+    pub content: String,
+}
@@ -0,0 +1,200 @@
+use crate::content::ContentPart;
+use crate::prompt::Chat;
+use crate::tools::{ToolCall, ToolResult};
+use serde::{Deserialize, Serialize};
+use std::fmt;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Role {
+    System,
+    User,
+    Assistant,
+    Tool,
+}
+
+impl Role {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Role::System => "system",
+            Role::User => "user",
+            Role::Assistant => "assistant",
+            Role::Tool => "tool",
+        }
+    }
+}
+
+impl fmt::Display for Role {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.as_str())
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ChatMessage {
+    pub role: Role,
+    pub content: String,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub parts: Vec<ContentPart>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub tool_calls: Vec<ToolCall>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub tool_call_id: Option<String>,
+}
+
+impl ChatMessage {
+    pub fn new(role: Role, content: impl Into<String>) -> ChatMessage {
+        ChatMessage {
+            role,
+            content: content.into(),
+            parts: Vec::new(),
+            tool_calls: Vec::new(),
+            tool_call_id: None,
+        }
+    }
+
+    pub fn tool_calls(calls: Vec<ToolCall>) -> ChatMessage {
+        ChatMessage {
+            tool_calls: calls,
+            ..ChatMessage::new(Role::Assistant, "")
+        }
+    }
+
+    pub fn tool_result(result: &ToolResult) -> ChatMessage {
+        ChatMessage {
+            tool_call_id: Some(result.id.clone()),
+            ..ChatMessage::new(Role::Tool, result.content.clone())
+        }
+    }
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Transcript {
+    pub messages: Vec<ChatMessage>,
+}
+
+impl Transcript {
+    pub fn new() -> Transcript {
+        Transcript::default()
+    }
+
+    pub fn push(&mut self, role: Role, content: impl Into<String>) {
+        self.messages.push(ChatMessage::new(role, content));
+    }
+
+    pub fn push_user(&mut self, content: impl Into<String>) {
+        self.push(Role::User, content);
+    }
+
+    pub fn push_assistant(&mut self, content: impl Into<String>) {
+        self.push(Role::Assistant, content);
+    }
+
+    pub fn system(&self) -> Option<&str> {
+        self.messages
+            .iter()
+            .find(|m| m.role == Role::System)
+            .map(|m| m.content.as_str())
+    }
+
+    pub fn without_system(&self) -> impl Iterator<Item = &ChatMessage> {
+        self.messages.iter().filter(|m| m.role != Role::System)
+    }
+
+    pub fn len(&self) -> usize {
+        self.messages.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.messages.is_empty()
+    }
+}
+
+impl IntoIterator for Transcript {
+    type Item = ChatMessage;
+    type IntoIter = std::vec::IntoIter<ChatMessage>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.messages.into_iter()
+    }
+}
+
+impl Chat {
+    pub fn to_transcript(&self) -> Transcript {
+        let mut transcript = Transcript::new();
+        if let Some(context) = &self.context {
+            transcript.push(Role::System, context.clone());
+        }
+        for example in self.examples.iter().flatten() {
+            transcript.push_user(example.input.clone());
+            if let Some(output) = &example.output {
+                transcript.push_assistant(output.clone());
+            }
+        }
+        for message in self.messages.iter().flatten() {
+            transcript.messages.push(ChatMessage {
+                parts: message.content.clone().unwrap_or_default(),
+                ..ChatMessage::new(Role::User, message.input.clone())
+            });
+            if let Some(calls) = &message.tool_calls {
+                transcript
+                    .messages
+                    .push(ChatMessage::tool_calls(calls.clone()));
+            }
+            for result in message.tool_results.iter().flatten() {
+                transcript.messages.push(ChatMessage::tool_result(result));
+            }
+            if let Some(output) = &message.output {
+                transcript.push_assistant(output.clone());
+            }
+        }
+        transcript
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::prompt::{deserialize_prompt, Prompt};
+
+    #[test]
+    fn test_to_transcript() {
+        let yaml = r#"
+            type: chat
+            vendor: google
+            model: chat-bison
+            context: Be brief
+            examples:
+                - input: who are u?
+                  output: I'm google
+            messages:
+                - input: hi
+                  output: hello
+                - input: what's your name?
+        "#;
+
+        if let Prompt::Chat(chat) = deserialize_prompt(yaml) {
+            let mut transcript = chat.to_transcript();
+            assert_eq!(
+                transcript.messages,
+                vec![
+                    ChatMessage::new(Role::System, "Be brief"),
+                    ChatMessage::new(Role::User, "who are u?"),
+                    ChatMessage::new(Role::Assistant, "I'm google"),
+                    ChatMessage::new(Role::User, "hi"),
+                    ChatMessage::new(Role::Assistant, "hello"),
+                    ChatMessage::new(Role::User, "what's your name?"),
+                ]
+            );
+            assert_eq!(transcript.system(), Some("Be brief"));
+
+            transcript.push_assistant("Bard");
+            transcript.push_user("thanks");
+            assert_eq!(transcript.len(), 8);
+            assert_eq!(transcript.without_system().count(), 7);
+            assert_eq!(transcript.messages[7].role, Role::User);
+        } else {
+            panic!("Expected Prompt::Chat");
+        }
+    }
+}
@@ -0,0 +1,654 @@
+use crate::content::ContentPart;
+use crate::models::{ModelRegistry, ModelStatus};
+use crate::output::OutputSpec;
+use crate::parameters::ParameterError;
+use crate::pattern::Pattern;
+use crate::pipeline::Step;
+use crate::prompt::{Chat, Completion, Embedding, NameStyle, Parameter, Prompt, PromptMeta};
+use crate::request::{Endpoint, Vendor};
+use crate::sampling::Sampling;
+use crate::sanitize::Sanitizer;
+use crate::variant::DEFAULT_VARIANT;
+use std::fmt;
+use std::sync::OnceLock;
+
+const MAX_TOKEN_PARAMETERS: &[&str] = &["max_tokens", "max_output_tokens", "max_completion_tokens"];
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Severity {
+    Warning,
+    Error,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ValidationIssue {
+    pub severity: Severity,
+    pub code: &'static str,
+    pub field: String,
+    pub message: String,
+}
+
+impl ValidationIssue {
+    pub fn error(code: &'static str, field: impl Into<String>, message: impl Into<String>) -> Self {
+        ValidationIssue {
+            severity: Severity::Error,
+            code,
+            field: field.into(),
+            message: message.into(),
+        }
+    }
+
+    pub fn warning(
+        code: &'static str,
+        field: impl Into<String>,
+        message: impl Into<String>,
+    ) -> Self {
+        ValidationIssue {
+            severity: Severity::Warning,
+            code,
+            field: field.into(),
+            message: message.into(),
+        }
+    }
+
+    pub fn is_error(&self) -> bool {
+        self.severity == Severity::Error
+    }
+}
+
+impl fmt::Display for ValidationIssue {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let severity = match self.severity {
+            Severity::Warning => "warning",
+            Severity::Error => "error",
+        };
+        write!(
+            f,
+            "{} [{}] {}: {}",
+            severity, self.code, self.field, self.message
+        )
+    }
+}
+
+pub fn has_errors(issues: &[ValidationIssue]) -> bool {
+    issues.iter().any(|i| i.is_error())
+}
+
+fn check_required(issues: &mut Vec<ValidationIssue>, field: &str, value: &str) {
+    if value.trim().is_empty() {
+        issues.push(ValidationIssue::error(
+            "empty-field",
+            field,
+            format!("'{}' must not be empty", field),
+        ));
+    }
+}
+
+fn check_vendor(issues: &mut Vec<ValidationIssue>, vendor: &str, endpoint: &Endpoint) {
+    if let Some(style) = &endpoint.api_style {
+        if Vendor::from_name(style).is_none() {
+            issues.push(ValidationIssue::error(
+                "unknown-api-style",
+                "api_style",
+                format!("api style '{}' is not known", style),
+            ));
+        }
+    } else if !vendor.trim().is_empty() && endpoint.api_vendor(vendor).is_none() {
+        issues.push(ValidationIssue::warning(
+            "unknown-vendor",
+            "vendor",
+            format!("vendor '{}' is not known", vendor),
+        ));
+    }
+}
+
+fn check_parameters(
+    issues: &mut Vec<ValidationIssue>,
+    parameters: &Option<Vec<Parameter>>,
+    errors: Vec<ParameterError>,
+) {
+    let parameters = parameters.as_deref().unwrap_or(&[]);
+    for (i, parameter) in parameters.iter().enumerate() {
+        if parameters[..i].iter().any(|p| p.name == parameter.name) {
+            issues.push(ValidationIssue::error(
+                "duplicate-parameter",
+                format!("parameters[{}]", i),
+                format!("parameter '{}' is defined more than once", parameter.name),
+            ));
+        }
+    }
+    for error in errors {
+        let code = match error {
+            ParameterError::OutOfRange { .. } => "parameter-out-of-range",
+            _ => "invalid-parameter",
+        };
+        issues.push(ValidationIssue::error(
+            code,
+            "parameters",
+            error.to_string(),
+        ));
+    }
+}
+
+fn check_sampling(
+    issues: &mut Vec<ValidationIssue>,
+    sampling: &Sampling,
+    parameters: &Option<Vec<Parameter>>,
+    vendor: Option<Vendor>,
+) {
+    for error in sampling.validate(vendor) {
+        let (code, field) = match &error {
+            ParameterError::OutOfRange { name, .. } => ("parameter-out-of-range", name.clone()),
+            ParameterError::Invalid { name, .. } => ("invalid-parameter", name.clone()),
+            other => ("invalid-parameter", other.to_string()),
+        };
+        issues.push(ValidationIssue::error(code, field, error.to_string()));
+    }
+    for name in vendor.map(|v| sampling.unsupported(v)).unwrap_or_default() {
+        issues.push(ValidationIssue::warning(
+            "unsupported-parameter",
+            name,
+            format!(
+                "'{}' is not supported by this vendor and will not be sent",
+                name
+            ),
+        ));
+    }
+    for name in sampling.conflicts(parameters) {
+        issues.push(ValidationIssue::warning(
+            "conflicting-parameter",
+            name,
+            format!("'{}' is also set in parameters, the typed field wins", name),
+        ));
+    }
+}
+
+fn check_output(issues: &mut Vec<ValidationIssue>, output: &Option<OutputSpec>) {
+    if let Some(Err(error)) = output.as_ref().map(|o| o.check()) {
+        issues.push(ValidationIssue::error(
+            "invalid-output",
+            "output",
+            error.to_string(),
+        ));
+    }
+}
+
+fn check_post_process(issues: &mut Vec<ValidationIssue>, steps: &Option<Vec<Step>>) {
+    let steps = steps.as_deref().unwrap_or(&[]);
+    for (i, step) in steps.iter().enumerate() {
+        if let Step::Redact { pattern, .. } = step {
+            if let Err(error) = Pattern::new(pattern) {
+                issues.push(ValidationIssue::error(
+                    "invalid-post-process",
+                    format!("post_process[{}]", i),
+                    error.to_string(),
+                ));
+            }
+        }
+    }
+}
+
+fn check_variables(issues: &mut Vec<ValidationIssue>, meta: Option<&PromptMeta>) {
+    let variables = meta.and_then(|m| m.variables.as_ref());
+    for (name, spec) in variables.into_iter().flatten() {
+        if let Err(error) = Sanitizer::from_rules(spec.sanitize.as_deref().unwrap_or(&[])) {
+            issues.push(ValidationIssue::error(
+                "invalid-sanitizer",
+                format!("variables.{}.sanitize", name),
+                error.to_string(),
+            ));
+        }
+        if let (Some(kind), Some(default)) = (spec.kind, &spec.default) {
+            if !kind.accepts(default) {
+                issues.push(ValidationIssue::error(
+                    "invalid-default",
+                    format!("variables.{}.default", name),
+                    format!("default {} is not a {}", default, kind),
+                ));
+            }
+        }
+    }
+}
+
+fn check_variants(issues: &mut Vec<ValidationIssue>, prompt: &Prompt) {
+    let variants = prompt.variants();
+    let mut total = 0.0;
+    for (i, variant) in variants.iter().enumerate() {
+        let field = format!("variants[{}]", i);
+        let mut invalid = |message: String| {
+            issues.push(ValidationIssue::error("invalid-variant", &field, message));
+        };
+        if variant.name == DEFAULT_VARIANT || variants[..i].iter().any(|v| v.name == variant.name) {
+            invalid(format!("variant name '{}' is already taken", variant.name));
+        }
+        match variant.weight {
+            Some(weight) if !(0.0..=1.0).contains(&weight) => {
+                invalid(format!("weight {} must be between 0 and 1", weight))
+            }
+            Some(weight) => total += weight,
+            None => {}
+        }
+        if let Err(error) = prompt.variant(&variant.name) {
+            invalid(error.to_string());
+        }
+    }
+    if total > 1.0 + f64::EPSILON {
+        issues.push(ValidationIssue::error(
+            "invalid-variant",
+            "variants",
+            format!("variant weights add up to {}, more than 1", total),
+        ));
+    }
+}
+
+fn check_model(issues: &mut Vec<ValidationIssue>, models: &ModelRegistry, prompt: &Prompt) {
+    let (vendor, model, parameters, sampling, tools, vision) = match prompt {
+        Prompt::Completion(c) => (
+            &c.vendor,
+            &c.model,
+            &c.parameters,
+            Some(&c.sampling),
+            false,
+            false,
+        ),
+        Prompt::Chat(c) => {
+            let images = c
+                .messages
+                .iter()
+                .flatten()
+                .flat_map(|m| m.content.iter().flatten())
+                .any(|part| matches!(part, ContentPart::Image(_)));
+            let tools = c.tools.iter().flatten().next().is_some();
+            (
+                &c.vendor,
+                &c.model,
+                &c.parameters,
+                Some(&c.sampling),
+                tools,
+                images,
+            )
+        }
+        Prompt::Embedding(e) => (&e.vendor, &e.model, &None, None, false, false),
+        Prompt::Custom(_) | Prompt::Unknown => return,
+    };
+    let Some(info) = models.model(vendor, model) else {
+        return;
+    };
+    if info.status != ModelStatus::Active {
+        let mut message = format!("model '{}' is {}", model, info.status);
+        if let Some(replacement) = &info.replacement {
+            message.push_str(&format!(", use '{}' instead", replacement));
+        }
+        issues.push(match info.status {
+            ModelStatus::Retired => ValidationIssue::error("retired-model", "model", message),
+            _ => ValidationIssue::warning("deprecated-model", "model", message),
+        });
+    }
+    let sampling = sampling.map(|s| s.fields()).unwrap_or_default();
+    let names = parameters
+        .iter()
+        .flatten()
+        .map(|p| p.name.as_str())
+        .chain(sampling.iter().map(|(name, _)| *name));
+    for name in names {
+        let snake = NameStyle::SnakeCase.convert(name);
+        if !info.supports_parameter(&snake) {
+            let mut message = format!("'{}' is not supported by model '{}'", name, model);
+            if snake == "max_tokens" && info.supports_parameter("max_completion_tokens") {
+                message.push_str(", use 'max_completion_tokens' instead");
+            }
+            issues.push(ValidationIssue::warning(
+                "unsupported-parameter",
+                name,
+                message,
+            ));
+        }
+    }
+    for parameter in parameters.iter().flatten() {
+        let name = NameStyle::SnakeCase.convert(&parameter.name);
+        match parameter.value.as_u64() {
+            Some(tokens)
+                if MAX_TOKEN_PARAMETERS.contains(&name.as_str())
+                    && tokens as usize > info.context_window =>
+            {
+                issues.push(ValidationIssue::warning(
+                    "context-window",
+                    &parameter.name,
+                    format!(
+                        "{} tokens exceed the {} token context window of '{}'",
+                        tokens, info.context_window, model
+                    ),
+                ));
+            }
+            _ => {}
+        }
+    }
+    if tools && !info.supports_tools {
+        issues.push(ValidationIssue::warning(
+            "unsupported-tools",
+            "tools",
+            format!("model '{}' does not support tools", model),
+        ));
+    }
+    if vision && !info.supports_vision {
+        issues.push(ValidationIssue::warning(
+            "unsupported-vision",
+            "messages",
+            format!("model '{}' does not accept images", model),
+        ));
+    }
+}
+
+fn check_unknown_fields(issues: &mut Vec<ValidationIssue>, prompt: &Prompt) {
+    for field in prompt.unknown_fields() {
+        issues.push(ValidationIssue::warning(
+            "unknown-field",
+            &field.path,
+            format!("{}, it will be ignored", field),
+        ));
+    }
+}
+
+fn check_template(issues: &mut Vec<ValidationIssue>, prompt: &Prompt) {
+    if let Err(error) = prompt.required_variables() {
+        issues.push(ValidationIssue::error(
+            "invalid-template",
+            "prompt",
+            error.to_string(),
+        ));
+    }
+    for field in prompt.replacement_char_fields() {
+        issues.push(ValidationIssue::warning(
+            "replacement-character",
+            field,
+            "contains U+FFFD, the file may have an encoding problem",
+        ));
+    }
+}
+
+impl Completion {
+    fn validate_into(&self, issues: &mut Vec<ValidationIssue>) {
+        check_required(issues, "vendor", &self.vendor);
+        check_required(issues, "model", &self.model);
+        check_required(issues, "prompt", &self.prompt);
+        check_vendor(issues, &self.vendor, &self.meta.endpoint);
+        check_parameters(issues, &self.parameters, self.validate_parameters());
+        let vendor = self.meta.endpoint.api_vendor(&self.vendor);
+        check_sampling(issues, &self.sampling, &self.parameters, vendor);
+        check_output(issues, &self.output);
+        check_post_process(issues, &self.post_process);
+
+        let columns = self.examples.as_deref().unwrap_or(&[]);
+        let rows = self.example_count();
+        for (i, column) in columns.iter().enumerate() {
+            if columns[..i].iter().any(|c| c.name == column.name) {
+                issues.push(ValidationIssue::error(
+                    "duplicate-column",
+                    format!("examples[{}]", i),
+                    format!("example column '{}' is defined more than once", column.name),
+                ));
+            }
+            if column.values.len() != rows {
+                issues.push(ValidationIssue::error(
+                    "mismatched-columns",
+                    format!("examples[{}].values", i),
+                    format!(
+                        "column '{}' has {} values but other columns have {}",
+                        column.name,
+                        column.values.len(),
+                        rows
+                    ),
+                ));
+            }
+        }
+    }
+}
+
+impl Chat {
+    fn validate_into(&self, issues: &mut Vec<ValidationIssue>) {
+        check_required(issues, "vendor", &self.vendor);
+        check_required(issues, "model", &self.model);
+        check_vendor(issues, &self.vendor, &self.meta.endpoint);
+        check_parameters(issues, &self.parameters, self.validate_parameters());
+        let vendor = self.meta.endpoint.api_vendor(&self.vendor);
+        check_sampling(issues, &self.sampling, &self.parameters, vendor);
+        check_output(issues, &self.output);
+        check_post_process(issues, &self.post_process);
+
+        let no_examples = self.examples.iter().flatten().next().is_none();
+        let no_messages = self.messages.iter().flatten().next().is_none();
+        if no_examples && no_messages {
+            issues.push(ValidationIssue::warning(
+                "empty-chat",
+                "messages",
+                "chat has neither messages nor examples",
+            ));
+        }
+    }
+}
+
+impl Embedding {
+    fn validate_into(&self, issues: &mut Vec<ValidationIssue>) {
+        check_required(issues, "vendor", &self.vendor);
+        check_required(issues, "model", &self.model);
+        check_vendor(issues, &self.vendor, &self.meta.endpoint);
+        if self.input.is_empty() {
+            issues.push(ValidationIssue::error(
+                "empty-field",
+                "input",
+                "'input' must not be empty",
+            ));
+        }
+        for (i, input) in self.input.iter().enumerate() {
+            check_required(issues, &format!("input[{}]", i), input);
+        }
+        if self.dimensions == Some(0) {
+            issues.push(ValidationIssue::error(
+                "invalid-dimensions",
+                "dimensions",
+                "'dimensions' must be greater than zero",
+            ));
+        }
+    }
+}
+
+impl Prompt {
+    pub fn validate(&self) -> Vec<ValidationIssue> {
+        static MODELS: OnceLock<ModelRegistry> = OnceLock::new();
+        self.validate_with(MODELS.get_or_init(ModelRegistry::builtin))
+    }
+
+    pub fn validate_with(&self, models: &ModelRegistry) -> Vec<ValidationIssue> {
+        let mut issues = Vec::new();
+        match self {
+            Prompt::Completion(completion) => completion.validate_into(&mut issues),
+            Prompt::Chat(chat) => chat.validate_into(&mut issues),
+            Prompt::Embedding(embedding) => embedding.validate_into(&mut issues),
+            Prompt::Custom(kind) => issues.extend(kind.validate()),
+            Prompt::Unknown => {
+                issues.push(ValidationIssue::error(
+                    "unknown-type",
+                    "type",
+                    "prompt type is not supported",
+                ));
+                return issues;
+            }
+        }
+        check_unknown_fields(&mut issues, self);
+        check_model(&mut issues, models, self);
+        check_variables(&mut issues, self.meta());
+        check_variants(&mut issues, self);
+        check_template(&mut issues, self);
+        issues
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::ModelInfo;
+    use crate::prompt::deserialize_prompt;
+
+    fn codes(issues: &[ValidationIssue]) -> Vec<&'static str> {
+        issues.iter().map(|i| i.code).collect()
+    }
+
+    #[test]
+    fn test_valid_prompt_has_no_issues() {
+        let yaml = r#"
+            type: completion
+            vendor: google
+            model: gemini-2.0-flash
+            prompt: Write a hello world in {{language}}
+            parameters:
+                - name: temperature
+                  value: 0.4
+            examples:
+                - name: input
+                  values: [a, b]
+                - name: output
+                  values: [x, y]
+        "#;
+        assert!(deserialize_prompt(yaml).validate().is_empty());
+    }
+
+    #[test]
+    fn test_completion_issues() {
+        let yaml = r#"
+            type: completion
+            vendor: google
+            model: text-bison
+            prompt: " "
+            parameters:
+                - name: temperature
+                  value: 1.5
+                - name: temperature
+                  value: 0.2
+            examples:
+                - name: input
+                  values: [a, b]
+                - name: output
+                  values: [x]
+        "#;
+        let issues = deserialize_prompt(yaml).validate();
+        assert_eq!(
+            codes(&issues),
+            vec![
+                "empty-field",
+                "duplicate-parameter",
+                "parameter-out-of-range",
+                "mismatched-columns",
+                "deprecated-model"
+            ]
+        );
+        assert!(has_errors(&issues));
+    }
+
+    #[test]
+    fn test_sampling_issues() {
+        let yaml = r#"
+            type: completion
+            vendor: anthropic
+            model: claude-3-haiku
+            prompt: hi
+            top_p: 1.2
+            seed: 3
+            stop: [END]
+            parameters:
+                - name: stop_sequences
+                  value: [STOP]
+        "#;
+        let issues = deserialize_prompt(yaml).validate();
+        assert_eq!(
+            codes(&issues),
+            vec![
+                "parameter-out-of-range",
+                "unsupported-parameter",
+                "conflicting-parameter"
+            ]
+        );
+        assert_eq!(issues[1].field, "seed");
+    }
+
+    #[test]
+    fn test_chat_warnings() {
+        let yaml = r#"
+            type: chat
+            vendor: acme
+            model: chat-1
+            context: Hello {{name
+        "#;
+        let issues = deserialize_prompt(yaml).validate();
+        assert_eq!(
+            codes(&issues),
+            vec!["unknown-vendor", "empty-chat", "invalid-template"]
+        );
+        assert_eq!(issues[0].severity, Severity::Warning);
+        assert_eq!(issues[2].severity, Severity::Error);
+        assert_eq!(
+            issues[1].to_string(),
+            "warning [empty-chat] messages: chat has neither messages nor examples"
+        );
+
+        let styled =
+            deserialize_prompt(&yaml.replace("chat-1", "chat-1\n            api_style: openai"));
+        assert_eq!(
+            codes(&styled.validate()),
+            vec!["empty-chat", "invalid-template"]
+        );
+        let styled =
+            deserialize_prompt(&yaml.replace("chat-1", "chat-1\n            api_style: soap"));
+        assert_eq!(codes(&styled.validate())[0], "unknown-api-style");
+    }
+
+    #[test]
+    fn test_model_issues() {
+        let yaml = r#"
+            type: chat
+            vendor: openai
+            model: o1-mini
+            parameters:
+                - name: temperature
+                  value: 0.2
+                - name: maxCompletionTokens
+                  value: 500000
+            messages:
+                - input: hi
+            tools:
+                - name: lookup
+                  description: Look something up
+                  parameters: { type: object }
+        "#;
+        let issues = deserialize_prompt(yaml).validate();
+        assert_eq!(
+            codes(&issues),
+            vec![
+                "deprecated-model",
+                "unsupported-parameter",
+                "context-window",
+                "unsupported-tools"
+            ]
+        );
+        assert_eq!(
+            issues[0].message,
+            "model 'o1-mini' is deprecated, use 'o3-mini' instead"
+        );
+        assert_eq!(issues[1].field, "temperature");
+
+        let o1 = yaml
+            .replace("o1-mini", "o1")
+            .replace("maxCompletionTokens", "max_tokens");
+        let issues = deserialize_prompt(&o1).validate();
+        assert_eq!(
+            issues[1].message,
+            "'max_tokens' is not supported by model 'o1', use 'max_completion_tokens' instead"
+        );
+
+        let models = ModelRegistry::new().with_model(
+            ModelInfo::new("openai", "o1-mini", 1_000_000).status(ModelStatus::Retired, None),
+        );
+        let issues = deserialize_prompt(yaml).validate_with(&models);
+        assert_eq!(codes(&issues), vec!["retired-model", "unsupported-tools"]);
+        assert!(has_errors(&issues));
+    }
+}
@@ -0,0 +1,390 @@
+use crate::sanitize::{sanitize_context, Rule, SanitizerCache};
+use crate::template::{Context, RenderError};
+use serde::de::value::MapAccessDeserializer;
+use serde::de::{self, MapAccess, SeqAccess, Visitor};
+use serde::{Deserialize, Deserializer, Serialize};
+use serde_json::Value;
+use std::borrow::Cow;
+use std::collections::{BTreeMap, HashMap};
+use std::fmt;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum VariableType {
+    #[serde(alias = "str")]
+    String,
+    #[serde(alias = "integer")]
+    Int,
+    #[serde(alias = "number")]
+    Float,
+    #[serde(alias = "boolean")]
+    Bool,
+    #[serde(alias = "array")]
+    List,
+    Object,
+}
+
+impl fmt::Display for VariableType {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let name = match self {
+            VariableType::String => "string",
+            VariableType::Int => "int",
+            VariableType::Float => "float",
+            VariableType::Bool => "bool",
+            VariableType::List => "list",
+            VariableType::Object => "object",
+        };
+        write!(f, "{}", name)
+    }
+}
+
+impl VariableType {
+    pub fn parse(&self, text: &str) -> Option<Value> {
+        let text = text.trim();
+        match self {
+            VariableType::String => Some(Value::String(text.to_string())),
+            VariableType::Int => text.parse::<i64>().ok().map(Value::from),
+            VariableType::Float => text.parse::<f64>().ok().map(Value::from),
+            VariableType::Bool => match text.to_lowercase().as_str() {
+                "true" => Some(Value::Bool(true)),
+                "false" => Some(Value::Bool(false)),
+                _ => None,
+            },
+            VariableType::List => serde_json::from_str(text).ok().filter(Value::is_array),
+            VariableType::Object => serde_json::from_str(text).ok().filter(Value::is_object),
+        }
+    }
+
+    pub fn accepts(&self, value: &Value) -> bool {
+        match (self, value) {
+            (VariableType::String, Value::String(_)) => true,
+            (_, Value::String(text)) => self.parse(text).is_some(),
+            (VariableType::Int, Value::Number(n)) => n.is_i64() || n.is_u64(),
+            (VariableType::Float, Value::Number(_)) => true,
+            (VariableType::Bool, Value::Bool(_)) => true,
+            (VariableType::List, Value::Array(_)) => true,
+            (VariableType::Object, Value::Object(_)) => true,
+            _ => false,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize, PartialEq, Eq)]
+pub struct VariableSpec {
+    #[serde(rename = "type", default, skip_serializing_if = "Option::is_none")]
+    pub kind: Option<VariableType>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub required: Option<bool>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub default: Option<Value>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub description: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub sanitize: Option<Vec<Rule>>,
+    #[serde(skip)]
+    pub sanitizer: SanitizerCache,
+}
+
+impl fmt::Display for VariableSpec {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.kind.unwrap_or(VariableType::String))?;
+        match (&self.default, self.required) {
+            (Some(default), _) => write!(f, " = {}", default)?,
+            (None, Some(true)) => write!(f, " (required)")?,
+            (None, _) => write!(f, " (optional)")?,
+        }
+        if let Some(description) = &self.description {
+            write!(f, " - {}", description)?;
+        }
+        Ok(())
+    }
+}
+
+pub type Variables = BTreeMap<String, VariableSpec>;
+
+struct Declaration {
+    name: String,
+    spec: VariableSpec,
+}
+
+impl<'de> Deserialize<'de> for Declaration {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Declaration, D::Error> {
+        struct DeclarationVisitor;
+
+        impl<'de> Visitor<'de> for DeclarationVisitor {
+            type Value = Declaration;
+
+            fn expecting(&self, f: &mut fmt::Formatter) -> fmt::Result {
+                write!(f, "a variable name or a map with a name")
+            }
+
+            fn visit_str<E: de::Error>(self, name: &str) -> Result<Declaration, E> {
+                Ok(Declaration {
+                    name: name.to_string(),
+                    spec: VariableSpec::default(),
+                })
+            }
+
+            fn visit_map<A: MapAccess<'de>>(self, map: A) -> Result<Declaration, A::Error> {
+                #[derive(Deserialize)]
+                struct Named {
+                    name: String,
+                    #[serde(flatten)]
+                    spec: VariableSpec,
+                }
+                let named = Named::deserialize(MapAccessDeserializer::new(map))?;
+                Ok(Declaration {
+                    name: named.name,
+                    spec: named.spec,
+                })
+            }
+        }
+
+        deserializer.deserialize_any(DeclarationVisitor)
+    }
+}
+
+struct Declarations(Variables);
+
+impl<'de> Deserialize<'de> for Declarations {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Declarations, D::Error> {
+        struct DeclarationsVisitor;
+
+        impl<'de> Visitor<'de> for DeclarationsVisitor {
+            type Value = Declarations;
+
+            fn expecting(&self, f: &mut fmt::Formatter) -> fmt::Result {
+                write!(f, "a map or a list of variable declarations")
+            }
+
+            fn visit_map<A: MapAccess<'de>>(self, map: A) -> Result<Declarations, A::Error> {
+                Variables::deserialize(MapAccessDeserializer::new(map)).map(Declarations)
+            }
+
+            fn visit_seq<A: SeqAccess<'de>>(self, mut seq: A) -> Result<Declarations, A::Error> {
+                let mut variables = Variables::new();
+                while let Some(Declaration { name, spec }) = seq.next_element()? {
+                    if variables.insert(name.clone(), spec).is_some() {
+                        return Err(de::Error::custom(format!(
+                            "variable '{}' is declared more than once",
+                            name
+                        )));
+                    }
+                }
+                Ok(Declarations(variables))
+            }
+        }
+
+        deserializer.deserialize_any(DeclarationsVisitor)
+    }
+}
+
+pub(crate) fn deserialize_variables<'de, D>(deserializer: D) -> Result<Option<Variables>, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    Ok(Option::<Declarations>::deserialize(deserializer)?.map(|d| d.0))
+}
+
+fn invalid(name: &str, expected: VariableType) -> RenderError {
+    RenderError::InvalidVariable {
+        name: name.to_string(),
+        expected,
+    }
+}
+
+fn typed_context(
+    variables: Option<&Variables>,
+    vars: &HashMap<String, String>,
+) -> Result<Context, RenderError> {
+    let mut context = Context::new();
+    for (name, text) in vars {
+        let kind = variables.and_then(|v| v.get(name)?.kind);
+        let value = match kind {
+            None | Some(VariableType::String) => Value::String(text.clone()),
+            Some(kind) => kind.parse(text).ok_or_else(|| invalid(name, kind))?,
+        };
+        context.insert(name.clone(), value);
+    }
+    Ok(context)
+}
+
+fn declare_context<'a>(
+    variables: Option<&Variables>,
+    context: &'a Context,
+) -> Result<Cow<'a, Context>, RenderError> {
+    let mut declared = Cow::Borrowed(context);
+    for (name, spec) in variables.into_iter().flatten() {
+        match (context.get(name), &spec.default, spec.required) {
+            (Some(value), _, _) => match spec.kind {
+                Some(kind) if !kind.accepts(value) => return Err(invalid(name, kind)),
+                _ => {}
+            },
+            (None, Some(default), _) => {
+                declared.to_mut().insert(name.clone(), default.clone());
+            }
+            (None, None, Some(true)) => return Err(RenderError::MissingVariable(name.clone())),
+            (None, None, Some(false)) => {
+                declared
+                    .to_mut()
+                    .insert(name.clone(), Value::String(String::new()));
+            }
+            (None, None, None) => {}
+        }
+    }
+    Ok(declared)
+}
+
+pub(crate) fn prepare_vars(
+    variables: Option<&Variables>,
+    vars: &HashMap<String, String>,
+) -> Result<Context, RenderError> {
+    let context = typed_context(variables, vars)?;
+    Ok(prepare_context(variables, &context)?.into_owned())
+}
+
+pub(crate) fn prepare_context<'a>(
+    variables: Option<&Variables>,
+    context: &'a Context,
+) -> Result<Cow<'a, Context>, RenderError> {
+    match declare_context(variables, context)? {
+        Cow::Borrowed(context) => sanitize_context(variables, context),
+        Cow::Owned(context) => Ok(Cow::Owned(
+            sanitize_context(variables, &context)?.into_owned(),
+        )),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::prompt::{deserialize_prompt, Prompt};
+    use serde_json::json;
+
+    const PROMPT: &str = r#"
+type: completion
+vendor: openai
+model: gpt-4o
+prompt: "List {{max_items}} items in {{language}}{{#if tone}} ({{tone}}){{/if}}."
+variables:
+  - name: language
+    type: string
+    required: true
+  - name: max_items
+    type: int
+    default: 5
+  - name: tone
+    required: false
+"#;
+
+    fn vars(pairs: &[(&str, &str)]) -> HashMap<String, String> {
+        pairs
+            .iter()
+            .map(|(k, v)| (k.to_string(), v.to_string()))
+            .collect()
+    }
+
+    #[test]
+    fn test_declared_variables() {
+        let Prompt::Completion(completion) = deserialize_prompt(PROMPT) else {
+            panic!("expected a completion");
+        };
+        let variables = completion.meta.variables.as_ref().unwrap();
+        assert_eq!(variables["max_items"].kind, Some(VariableType::Int));
+        assert_eq!(variables["max_items"].to_string(), "int = 5");
+        assert_eq!(variables["language"].to_string(), "string (required)");
+
+        assert_eq!(
+            completion.render(&vars(&[("language", "Rust")])),
+            Ok("List 5 items in Rust.".to_string())
+        );
+        assert_eq!(
+            completion.render(&vars(&[
+                ("language", "Go"),
+                ("max_items", "2"),
+                ("tone", "dry")
+            ])),
+            Ok("List 2 items in Go (dry).".to_string())
+        );
+        assert_eq!(
+            completion.render(&vars(&[])),
+            Err(RenderError::MissingVariable("language".to_string()))
+        );
+        assert_eq!(
+            completion.render(&vars(&[("language", "Go"), ("max_items", "a few")])),
+            Err(RenderError::InvalidVariable {
+                name: "max_items".to_string(),
+                expected: VariableType::Int,
+            })
+        );
+        let context = json!({ "language": "C", "max_items": 3 });
+        assert_eq!(
+            completion.render_context(context.as_object().unwrap()),
+            Ok("List 3 items in C.".to_string())
+        );
+        let context = json!({ "language": "C", "max_items": 2.5 });
+        assert!(completion
+            .render_context(context.as_object().unwrap())
+            .is_err());
+
+        let listed = deserialize_prompt(
+            "type: completion\nvendor: openai\nmodel: gpt\nprompt: '{{#each items}}[{{this}}]{{/each}}'\nvariables: { items: { type: list } }\n",
+        );
+        let Prompt::Completion(listed) = listed else {
+            panic!("expected a completion");
+        };
+        assert_eq!(
+            listed.render(&vars(&[("items", r#"["a", "b"]"#)])),
+            Ok("[a][b]".to_string())
+        );
+
+        let invalid = deserialize_prompt(&PROMPT.replace("default: 5", "default: many"));
+        let issues = invalid.validate();
+        assert!(issues.iter().any(|i| i.code == "invalid-default"));
+    }
+
+    #[test]
+    fn test_declaration_forms() {
+        let map: Variables = serde_yaml::from_str("a: { type: bool }\nb: {}\n").unwrap();
+        let parse = |yaml: &str| {
+            #[derive(Deserialize)]
+            struct Doc {
+                #[serde(default, deserialize_with = "deserialize_variables")]
+                variables: Option<Variables>,
+            }
+            serde_yaml::from_str::<Doc>(yaml).map(|d| d.variables)
+        };
+        assert_eq!(
+            parse("variables: { a: { type: bool }, b: {} }").unwrap(),
+            Some(map.clone())
+        );
+        assert_eq!(
+            parse("variables: [{ name: a, type: boolean }, b]").unwrap(),
+            Some(map)
+        );
+        assert_eq!(parse("{}").unwrap(), None);
+        assert!(parse("variables: [a, { name: a }]").is_err());
+        for yaml in [
+            "variables: { a: { sanitize: [strip_injektion] } }",
+            "variables: [{ name: a, sanitize: [strip_injektion] }]",
+        ] {
+            let error = parse(yaml).unwrap_err().to_string();
+            assert!(
+                error.contains("invalid sanitize rule 'strip_injektion'"),
+                "{}",
+                error
+            );
+        }
+        let error = parse("variables: { a: { type: text } }").unwrap_err();
+        assert!(
+            error.to_string().contains("unknown variant `text`"),
+            "{}",
+            error
+        );
+        assert!(error
+            .location()
+            .is_some_and(|l| l.line() == 1 && l.column() > 1));
+        assert!(VariableType::List.accepts(&json!("[1, 2]")));
+        assert!(!VariableType::Object.accepts(&json!([1])));
+    }
+}
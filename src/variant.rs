@@ -0,0 +1,294 @@
+use crate::format::prompt_from_value;
+use crate::prompt::{Prompt, PromptError};
+use crate::select::split_mix;
+use crate::sha256::digest;
+use serde::{Deserialize, Serialize};
+use serde_json::{Map, Value};
+use std::collections::hash_map::RandomState;
+use std::fmt;
+use std::hash::{BuildHasher, Hasher};
+use std::sync::atomic::{AtomicU64, Ordering};
+
+pub const DEFAULT_VARIANT: &str = "default";
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct Variant {
+    pub name: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub weight: Option<f64>,
+    #[serde(flatten)]
+    pub overrides: Map<String, Value>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+pub struct ChangelogEntry {
+    #[serde(deserialize_with = "crate::prompt::deserialize_version")]
+    pub version: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub date: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub notes: Option<String>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum VariantError {
+    UnknownVariant(String),
+    Invalid { variant: String, error: PromptError },
+}
+
+impl fmt::Display for VariantError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            VariantError::UnknownVariant(name) => write!(f, "unknown variant '{}'", name),
+            VariantError::Invalid { variant, error } => {
+                write!(f, "variant '{}': {}", variant, error)
+            }
+        }
+    }
+}
+
+impl std::error::Error for VariantError {}
+
+fn merge(base: &mut Map<String, Value>, overrides: &Map<String, Value>) {
+    for (key, value) in overrides {
+        match (base.get_mut(key), value) {
+            (Some(Value::Object(existing)), Value::Object(value)) => merge(existing, value),
+            (Some(Value::Array(existing)), Value::Array(value)) if key == "parameters" => {
+                for parameter in value {
+                    let name = parameter.get("name");
+                    match existing.iter_mut().find(|p| p.get("name") == name) {
+                        Some(existing) => *existing = parameter.clone(),
+                        None => existing.push(parameter.clone()),
+                    }
+                }
+            }
+            _ => {
+                base.insert(key.clone(), value.clone());
+            }
+        }
+    }
+}
+
+impl Prompt {
+    /// Variants declared inline under `variants:` in the prompt file.
+    ///
+    /// Variant files laid out as `name/variant.yaml` are not merged in: the
+    /// registry already reads nested directories as separately named prompts.
+    pub fn variants(&self) -> &[Variant] {
+        self.meta()
+            .and_then(|m| m.variants.as_deref())
+            .unwrap_or_default()
+    }
+
+    pub fn variant_names(&self) -> Vec<&str> {
+        let mut names = vec![DEFAULT_VARIANT];
+        names.extend(self.variants().iter().map(|v| v.name.as_str()));
+        names
+    }
+
+    pub fn variant_weights(&self) -> Vec<(&str, f64)> {
+        let variants = self.variants();
+        let explicit: f64 = variants.iter().filter_map(|v| v.weight).sum();
+        let unweighted = 1 + variants.iter().filter(|v| v.weight.is_none()).count();
+        let share = (1.0 - explicit).max(0.0) / unweighted as f64;
+        let mut weights = vec![(DEFAULT_VARIANT, share)];
+        weights.extend(
+            variants
+                .iter()
+                .map(|v| (v.name.as_str(), v.weight.unwrap_or(share))),
+        );
+        weights
+    }
+
+    pub fn variant(&self, name: &str) -> Result<Prompt, VariantError> {
+        let overrides = match name {
+            DEFAULT_VARIANT => None,
+            _ => Some(
+                self.variants()
+                    .iter()
+                    .find(|v| v.name == name)
+                    .ok_or_else(|| VariantError::UnknownVariant(name.to_string()))?,
+            ),
+        };
+        let invalid = |error| VariantError::Invalid {
+            variant: name.to_string(),
+            error,
+        };
+        let document = serde_json::to_value(self)
+            .map_err(|e| invalid(PromptError::Serialization(e.to_string())))?;
+        let Value::Object(mut document) = document else {
+            return Ok(self.clone());
+        };
+        if let Some(variant) = overrides {
+            merge(&mut document, &variant.overrides);
+        }
+        document.remove("variants");
+        document.insert("variant".to_string(), Value::String(name.to_string()));
+        prompt_from_value(Value::Object(document)).map_err(invalid)
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Selection {
+    pub variant: String,
+    pub prompt: Prompt,
+}
+
+#[derive(Debug, Default)]
+pub struct VariantSelector {
+    pin: Option<String>,
+    seed: Option<u64>,
+    draws: AtomicU64,
+}
+
+fn fraction(bits: u64) -> f64 {
+    (bits >> 11) as f64 / (1u64 << 53) as f64
+}
+
+impl VariantSelector {
+    pub fn new() -> VariantSelector {
+        VariantSelector::default()
+    }
+
+    pub fn pin(mut self, variant: impl Into<String>) -> Self {
+        self.pin = Some(variant.into());
+        self
+    }
+
+    pub fn seed(mut self, seed: u64) -> Self {
+        self.seed = Some(seed);
+        self
+    }
+
+    fn pick(prompt: &Prompt, point: f64) -> &str {
+        let weights = prompt.variant_weights();
+        let total: f64 = weights.iter().map(|(_, w)| w.max(0.0)).sum();
+        let mut cumulative = 0.0;
+        for (name, weight) in &weights {
+            cumulative += weight.max(0.0);
+            if point * total < cumulative {
+                return name;
+            }
+        }
+        DEFAULT_VARIANT
+    }
+
+    fn choose(&self, prompt: &Prompt, point: f64) -> Result<Selection, VariantError> {
+        let variant = match &self.pin {
+            Some(pin) => pin.as_str(),
+            None => VariantSelector::pick(prompt, point),
+        };
+        Ok(Selection {
+            variant: variant.to_string(),
+            prompt: prompt.variant(variant)?,
+        })
+    }
+
+    pub fn select(&self, prompt: &Prompt) -> Result<Selection, VariantError> {
+        let draw = self.draws.fetch_add(1, Ordering::Relaxed);
+        let mut state = match self.seed {
+            Some(seed) => seed ^ draw,
+            None => RandomState::new().build_hasher().finish() ^ draw,
+        };
+        self.choose(prompt, fraction(split_mix(&mut state)))
+    }
+
+    pub fn select_for(&self, prompt: &Prompt, user_id: &str) -> Result<Selection, VariantError> {
+        let name = prompt.meta().and_then(|m| m.name.as_deref()).unwrap_or("");
+        let hash = digest(format!("{}:{}", name, user_id).as_bytes());
+        let mut bits = [0u8; 8];
+        bits.copy_from_slice(&hash[..8]);
+        self.choose(prompt, fraction(u64::from_be_bytes(bits)))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::prompt::deserialize_prompt;
+
+    const YAML: &str = r#"
+name: summarize
+version: 3
+type: completion
+vendor: openai
+model: gpt-4o
+prompt: Summarize {{text}}
+parameters:
+  - name: temperature
+    value: 0.2
+  - name: max_tokens
+    value: 200
+changelog:
+  - version: 3
+    date: 2024-05-01
+    notes: Shorter summaries
+variants:
+  - name: terse
+    weight: 0.1
+    prompt: Summarize {{text}} in one line
+    parameters:
+      - name: temperature
+        value: 0.0
+  - name: mini
+    weight: 0.2
+    model: gpt-4o-mini
+"#;
+
+    #[test]
+    fn test_variant_prompts() {
+        let prompt = deserialize_prompt(YAML);
+        assert_eq!(prompt.variant_names(), vec!["default", "terse", "mini"]);
+        let weights = prompt.variant_weights();
+        assert!((weights[0].1 - 0.7).abs() < 1e-9);
+        assert_eq!(
+            prompt.meta().unwrap().changelog.as_ref().unwrap()[0]
+                .version
+                .as_deref(),
+            Some("3")
+        );
+
+        let Prompt::Completion(terse) = prompt.variant("terse").unwrap() else {
+            panic!("Expected Prompt::Completion");
+        };
+        assert_eq!(terse.prompt, "Summarize {{text}} in one line");
+        assert_eq!(terse.find_parameter_as_f32("temperature"), Some(0.0));
+        assert_eq!(terse.find_parameter_as_i32("max_tokens"), Some(200));
+        assert_eq!(terse.meta.variant.as_deref(), Some("terse"));
+        assert_eq!(terse.meta.variants, None);
+        assert!(prompt.validate().is_empty());
+        let broken = deserialize_prompt(&YAML.replace("weight: 0.2", "weight: 0.95"));
+        assert_eq!(broken.validate()[0].code, "invalid-variant");
+        assert_eq!(
+            prompt.variant("v9"),
+            Err(VariantError::UnknownVariant("v9".to_string()))
+        );
+    }
+
+    #[test]
+    fn test_variant_selection() {
+        let prompt = deserialize_prompt(YAML);
+        let selector = VariantSelector::new().seed(7);
+        let mut counts = std::collections::HashMap::new();
+        for _ in 0..2000 {
+            *counts
+                .entry(selector.select(&prompt).unwrap().variant)
+                .or_insert(0) += 1;
+        }
+        assert!((1200..1600).contains(&counts["default"]));
+        assert!((100..300).contains(&counts["terse"]));
+
+        let sticky = VariantSelector::new();
+        let first = sticky.select_for(&prompt, "user-42").unwrap();
+        for _ in 0..5 {
+            assert_eq!(sticky.select_for(&prompt, "user-42").unwrap(), first);
+        }
+
+        let pinned = VariantSelector::new().pin("mini").select(&prompt).unwrap();
+        assert_eq!(pinned.variant, "mini");
+        let Prompt::Completion(mini) = pinned.prompt else {
+            panic!("Expected Prompt::Completion");
+        };
+        assert_eq!(mini.model, "gpt-4o-mini");
+    }
+}
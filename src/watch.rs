@@ -0,0 +1,210 @@
+use crate::registry::{collect_files, PromptRegistry, RegistryError};
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::mpsc::{self, Receiver};
+use std::sync::{Arc, RwLock};
+use std::thread::{self, JoinHandle};
+use std::time::{Duration, Instant, SystemTime};
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum RegistryEvent {
+    Added(String),
+    Modified(String),
+    Removed(String),
+    Error(RegistryError),
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct WatchOptions {
+    pub interval: Duration,
+    pub debounce: Duration,
+}
+
+impl Default for WatchOptions {
+    fn default() -> Self {
+        WatchOptions {
+            interval: Duration::from_millis(500),
+            debounce: Duration::from_millis(200),
+        }
+    }
+}
+
+type Snapshot = BTreeMap<PathBuf, (Option<SystemTime>, u64)>;
+
+fn snapshot(root: &Path) -> Snapshot {
+    let mut files = Vec::new();
+    let _ = collect_files(root, &mut files);
+    files
+        .into_iter()
+        .filter_map(|path| {
+            let metadata = fs::metadata(&path).ok()?;
+            Some((path, (metadata.modified().ok(), metadata.len())))
+        })
+        .collect()
+}
+
+fn changes(old: &PromptRegistry, new: &PromptRegistry) -> Vec<RegistryEvent> {
+    let mut events = Vec::new();
+    for name in new.list() {
+        match old.source(name) {
+            None => events.push(RegistryEvent::Added(name.to_string())),
+            Some(source) if Some(source) != new.source(name) => {
+                events.push(RegistryEvent::Modified(name.to_string()))
+            }
+            Some(_) => {}
+        }
+    }
+    for name in old.list() {
+        if new.source(name).is_none() {
+            events.push(RegistryEvent::Removed(name.to_string()));
+        }
+    }
+    events
+}
+
+pub struct RegistryWatcher {
+    current: Arc<RwLock<Arc<PromptRegistry>>>,
+    stop: Arc<AtomicBool>,
+    handle: Option<JoinHandle<()>>,
+}
+
+impl RegistryWatcher {
+    pub fn current(&self) -> Arc<PromptRegistry> {
+        self.current
+            .read()
+            .unwrap_or_else(|e| e.into_inner())
+            .clone()
+    }
+}
+
+impl Drop for RegistryWatcher {
+    fn drop(&mut self) {
+        self.stop.store(true, Ordering::Relaxed);
+        if let Some(handle) = self.handle.take() {
+            handle.thread().unpark();
+            let _ = handle.join();
+        }
+    }
+}
+
+impl PromptRegistry {
+    pub fn watch(self, options: WatchOptions) -> (RegistryWatcher, Receiver<RegistryEvent>) {
+        let (sender, receiver) = mpsc::channel();
+        let watcher = self.watch_with(options, move |event| {
+            let _ = sender.send(event.clone());
+        });
+        (watcher, receiver)
+    }
+
+    pub fn watch_with(
+        self,
+        options: WatchOptions,
+        on_event: impl Fn(&RegistryEvent) + Send + 'static,
+    ) -> RegistryWatcher {
+        let root = self.root().to_path_buf();
+        let mode = self.mode();
+        let current = Arc::new(RwLock::new(Arc::new(self)));
+        let stop = Arc::new(AtomicBool::new(false));
+
+        let (shared, stopped) = (current.clone(), stop.clone());
+        let mut applied = snapshot(&root);
+        let handle = thread::spawn(move || {
+            let mut pending: Option<(Snapshot, Instant)> = None;
+            while !stopped.load(Ordering::Relaxed) {
+                thread::park_timeout(options.interval);
+                let latest = snapshot(&root);
+                if latest == applied {
+                    pending = None;
+                    continue;
+                }
+                let settled = match &pending {
+                    Some((seen, since)) if *seen == latest => since.elapsed() >= options.debounce,
+                    _ => {
+                        pending = Some((latest.clone(), Instant::now()));
+                        options.debounce.is_zero()
+                    }
+                };
+                if !settled {
+                    continue;
+                }
+                pending = None;
+                applied = latest;
+                match PromptRegistry::with_mode(&root, mode) {
+                    Ok(registry) => {
+                        let registry = Arc::new(registry);
+                        let previous = std::mem::replace(
+                            &mut *shared.write().unwrap_or_else(|e| e.into_inner()),
+                            registry.clone(),
+                        );
+                        for event in changes(&previous, &registry) {
+                            on_event(&event);
+                        }
+                    }
+                    Err(error) => on_event(&RegistryEvent::Error(error)),
+                }
+            }
+        });
+        RegistryWatcher {
+            current,
+            stop,
+            handle: Some(handle),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::prompt::Prompt;
+    use crate::registry::tests::{temp_dir, write};
+
+    const COMPLETION: &str = "type: completion\nvendor: google\nmodel: text-bison\nprompt: hi\n";
+
+    #[test]
+    fn test_watch_reloads() {
+        let dir = temp_dir("watch");
+        write(&dir, "greet.yaml", COMPLETION);
+        write(&dir, "old.yaml", COMPLETION);
+        let options = WatchOptions {
+            interval: Duration::from_millis(10),
+            debounce: Duration::from_millis(30),
+        };
+        let (watcher, events) = PromptRegistry::load(&dir).unwrap().watch(options);
+        let before = watcher.current();
+
+        write(&dir, "greet.yaml", &COMPLETION.replace("hi", "hello there"));
+        write(&dir, "new.yaml", COMPLETION);
+        fs::remove_file(dir.join("old.yaml")).unwrap();
+        let wait = Duration::from_secs(5);
+        let mut received = vec![
+            events.recv_timeout(wait).unwrap(),
+            events.recv_timeout(wait).unwrap(),
+            events.recv_timeout(wait).unwrap(),
+        ];
+        received.sort_by_key(|e| format!("{:?}", e));
+        assert_eq!(
+            received,
+            vec![
+                RegistryEvent::Added("new".to_string()),
+                RegistryEvent::Modified("greet".to_string()),
+                RegistryEvent::Removed("old".to_string()),
+            ]
+        );
+        match watcher.current().get("greet").unwrap() {
+            Prompt::Completion(c) => assert_eq!(c.prompt, "hello there"),
+            other => panic!("Expected Prompt::Completion, got {:?}", other),
+        }
+        assert!(before.get("old").is_ok());
+
+        write(&dir, "broken.yaml", "type: completion\n");
+        assert!(matches!(
+            events.recv_timeout(wait).unwrap(),
+            RegistryEvent::Error(RegistryError::Parse { .. })
+        ));
+        assert!(watcher.current().get("new").is_ok());
+        drop(watcher);
+        fs::remove_dir_all(&dir).unwrap();
+    }
+}
@@ -0,0 +1,459 @@
+use crate::exec::{block_on, ExecError, ExecutionResult, PromptExecutor};
+use crate::pattern::Pattern;
+use crate::prompt::Prompt;
+#[cfg(feature = "fs")]
+use crate::registry::PromptRegistry;
+use crate::registry::RegistryError;
+use crate::template::{render_template, MissingVariable, RenderError, Template};
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashMap};
+use std::fmt;
+#[cfg(feature = "fs")]
+use std::fs;
+#[cfg(feature = "fs")]
+use std::path::Path;
+
+const END: &str = "end";
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum WorkflowError {
+    Parse(String),
+    Invalid { step: String, message: String },
+    Prompt { step: String, error: RegistryError },
+    Render { step: String, error: RenderError },
+    Exec { step: String, error: ExecError },
+}
+
+impl fmt::Display for WorkflowError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            WorkflowError::Parse(message) => write!(f, "invalid workflow: {}", message),
+            WorkflowError::Invalid { step, message } => write!(f, "step '{}': {}", step, message),
+            WorkflowError::Prompt { step, error } => write!(f, "step '{}': {}", step, error),
+            WorkflowError::Render { step, error } => write!(f, "step '{}': {}", step, error),
+            WorkflowError::Exec { step, error } => write!(f, "step '{}': {}", step, error),
+        }
+    }
+}
+
+impl std::error::Error for WorkflowError {}
+
+#[derive(Debug, Clone, PartialEq, Eq, Default, Serialize, Deserialize)]
+pub struct Condition {
+    pub var: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub equals: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub contains: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub matches: Option<String>,
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub not: bool,
+}
+
+impl Condition {
+    pub fn evaluate(&self, vars: &HashMap<String, String>) -> bool {
+        let value = vars.get(&self.var).map_or("", |v| v.trim());
+        let equals = self.equals.as_ref().is_none_or(|e| value == e.trim());
+        let contains = self
+            .contains
+            .as_ref()
+            .is_none_or(|c| value.contains(c.as_str()));
+        let matches = self
+            .matches
+            .as_ref()
+            .is_none_or(|m| Pattern::new(m).is_ok_and(|p| p.is_match(value)));
+        (equals && contains && matches) != self.not
+    }
+
+    fn check(&self) -> Result<(), String> {
+        if self.equals.is_none() && self.contains.is_none() && self.matches.is_none() {
+            return Err(format!(
+                "condition on '{}' needs equals, contains or matches",
+                self.var
+            ));
+        }
+        match &self.matches {
+            Some(pattern) => Pattern::new(pattern)
+                .map(|_| ())
+                .map_err(|e| format!("invalid pattern '{}': {}", pattern, e)),
+            None => Ok(()),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Branch {
+    pub when: Condition,
+    pub goto: String,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct WorkflowStep {
+    pub name: String,
+    pub prompt: String,
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub vars: BTreeMap<String, String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub output: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub when: Option<Condition>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub branches: Vec<Branch>,
+}
+
+impl WorkflowStep {
+    pub fn output_var(&self) -> &str {
+        self.output.as_deref().unwrap_or(&self.name)
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Workflow {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub name: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub description: Option<String>,
+    pub steps: Vec<WorkflowStep>,
+}
+
+impl Workflow {
+    pub fn from_yaml(yaml: &str) -> Result<Workflow, WorkflowError> {
+        let workflow: Workflow =
+            serde_yaml::from_str(yaml).map_err(|e| WorkflowError::Parse(e.to_string()))?;
+        workflow.check()?;
+        Ok(workflow)
+    }
+
+    #[cfg(feature = "fs")]
+    pub fn load(path: impl AsRef<Path>) -> Result<Workflow, WorkflowError> {
+        let path = path.as_ref();
+        let source = fs::read_to_string(path)
+            .map_err(|e| WorkflowError::Parse(format!("{}: {}", path.display(), e)))?;
+        Workflow::from_yaml(&source)
+    }
+
+    pub fn step(&self, name: &str) -> Option<&WorkflowStep> {
+        self.steps.iter().find(|s| s.name == name)
+    }
+
+    fn position(&self, name: &str) -> Option<usize> {
+        self.steps.iter().position(|s| s.name == name)
+    }
+
+    pub fn check(&self) -> Result<(), WorkflowError> {
+        if self.steps.is_empty() {
+            return Err(WorkflowError::Parse("workflow has no steps".to_string()));
+        }
+        for (i, step) in self.steps.iter().enumerate() {
+            let invalid = |message: String| WorkflowError::Invalid {
+                step: step.name.clone(),
+                message,
+            };
+            if step.name == END || self.steps[..i].iter().any(|s| s.name == step.name) {
+                return Err(invalid(
+                    "step name must be unique and not 'end'".to_string(),
+                ));
+            }
+            let conditions = step
+                .when
+                .iter()
+                .chain(step.branches.iter().map(|b| &b.when));
+            for condition in conditions {
+                condition.check().map_err(invalid)?;
+            }
+            for branch in &step.branches {
+                if branch.goto != END && self.position(&branch.goto).is_none_or(|p| p <= i) {
+                    return Err(invalid(format!(
+                        "branch target '{}' must be a later step or 'end'",
+                        branch.goto
+                    )));
+                }
+            }
+            for template in step.vars.values() {
+                Template::parse(template).map_err(|e| invalid(e.to_string()))?;
+            }
+        }
+        Ok(())
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct StepOutput {
+    pub step: String,
+    pub vars: HashMap<String, String>,
+    pub result: ExecutionResult,
+}
+
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct WorkflowRun {
+    pub steps: Vec<StepOutput>,
+    pub skipped: Vec<String>,
+    pub vars: HashMap<String, String>,
+}
+
+impl WorkflowRun {
+    pub fn output(&self, step: &str) -> Option<&str> {
+        self.steps
+            .iter()
+            .find(|s| s.step == step)
+            .map(|s| s.result.text.as_str())
+    }
+
+    pub fn last(&self) -> Option<&StepOutput> {
+        self.steps.last()
+    }
+}
+
+pub struct WorkflowRunner<'a> {
+    executor: &'a dyn PromptExecutor,
+    #[cfg(feature = "fs")]
+    registry: Option<&'a PromptRegistry>,
+    prompts: HashMap<String, Prompt>,
+}
+
+impl<'a> WorkflowRunner<'a> {
+    pub fn new(executor: &'a dyn PromptExecutor) -> WorkflowRunner<'a> {
+        WorkflowRunner {
+            executor,
+            #[cfg(feature = "fs")]
+            registry: None,
+            prompts: HashMap::new(),
+        }
+    }
+
+    #[cfg(feature = "fs")]
+    pub fn registry(mut self, registry: &'a PromptRegistry) -> Self {
+        self.registry = Some(registry);
+        self
+    }
+
+    pub fn prompt(mut self, name: impl Into<String>, prompt: Prompt) -> Self {
+        self.prompts.insert(name.into(), prompt);
+        self
+    }
+
+    fn resolve(&self, step: &WorkflowStep) -> Result<&Prompt, WorkflowError> {
+        let error = |error| WorkflowError::Prompt {
+            step: step.name.clone(),
+            error,
+        };
+        if let Some(prompt) = self.prompts.get(&step.prompt) {
+            return Ok(prompt);
+        }
+        #[cfg(feature = "fs")]
+        if let Some(registry) = self.registry {
+            return registry.get(&step.prompt).map_err(error);
+        }
+        Err(error(RegistryError::NotFound(step.prompt.clone())))
+    }
+
+    fn step_vars(
+        step: &WorkflowStep,
+        vars: &HashMap<String, String>,
+    ) -> Result<HashMap<String, String>, WorkflowError> {
+        let mut step_vars = vars.clone();
+        for (name, template) in &step.vars {
+            let value =
+                render_template(template, vars, MissingVariable::Error).map_err(|error| {
+                    WorkflowError::Render {
+                        step: step.name.clone(),
+                        error,
+                    }
+                })?;
+            step_vars.insert(name.clone(), value);
+        }
+        Ok(step_vars)
+    }
+
+    pub async fn run(
+        &self,
+        workflow: &Workflow,
+        vars: &HashMap<String, String>,
+    ) -> Result<WorkflowRun, WorkflowError> {
+        let mut run = WorkflowRun {
+            vars: vars.clone(),
+            ..WorkflowRun::default()
+        };
+        let mut index = 0;
+        while let Some(step) = workflow.steps.get(index) {
+            if step.when.as_ref().is_some_and(|w| !w.evaluate(&run.vars)) {
+                run.skipped.push(step.name.clone());
+                index += 1;
+                continue;
+            }
+            let prompt = self.resolve(step)?;
+            let step_vars = Self::step_vars(step, &run.vars)?;
+            let result = self
+                .executor
+                .execute(prompt, &step_vars)
+                .await
+                .map_err(|error| WorkflowError::Exec {
+                    step: step.name.clone(),
+                    error,
+                })?;
+            run.vars.insert(
+                step.output_var().to_string(),
+                result.text.trim().to_string(),
+            );
+            run.steps.push(StepOutput {
+                step: step.name.clone(),
+                vars: step_vars,
+                result,
+            });
+            index = match step.branches.iter().find(|b| b.when.evaluate(&run.vars)) {
+                Some(branch) => workflow
+                    .position(&branch.goto)
+                    .unwrap_or(workflow.steps.len()),
+                None => index + 1,
+            };
+        }
+        Ok(run)
+    }
+
+    pub fn run_blocking(
+        &self,
+        workflow: &Workflow,
+        vars: &HashMap<String, String>,
+    ) -> Result<WorkflowRun, WorkflowError> {
+        block_on(self.run(workflow, vars))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::exec::BoxFuture;
+    use crate::prompt::deserialize_prompt;
+    use serde_json::Value;
+
+    struct Echo;
+
+    impl PromptExecutor for Echo {
+        fn execute<'a>(
+            &'a self,
+            prompt: &'a Prompt,
+            vars: &'a HashMap<String, String>,
+        ) -> BoxFuture<'a, Result<ExecutionResult, ExecError>> {
+            Box::pin(async move {
+                let Prompt::Completion(completion) = prompt else {
+                    return Err(ExecError::InvalidResponse("not a completion".to_string()));
+                };
+                let text = completion.render(vars)?;
+                let text = match text.strip_prefix("classify: ") {
+                    Some(ticket) if ticket.contains("refund") => "billing".to_string(),
+                    Some(_) => "other".to_string(),
+                    None => text,
+                };
+                Ok(ExecutionResult {
+                    text,
+                    raw: Value::Null,
+                })
+            })
+        }
+    }
+
+    fn completion(prompt: &str) -> Prompt {
+        deserialize_prompt(&format!(
+            "type: completion\nvendor: openai\nmodel: gpt\nprompt: '{}'",
+            prompt
+        ))
+    }
+
+    const WORKFLOW: &str = r#"
+        name: support
+        steps:
+            - name: classify
+              prompt: classify
+              vars: { ticket: "{{subject}}: {{body}}" }
+              output: category
+              branches:
+                  - when: { var: category, equals: other }
+                    goto: general
+            - name: billing
+              prompt: reply
+              vars: { team: billing }
+              branches:
+                  - when: { var: category, matches: '.*' }
+                    goto: end
+            - name: general
+              prompt: reply
+              vars: { team: general }
+            - name: escalate
+              prompt: reply
+              vars: { team: escalations }
+              when: { var: subject, contains: urgent }
+    "#;
+
+    fn runner(executor: &Echo) -> WorkflowRunner<'_> {
+        WorkflowRunner::new(executor)
+            .prompt("classify", completion("classify: {{ticket}}"))
+            .prompt("reply", completion("{{team}} handles {{category}}"))
+    }
+
+    fn vars(subject: &str) -> HashMap<String, String> {
+        HashMap::from([
+            ("subject".to_string(), subject.to_string()),
+            ("body".to_string(), "please refund me".to_string()),
+        ])
+    }
+
+    #[test]
+    fn test_workflow_branching() {
+        let workflow = Workflow::from_yaml(WORKFLOW).unwrap();
+        let executor = Echo;
+        let runner = runner(&executor);
+
+        let run = runner.run_blocking(&workflow, &vars("order")).unwrap();
+        let steps: Vec<&str> = run.steps.iter().map(|s| s.step.as_str()).collect();
+        assert_eq!(steps, vec!["classify", "billing"]);
+        assert_eq!(run.vars["category"], "billing");
+        assert_eq!(run.steps[0].vars["ticket"], "order: please refund me");
+        assert_eq!(run.output("billing"), Some("billing handles billing"));
+
+        let vars = HashMap::from([
+            ("subject".to_string(), "urgent".to_string()),
+            ("body".to_string(), "it broke".to_string()),
+        ]);
+        let run = block_on(runner.run(&workflow, &vars)).unwrap();
+        let steps: Vec<&str> = run.steps.iter().map(|s| s.step.as_str()).collect();
+        assert_eq!(steps, vec!["classify", "general", "escalate"]);
+        assert_eq!(run.last().unwrap().result.text, "escalations handles other");
+    }
+
+    #[test]
+    fn test_workflow_errors() {
+        let backward =
+            "steps:\n  - {name: a, prompt: p}\n  - {name: b, prompt: p, branches: [{when: {var: a, equals: x}, goto: a}]}";
+        assert!(matches!(
+            Workflow::from_yaml(backward),
+            Err(WorkflowError::Invalid { step, .. }) if step == "b"
+        ));
+        assert!(matches!(
+            Workflow::from_yaml("steps:\n  - {name: a, prompt: p, when: {var: a}}"),
+            Err(WorkflowError::Invalid { .. })
+        ));
+        assert!(matches!(
+            Workflow::from_yaml("steps: []"),
+            Err(WorkflowError::Parse(_))
+        ));
+
+        let workflow = Workflow::from_yaml("steps:\n  - {name: a, prompt: missing}").unwrap();
+        let executor = Echo;
+        assert_eq!(
+            runner(&executor).run_blocking(&workflow, &HashMap::new()),
+            Err(WorkflowError::Prompt {
+                step: "a".to_string(),
+                error: RegistryError::NotFound("missing".to_string()),
+            })
+        );
+        let workflow = Workflow::from_yaml(
+            "steps:\n  - {name: a, prompt: classify, vars: {ticket: '{{body}}'}}",
+        )
+        .unwrap();
+        assert!(matches!(
+            runner(&executor).run_blocking(&workflow, &HashMap::new()),
+            Err(WorkflowError::Render { .. })
+        ));
+    }
+}